@@ -0,0 +1,616 @@
+//! `--apply`: ask for a unified diff, preview it and patch the file in place
+
+use super::Args;
+use crate::config::Config;
+use crate::executor::ConfirmPolicy;
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use std::path::{Component, Path, PathBuf};
+
+/// Extra system instruction for `--apply`
+pub const APPLY_DIRECTIVE: &str = "IMPORTANT: Answer ONLY with a unified diff that makes the \
+     requested change: `--- a/<path>` and `+++ b/<path>` headers using the file's name as given \
+     in the input, then `@@ -start,count +start,count @@` hunks with 3 lines of context. \
+     No explanations, no code fences.";
+
+/// One line of a hunk body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// 1-based first line in the original file, from the `@@` header
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Lines the original must contain here (context and removals)
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    /// Lines that replace them (context and additions)
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Add(s) => Some(s.as_str()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// The hunks for one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatch {
+    /// Path from the `---` header, or None for a new file (`/dev/null`)
+    pub old_path: Option<String>,
+    /// Path from the `+++` header, or None for a deleted file
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+/// Parse a unified diff out of a model answer. Prose or code fences around the
+/// diff are skipped; the line counts in `@@` headers are not trusted, since
+/// models often get them wrong, so each hunk runs until the next header.
+pub fn parse_diff(text: &str) -> Result<Vec<FilePatch>> {
+    let text = crate::providers::strip_code_fences(text);
+    let lines: Vec<&str> = text.lines().collect();
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut in_hunk = false;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+
+        if line.starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ ")) {
+            patches.push(FilePatch {
+                old_path: header_path(&line[4..]),
+                new_path: header_path(&lines[i + 1][4..]),
+                hunks: Vec::new(),
+            });
+            in_hunk = false;
+            i += 2;
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            let Some(patch) = patches.last_mut() else {
+                bail!("Hunk header before any '---'/'+++' file header: {}", line);
+            };
+            patch.hunks.push(Hunk {
+                old_start: parse_old_start(line)?,
+                lines: Vec::new(),
+            });
+            in_hunk = true;
+            i += 1;
+            continue;
+        }
+
+        if in_hunk {
+            let hunk = patches
+                .last_mut()
+                .and_then(|p| p.hunks.last_mut())
+                .expect("in_hunk implies a hunk");
+            match line.chars().next() {
+                Some(' ') => hunk.lines.push(HunkLine::Context(line[1..].to_string())),
+                Some('-') => hunk.lines.push(HunkLine::Remove(line[1..].to_string())),
+                Some('+') => hunk.lines.push(HunkLine::Add(line[1..].to_string())),
+                // Blank context lines often lose their leading space
+                None => hunk.lines.push(HunkLine::Context(String::new())),
+                // "\ No newline at end of file"
+                Some('\\') => {}
+                // "diff --git", "index ...", or prose after the diff
+                _ => in_hunk = false,
+            }
+        }
+        i += 1;
+    }
+
+    if patches.is_empty() {
+        bail!("The answer does not contain a unified diff");
+    }
+    for patch in &patches {
+        let name = patch.display_path();
+        if patch.hunks.is_empty() {
+            bail!("The diff for {} has no hunks", name);
+        }
+        for hunk in &patch.hunks {
+            if !hunk
+                .lines
+                .iter()
+                .any(|l| !matches!(l, HunkLine::Context(_)))
+            {
+                bail!("A hunk for {} changes nothing", name);
+            }
+        }
+    }
+    Ok(patches)
+}
+
+impl FilePatch {
+    fn display_path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or("?")
+    }
+}
+
+/// `a/src/x.rs\t2024-01-01` -> `a/src/x.rs`; `/dev/null` -> None
+fn header_path(raw: &str) -> Option<String> {
+    let path = raw.split('\t').next().unwrap_or("").trim();
+    if path.is_empty() || path == "/dev/null" {
+        return None;
+    }
+    Some(path.to_string())
+}
+
+/// `@@ -12,7 +12,8 @@ fn main` -> 12
+fn parse_old_start(header: &str) -> Result<usize> {
+    header
+        .trim_start_matches('@')
+        .split_whitespace()
+        .next()
+        .and_then(|range| range.strip_prefix('-'))
+        .and_then(|range| range.split(',').next())
+        .and_then(|start| start.parse().ok())
+        .with_context(|| format!("Malformed hunk header: {}", header))
+}
+
+/// Apply every hunk to `original`, or fail if any of them does not match exactly.
+/// A hunk is looked for at its stated line first, then anywhere after the previous hunk.
+/// CRLF files stay CRLF, and a missing final newline stays missing.
+pub fn apply_patch(original: &str, patch: &FilePatch) -> Result<String> {
+    let eol = if original.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let lines: Vec<&str> = original.lines().collect();
+    let mut result: Vec<&str> = Vec::with_capacity(lines.len());
+    let mut cursor = 0;
+
+    for (n, hunk) in patch.hunks.iter().enumerate() {
+        let old = hunk.old_lines();
+        let matches_at = |pos: usize| lines.get(pos..pos + old.len()) == Some(old.as_slice());
+
+        // With no old lines, `-N,0` means "insert after line N"
+        let stated = if old.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let pos = if stated >= cursor && matches_at(stated) {
+            stated
+        } else {
+            (cursor..=lines.len())
+                .find(|&pos| matches_at(pos))
+                .with_context(|| {
+                    format!(
+                        "Hunk {} for {} does not apply cleanly; nothing was changed",
+                        n + 1,
+                        patch.display_path()
+                    )
+                })?
+        };
+
+        result.extend_from_slice(&lines[cursor..pos]);
+        result.extend(hunk.new_lines());
+        cursor = pos + old.len();
+    }
+    result.extend_from_slice(&lines[cursor..]);
+
+    let mut patched = result.join(eol);
+    if !patched.is_empty() && (original.is_empty() || original.ends_with('\n')) {
+        patched.push_str(eol);
+    }
+    Ok(patched)
+}
+
+/// Colorized diff for the preview
+pub fn render_diff(patches: &[FilePatch]) -> String {
+    let mut out = Vec::new();
+    for patch in patches {
+        out.push(
+            format!("--- {}", patch.old_path.as_deref().unwrap_or("/dev/null"))
+                .bold()
+                .to_string(),
+        );
+        out.push(
+            format!("+++ {}", patch.new_path.as_deref().unwrap_or("/dev/null"))
+                .bold()
+                .to_string(),
+        );
+        for hunk in &patch.hunks {
+            out.push(format!("@@ -{} @@", hunk.old_start).cyan().to_string());
+            for line in &hunk.lines {
+                out.push(match line {
+                    HunkLine::Context(s) => format!(" {}", s),
+                    HunkLine::Remove(s) => format!("-{}", s).red().to_string(),
+                    HunkLine::Add(s) => format!("+{}", s).green().to_string(),
+                });
+            }
+        }
+    }
+    out.join("\n")
+}
+
+/// Where a patch reads from (None for a new file) and writes to, relative to
+/// `root`. Header paths are tried as given, then without git's `a/` / `b/`
+/// prefix. A rename reads the `---` file and writes the `+++` one.
+fn resolve_target(patch: &FilePatch, root: &Path) -> Result<(Option<PathBuf>, PathBuf)> {
+    let Some(new_path) = patch.new_path.as_deref() else {
+        bail!(
+            "The diff deletes {}; --apply only edits or creates files",
+            patch.display_path()
+        );
+    };
+
+    let news = candidates(new_path)?;
+    let (read, write) = match patch.old_path.as_deref() {
+        Some(old_path) => {
+            let olds = candidates(old_path)?;
+            let renamed = strip_git_prefix(old_path) != strip_git_prefix(new_path);
+            if let Some(i) = olds.iter().position(|p| root.join(p).is_file()) {
+                let write = if renamed {
+                    news[i.min(news.len() - 1)].clone()
+                } else {
+                    olds[i].clone()
+                };
+                (Some(olds[i].clone()), write)
+            } else if let Some(p) = news.iter().find(|p| root.join(p).is_file()) {
+                (Some(p.clone()), p.clone())
+            } else {
+                bail!(
+                    "Cannot find {} to patch. Attach the file as @path so the model sees its name",
+                    old_path
+                );
+            }
+        }
+        // New file: written without the b/ prefix, and never over an existing one
+        None => {
+            let write = news.last().cloned().expect("at least one candidate");
+            if root.join(&write).is_file() {
+                bail!("The diff creates {}, which already exists", write.display());
+            }
+            (None, write)
+        }
+    };
+
+    for path in read.iter().chain([&write]) {
+        ensure_inside(root, path)?;
+    }
+    Ok((read, write))
+}
+
+fn strip_git_prefix(raw: &str) -> &str {
+    raw.strip_prefix("a/")
+        .or(raw.strip_prefix("b/"))
+        .unwrap_or(raw)
+}
+
+/// A header path as given, then without its `a/` / `b/` prefix. Absolute
+/// paths and `..` are refused so a diff cannot reach outside the current directory.
+fn candidates(raw: &str) -> Result<Vec<PathBuf>> {
+    let escapes = Path::new(raw)
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        bail!(
+            "Refusing to patch {}: paths in the diff must be relative and stay inside the current directory",
+            raw
+        );
+    }
+    let mut paths = vec![PathBuf::from(raw)];
+    if strip_git_prefix(raw) != raw {
+        paths.push(PathBuf::from(strip_git_prefix(raw)));
+    }
+    Ok(paths)
+}
+
+/// Fail unless `path` (or, for a new file, its nearest existing parent)
+/// resolves inside `root` once symlinks are followed
+fn ensure_inside(root: &Path, path: &Path) -> Result<()> {
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("Could not resolve {}", root.display()))?;
+    let full = root.join(path);
+    let existing = full
+        .ancestors()
+        .find(|p| p.exists())
+        .unwrap_or(root.as_path());
+    let resolved = existing
+        .canonicalize()
+        .with_context(|| format!("Could not resolve {}", existing.display()))?;
+    if !resolved.starts_with(&root) {
+        bail!(
+            "Refusing to patch {}: it resolves outside the current directory",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Apply every patch in memory, so a bad hunk leaves every file untouched.
+/// A later patch to the same file builds on the earlier one's result.
+fn plan_writes(patches: &[FilePatch], root: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let mut writes: Vec<(PathBuf, String)> = Vec::new();
+    for patch in patches {
+        let (source, target) = resolve_target(patch, root)?;
+        let pending = writes
+            .iter()
+            .position(|(path, _)| Some(path) == source.as_ref());
+        let original = match (pending, source) {
+            (Some(i), _) => writes.remove(i).1,
+            (None, Some(source)) => std::fs::read_to_string(root.join(&source))
+                .with_context(|| format!("Could not read {}", source.display()))?,
+            (None, None) => String::new(),
+        };
+        if writes.iter().any(|(path, _)| *path == target) {
+            bail!("The diff writes {} more than once", target.display());
+        }
+        writes.push((target, apply_patch(&original, patch)?));
+    }
+    Ok(writes)
+}
+
+/// Parse the answer, show the diff, and write every file once all hunks apply
+pub fn apply_response(config: &Config, args: &Args, response: &str) -> Result<()> {
+    let patches = parse_diff(response)?;
+    let root = std::env::current_dir().context("Could not read the current directory")?;
+    let writes = plan_writes(&patches, &root)?;
+
+    println!("{}", render_diff(&patches));
+    println!();
+
+    let names: Vec<String> = writes
+        .iter()
+        .map(|(path, _)| path.display().to_string())
+        .collect();
+    let confirmed = args.yes == Some(true)
+        || ConfirmPolicy::from_behavior(&config.behavior).confirm(
+            &format!("Apply to {}?", names.join(", ").bright_white().bold()),
+            false,
+        );
+    if !confirmed {
+        println!("{}", "Not applied.".bright_black());
+        return Ok(());
+    }
+
+    for (path, content) in &writes {
+        write_file(&root.join(path), content)?;
+    }
+    println!("{} {}", "Applied to".green(), names.join(", "));
+    Ok(())
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Could not create {}", parent.display()))?;
+    }
+    std::fs::write(path, content).with_context(|| format!("Could not write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "Here is the fix:
+
+```diff
+--- a/src/x.rs
++++ b/src/x.rs
+@@ -2,3 +2,3 @@ fn main() {
+     let v = vec![1, 2, 3];
+-    for i in 0..=v.len() {
++    for i in 0..v.len() {
+         println!(\"{}\", v[i]);
+```
+";
+
+    const SOURCE: &str = "fn main() {
+    let v = vec![1, 2, 3];
+    for i in 0..=v.len() {
+        println!(\"{}\", v[i]);
+    }
+}
+";
+
+    #[test]
+    fn test_parse_diff() {
+        let patches = parse_diff(DIFF).unwrap();
+        assert_eq!(patches.len(), 1);
+        assert_eq!(patches[0].old_path.as_deref(), Some("a/src/x.rs"));
+        assert_eq!(patches[0].new_path.as_deref(), Some("b/src/x.rs"));
+
+        let hunk = &patches[0].hunks[0];
+        assert_eq!(hunk.old_start, 2);
+        assert_eq!(hunk.lines.len(), 4);
+        assert_eq!(
+            hunk.lines[1],
+            HunkLine::Remove("    for i in 0..=v.len() {".into())
+        );
+    }
+
+    #[test]
+    fn test_parse_diff_rejects_non_diffs() {
+        assert!(parse_diff("Just change the loop bound.").is_err());
+        assert!(parse_diff("--- a/x\n+++ b/x\n").is_err(), "no hunks");
+        assert!(parse_diff("--- a/x\n+++ b/x\n@@ -1 +1 @@\n same\n").is_err());
+        assert!(
+            parse_diff("@@ -1 +1 @@\n-a\n+b\n").is_err(),
+            "no file header"
+        );
+    }
+
+    #[test]
+    fn test_apply_simple_hunk() {
+        let patches = parse_diff(DIFF).unwrap();
+        let patched = apply_patch(SOURCE, &patches[0]).unwrap();
+        assert_eq!(patched, SOURCE.replace("0..=v.len()", "0..v.len()"));
+    }
+
+    #[test]
+    fn test_apply_finds_shifted_hunk() {
+        let patches = parse_diff(DIFF).unwrap();
+        let shifted = format!("// header\n\n{}", SOURCE);
+        let patched = apply_patch(&shifted, &patches[0]).unwrap();
+        assert_eq!(patched, shifted.replace("0..=v.len()", "0..v.len()"));
+    }
+
+    #[test]
+    fn test_apply_requires_clean_match() {
+        let patches = parse_diff(DIFF).unwrap();
+        let drifted = SOURCE.replace("vec![1, 2, 3]", "vec![4, 5]");
+        let err = apply_patch(&drifted, &patches[0]).unwrap_err();
+        assert!(err.to_string().contains("does not apply cleanly"));
+    }
+
+    #[test]
+    fn test_apply_new_file() {
+        let patches =
+            parse_diff("--- /dev/null\n+++ b/notes.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n").unwrap();
+        assert_eq!(patches[0].old_path, None);
+        assert_eq!(apply_patch("", &patches[0]).unwrap(), "one\ntwo\n");
+    }
+
+    fn file_patch(old_path: Option<&str>, new_path: &str) -> FilePatch {
+        FilePatch {
+            old_path: old_path.map(String::from),
+            new_path: Some(new_path.to_string()),
+            hunks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_target_strips_git_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/x.rs"), SOURCE).unwrap();
+        let x = PathBuf::from("src/x.rs");
+
+        let plain = file_patch(Some("src/x.rs"), "src/x.rs");
+        assert_eq!(
+            resolve_target(&plain, dir.path()).unwrap(),
+            (Some(x.clone()), x.clone())
+        );
+        let git = file_patch(Some("a/src/x.rs"), "b/src/x.rs");
+        assert_eq!(
+            resolve_target(&git, dir.path()).unwrap(),
+            (Some(x.clone()), x.clone())
+        );
+
+        let missing = file_patch(Some("a/definitely/missing.rs"), "b/definitely/missing.rs");
+        assert!(resolve_target(&missing, dir.path()).is_err());
+
+        let created = file_patch(None, "b/notes/new.txt");
+        assert_eq!(
+            resolve_target(&created, dir.path()).unwrap(),
+            (None, PathBuf::from("notes/new.txt"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_target_refuses_to_create_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "keep me\n").unwrap();
+
+        let err = resolve_target(&file_patch(None, "b/notes.txt"), dir.path()).unwrap_err();
+        assert!(err.to_string().contains("which already exists"), "{}", err);
+    }
+
+    #[test]
+    fn test_plan_writes_merges_patches_to_one_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("list.txt"), "a\nb\nc\nd\ne\nf\ng\n").unwrap();
+
+        let patches = parse_diff(
+            "--- a/list.txt\n+++ b/list.txt\n@@ -1,2 +1,2 @@\n-a\n+A\n b\n\
+             --- a/list.txt\n+++ b/list.txt\n@@ -6,2 +6,2 @@\n f\n-g\n+G\n",
+        )
+        .unwrap();
+        let writes = plan_writes(&patches, dir.path()).unwrap();
+        assert_eq!(
+            writes,
+            vec![(
+                PathBuf::from("list.txt"),
+                "A\nb\nc\nd\ne\nf\nG\n".to_string()
+            )]
+        );
+
+        let twice = parse_diff(
+            "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+one\n\
+             --- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+two\n",
+        )
+        .unwrap();
+        let err = plan_writes(&twice, dir.path()).unwrap_err();
+        assert!(err.to_string().contains("more than once"), "{}", err);
+    }
+
+    #[test]
+    fn test_resolve_target_writes_renames_to_new_path() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("old.rs"), SOURCE).unwrap();
+
+        let rename = file_patch(Some("a/old.rs"), "b/new.rs");
+        assert_eq!(
+            resolve_target(&rename, dir.path()).unwrap(),
+            (Some(PathBuf::from("old.rs")), PathBuf::from("new.rs"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_target_stays_inside_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("x.rs"), SOURCE).unwrap();
+        let absolute = outside.path().join("x.rs").display().to_string();
+
+        for (old, new) in [
+            (Some(absolute.as_str()), absolute.as_str()),
+            (None, "/etc/cron.d/job"),
+            (None, "b/../escape.txt"),
+            (Some("a/x.rs"), "b/../../x.rs"),
+        ] {
+            let err = resolve_target(&file_patch(old, new), dir.path()).unwrap_err();
+            assert!(err.to_string().contains("Refusing to patch"), "{}", err);
+        }
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+            let err = resolve_target(&file_patch(Some("link/x.rs"), "link/x.rs"), dir.path())
+                .unwrap_err();
+            assert!(err.to_string().contains("outside the current directory"));
+            let err = resolve_target(&file_patch(None, "link/new.rs"), dir.path()).unwrap_err();
+            assert!(err.to_string().contains("outside the current directory"));
+        }
+    }
+
+    #[test]
+    fn test_apply_keeps_line_endings() {
+        let patches = parse_diff(DIFF).unwrap();
+
+        let crlf = SOURCE.replace('\n', "\r\n");
+        let patched = apply_patch(&crlf, &patches[0]).unwrap();
+        assert_eq!(patched, crlf.replace("0..=v.len()", "0..v.len()"));
+
+        let unterminated = SOURCE.trim_end();
+        let patched = apply_patch(unterminated, &patches[0]).unwrap();
+        assert_eq!(patched, unterminated.replace("0..=v.len()", "0..v.len()"));
+    }
+}
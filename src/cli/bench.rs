@@ -0,0 +1,305 @@
+//! Latency benchmark across configured profiles (`ask --bench`)
+
+use super::Args;
+use crate::config::Config;
+use crate::providers::{
+    build_unified_prompt, create_provider, Message, PromptContext, ProviderOptions,
+};
+use anyhow::Result;
+use colored::Colorize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Keep benchmark calls cheap: we only care about latency, not the answer
+const BENCH_MAX_TOKENS: u32 = 64;
+
+/// Outcome of benchmarking a single profile
+#[derive(Debug, Clone)]
+pub enum BenchOutcome {
+    Ok {
+        first_token: Option<Duration>,
+        total: Duration,
+    },
+    Skipped(String),
+    Failed(String),
+}
+
+/// Benchmark result for a single profile
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub profile: String,
+    pub provider: String,
+    pub model: String,
+    pub outcome: BenchOutcome,
+}
+
+impl BenchResult {
+    fn sort_key(&self) -> (u8, Duration) {
+        match self.outcome {
+            BenchOutcome::Ok { total, .. } => (0, total),
+            BenchOutcome::Failed(_) => (1, Duration::ZERO),
+            BenchOutcome::Skipped(_) => (2, Duration::ZERO),
+        }
+    }
+}
+
+/// Run the same query against every selected profile and print a latency table
+pub async fn run_bench(config: &Config, args: &Args) -> Result<()> {
+    let query = args.query.join(" ");
+    if query.trim().is_empty() {
+        anyhow::bail!("Benchmark requires a query, e.g.: ask --bench list files");
+    }
+
+    let mut names: Vec<String> = config.profiles.keys().cloned().collect();
+    names.sort();
+
+    if let Some(ref selected) = args.bench_profiles {
+        for name in selected {
+            if !config.profiles.contains_key(name) {
+                anyhow::bail!("Unknown profile: {}", name);
+            }
+        }
+        names.retain(|n| selected.contains(n));
+    }
+
+    println!(
+        "{} {} {}",
+        "Benchmarking".cyan(),
+        names.len(),
+        "profile(s)...".cyan()
+    );
+
+    let mut results = Vec::new();
+    for name in names {
+        let mut profile_args = args.clone();
+        profile_args.profile = Some(name.clone());
+        profile_args.provider = None;
+        let profile_config = config.clone().with_cli_overrides(&profile_args);
+
+        eprint!("{}", format!("  {}...", name).bright_black());
+        let result = bench_profile(&profile_config, &name, &query).await;
+        eprintln!(" {}", "done".bright_black());
+        results.push(result);
+    }
+
+    println!();
+    print!("{}", format_bench_table(&results));
+
+    Ok(())
+}
+
+async fn bench_profile(config: &Config, name: &str, query: &str) -> BenchResult {
+    let provider_name = config.active_provider().to_string();
+    let model = config.active_model().to_string();
+
+    let result = |outcome| BenchResult {
+        profile: name.to_string(),
+        provider: provider_name.clone(),
+        model: model.clone(),
+        outcome,
+    };
+
+    let provider = match create_provider(config) {
+        Ok(p) => p,
+        Err(e) => return result(BenchOutcome::Skipped(e.to_string())),
+    };
+
+    let ctx = PromptContext::from_env(false, false, false);
+    let messages = vec![
+        Message {
+            role: "system".to_string(),
+            content: build_unified_prompt(&ctx),
+            ..Default::default()
+        },
+        Message {
+            role: "user".to_string(),
+            content: query.to_string(),
+            ..Default::default()
+        },
+    ];
+    let options = ProviderOptions {
+        max_tokens: Some(BENCH_MAX_TOKENS),
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    let first_token: Arc<Mutex<Option<Duration>>> = Arc::new(Mutex::new(None));
+    let first_token_clone = first_token.clone();
+    let callback: crate::providers::StreamCallback = Box::new(move |_chunk: &str| {
+        let mut first = first_token_clone.lock().unwrap();
+        if first.is_none() {
+            *first = Some(start.elapsed());
+        }
+    });
+
+    match provider
+        .stream_with_options(&messages, callback, &options)
+        .await
+    {
+        Ok(_) => {
+            let total = start.elapsed();
+            let first_token = *first_token.lock().unwrap();
+            result(BenchOutcome::Ok { first_token, total })
+        }
+        Err(e) => result(BenchOutcome::Failed(e.to_string())),
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    format!("{} ms", d.as_millis())
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or("")
+}
+
+/// Format results as a plain-text table sorted by total latency.
+/// Failed and skipped profiles are listed last.
+pub fn format_bench_table(results: &[BenchResult]) -> String {
+    let mut sorted: Vec<&BenchResult> = results.iter().collect();
+    sorted.sort_by(|a, b| {
+        a.sort_key()
+            .cmp(&b.sort_key())
+            .then_with(|| a.profile.cmp(&b.profile))
+    });
+
+    let rows: Vec<[String; 5]> = sorted
+        .iter()
+        .map(|r| {
+            let (first, total) = match &r.outcome {
+                BenchOutcome::Ok { first_token, total } => (
+                    first_token
+                        .map(format_duration)
+                        .unwrap_or_else(|| "-".to_string()),
+                    format_duration(*total),
+                ),
+                BenchOutcome::Skipped(reason) => {
+                    ("-".to_string(), format!("skipped: {}", first_line(reason)))
+                }
+                BenchOutcome::Failed(err) => {
+                    ("-".to_string(), format!("failed: {}", first_line(err)))
+                }
+            };
+            [
+                r.profile.clone(),
+                r.provider.clone(),
+                r.model.clone(),
+                first,
+                total,
+            ]
+        })
+        .collect();
+
+    let headers = ["PROFILE", "PROVIDER", "MODEL", "FIRST TOKEN", "TOTAL"];
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (i, cell) in row.iter().enumerate().take(4) {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: [&str; 5]| -> String {
+        let mut line = String::new();
+        for (i, cell) in cells.iter().enumerate() {
+            if i == 4 {
+                line.push_str(cell);
+            } else {
+                line.push_str(&format!("{:<width$}  ", cell, width = widths[i]));
+            }
+        }
+        line.trim_end().to_string()
+    };
+
+    let mut out = String::new();
+    out.push_str(&format_row(headers));
+    out.push('\n');
+    for row in &rows {
+        out.push_str(&format_row([
+            row[0].as_str(),
+            row[1].as_str(),
+            row[2].as_str(),
+            row[3].as_str(),
+            row[4].as_str(),
+        ]));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok(profile: &str, first_ms: u64, total_ms: u64) -> BenchResult {
+        BenchResult {
+            profile: profile.to_string(),
+            provider: "openai".to_string(),
+            model: "gpt-test".to_string(),
+            outcome: BenchOutcome::Ok {
+                first_token: Some(Duration::from_millis(first_ms)),
+                total: Duration::from_millis(total_ms),
+            },
+        }
+    }
+
+    #[test]
+    fn test_format_bench_table_sorted_by_latency() {
+        let results = vec![
+            ok("slow", 900, 2500),
+            BenchResult {
+                profile: "nokey".to_string(),
+                provider: "gemini".to_string(),
+                model: "gemini-flash".to_string(),
+                outcome: BenchOutcome::Skipped("No API key found".to_string()),
+            },
+            ok("fast", 120, 480),
+            BenchResult {
+                profile: "broken".to_string(),
+                provider: "anthropic".to_string(),
+                model: "claude".to_string(),
+                outcome: BenchOutcome::Failed("API error (500)\nbody".to_string()),
+            },
+        ];
+
+        let table = format_bench_table(&results);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("PROFILE"));
+        assert!(lines[1].starts_with("fast"));
+        assert!(lines[1].contains("120 ms"));
+        assert!(lines[1].ends_with("480 ms"));
+        assert!(lines[2].starts_with("slow"));
+        assert!(lines[2].ends_with("2500 ms"));
+        assert!(lines[3].starts_with("broken"));
+        assert!(lines[3].ends_with("failed: API error (500)"));
+        assert!(lines[4].starts_with("nokey"));
+        assert!(lines[4].ends_with("skipped: No API key found"));
+    }
+
+    #[test]
+    fn test_format_bench_table_aligns_columns() {
+        let results = vec![ok("a", 1, 2), ok("longer-name", 3, 4)];
+        let table = format_bench_table(&results);
+        let lines: Vec<&str> = table.lines().collect();
+
+        let col = lines[0].find("PROVIDER").unwrap();
+        assert_eq!(lines[1].find("openai"), Some(col));
+        assert_eq!(lines[2].find("openai"), Some(col));
+    }
+
+    #[test]
+    fn test_format_bench_table_missing_first_token() {
+        let results = vec![BenchResult {
+            profile: "quiet".to_string(),
+            provider: "openai".to_string(),
+            model: "o3".to_string(),
+            outcome: BenchOutcome::Ok {
+                first_token: None,
+                total: Duration::from_millis(1500),
+            },
+        }];
+        let table = format_bench_table(&results);
+        assert!(table.lines().nth(1).unwrap().contains(" - "));
+    }
+}
@@ -0,0 +1,215 @@
+//! Several candidate answers for one query (`ask -N 3 ...`)
+
+use super::{
+    copy_response, cost, is_likely_command, maybe_execute_command, normalize_command_response,
+    save_exchange, strip_command_preamble, Args,
+};
+use crate::config::Config;
+use crate::output::{OutputFormatter, Spinner};
+use crate::providers::{Message, Provider, ProviderOptions, ProviderResponse, Usage};
+use anyhow::Result;
+use colored::Colorize;
+use requestty::Question;
+use std::io::IsTerminal;
+
+/// Most candidates one query may ask for
+pub const MAX_CANDIDATES: usize = 10;
+
+const CANCEL_CHOICE: &str = "Cancel";
+
+/// Ask for `options.candidates` answers; pick one to run in command mode, list them otherwise
+pub async fn candidates_query(
+    config: &Config,
+    args: &Args,
+    provider: &dyn Provider,
+    query: &str,
+    messages: &[Message],
+    options: &ProviderOptions,
+    formatter: &OutputFormatter,
+) -> Result<()> {
+    let spinner = (!args.raw && !args.json && std::io::stdout().is_terminal()).then(Spinner::start);
+    let responses = provider.complete_candidates(messages, options).await;
+    drop(spinner);
+    let responses = responses?;
+
+    let candidates = distinct_candidates(responses.iter().map(|r| {
+        let text = strip_command_preamble(args, r.text.clone());
+        normalize_command_response(
+            &text,
+            config.behavior.max_command_chars,
+            config.behavior.flatten_commands,
+        )
+        .unwrap_or(text)
+    }));
+
+    if args.cost {
+        cost::print_cost(config, provider.model(), total_usage(&responses));
+    }
+
+    if args.json {
+        let output = serde_json::json!({ "candidates": candidates, "success": true });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return save_exchange(config, args, query, &numbered_list(&candidates));
+    }
+
+    let commands = args.command_mode == Some(true)
+        || candidates
+            .iter()
+            .all(|c| is_likely_command(c.trim(), config.behavior.max_command_chars));
+    let interactive = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+
+    if commands && interactive && !args.more {
+        let Some(command) = pick_candidate(&candidates)? else {
+            return Ok(());
+        };
+        save_exchange(config, args, query, &command)?;
+        if args.copy {
+            copy_response(config, &command);
+        }
+        return maybe_execute_command(config, args, &command).await;
+    }
+
+    for (i, candidate) in candidates.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!(
+            "{}",
+            crate::output::theme()
+                .heading(&format!("[{}]", i + 1))
+                .bold()
+        );
+        formatter.format(candidate);
+    }
+
+    let listed = numbered_list(&candidates);
+    if args.copy {
+        copy_response(config, &listed);
+    }
+    save_exchange(config, args, query, &listed)
+}
+
+/// Trimmed, non-empty answers in order, without repeats
+pub fn distinct_candidates<I: IntoIterator<Item = String>>(texts: I) -> Vec<String> {
+    let mut candidates: Vec<String> = Vec::new();
+    for text in texts {
+        let text = text.trim().to_string();
+        if !text.is_empty() && !candidates.contains(&text) {
+            candidates.push(text);
+        }
+    }
+    candidates
+}
+
+/// `1. first\n2. second` - what the context remembers when nothing was picked
+pub fn numbered_list(candidates: &[String]) -> String {
+    candidates
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("{}. {}", i + 1, c))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Menu entries: every candidate, then a way out
+pub fn picker_choices(candidates: &[String]) -> Vec<String> {
+    let mut choices = candidates.to_vec();
+    choices.push(CANCEL_CHOICE.to_string());
+    choices
+}
+
+/// Map the selected menu index back to a candidate (None = Cancel)
+pub fn picked(candidates: &[String], index: usize) -> Option<String> {
+    candidates.get(index).cloned()
+}
+
+fn pick_candidate(candidates: &[String]) -> Result<Option<String>> {
+    if candidates.len() == 1 {
+        return Ok(candidates.first().cloned());
+    }
+
+    let question = Question::raw_select("candidate")
+        .message("Pick a command")
+        .choices(picker_choices(candidates))
+        .default(0)
+        .build();
+    let answer = requestty::prompt_one(question)?;
+    Ok(answer
+        .as_list_item()
+        .and_then(|item| picked(candidates, item.index)))
+}
+
+/// Usage summed over every response; None when no response reported any
+pub fn total_usage(responses: &[ProviderResponse]) -> Option<Usage> {
+    responses
+        .iter()
+        .filter_map(|r| r.usage)
+        .reduce(|a, b| Usage {
+            input_tokens: a.input_tokens + b.input_tokens,
+            output_tokens: a.output_tokens + b.output_tokens,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_distinct_candidates_drops_repeats_and_blanks() {
+        let candidates = distinct_candidates(strings(&[
+            "tar czf out.tgz dir",
+            "  zip -r out.zip dir\n",
+            "",
+            "tar czf out.tgz dir",
+        ]));
+        assert_eq!(
+            candidates,
+            strings(&["tar czf out.tgz dir", "zip -r out.zip dir"])
+        );
+    }
+
+    #[test]
+    fn test_picker_choices_and_selection() {
+        let candidates = strings(&["tar czf a.tgz a", "zip -r a.zip a"]);
+        let choices = picker_choices(&candidates);
+        assert_eq!(choices.len(), 3);
+        assert_eq!(choices[2], "Cancel");
+
+        assert_eq!(picked(&candidates, 1).as_deref(), Some("zip -r a.zip a"));
+        assert_eq!(picked(&candidates, 2), None, "Cancel runs nothing");
+    }
+
+    #[test]
+    fn test_numbered_list() {
+        let candidates = strings(&["first", "second"]);
+        assert_eq!(numbered_list(&candidates), "1. first\n2. second");
+    }
+
+    #[test]
+    fn test_total_usage() {
+        let response = |input, output| ProviderResponse {
+            usage: Some(Usage {
+                input_tokens: input,
+                output_tokens: output,
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            total_usage(&[
+                response(10, 5),
+                ProviderResponse::default(),
+                response(10, 7)
+            ]),
+            Some(Usage {
+                input_tokens: 20,
+                output_tokens: 12
+            })
+        );
+        assert_eq!(total_usage(&[ProviderResponse::default()]), None);
+    }
+}
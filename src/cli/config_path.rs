@@ -0,0 +1,71 @@
+//! Which config files are in effect (`ask --dump-config-path`)
+
+use crate::config::loader::ConfigSource;
+use crate::config::Config;
+use colored::Colorize;
+
+/// One line per location, highest precedence first: ✓ merged, ! present but left out, - missing
+pub fn format_sources(sources: &[ConfigSource]) -> String {
+    let width = sources.iter().map(|s| s.kind.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for source in sources {
+        let (mark, detail) = match (source.contributes, source.exists, &source.note) {
+            (true, _, _) => ("✓".green(), String::new()),
+            (false, _, Some(note)) => ("!".yellow(), format!(" - {}", note)),
+            (false, true, None) => ("!".yellow(), String::new()),
+            (false, false, None) => ("-".bright_black(), " - not found".to_string()),
+        };
+        out.push_str(&format!(
+            "{} {:width$}  {}{}\n",
+            mark,
+            source.kind,
+            source.path.display(),
+            detail.bright_black(),
+        ));
+    }
+    out
+}
+
+pub fn print_config_paths() {
+    let sources = Config::config_sources();
+    println!("{}", "Config files, highest precedence first:".cyan());
+    print!("{}", format_sources(&sources));
+    if !sources.iter().any(|s| s.contributes) {
+        println!(
+            "{}",
+            "No config file is in use; defaults apply. Run 'ask init' to create one."
+                .bright_black()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn source(kind: &'static str, exists: bool, contributes: bool) -> ConfigSource {
+        ConfigSource {
+            kind,
+            path: PathBuf::from(format!("/cfg/{}.toml", kind)),
+            exists,
+            contributes,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_format_sources() {
+        let mut broken = source("home", true, false);
+        broken.note = Some("ignored: expected `=`".to_string());
+        let out = format_sources(&[
+            source("project", true, true),
+            broken,
+            source("xdg", false, false),
+        ]);
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].contains('✓') && lines[0].contains("project  /cfg/project.toml"));
+        assert!(lines[1].contains('!') && lines[1].contains("ignored: expected `=`"));
+        assert!(lines[2].contains("xdg      /cfg/xdg.toml") && lines[2].contains("not found"));
+    }
+}
@@ -0,0 +1,121 @@
+//! Cost estimate from reported token usage (`ask --cost`)
+
+use crate::config::{Config, ModelPrice, DEFAULT_PRICING};
+use crate::providers::Usage;
+use colored::Colorize;
+use std::collections::HashMap;
+
+/// Price for a model: config `[pricing]` first, then the built-in table
+pub fn price_for(model: &str, pricing: &HashMap<String, ModelPrice>) -> Option<ModelPrice> {
+    pricing.get(model).copied().or_else(|| {
+        DEFAULT_PRICING
+            .iter()
+            .find(|(name, _, _)| *name == model)
+            .map(|(_, input, output)| ModelPrice {
+                input_per_mtok: *input,
+                output_per_mtok: *output,
+            })
+    })
+}
+
+/// Estimated USD cost of a request, or None when the model has no known price
+pub fn estimate_cost(
+    model: &str,
+    usage: &Usage,
+    pricing: &HashMap<String, ModelPrice>,
+) -> Option<f64> {
+    let price = price_for(model, pricing)?;
+    let input = usage.input_tokens as f64 * price.input_per_mtok;
+    let output = usage.output_tokens as f64 * price.output_per_mtok;
+    Some((input + output) / 1_000_000.0)
+}
+
+pub fn format_cost(cost: f64) -> String {
+    format!("~${:.4}", cost)
+}
+
+/// Print the estimate (or why there is none) on stderr, keeping stdout clean for pipes
+pub fn print_cost(config: &Config, model: &str, usage: Option<Usage>) {
+    let line = match usage {
+        None => "cost: unknown (provider reported no usage)".to_string(),
+        Some(usage) => match estimate_cost(model, &usage, &config.pricing) {
+            Some(cost) => format!(
+                "{} ({} in / {} out tokens)",
+                format_cost(cost),
+                usage.input_tokens,
+                usage.output_tokens
+            ),
+            None => format!(
+                "cost: unknown (no price for '{}', add it under [pricing])",
+                model
+            ),
+        },
+    };
+    eprintln!("{}", line.bright_black());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input: u64, output: u64) -> Usage {
+        Usage {
+            input_tokens: input,
+            output_tokens: output,
+        }
+    }
+
+    #[test]
+    fn test_estimate_cost_math() {
+        let mut pricing = HashMap::new();
+        pricing.insert(
+            "test-model".to_string(),
+            ModelPrice {
+                input_per_mtok: 2.0,
+                output_per_mtok: 8.0,
+            },
+        );
+
+        // 500 * 2 / 1M + 250 * 8 / 1M = 0.001 + 0.002
+        let cost = estimate_cost("test-model", &usage(500, 250), &pricing).unwrap();
+        assert!((cost - 0.003).abs() < 1e-12);
+        assert_eq!(format_cost(cost), "~$0.0030");
+
+        let cost = estimate_cost("test-model", &usage(0, 0), &pricing).unwrap();
+        assert_eq!(cost, 0.0);
+    }
+
+    #[test]
+    fn test_builtin_price_used_without_config() {
+        let pricing = HashMap::new();
+        let price = price_for("gpt-4o-mini", &pricing).unwrap();
+        assert_eq!(price.input_per_mtok, 0.15);
+        assert_eq!(price.output_per_mtok, 0.60);
+
+        let cost = estimate_cost("gpt-4o-mini", &usage(1_000_000, 1_000_000), &pricing).unwrap();
+        assert!((cost - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_config_price_overrides_builtin() {
+        let mut pricing = HashMap::new();
+        pricing.insert(
+            "gpt-4o-mini".to_string(),
+            ModelPrice {
+                input_per_mtok: 1.0,
+                output_per_mtok: 1.0,
+            },
+        );
+
+        let cost = estimate_cost("gpt-4o-mini", &usage(1_000_000, 1_000_000), &pricing).unwrap();
+        assert!((cost - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_unknown_model_has_no_estimate() {
+        assert_eq!(
+            estimate_cost("mystery-model", &usage(10, 10), &HashMap::new()),
+            None
+        );
+    }
+}
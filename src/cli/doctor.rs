@@ -0,0 +1,405 @@
+//! Environment diagnostics (`ask doctor`)
+
+use super::Args;
+use crate::config::Config;
+use crate::executor::{detect_injection_method, InjectionMethod};
+use crate::providers::{
+    create_provider, is_reasoning_model, supports_none_reasoning, Message, ProviderOptions,
+};
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// One line of the doctor checklist
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    /// How to fix it, shown for warnings and failures
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Run every check, print the checklist and fail if anything is broken
+pub async fn run_doctor(args: &Args) -> Result<()> {
+    let mut checks = Vec::new();
+
+    let files = Config::config_files();
+    if files.is_empty() {
+        checks.push(Check::warn(
+            "config",
+            "no config file found, using defaults",
+            "Run 'ask init' to create one",
+        ));
+    }
+    for file in &files {
+        checks.push(check_config_file(file));
+    }
+
+    match Config::load() {
+        Ok(config) => {
+            crate::http::set_proxy_override(config.http.proxy.clone());
+
+            let mut names: Vec<&String> = config.profiles.keys().collect();
+            names.sort();
+            for name in names {
+                checks.push(check_profile_key(&config, name));
+            }
+
+            let active = config.with_cli_overrides(args);
+            eprint!("{}", "Pinging the active provider...".bright_black());
+            let ping = ping_provider(&active).await;
+            eprintln!();
+            checks.push(ping);
+            checks.extend(check_reasoning(&active));
+            checks.push(check_storage_writable(&active.context_storage_path()));
+        }
+        Err(e) => checks.push(Check::fail(
+            "config",
+            e.to_string(),
+            "Fix the file reported above or run 'ask init'",
+        )),
+    }
+
+    checks.push(check_injection(detect_injection_method()));
+
+    println!("{}", "ask doctor".cyan().bold());
+    println!();
+    print!("{}", format_checks(&checks));
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failed > 0 {
+        anyhow::bail!("{} check(s) failed", failed);
+    }
+    println!();
+    println!("{}", "Everything looks good.".green());
+    Ok(())
+}
+
+/// A config file must be readable and parse as a config
+pub fn check_config_file(path: &Path) -> Check {
+    let name = format!("config {}", path.display());
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            return Check::fail(
+                name,
+                format!("unreadable: {}", e),
+                "Check the file exists and its permissions",
+            )
+        }
+    };
+
+    match toml::from_str::<Config>(&content) {
+        Ok(_) => Check::pass(name, "valid"),
+        Err(e) => Check::fail(
+            name,
+            format!("invalid: {}", first_line(&e.to_string())),
+            "Fix the TOML syntax, or compare with 'ask --make-config'",
+        ),
+    }
+}
+
+/// A profile is usable when its provider key resolves (Ollama needs none)
+pub fn check_profile_key(config: &Config, profile: &str) -> Check {
+    let args = Args {
+        profile: Some(profile.to_string()),
+        ..Default::default()
+    };
+    let resolved = config.clone().with_cli_overrides(&args);
+    let provider = resolved.active_provider().to_string();
+    let name = format!("profile {}", profile);
+
+    if provider == "ollama" {
+        return Check::pass(name, "ollama (no key needed)");
+    }
+    match resolved.api_key() {
+        Some(key) if !key.trim().is_empty() => Check::pass(name, format!("{} key found", provider)),
+        _ => Check::fail(
+            name,
+            format!("no API key for {}", provider),
+            format!(
+                "Set api_key in [profiles.{}] or export ASK_{}_API_KEY",
+                profile,
+                provider.to_uppercase()
+            ),
+        ),
+    }
+}
+
+/// Send a one-token completion to the active provider to prove connectivity and auth
+async fn ping_provider(config: &Config) -> Check {
+    let name = format!(
+        "ping {}/{}",
+        config.active_provider(),
+        config.active_model()
+    );
+
+    let provider = match create_provider(config) {
+        Ok(provider) => provider,
+        Err(e) => return Check::fail(name, first_line(&e.to_string()), "Run 'ask init'"),
+    };
+
+    let messages = vec![Message {
+        role: "user".to_string(),
+        content: "ping".to_string(),
+        ..Default::default()
+    }];
+    let options = ProviderOptions {
+        max_tokens: Some(1),
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    match provider.complete_with_options(&messages, &options).await {
+        Ok(_) => Check::pass(name, format!("ok in {} ms", start.elapsed().as_millis())),
+        Err(e) => Check::fail(
+            name,
+            first_line(&e.to_string()),
+            "Check the API key, base_url, model name and network/proxy settings",
+        ),
+    }
+}
+
+/// OpenAI reasoning models: whether turning reasoning off really turns it off
+pub fn check_reasoning(config: &Config) -> Option<Check> {
+    if !matches!(
+        config.active_provider(),
+        "openai" | "openai_compatible" | "azure"
+    ) {
+        return None;
+    }
+    let model = config.active_model();
+    if !is_reasoning_model(model) {
+        return None;
+    }
+
+    let name = "reasoning";
+    let (enabled, _) = config.get_thinking_config();
+    if supports_none_reasoning(model) {
+        Some(Check::pass(
+            name,
+            format!("{} accepts reasoning 'none'", model),
+        ))
+    } else if enabled {
+        Some(Check::pass(
+            name,
+            format!("{} reasons at the configured effort", model),
+        ))
+    } else {
+        Some(Check::warn(
+            name,
+            format!(
+                "reasoning 'none' not supported by {}, 'minimal' is sent",
+                model
+            ),
+            "Use gpt-5.1 or newer to turn reasoning fully off",
+        ))
+    }
+}
+
+/// Context storage must accept new files
+pub fn check_storage_writable(path: &Path) -> Check {
+    let name = "context storage";
+    let probe = path.join(".ask-doctor-probe");
+    let result = std::fs::create_dir_all(path)
+        .and_then(|_| std::fs::write(&probe, b"ok"))
+        .and_then(|_| std::fs::remove_file(&probe));
+
+    match result {
+        Ok(()) => Check::pass(name, format!("{} is writable", path.display())),
+        Err(e) => Check::fail(
+            name,
+            format!("{} is not writable: {}", path.display(), e),
+            "Fix the directory permissions or set context.storage_path",
+        ),
+    }
+}
+
+/// Report how suggested commands will reach the prompt
+pub fn check_injection(method: InjectionMethod) -> Check {
+    let name = "command injection";
+    match method {
+        InjectionMethod::GuiPaste => Check::pass(name, "clipboard paste (GUI session)"),
+        InjectionMethod::TmuxSendKeys => Check::pass(name, "tmux send-keys"),
+        InjectionMethod::ScreenStuff => Check::pass(name, "screen stuff"),
+        InjectionMethod::Fallback => Check::warn(
+            name,
+            "no GUI or multiplexer, commands are shown for manual copy",
+            "Run inside tmux/screen or a desktop session, or use --copy / -y",
+        ),
+    }
+}
+
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or("").to_string()
+}
+
+/// Render the checklist with ✓/!/✗ marks and indented hints
+pub fn format_checks(checks: &[Check]) -> String {
+    let mut out = String::new();
+    for check in checks {
+        let mark = match check.status {
+            Status::Pass => "✓".green(),
+            Status::Warn => "!".yellow(),
+            Status::Fail => "✗".red(),
+        };
+        out.push_str(&format!(
+            "{} {} {}\n",
+            mark,
+            check.name,
+            format!("- {}", check.detail).bright_black()
+        ));
+        if let Some(ref hint) = check.hint {
+            out.push_str(&format!("    {}\n", format!("→ {}", hint).bright_black()));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_config_file_valid_and_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let good = dir.path().join("good.toml");
+        std::fs::write(&good, "[behavior]\nauto_execute = true\n").unwrap();
+        assert_eq!(check_config_file(&good).status, Status::Pass);
+
+        let bad = dir.path().join("bad.toml");
+        std::fs::write(&bad, "[behavior\nauto_execute = ").unwrap();
+        let check = check_config_file(&bad);
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.detail.starts_with("invalid"));
+
+        let missing = dir.path().join("missing.toml");
+        assert_eq!(check_config_file(&missing).status, Status::Fail);
+    }
+
+    #[test]
+    fn test_check_profile_key() {
+        let config = Config::from_toml(
+            r#"
+[profiles.keyed]
+provider = "anthropic"
+api_key = "sk-test"
+
+[profiles.local]
+provider = "ollama"
+
+[profiles.keyless]
+provider = "azure"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(check_profile_key(&config, "keyed").status, Status::Pass);
+        assert_eq!(check_profile_key(&config, "local").status, Status::Pass);
+
+        let check = check_profile_key(&config, "keyless");
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.hint.unwrap().contains("ASK_AZURE_API_KEY"));
+    }
+
+    #[test]
+    fn test_check_reasoning() {
+        let config = |toml: &str| {
+            let args = Args {
+                profile: Some("p".to_string()),
+                ..Default::default()
+            };
+            Config::from_toml(toml).unwrap().with_cli_overrides(&args)
+        };
+
+        let old = config("[profiles.p]\nprovider = \"openai\"\nmodel = \"gpt-5-mini\"\n");
+        let check = check_reasoning(&old).unwrap();
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.detail.contains("not supported by gpt-5-mini"));
+
+        let new = config("[profiles.p]\nprovider = \"openai\"\nmodel = \"gpt-5.1\"\n");
+        assert_eq!(check_reasoning(&new).unwrap().status, Status::Pass);
+
+        let plain = config("[profiles.p]\nprovider = \"openai\"\nmodel = \"gpt-4o\"\n");
+        assert!(check_reasoning(&plain).is_none());
+
+        let gemini = config("[profiles.p]\nprovider = \"gemini\"\n");
+        assert!(check_reasoning(&gemini).is_none());
+    }
+
+    #[test]
+    fn test_check_storage_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a").join("b");
+        assert_eq!(check_storage_writable(&nested).status, Status::Pass);
+        assert!(!nested.join(".ask-doctor-probe").exists());
+
+        let file = dir.path().join("file");
+        std::fs::write(&file, "x").unwrap();
+        assert_eq!(check_storage_writable(&file).status, Status::Fail);
+    }
+
+    #[test]
+    fn test_check_injection() {
+        assert_eq!(
+            check_injection(InjectionMethod::TmuxSendKeys).status,
+            Status::Pass
+        );
+        let check = check_injection(InjectionMethod::Fallback);
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.hint.is_some());
+    }
+
+    #[test]
+    fn test_format_checks_marks_and_hints() {
+        let out = format_checks(&[
+            Check::pass("a", "fine"),
+            Check::fail("b", "broken", "fix it"),
+        ]);
+
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains('✓') && lines[0].contains("fine"));
+        assert!(lines[1].contains('✗') && lines[1].contains("broken"));
+        assert!(lines[2].starts_with("    ") && lines[2].contains("→ fix it"));
+    }
+}
@@ -0,0 +1,174 @@
+//! Correct the answer in $EDITOR before it goes into the context (`--edit-answer`)
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::Command;
+
+/// `$VISUAL`, then `$EDITOR`, then the platform's usual editor
+fn editor() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        })
+}
+
+/// The answer to store. Without a terminal, or when the editor fails or
+/// leaves the file empty, the answer is stored as received.
+pub fn answer_to_save(response: &str) -> String {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return response.to_string();
+    }
+
+    match edit_with(&editor(), response) {
+        Ok(edited) if !edited.trim().is_empty() => edited,
+        Ok(_) => {
+            eprintln!(
+                "{}",
+                crate::output::theme().hint("Edited answer is empty; saving the original")
+            );
+            response.to_string()
+        }
+        Err(e) => {
+            eprintln!(
+                "{} {}",
+                "Could not edit the answer:".yellow(),
+                format!("{:#}; saving the original", e).bright_black()
+            );
+            response.to_string()
+        }
+    }
+}
+
+/// Open `text` in `editor` and return the file as saved. The editor is a shell
+/// command, as with git, so `code --wait` or a quoted path works. An unchanged
+/// file gives `text` back.
+fn edit_with(editor: &str, text: &str) -> Result<String> {
+    if editor.trim().is_empty() {
+        bail!("no editor set");
+    }
+
+    // A fresh file only we can read, removed when `path` is dropped
+    let mut file = tempfile::Builder::new()
+        .prefix("ask-answer-")
+        .suffix(".md")
+        .tempfile()
+        .context("Could not create a temporary file")?;
+    std::io::Write::write_all(&mut file, format!("{}\n", text.trim_end()).as_bytes())
+        .with_context(|| format!("Could not write {}", file.path().display()))?;
+    let path = file.into_temp_path();
+
+    let status = editor_command(editor, &path)
+        .status()
+        .with_context(|| format!("Could not start '{}'", editor))?;
+    if !status.success() {
+        bail!("'{}' exited with {}", editor, status);
+    }
+    let edited = std::fs::read_to_string(&path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+
+    if edited.trim_end() == text.trim_end() {
+        Ok(text.to_string())
+    } else {
+        Ok(edited.trim_end().to_string())
+    }
+}
+
+/// `sh -c '$EDITOR "$@"'` with the file as the argument, so the path is never re-parsed
+fn editor_command(editor: &str, path: &Path) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C")
+            .arg(format!("{} \"{}\"", editor, path.display()));
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(format!("{} \"$@\"", editor))
+            .arg(editor)
+            .arg(path);
+        cmd
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// A stand-in editor: `sh script` with the file as `$1`
+    fn fake_editor(dir: &std::path::Path, name: &str, body: &str) -> String {
+        let script = dir.join(name);
+        std::fs::write(&script, body).unwrap();
+        format!("sh {}", script.display())
+    }
+
+    #[test]
+    fn test_edited_answer_replaces_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let seen = dir.path().join("seen.md");
+        let editor = fake_editor(
+            dir.path(),
+            "edit.sh",
+            &format!(
+                "cp \"$1\" '{}'\nprintf 'Use ss -tlnp instead.\\n' > \"$1\"",
+                seen.display()
+            ),
+        );
+
+        let edited = edit_with(&editor, "Use netstat -tlnp.").unwrap();
+        assert_eq!(edited, "Use ss -tlnp instead.");
+        assert_eq!(
+            std::fs::read_to_string(seen).unwrap(),
+            "Use netstat -tlnp.\n"
+        );
+    }
+
+    #[test]
+    fn test_unchanged_file_keeps_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let editor = fake_editor(dir.path(), "keep.sh", "true");
+        assert_eq!(edit_with(&editor, "As is.\n\n").unwrap(), "As is.\n\n");
+    }
+
+    #[test]
+    fn test_editor_arguments_and_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let editor = fake_editor(
+            dir.path(),
+            "wait.sh",
+            "[ \"$1\" = --wait ] && printf 'waited' > \"$2\"",
+        );
+        assert_eq!(
+            edit_with(&format!("{} --wait", editor), "x").unwrap(),
+            "waited"
+        );
+
+        let failing = fake_editor(dir.path(), "fail.sh", "exit 3");
+        assert!(edit_with(&failing, "x").is_err());
+        assert!(edit_with("   ", "x").is_err());
+    }
+
+    #[test]
+    fn test_editor_is_a_shell_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let spaced = dir.path().join("my editors");
+        std::fs::create_dir(&spaced).unwrap();
+        let script = spaced.join("edit.sh");
+        std::fs::write(
+            &script,
+            "case \"$1\" in *.md) printf 'markdown' > \"$1\";; esac",
+        )
+        .unwrap();
+
+        let editor = format!("sh '{}'", script.display());
+        assert_eq!(edit_with(&editor, "x").unwrap(), "markdown");
+    }
+}
@@ -0,0 +1,177 @@
+//! Exit codes scripts can branch on (`ask ... || case $? in ...`)
+
+use std::fmt;
+
+/// Why a run failed; each kind maps to one process exit code
+#[derive(Debug)]
+pub enum AskError {
+    /// Anything not covered below (exit 1)
+    Other(anyhow::Error),
+    /// Invalid flags or flag combinations (exit 2)
+    Usage(anyhow::Error),
+    /// Missing or rejected API key (exit 3)
+    Auth(anyhow::Error),
+    /// Rate limited, or retryable errors left after every fallback (exit 4)
+    RateLimited(anyhow::Error),
+    /// The suggested command ran and exited with this non-zero code (exit 5)
+    CommandFailed(i32),
+    /// Ctrl-C at a prompt (exit 130)
+    Interrupted,
+}
+
+impl AskError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AskError::Other(_) => 1,
+            AskError::Usage(_) => 2,
+            AskError::Auth(_) => 3,
+            AskError::RateLimited(_) => 4,
+            AskError::CommandFailed(_) => 5,
+            AskError::Interrupted => 130,
+        }
+    }
+
+    /// What to print on stderr; None when the command or terminal already said it
+    pub fn report(&self) -> Option<String> {
+        match self {
+            AskError::Other(err)
+            | AskError::Usage(err)
+            | AskError::Auth(err)
+            | AskError::RateLimited(err) => Some(format!("Error: {:?}", err)),
+            AskError::CommandFailed(_) | AskError::Interrupted => None,
+        }
+    }
+
+    /// Sort an error from the run into its kind: typed errors keep theirs,
+    /// provider errors are recognized by their message
+    pub fn classify(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<AskError>() {
+            Ok(typed) => return typed,
+            Err(err) => err,
+        };
+
+        if matches!(
+            err.downcast_ref::<requestty::ErrorKind>(),
+            Some(requestty::ErrorKind::Interrupted)
+        ) {
+            return AskError::Interrupted;
+        }
+
+        if is_auth_error(&err) {
+            AskError::Auth(err)
+        } else if super::is_retryable_error(&err) || is_quota_error(&err) {
+            AskError::RateLimited(err)
+        } else {
+            AskError::Other(err)
+        }
+    }
+}
+
+impl fmt::Display for AskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AskError::Other(err)
+            | AskError::Usage(err)
+            | AskError::Auth(err)
+            | AskError::RateLimited(err) => write!(f, "{}", err),
+            AskError::CommandFailed(code) => write!(f, "command exited with code {}", code),
+            AskError::Interrupted => write!(f, "interrupted"),
+        }
+    }
+}
+
+impl std::error::Error for AskError {}
+
+impl From<anyhow::Error> for AskError {
+    fn from(err: anyhow::Error) -> Self {
+        AskError::classify(err)
+    }
+}
+
+/// A bad flag combination, reported with exit code 2
+pub fn usage(message: impl fmt::Display) -> anyhow::Error {
+    AskError::Usage(anyhow::anyhow!("{}", message)).into()
+}
+
+/// The executed command failed, reported with exit code 5
+pub fn command_failed(code: i32) -> anyhow::Error {
+    AskError::CommandFailed(code).into()
+}
+
+fn is_auth_error(err: &anyhow::Error) -> bool {
+    let msg = err.to_string().to_lowercase();
+    msg.contains("(401")
+        || msg.contains("(403")
+        || msg.contains("unauthorized")
+        || msg.contains("api key")
+        || msg.contains("api_key")
+        || msg.contains("x-api-key")
+        || msg.contains("authentication")
+}
+
+fn is_quota_error(err: &anyhow::Error) -> bool {
+    let msg = err.to_string().to_lowercase();
+    msg.contains("quota") || msg.contains("resource_exhausted")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code_of(err: anyhow::Error) -> i32 {
+        AskError::classify(err).exit_code()
+    }
+
+    #[test]
+    fn test_typed_errors_keep_their_code() {
+        assert_eq!(code_of(usage("--template and --prompt-file; use one")), 2);
+        assert_eq!(code_of(command_failed(42)), 5);
+        assert_eq!(code_of(AskError::Interrupted.into()), 130);
+        assert_eq!(
+            code_of(requestty::ErrorKind::Interrupted.into()),
+            130,
+            "Ctrl-C inside a prompt"
+        );
+    }
+
+    #[test]
+    fn test_provider_errors_by_message() {
+        assert_eq!(
+            code_of(anyhow::anyhow!(
+                "OpenAI API error (401 Unauthorized): invalid key"
+            )),
+            3
+        );
+        assert_eq!(
+            code_of(anyhow::anyhow!(
+                "No API key found for provider 'gemini'. Run 'ask init' to configure."
+            )),
+            3
+        );
+        assert_eq!(
+            code_of(anyhow::anyhow!(
+                "Anthropic API error (429 Too Many Requests): slow down"
+            )),
+            4
+        );
+        assert_eq!(
+            code_of(anyhow::anyhow!(
+                "Gemini API error (503 Service Unavailable): overloaded"
+            )),
+            4
+        );
+        assert_eq!(
+            code_of(anyhow::anyhow!("Gemini error: Quota exceeded for metric")),
+            4
+        );
+        assert_eq!(code_of(anyhow::anyhow!("Failed to read config")), 1);
+    }
+
+    #[test]
+    fn test_report_skips_errors_already_shown() {
+        assert!(AskError::CommandFailed(1).report().is_none());
+        assert!(AskError::Interrupted.report().is_none());
+        let report = AskError::classify(usage("bad flags")).report().unwrap();
+        assert!(report.starts_with("Error: bad flags"));
+    }
+}
@@ -0,0 +1,201 @@
+//! Check one provider's connectivity and key, ignoring the config (`--health NAME`)
+
+use super::Args;
+use crate::config::Config;
+use crate::providers::{create_provider, Message, ProviderOptions};
+use anyhow::{bail, Result};
+use colored::Colorize;
+use std::time::Instant;
+
+/// What a health check found
+#[derive(Debug, Clone, PartialEq)]
+pub enum Health {
+    Ok {
+        ms: u128,
+    },
+    /// The provider answered, but rejected the key (or there was none)
+    Auth {
+        ms: u128,
+        message: String,
+    },
+    /// Network, base URL, model or server trouble
+    Error {
+        ms: u128,
+        message: String,
+    },
+}
+
+/// A config with only the flags applied: `-k`, `-m` and `--base-url`, falling back to
+/// `ASK_<PROVIDER>_*` variables and the provider defaults. Config files are not read.
+pub fn health_config(provider: &str, args: &Args) -> Config {
+    let overrides = Args {
+        provider: Some(provider.to_string()),
+        model: args.model.clone(),
+        api_key: args.api_key.clone(),
+        ..Default::default()
+    };
+    let mut config = Config::default().with_cli_overrides(&overrides);
+    if args.base_url.is_some() {
+        config.active.base_url = args.base_url.clone();
+    }
+    config
+}
+
+pub async fn run_health(provider: &str, args: &Args) -> Result<()> {
+    let config = health_config(provider, args);
+    let target = format!("{}/{}", config.active_provider(), config.active_model());
+
+    let health = check(&config).await;
+    println!("{}", format_health(&target, &health));
+    match health {
+        Health::Ok { .. } => Ok(()),
+        _ => bail!("{} is not healthy", target),
+    }
+}
+
+/// One-token completion, timed
+async fn check(config: &Config) -> Health {
+    let start = Instant::now();
+    let provider = match create_provider(config) {
+        Ok(provider) => provider,
+        Err(e) => {
+            return classify(Err(first_line(&e.to_string())), start.elapsed().as_millis());
+        }
+    };
+
+    let messages = vec![Message {
+        role: "user".to_string(),
+        content: "ping".to_string(),
+        ..Default::default()
+    }];
+    let options = ProviderOptions {
+        max_tokens: Some(1),
+        ..Default::default()
+    };
+    let result = provider
+        .complete_with_options(&messages, &options)
+        .await
+        .map(|_| ())
+        .map_err(|e| first_line(&e.to_string()));
+    classify(result, start.elapsed().as_millis())
+}
+
+/// Sort a failure into auth (401/403 or no key) or anything else
+fn classify(result: Result<(), String>, ms: u128) -> Health {
+    match result {
+        Ok(()) => Health::Ok { ms },
+        Err(message)
+            if message.contains("(401")
+                || message.contains("(403")
+                || message.starts_with("No API key") =>
+        {
+            Health::Auth { ms, message }
+        }
+        Err(message) => Health::Error { ms, message },
+    }
+}
+
+fn format_health(target: &str, health: &Health) -> String {
+    match health {
+        Health::Ok { ms } => format!("{} {} - ok in {} ms", "✓".green(), target, ms),
+        Health::Auth { ms, message } => format!(
+            "{} {} - auth failed in {} ms\n    {}",
+            "✗".red(),
+            target,
+            ms,
+            message.bright_black()
+        ),
+        Health::Error { ms, message } => format!(
+            "{} {} - error after {} ms\n    {}",
+            "✗".red(),
+            target,
+            ms,
+            message.bright_black()
+        ),
+    }
+}
+
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or("").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_config_from_flags() {
+        let args = Args {
+            api_key: Some("sk-flag".to_string()),
+            model: Some("gpt-test".to_string()),
+            base_url: Some("http://127.0.0.1:9/v1".to_string()),
+            ..Default::default()
+        };
+        let config = health_config("openai", &args);
+
+        assert_eq!(config.active_provider(), "openai");
+        assert_eq!(config.active_model(), "gpt-test");
+        assert_eq!(config.api_key().as_deref(), Some("sk-flag"));
+        assert_eq!(config.base_url().as_deref(), Some("http://127.0.0.1:9/v1"));
+        assert!(config.profiles.is_empty(), "config files are not read");
+        assert!(create_provider(&config).is_ok());
+    }
+
+    #[test]
+    fn test_health_config_defaults_model() {
+        let config = health_config("ollama", &Args::default());
+        assert_eq!(config.active_provider(), "ollama");
+        assert!(!config.active_model().is_empty());
+        assert!(create_provider(&config).is_ok(), "ollama needs no key");
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(Ok(()), 12), Health::Ok { ms: 12 });
+        assert!(matches!(
+            classify(
+                Err("OpenAI API error (401 Unauthorized): Incorrect API key".into()),
+                5
+            ),
+            Health::Auth { ms: 5, .. }
+        ));
+        assert!(matches!(
+            classify(
+                Err("No API key found for provider 'gemini'. Run 'ask init' to configure.".into()),
+                0
+            ),
+            Health::Auth { .. }
+        ));
+        assert!(matches!(
+            classify(Err("error sending request".into()), 30_000),
+            Health::Error { ms: 30_000, .. }
+        ));
+    }
+
+    #[test]
+    fn test_format_health() {
+        let ok = format_health("openai/gpt-4o", &Health::Ok { ms: 312 });
+        assert!(ok.contains('✓') && ok.contains("openai/gpt-4o - ok in 312 ms"));
+
+        let auth = format_health(
+            "anthropic/claude",
+            &Health::Auth {
+                ms: 90,
+                message: "Anthropic API error (401 Unauthorized): invalid x-api-key".into(),
+            },
+        );
+        let lines: Vec<&str> = auth.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains('✗') && lines[0].contains("auth failed in 90 ms"));
+        assert!(lines[1].starts_with("    ") && lines[1].contains("invalid x-api-key"));
+
+        let error = format_health(
+            "gemini/flash",
+            &Health::Error {
+                ms: 40,
+                message: "timeout".into(),
+            },
+        );
+        assert!(error.contains("gemini/flash - error after 40 ms"));
+    }
+}
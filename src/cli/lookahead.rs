@@ -0,0 +1,146 @@
+//! Hold back streamed answers that look like commands, so nothing has to be
+//! erased from the screen before the command is injected
+
+use super::{starts_like_command, too_long_for_command};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    /// Print chunks as they arrive
+    Prose,
+    /// Keep chunks back until the answer is complete
+    Command,
+}
+
+/// Buffers streamed chunks until the first word shows whether the answer is a
+/// command. Prose is then printed live; a command is held for injection.
+#[derive(Debug)]
+pub struct CommandLookahead {
+    buffer: String,
+    decision: Option<Decision>,
+    max_chars: usize,
+}
+
+impl CommandLookahead {
+    /// `command_mode` (`-x`) holds from the start, so a lead-in like "Sure!" that
+    /// gets stripped later is never shown
+    pub fn new(max_chars: usize, command_mode: bool) -> Self {
+        Self {
+            buffer: String::new(),
+            decision: command_mode.then_some(Decision::Command),
+            max_chars,
+        }
+    }
+
+    /// Take a chunk; returns the text to print now, if any
+    pub fn push(&mut self, chunk: &str) -> Option<String> {
+        if self.decision == Some(Decision::Prose) {
+            return Some(chunk.to_string());
+        }
+
+        self.buffer.push_str(chunk);
+        if self.decision.is_none() {
+            self.decision = decide(&self.buffer);
+        }
+        // A held answer that grows into prose is released
+        if self.decision == Some(Decision::Command)
+            && too_long_for_command(self.buffer.trim(), self.max_chars)
+        {
+            self.decision = Some(Decision::Prose);
+        }
+
+        if self.decision == Some(Decision::Prose) {
+            Some(std::mem::take(&mut self.buffer))
+        } else {
+            None
+        }
+    }
+
+    /// The text still held back when the stream ended
+    pub fn finish(self) -> String {
+        self.buffer
+    }
+}
+
+/// Decide once the first word is complete; a leading code fence is skipped
+fn decide(buffer: &str) -> Option<Decision> {
+    let mut text = buffer.trim_start();
+    if text.starts_with("```") {
+        let (_, rest) = text.split_once('\n')?;
+        text = rest.trim_start();
+    }
+    if !text.contains(char::is_whitespace) {
+        return None;
+    }
+
+    Some(if starts_like_command(text) {
+        Decision::Command
+    } else {
+        Decision::Prose
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What gets printed while streaming, and what is still held at the end
+    fn run(chunks: &[&str], command_mode: bool) -> (String, String) {
+        let mut lookahead = CommandLookahead::new(500, command_mode);
+        let printed: String = chunks.iter().filter_map(|c| lookahead.push(c)).collect();
+        (printed, lookahead.finish())
+    }
+
+    #[test]
+    fn test_prose_streams_after_first_word() {
+        let mut lookahead = CommandLookahead::new(500, false);
+        assert_eq!(lookahead.push("The"), None, "first word still open");
+        assert_eq!(lookahead.push(" answer"), Some("The answer".to_string()));
+        assert_eq!(lookahead.push(" is 42."), Some(" is 42.".to_string()));
+        assert_eq!(lookahead.finish(), "");
+    }
+
+    #[test]
+    fn test_command_is_held() {
+        let (printed, held) = run(&["ls", " -la", " /tmp"], false);
+        assert_eq!(printed, "");
+        assert_eq!(held, "ls -la /tmp");
+    }
+
+    #[test]
+    fn test_single_word_stays_held_until_finish() {
+        let (printed, held) = run(&["pwd"], false);
+        assert_eq!(printed, "");
+        assert_eq!(held, "pwd");
+    }
+
+    #[test]
+    fn test_code_fence_is_skipped_when_deciding() {
+        let (printed, held) = run(&["```", "bash\n", "git status", "\n```"], false);
+        assert_eq!(printed, "");
+        assert_eq!(held, "```bash\ngit status\n```");
+
+        let (printed, held) = run(&["```\n", "Here is ", "an example"], false);
+        assert_eq!(printed, "```\nHere is an example");
+        assert_eq!(held, "");
+    }
+
+    #[test]
+    fn test_long_prose_after_command_word_is_released() {
+        let mut lookahead = CommandLookahead::new(40, false);
+        assert_eq!(lookahead.push("git is a version control system."), None);
+        let released = lookahead.push(" It tracks changes to files over time.");
+        assert_eq!(
+            released.as_deref(),
+            Some("git is a version control system. It tracks changes to files over time.")
+        );
+        assert_eq!(lookahead.push(" More"), Some(" More".to_string()));
+        assert_eq!(lookahead.finish(), "");
+    }
+
+    #[test]
+    fn test_command_mode_holds_lead_in() {
+        let (printed, held) = run(&["Sure! Here you go:\n\n", "ls -la"], true);
+        assert_eq!(printed, "");
+        assert_eq!(held, "Sure! Here you go:\n\nls -la");
+    }
+}
@@ -1,20 +1,50 @@
 //! CLI module - handles argument parsing and command execution
 
+mod apply;
+mod bench;
+mod candidates;
+mod config_path;
+mod cost;
+mod doctor;
+mod edit_answer;
+mod exit;
+mod health;
+mod lookahead;
+mod model_info;
+mod models;
+mod notify;
 mod parser;
-
+mod profile_env;
+mod rate_limit;
+mod retry;
+mod review;
+mod round_robin;
+mod stats;
+mod tee;
+mod templates;
+mod version;
+mod watch;
+
+pub use exit::AskError;
 pub use parser::*;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::Colorize;
 
-use crate::config::Config;
+use crate::config::{Config, InjectMode};
 use crate::context::ContextManager;
 use crate::executor::CommandExecutor;
 use crate::output::OutputFormatter;
 use crate::providers::{
-    build_unified_prompt, create_provider, expand_prompt_variables, flatten_command_if_safe,
-    load_custom_prompt, strip_code_fences, PromptContext, ProviderOptions,
+    append_prompt_fragment, build_unified_prompt, create_provider, expand_prompt_variables,
+    flatten_command_if_safe, load_custom_prompt, split_command_lines, strip_code_fences,
+    strip_preamble_for_command, PromptContext, ProviderOptions, ShellFamily,
 };
+use lookahead::CommandLookahead;
+use std::sync::OnceLock;
+
+/// Update notice held back from `--json` runs, reported in the envelope instead
+static JSON_UPDATE: OnceLock<crate::update::UpdateNotification> = OnceLock::new();
 
 /// Check if an error is retryable with a fallback profile
 fn is_retryable_error(err: &anyhow::Error) -> bool {
@@ -34,13 +64,21 @@ fn is_retryable_error(err: &anyhow::Error) -> bool {
         || msg.contains("service unavailable")
 }
 
-/// Main entry point for the CLI
-pub async fn run(update_notification: Option<crate::update::UpdateNotification>) -> Result<()> {
+/// Main entry point for the CLI; the error says which exit code to use
+pub async fn run(
+    update_notification: Option<crate::update::UpdateNotification>,
+) -> std::result::Result<(), AskError> {
+    run_args(update_notification)
+        .await
+        .map_err(AskError::classify)
+}
+
+async fn run_args(update_notification: Option<crate::update::UpdateNotification>) -> Result<()> {
     let args = Args::parse_flexible();
 
     // Show update notification (unless JSON or raw mode)
     if let Some(ref notification) = update_notification {
-        if !args.json && !args.raw {
+        if !args.json && !args.raw && !args.raw_stream && !args.json_stream {
             println!(
                 "{} {} {} {}",
                 "Updated:".green().bold(),
@@ -55,19 +93,39 @@ pub async fn run(update_notification: Option<crate::update::UpdateNotification>)
                 }
             }
             println!();
+        } else if args.json {
+            let _ = JSON_UPDATE.set(notification.clone());
         }
     }
 
     // Handle internal --inject-raw command first (used by background injection)
     if let Some(ref cmd) = args.inject_raw {
         std::thread::sleep(std::time::Duration::from_millis(150));
-        return crate::executor::inject_raw_only(cmd);
+        let restore_ms = Config::load()
+            .unwrap_or_default()
+            .behavior
+            .clipboard_restore_ms;
+        return crate::executor::inject_raw_only(cmd, restore_ms);
+    }
+    if args.hold_clipboard {
+        return crate::executor::hold_clipboard();
+    }
+
+    // Already printed while parsing
+    if args.show_expansion {
+        return Ok(());
     }
 
+    // Everything below sees the previous query's arguments instead
+    let args = if args.retry_last {
+        retry::retry_args(&args)?
+    } else {
+        args
+    };
+
     // Handle special commands first
     if args.version {
-        println!("ask {}", env!("CARGO_PKG_VERSION"));
-        return Ok(());
+        return version::print_version(&args);
     }
 
     if args.update {
@@ -84,6 +142,10 @@ pub async fn run(update_notification: Option<crate::update::UpdateNotification>)
         return Ok(());
     }
 
+    if let Some(ref assignment) = args.set {
+        return crate::config::set_config_value(assignment);
+    }
+
     // Handle completions generation
     if let Some(ref shell) = args.completions {
         crate::completions::generate_completions(shell);
@@ -91,35 +153,69 @@ pub async fn run(update_notification: Option<crate::update::UpdateNotification>)
     }
 
     if args.profile.is_some() && args.provider.is_some() {
-        anyhow::bail!(
+        return Err(exit::usage(
             "Cannot use --profile (-p) and --provider (-P) together.\n\
              Use --profile to select a configured profile, or\n\
-             Use --provider for ad-hoc mode (requires --api-key or ASK_{{PROVIDER}}_API_KEY)"
-        );
+             Use --provider for ad-hoc mode (requires --api-key or ASK_{PROVIDER}_API_KEY)",
+        ));
     }
 
     let env_profile = std::env::var("ASK_PROFILE").ok();
     let env_provider = std::env::var("ASK_PROVIDER").ok();
     if env_profile.is_some() && env_provider.is_some() {
-        anyhow::bail!(
+        return Err(exit::usage(
             "Cannot use ASK_PROFILE and ASK_PROVIDER together.\n\
              Use ASK_PROFILE to select a configured profile, or\n\
-             Use ASK_PROVIDER for ad-hoc mode"
-        );
+             Use ASK_PROVIDER for ad-hoc mode",
+        ));
+    }
+
+    if args.offline {
+        crate::http::set_offline(true);
+    }
+    if args.debug_request {
+        crate::http::set_debug_request(true);
+    }
+
+    // Before loading, so a broken config gets diagnosed instead of aborting
+    if args.doctor {
+        return doctor::run_doctor(&args).await;
+    }
+
+    // Before loading, so a pinned file that fails to parse is listed too
+    if args.dump_config_path {
+        config_path::print_config_paths();
+        return Ok(());
+    }
+
+    // Independent of the config entirely
+    if let Some(ref provider) = args.health {
+        return health::run_health(provider, &args).await;
     }
 
     // Load configuration
     let config = Config::load()?;
+    crate::http::set_proxy_override(config.http.proxy.clone());
+    crate::output::set_theme(crate::output::Theme::resolve(
+        &config.theme,
+        args.theme.as_deref(),
+    )?);
     let config = config.with_cli_overrides(&args);
 
     // Handle init command
     if args.init {
         if args.non_interactive {
-            return crate::config::init_config_non_interactive(
-                args.provider.as_deref(),
-                args.model.as_deref(),
-                args.api_key.as_deref(),
-            );
+            return crate::config::init_config_non_interactive(crate::config::NonInteractiveInit {
+                provider: args.provider.as_deref(),
+                model: args.model.as_deref(),
+                api_key: args.api_key.as_deref(),
+                key_from_stdin: args.key_from_stdin,
+                profile_name: args.profile_name.as_deref(),
+                base_url: args.base_url.as_deref(),
+                stream: args.stream,
+                thinking: args.thinking.as_deref(),
+                force: args.force,
+            });
         }
         return crate::config::init_config().await;
     }
@@ -128,11 +224,67 @@ pub async fn run(update_notification: Option<crate::update::UpdateNotification>)
         return list_profiles(&config);
     }
 
+    if args.list_templates {
+        return templates::print_templates();
+    }
+
+    if args.list_models {
+        return models::list_models(&config, &args);
+    }
+
+    if args.stats {
+        return stats::print_stats(&config, args.since.as_deref());
+    }
+
+    if let Some(ref words) = args.alias_command {
+        return run_alias_command(&config, words);
+    }
+
+    if args.model_info {
+        return model_info::print_model_info(&config, &args);
+    }
+
+    if let Some(ref name) = args.profile_env {
+        return profile_env::print_profile_env(&config, name);
+    }
+
     if args.history_subcommand {
         if args.history_prune {
             return ContextManager::prune_deleted(&config, args.yes == Some(true));
         }
 
+        if let Some(ref path) = args.export {
+            let target = args.history_target.as_deref().unwrap_or(".");
+            let count =
+                ContextManager::export_specific(&config, target, std::path::Path::new(path))?;
+            println!("{} {} ({} messages)", "Exported to".green(), path, count);
+            return Ok(());
+        }
+
+        if args.history_browse {
+            use std::io::IsTerminal;
+
+            // Interactive browsing needs a terminal; fall back to the flat list
+            if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+                return ContextManager::list_global(&config);
+            }
+
+            if let Some(id) = ContextManager::browse_contexts(&config)? {
+                let manager = context_manager(&config, &args)?;
+                let count = manager.resume(&config, &id)?;
+                println!(
+                    "{} {} messages into the current directory context.",
+                    "Resumed".green(),
+                    count
+                );
+                println!(
+                    "{}",
+                    "Use 'ask -c <question>' to continue the conversation.".bright_black()
+                );
+            }
+            return Ok(());
+        }
+
         if let Some(ref query) = args.history_search {
             return ContextManager::search_global(&config, query);
         }
@@ -143,9 +295,83 @@ pub async fn run(update_notification: Option<crate::update::UpdateNotification>)
         return ContextManager::list_global(&config);
     }
 
+    if (args.session.is_some() || args.fork.is_some()) && !args.has_context() {
+        return Err(exit::usage(
+            "--session and --fork work on a context. Use with -c",
+        ));
+    }
+    if args.apply && (args.json || args.json_stream || args.raw_stream || args.count.is_some()) {
+        return Err(exit::usage(
+            "--apply previews and writes a diff; drop --json/--json-stream/--raw-stream/-N",
+        ));
+    }
+    if args.tee.is_some() && (args.json_stream || args.raw_stream || args.count.is_some()) {
+        return Err(exit::usage(
+            "--tee shows the answer and saves a copy; drop --json-stream/--raw-stream/-N",
+        ));
+    }
+    if args.watch
+        && (args.json
+            || args.json_stream
+            || args.raw_stream
+            || args.count.is_some()
+            || args.apply
+            || args.preserve
+            || args.more)
+    {
+        return Err(exit::usage(
+            "--watch runs the generated command on changes; drop --json/--json-stream/--raw-stream/-N/--apply/--preserve/--more",
+        ));
+    }
+    for url in &args.image_urls {
+        crate::providers::validate_image_url(url).map_err(exit::usage)?;
+    }
+    if args.ephemeral && !args.has_context() {
+        return Err(exit::usage(
+            "--ephemeral reads a context without saving to it. Use with -c",
+        ));
+    }
+    if args.edit_answer && !args.has_context() {
+        return Err(exit::usage(
+            "--edit-answer edits the answer saved to a context. Use with -c",
+        ));
+    }
+    if args.context_file.is_some() && (args.session.is_some() || args.fork.is_some()) {
+        return Err(exit::usage(
+            "--context-file already names the thread; drop --session/--fork",
+        ));
+    }
+    if args.resume.is_some()
+        && (args.context_file.is_some() || args.session.is_some() || args.fork.is_some())
+    {
+        return Err(exit::usage(
+            "--resume already names the context; drop --context-file/--session/--fork",
+        ));
+    }
+
     // Handle context commands
+    let mut args = args;
     if args.has_context() {
-        let manager = ContextManager::with_ttl(&config, args.context_ttl())?;
+        let mut manager = context_manager(&config, &args)?;
+
+        if let Some(name) = args.fork.take() {
+            let count = manager.fork(&name)?;
+            println!(
+                "{} {} messages into session '{}'.",
+                "Forked".green(),
+                count,
+                name
+            );
+            if args.query.is_empty() {
+                println!(
+                    "{}",
+                    format!("Continue with: ask -c --session {} <question>", name).bright_black()
+                );
+                return Ok(());
+            }
+            // The rest of this run continues inside the fork
+            args.session = Some(name);
+        }
 
         if args.clear_context {
             manager.clear_current()?;
@@ -157,10 +383,64 @@ pub async fn run(update_notification: Option<crate::update::UpdateNotification>)
             manager.show_history()?;
             return Ok(());
         }
+
+        if let Some(ref path) = args.import {
+            let count = manager.import(std::path::Path::new(path))?;
+            println!("{} {} messages from {}", "Imported".green(), count, path);
+            return Ok(());
+        }
+
+        if let Some(ref path) = args.export {
+            let count = manager.export(std::path::Path::new(path))?;
+            println!("{} {} ({} messages)", "Exported to".green(), path, count);
+            return Ok(());
+        }
+    }
+
+    if args.export.is_some() || args.import.is_some() {
+        return Err(exit::usage(
+            "--export and --import work on a context.\n\
+             Use with -c (current directory) or 'ask history [TARGET] --export FILE'",
+        ));
+    }
+
+    if args.bench {
+        return bench::run_bench(&config, &args).await;
+    }
+
+    if args.review {
+        return review::run_review(&config, &args).await;
+    }
+
+    if args.template.is_some() && args.prompt_file.is_some() {
+        return Err(exit::usage(
+            "--template and --prompt-file both set the system prompt; use one",
+        ));
+    }
+
+    if args.round_robin && (args.profile.is_some() || args.provider.is_some()) {
+        return Err(exit::usage(
+            "--round-robin picks the profile from provider_order; drop -p/-P",
+        ));
+    }
+
+    if args.more {
+        if !args.has_context() {
+            return Err(exit::usage(
+                "--more continues the previous answer in a context. Use: ask -c --more",
+            ));
+        }
+        let manager = context_manager(&config, &args)?;
+        if !manager.has_last_answer()? {
+            anyhow::bail!(
+                "No previous answer to continue in this directory.\n\
+                 Ask something first with: ask -c <question>"
+            );
+        }
     }
 
     // Check if we have a query
-    if args.query.is_empty() {
+    if args.query.is_empty() && !args.more {
         println!("{}", "Usage: ask [OPTIONS] <your question here>".cyan());
         println!();
         println!("Examples:");
@@ -193,10 +473,27 @@ pub async fn run(update_notification: Option<crate::update::UpdateNotification>)
 
 async fn execute_with_fallback(config: &Config, args: &Args) -> Result<()> {
     // Get piped input if available
-    let stdin_content = read_stdin_if_available();
+    let stdin_content = read_stdin_if_available(args.allow_binary)?;
+
+    // `@path` words attach files; everything else stays in the question
+    let (words, files) = expand_file_args(&args.query)?;
+
+    let (stdin_content, files) = if config.privacy.redact {
+        let patterns = crate::privacy::compile_patterns(&config.privacy.redact_patterns);
+        (
+            stdin_content.map(|s| crate::privacy::redact(&s, &patterns)),
+            files
+                .into_iter()
+                .map(|(name, content)| (name, crate::privacy::redact(&content, &patterns)))
+                .collect::<Vec<AttachedFile>>(),
+        )
+    } else {
+        (stdin_content, files)
+    };
+    let input = input_block(stdin_content.as_deref(), &files);
 
     // Check for custom command (first word of query)
-    let first_word = args.query.first().map(|s| s.as_str()).unwrap_or("");
+    let first_word = words.first().map(|s| s.as_str()).unwrap_or("");
     let mut custom_cmd = config.commands.get(first_word).cloned();
     if let Some(ref mut cmd) = custom_cmd {
         cmd.name = Some(first_word.to_string());
@@ -205,9 +502,9 @@ async fn execute_with_fallback(config: &Config, args: &Args) -> Result<()> {
     // Build the full query
     let (full_query, effective_args) = if let Some(ref cmd) = custom_cmd {
         // Custom command: use remaining query as input
-        let remaining: Vec<String> = args.query.iter().skip(1).cloned().collect();
-        let query_text = if let Some(ref stdin) = stdin_content {
-            format!("Input:\n```\n{}\n```\n\n{}", stdin, remaining.join(" "))
+        let remaining: Vec<String> = words.iter().skip(1).cloned().collect();
+        let query_text = if let Some(ref input) = input {
+            format!("{}\n\n{}", input, remaining.join(" "))
         } else {
             remaining.join(" ")
         };
@@ -227,36 +524,44 @@ async fn execute_with_fallback(config: &Config, args: &Args) -> Result<()> {
         (query_text, modified_args)
     } else {
         // Regular query
-        let query_text = if let Some(ref stdin) = stdin_content {
-            format!(
-                "Input:\n```\n{}\n```\n\nQuestion: {}",
-                stdin,
-                args.query.join(" ")
-            )
+        let question = words.join(" ");
+        let query_text = if args.more {
+            continue_query(&args.query.join(" "))
         } else {
-            args.query.join(" ")
+            wrap_input(
+                config.prompt.stdin_template.as_deref(),
+                stdin_content.as_deref(),
+                &files,
+                &question,
+            )
+            .unwrap_or(question)
         };
         (query_text, args.clone())
     };
-    let args = effective_args;
+    let full_query = frame_query(full_query, args.prefix.as_deref(), args.suffix.as_deref());
+    let mut args = effective_args;
+    args.command_mode = effective_command_mode(&args, config);
+
+    if args.round_robin {
+        return try_round_robin(config, &args, &full_query, custom_cmd.as_ref()).await;
+    }
 
     // Create provider (with custom command overrides if applicable)
-    let config = if let Some(ref cmd) = custom_cmd {
-        let mut cfg = config.clone();
-        if let Some(ref provider) = cmd.provider {
-            cfg.active.provider = provider.clone();
-        }
-        if let Some(ref model) = cmd.model {
-            cfg.active.model = model.clone();
-        }
-        cfg
-    } else {
-        config.clone()
-    };
+    let config = with_command_overrides(config.clone(), custom_cmd.as_ref());
 
     let active_profile = config.active_profile(&args);
     let result = try_query(&config, &args, &full_query, custom_cmd.as_ref()).await;
 
+    if let Err(ref err) = result {
+        tracing::debug!(
+            profile = active_profile.as_deref().unwrap_or("none"),
+            retryable = is_retryable_error(err),
+            fallback_enabled = args.fallback != Some(false),
+            error = %crate::http::redact_url(&err.to_string()),
+            "query failed"
+        );
+    }
+
     match result {
         Ok(()) => Ok(()),
         Err(err) if args.fallback != Some(false) && is_retryable_error(&err) => {
@@ -295,6 +600,7 @@ async fn try_with_fallback(
             break;
         }
 
+        tracing::debug!(from = %current, to = %fallback_name, "switching to fallback profile");
         eprintln!(
             "{} {}",
             "Provider error, retrying with fallback profile:".yellow(),
@@ -303,20 +609,10 @@ async fn try_with_fallback(
 
         let mut fallback_args = args.clone();
         fallback_args.profile = Some(fallback_name.clone());
-        let fallback_config = original_config.clone().with_cli_overrides(&fallback_args);
-
-        let fallback_config = if let Some(cmd) = custom_cmd {
-            let mut cfg = fallback_config;
-            if let Some(ref provider) = cmd.provider {
-                cfg.active.provider = provider.clone();
-            }
-            if let Some(ref model) = cmd.model {
-                cfg.active.model = model.clone();
-            }
-            cfg
-        } else {
-            fallback_config
-        };
+        let fallback_config = with_command_overrides(
+            original_config.clone().with_cli_overrides(&fallback_args),
+            custom_cmd,
+        );
 
         match try_query(&fallback_config, &fallback_args, query, custom_cmd).await {
             Ok(()) => return Ok(()),
@@ -332,6 +628,64 @@ async fn try_with_fallback(
     Err(anyhow::anyhow!("{}", original_err))
 }
 
+/// Start at the next profile in `provider_order` and fall through the list on errors
+async fn try_round_robin(
+    config: &Config,
+    args: &Args,
+    query: &str,
+    custom_cmd: Option<&crate::config::CustomCommand>,
+) -> Result<()> {
+    let order = round_robin::profile_order(config)?;
+    let start = round_robin::advance_index(&round_robin::index_path(), order.len());
+    let mut last_err = None;
+
+    for name in round_robin::rotation(&order, start) {
+        if last_err.is_some() {
+            eprintln!(
+                "{} {}",
+                "Provider error, retrying with next profile in provider_order:".yellow(),
+                name.bright_white()
+            );
+        }
+
+        let mut profile_args = args.clone();
+        profile_args.profile = Some(name.clone());
+        let profile_config =
+            with_command_overrides(config.clone().with_cli_overrides(&profile_args), custom_cmd);
+
+        match try_query(&profile_config, &profile_args, query, custom_cmd).await {
+            Ok(()) => return Ok(()),
+            Err(err)
+                if args.fallback != Some(false)
+                    && is_retryable_error(&err)
+                    && config.profiles[&name].fallback.as_deref() != Some("none") =>
+            {
+                tracing::debug!(profile = %name, error = %crate::http::redact_url(&err.to_string()), "round-robin profile failed");
+                last_err = Some(err);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| anyhow::anyhow!("provider_order is empty")))
+}
+
+/// A custom command's provider/model replace the profile's
+fn with_command_overrides(
+    mut config: Config,
+    custom_cmd: Option<&crate::config::CustomCommand>,
+) -> Config {
+    if let Some(cmd) = custom_cmd {
+        if let Some(ref provider) = cmd.provider {
+            config.active.provider = provider.clone();
+        }
+        if let Some(ref model) = cmd.model {
+            config.active.model = model.clone();
+        }
+    }
+    config
+}
+
 async fn try_query(
     config: &Config,
     args: &Args,
@@ -339,7 +693,8 @@ async fn try_query(
     custom_cmd: Option<&crate::config::CustomCommand>,
 ) -> Result<()> {
     let provider = create_provider(config)?;
-    let formatter = OutputFormatter::new(args);
+    let formatter =
+        OutputFormatter::new(args).with_wrap(args.wrap.unwrap_or(config.behavior.wrap_columns));
 
     handle_query(
         config,
@@ -352,27 +707,205 @@ async fn try_query(
     .await
 }
 
-fn read_stdin_if_available() -> Option<String> {
+fn read_stdin_if_available(allow_binary: bool) -> Result<Option<String>> {
     use std::io::{self, IsTerminal, Read};
 
-    if !io::stdin().is_terminal() {
-        let mut buffer = String::new();
-        io::stdin().read_to_string(&mut buffer).ok()?;
-        if !buffer.is_empty() {
-            return Some(buffer);
+    if io::stdin().is_terminal() {
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    if io::stdin().read_to_end(&mut bytes).is_err() {
+        return Ok(None);
+    }
+    stdin_text(bytes, allow_binary)
+}
+
+/// Piped bytes as text for the prompt. Binary input is refused, or sent
+/// base64-encoded with `--allow-binary`.
+fn stdin_text(bytes: Vec<u8>, allow_binary: bool) -> Result<Option<String>> {
+    use base64::Engine;
+
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+    if !looks_binary(&bytes) {
+        return Ok(String::from_utf8(bytes).ok());
+    }
+    if !allow_binary {
+        return Err(exit::usage(
+            "stdin appears to be binary; pipe text only (or pass --allow-binary to send it base64-encoded)",
+        ));
+    }
+    Ok(Some(format!(
+        "(binary data, {} bytes, base64-encoded)\n{}",
+        bytes.len(),
+        base64::engine::general_purpose::STANDARD.encode(&bytes)
+    )))
+}
+
+/// Not UTF-8, a NUL byte, or more than 10% control characters. Tabs, line
+/// breaks, form feeds and the escapes of colored output count as text.
+fn looks_binary(bytes: &[u8]) -> bool {
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return true;
+    };
+    if text.contains('\0') {
+        return true;
+    }
+    let control = text
+        .chars()
+        .filter(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b'))
+        .count();
+    control * 10 > text.chars().count()
+}
+
+/// `(path, content)` of a file attached with `@path`
+type AttachedFile = (String, String);
+
+/// Split `@path` words (existing files) out of the query and read them.
+/// `@word` that is not a file is left in the question as typed.
+fn expand_file_args(query: &[String]) -> Result<(Vec<String>, Vec<AttachedFile>)> {
+    let mut words = Vec::new();
+    let mut files = Vec::new();
+
+    for word in query {
+        match word.strip_prefix('@') {
+            Some(path) if !path.is_empty() && std::path::Path::new(path).is_file() => {
+                let content = std::fs::read_to_string(path)
+                    .with_context(|| format!("Could not read {}", path))?;
+                files.push((path.to_string(), content));
+            }
+            _ => words.push(word.clone()),
         }
     }
-    None
+
+    Ok((words, files))
 }
 
-fn normalize_command_response(text: &str) -> Option<String> {
-    let stripped = strip_code_fences(text);
+/// The `Input:` block sent before the question. Plain stdin keeps a single
+/// fence; attached files get a `=== name ===` header each (stdin included).
+fn input_block(stdin: Option<&str>, files: &[AttachedFile]) -> Option<String> {
+    if files.is_empty() {
+        return stdin.map(|s| format!("Input:\n```\n{}\n```", s));
+    }
+
+    Some(format!("Input:\n{}", file_sections(stdin, files)))
+}
+
+fn file_sections(stdin: Option<&str>, files: &[AttachedFile]) -> String {
+    let sections: Vec<String> = stdin
+        .map(|s| ("stdin", s))
+        .into_iter()
+        .chain(files.iter().map(|(name, c)| (name.as_str(), c.as_str())))
+        .map(|(name, content)| {
+            format!(
+                "=== {} ===\n```\n{}\n```",
+                name,
+                content.strip_suffix('\n').unwrap_or(content)
+            )
+        })
+        .collect();
+
+    sections.join("\n\n")
+}
+
+/// Piped input and the question as one user message. `prompt.stdin_template`
+/// replaces the default `Input:` ... `Question:` framing; its `{input}` is the
+/// piped text as-is (or the labeled sections when files are attached).
+fn wrap_input(
+    template: Option<&str>,
+    stdin: Option<&str>,
+    files: &[AttachedFile],
+    question: &str,
+) -> Option<String> {
+    let Some(template) = template else {
+        return input_block(stdin, files)
+            .map(|input| format!("{}\n\nQuestion: {}", input, question));
+    };
+
+    let input = if files.is_empty() {
+        stdin?.to_string()
+    } else {
+        file_sections(stdin, files)
+    };
+    Some(fill_placeholders(
+        template,
+        &[("{input}", &input), ("{question}", question)],
+    ))
+}
+
+/// Single-pass substitution, so placeholder text inside a value is left alone
+fn fill_placeholders(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some((at, key, value)) = values
+        .iter()
+        .filter_map(|(key, value)| rest.find(key).map(|at| (at, *key, *value)))
+        .min_by_key(|(at, _, _)| *at)
+    {
+        out.push_str(&rest[..at]);
+        out.push_str(value);
+        rest = &rest[at + key.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `--prefix`/`--suffix` around the assembled user turn: a space on a one-line
+/// query, a blank line when stdin or files made it multi-line
+fn frame_query(query: String, prefix: Option<&str>, suffix: Option<&str>) -> String {
+    if prefix.is_none() && suffix.is_none() {
+        return query;
+    }
 
-    if is_likely_command(&stripped) {
-        Some(flatten_command_if_safe(&stripped).unwrap_or(stripped))
+    let separator = if query.contains('\n') { "\n\n" } else { " " };
+    let parts: Vec<&str> = [prefix, Some(query.as_str()), suffix]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect();
+    parts.join(separator)
+}
+
+/// `-x`/`--question` (or a custom command's type), else `behavior.default_mode`
+fn effective_command_mode(args: &Args, config: &Config) -> Option<bool> {
+    args.command_mode.or(config
+        .behavior
+        .default_mode
+        .and_then(crate::config::DefaultMode::command_mode))
+}
+
+/// With -x, drop a "Sure! Here's how..." lead-in so only the command remains
+fn strip_command_preamble(args: &Args, text: String) -> String {
+    if args.command_mode == Some(true) {
+        strip_preamble_for_command(&text).unwrap_or(text)
     } else {
+        text
+    }
+}
+
+/// The command without code fences, its lines joined with `&&` when `flatten`
+/// (`behavior.flatten_commands`); `None` for prose
+fn normalize_command_response(text: &str, max_chars: usize, flatten: bool) -> Option<String> {
+    let stripped = strip_code_fences(text);
+
+    if !is_likely_command(&stripped, max_chars) {
         None
+    } else if flatten {
+        Some(flatten_command_if_safe(&stripped, ShellFamily::from_env()).unwrap_or(stripped))
+    } else {
+        Some(stripped.trim().to_string())
+    }
+}
+
+/// What to run for a confirmed command: one step per line when multi-line commands
+/// are not flattened, the shell is POSIX and the lines split safely, else the command
+/// as one script
+fn command_steps(command: &str, flatten: bool, shell: ShellFamily) -> Vec<String> {
+    if flatten || shell != ShellFamily::Posix || cfg!(windows) {
+        return vec![command.to_string()];
     }
+    split_command_lines(command).unwrap_or_else(|| vec![command.to_string()])
 }
 
 fn build_provider_options(args: &Args, config: &Config) -> ProviderOptions {
@@ -399,8 +932,12 @@ fn build_provider_options(args: &Args, config: &Config) -> ProviderOptions {
         web_search,
         allowed_domains,
         blocked_domains,
+        include_thoughts: args.show_thinking && thinking_enabled,
         thinking_enabled,
         thinking_value,
+        max_tokens: args.max_tokens,
+        candidates: None,
+        seed: args.seed,
     }
 }
 
@@ -412,6 +949,8 @@ async fn handle_query(
     formatter: &OutputFormatter,
     custom_cmd: Option<&crate::config::CustomCommand>,
 ) -> Result<()> {
+    retry::save_last_query(&retry::last_query_path(), args);
+
     if args.verbose {
         let profile_name = config.active_profile(args);
         let options = build_provider_options(args, config);
@@ -432,6 +971,16 @@ async fn handle_query(
             }
             .cyan()
         );
+        if let Some(note) = provider.reasoning_note(&options) {
+            eprintln!("{} {}", "[verbose]".bright_black(), note);
+        }
+        if options.seed.is_some() && !provider.supports_seed() {
+            eprintln!(
+                "{} {} does not support seeds: --seed is ignored, temperature 0 is applied",
+                "[verbose]".bright_black(),
+                provider.name()
+            );
+        }
 
         eprintln!(
             "{} flags: context={:?}, command_mode={:?}, yes={:?}, think={:?}, think_level={:?}, json={}, markdown={:?}, raw={}, color={:?}, follow={:?}, fallback={:?}, stream={:?}, search={:?}, citations={:?}, update={}, init={}, clear_context={}, show_history={}, make_prompt={}, make_config={}, list_profiles={}, non_interactive={}",
@@ -459,13 +1008,36 @@ async fn handle_query(
             args.list_profiles,
             args.non_interactive
         );
+
+        let prompt_files = match args.prompt_file {
+            Some(ref path) => vec![path.clone()],
+            None => crate::providers::custom_prompt_sources(
+                custom_cmd.and_then(|c| c.name.as_deref()),
+                config.prompt.merge,
+            ),
+        };
+        eprintln!(
+            "{} prompt file{}: {}",
+            "[verbose]".bright_black(),
+            if prompt_files.len() > 1 { "s" } else { "" },
+            if prompt_files.is_empty() {
+                "none (built-in)".to_string()
+            } else {
+                prompt_files
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        );
     }
 
-    let mut messages = Vec::new();
+    let mut history = Vec::new();
 
     if args.has_context() {
-        let manager = ContextManager::with_ttl(config, args.context_ttl())?;
-        messages.extend(manager.get_messages()?);
+        let manager = context_manager(config, args)?;
+        let max_chars = args.max_context_chars.unwrap_or(config.context.max_chars);
+        history.extend(trim_to_char_budget(manager.get_messages()?, max_chars));
         manager.print_echo_if_needed()?;
     }
 
@@ -475,8 +1047,23 @@ async fn handle_query(
         args.color.unwrap_or(true),
     );
 
-    let system_prompt = if let Some(cmd) = custom_cmd {
-        if let Some(custom_prompt) = load_custom_prompt(cmd.name.as_deref()) {
+    let system_prompt = if let Some(ref name) = args.template {
+        let template = templates::load_template(&templates::templates_dir(), name)?;
+        let mut prompt = templates::apply_template(&template, query, &ctx);
+        if args.command_mode == Some(true) {
+            prompt = format!("{}\n\n{}", COMMAND_MODE_DIRECTIVE, prompt);
+        }
+        prompt
+    } else if let Some(ref path) = args.prompt_file {
+        let custom_prompt = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read prompt file {}", path.display()))?;
+        let mut prompt = expand_prompt_variables(&custom_prompt, &ctx);
+        if args.command_mode == Some(true) {
+            prompt = format!("{}\n\n{}", COMMAND_MODE_DIRECTIVE, prompt);
+        }
+        prompt
+    } else if let Some(cmd) = custom_cmd {
+        if let Some(custom_prompt) = load_custom_prompt(cmd.name.as_deref(), config.prompt.merge) {
             expand_prompt_variables(&custom_prompt, &ctx)
         } else {
             format!(
@@ -484,36 +1071,93 @@ async fn handle_query(
                 cmd.system, ctx.os, ctx.shell, ctx.cwd, ctx.locale, ctx.now
             )
         }
-    } else if let Some(custom_prompt) = load_custom_prompt(None) {
+    } else if let Some(custom_prompt) = load_custom_prompt(None, config.prompt.merge) {
         let mut prompt = expand_prompt_variables(&custom_prompt, &ctx);
         if args.command_mode == Some(true) {
-            prompt = format!("IMPORTANT: User explicitly requested command mode. Return ONLY the shell command, nothing else.\n\n{}", prompt);
+            prompt = format!("{}\n\n{}", COMMAND_MODE_DIRECTIVE, prompt);
         }
         prompt
     } else {
         build_unified_prompt(&ctx)
     };
+    let system_prompt =
+        append_prompt_fragment(system_prompt, config.get_prompt_append().as_deref());
+    let system_prompt = if args.format == Some(OutputFormat::Table) {
+        append_prompt_fragment(system_prompt, Some(TABLE_DIRECTIVE))
+    } else {
+        system_prompt
+    };
+    let system_prompt = if args.apply {
+        append_prompt_fragment(system_prompt, Some(apply::APPLY_DIRECTIVE))
+    } else {
+        system_prompt
+    };
 
-    messages.insert(
-        0,
-        crate::providers::Message {
-            role: "system".to_string(),
-            content: system_prompt,
-        },
-    );
-
-    messages.push(crate::providers::Message {
-        role: "user".to_string(),
-        content: query.to_string(),
-    });
+    let examples = custom_cmd
+        .map(|c| c.examples.as_slice())
+        .unwrap_or_default();
+    let mut messages = assemble_messages(system_prompt, examples, history, query);
+    if let Some(question) = messages.last_mut() {
+        question.images = args
+            .image_urls
+            .iter()
+            .cloned()
+            .map(crate::providers::ImageInput::Url)
+            .collect();
+    }
 
     let options = build_provider_options(args, config);
 
+    let tee = args.tee.as_deref().map(tee::TeeFile::create).transpose()?;
+
+    rate_limit::throttle(config).await;
+
+    if args.raw_stream {
+        return raw_stream_query(config, args, provider, query, &messages, &options).await;
+    }
+
+    if args.json_stream {
+        return json_stream_query(config, args, provider, query, &messages, &options).await;
+    }
+
+    if args.watch {
+        return watch::watch_query(config, args, provider, query, &messages, &options).await;
+    }
+
+    if let Some(count) = args.count.filter(|&n| n > 1) {
+        let options = ProviderOptions {
+            candidates: Some(count as u32),
+            ..options
+        };
+        return candidates::candidates_query(
+            config, args, provider, query, &messages, &options, formatter,
+        )
+        .await;
+    }
+
     // Determine if streaming should be enabled
-    let should_stream = args.stream.unwrap_or(config.active.stream)
+    let should_stream = (args.stream.unwrap_or(config.active.stream) || args.tee.is_some())
         && !args.json
         && !args.raw
-        && !options.web_search;
+        && !options.web_search
+        && !args.cost
+        && !args.show_thinking
+        && !args.apply
+        && !args.preserve
+        && !args.edit_answer
+        // Streamed text is printed as it arrives, so an explicit --wrap needs the whole answer
+        && args.wrap.is_none_or(|columns| columns == 0)
+        && args.format != Some(OutputFormat::Table);
+
+    if args.show_thinking && !options.thinking_enabled {
+        eprintln!(
+            "{}",
+            "--show-thinking needs thinking enabled (-t or the profile's thinking setting)"
+                .bright_black()
+        );
+    }
+
+    let started = std::time::Instant::now();
 
     if should_stream {
         use crate::output::{Spinner, StreamingIndicator};
@@ -530,59 +1174,111 @@ async fn handle_query(
         let indicator = Arc::new(Mutex::new(StreamingIndicator::new()));
         let indicator_clone = indicator.clone();
 
+        // --tee copy of each chunk
+        let tee = Arc::new(Mutex::new(tee));
+        let tee_clone = tee.clone();
+
+        // Commands about to be injected are held back instead of printed
+        let injects = injects_commands(args, config);
+        let lookahead = Arc::new(Mutex::new(injects.then(|| {
+            CommandLookahead::new(
+                config.behavior.max_command_chars,
+                args.command_mode == Some(true),
+            )
+        })));
+        let lookahead_clone = lookahead.clone();
+
         let callback: crate::providers::StreamCallback = Box::new(move |chunk: &str| {
-            // Stop spinner on first chunk
-            if let Some(mut s) = spinner_clone.lock().unwrap().take() {
-                s.stop();
-            }
+            let printable = match lookahead_clone.lock().unwrap().as_mut() {
+                Some(lookahead) => lookahead.push(chunk),
+                None => Some(chunk.to_string()),
+            };
+            if let Some(text) = printable {
+                // Stop spinner on the first printed chunk
+                if let Some(mut s) = spinner_clone.lock().unwrap().take() {
+                    s.stop();
+                }
 
-            // Print chunk with indicator
-            indicator_clone.lock().unwrap().print_chunk(chunk);
+                // Print chunk with indicator
+                indicator_clone.lock().unwrap().print_chunk(&text);
+            }
+            if let Some(tee) = tee_clone.lock().unwrap().as_mut() {
+                tee.write_chunk(chunk);
+            }
             response_clone.lock().unwrap().push_str(chunk);
         });
 
-        provider
+        let streamed = provider
             .stream_with_options(&messages, callback, &options)
-            .await?;
+            .await;
+        let stream_end = match streamed {
+            Ok(end) => end,
+            Err(e) => {
+                // Don't leave the ● or a half line behind the error message
+                let mut indicator = indicator.lock().unwrap();
+                indicator.finish();
+                if indicator.printed() {
+                    println!();
+                }
+                return Err(e);
+            }
+        };
 
-        // Finish indicator and add newline
-        indicator.lock().unwrap().finish();
-        println!();
+        spinner.lock().unwrap().take();
 
-        let raw_response = full_response.lock().unwrap().clone();
-        let response_text = normalize_command_response(&raw_response).unwrap_or(raw_response);
+        if let Some(tee) = tee.lock().unwrap().take() {
+            tee.finish()?;
+        }
 
-        // For sync injection (tmux/screen), clear the streamed command before injecting
-        // For async injection (GUI paste), show a hint
-        if crate::executor::can_inject() && is_likely_command(response_text.trim()) {
-            if crate::executor::is_async_injection() {
-                use colored::Colorize;
-                println!("{}", "(disable streaming to hide this line)".bright_black());
-            } else {
-                // Sync injection: clear the command lines we just printed
-                // Count lines in the response (including the newline we added)
-                let line_count = response_text.lines().count() + 1;
-                // Move cursor up and clear each line
-                for _ in 0..line_count {
-                    // Move up one line and clear it
-                    print!("\x1b[A\x1b[2K");
-                }
-                use std::io::Write;
-                std::io::stdout().flush().ok();
+        stats::record_usage(config, provider.name(), provider.model(), None, started);
+        let raw_response = strip_command_preamble(args, full_response.lock().unwrap().clone());
+        let response_text = normalize_command_response(
+            &raw_response,
+            config.behavior.max_command_chars,
+            config.behavior.flatten_commands,
+        )
+        .unwrap_or(raw_response);
+        notify::notify_if_slow(config, started, &response_text);
+
+        // A held command goes straight to injection; anything else held is shown now
+        let held = lookahead
+            .lock()
+            .unwrap()
+            .take()
+            .map(CommandLookahead::finish)
+            .unwrap_or_default();
+        let inject_held =
+            injects && is_likely_command(response_text.trim(), config.behavior.max_command_chars);
+        {
+            let mut indicator = indicator.lock().unwrap();
+            if !inject_held && !held.is_empty() {
+                indicator.print_chunk(&held);
+            }
+            // Finish indicator and add newline, also when prose already shown
+            // turned out to be a command once normalized
+            indicator.finish();
+            if !inject_held || indicator.printed() {
+                println!();
             }
         }
 
-        if args.has_context() {
-            let manager = ContextManager::with_ttl(config, args.context_ttl())?;
-            manager.add_message("user", query)?;
-            manager.add_message("assistant", &response_text)?;
+        if stream_end.is_truncated() {
+            print_truncated_hint();
         }
 
-        maybe_execute_command(config, args, &response_text).await?;
-    } else {
-        use std::io::IsTerminal;
+        save_exchange(config, args, query, &response_text)?;
 
-        // Show spinner while waiting for response (only in terminal, not raw/json)
+        if args.copy && !args.json {
+            copy_response(config, &response_text);
+        }
+
+        if !args.more {
+            maybe_execute_command(config, args, &response_text).await?;
+        }
+    } else {
+        use std::io::IsTerminal;
+
+        // Show spinner while waiting for response (only in terminal, not raw/json)
         let use_spinner = !args.raw && !args.json && std::io::stdout().is_terminal();
 
         let spinner = if use_spinner {
@@ -592,95 +1288,685 @@ async fn handle_query(
         };
 
         let response = provider.complete_with_options(&messages, &options).await?;
-        let response_text =
-            normalize_command_response(&response.text).unwrap_or_else(|| response.text.clone());
+        stats::record_usage(
+            config,
+            provider.name(),
+            provider.model(),
+            response.usage,
+            started,
+        );
+        let response_text = if args.preserve {
+            response.text.clone()
+        } else {
+            let text = strip_command_preamble(args, response.text.clone());
+            normalize_command_response(
+                &text,
+                config.behavior.max_command_chars,
+                config.behavior.flatten_commands,
+            )
+            .unwrap_or(text)
+        };
 
         // Stop spinner before output
         drop(spinner);
+        notify::notify_if_slow(config, started, &response_text);
 
-        // Skip echo if command will be injected into terminal
-        let skip_echo = crate::executor::can_inject() && is_likely_command(response_text.trim());
+        // Streaming was off (--json, --cost, web search...), so --tee gets the whole answer
+        if let Some(mut tee) = tee {
+            tee.write_chunk(&response_text);
+            tee.finish()?;
+        }
 
-        if !skip_echo {
+        // The envelope is the whole output: commands are reported, never run or injected
+        if args.json {
+            let is_command = !args.preserve
+                && is_likely_command(response_text.trim(), config.behavior.max_command_chars);
+            println!(
+                "{}",
+                json_envelope(
+                    provider.name(),
+                    provider.model(),
+                    &response_text,
+                    is_command,
+                    response.usage,
+                    &response.citations,
+                    JSON_UPDATE.get()
+                )
+            );
+            if args.cost {
+                cost::print_cost(config, provider.model(), response.usage);
+            }
+            return save_exchange(config, args, query, &response_text);
+        }
+
+        if args.show_thinking && !args.json {
+            if let Some(ref thinking) = response.thinking_text {
+                print_thinking(thinking);
+            }
+        }
+
+        // Skip echo if command will be injected into terminal
+        let skip_echo = !args.preserve
+            && injects_commands(args, config)
+            && is_likely_command(response_text.trim(), config.behavior.max_command_chars);
+
+        if args.apply {
+            apply::apply_response(config, args, &response_text)?;
+        } else if args.preserve && !args.json {
+            print_preserved(&response_text)?;
+        } else if !skip_echo {
             formatter.format(&response_text);
         }
 
         if args.citations == Some(true) && !response.citations.is_empty() {
             println!();
-            println!("{}", "Sources:".cyan());
+            let theme = crate::output::theme();
+            println!("{}", theme.heading("Sources:"));
             for (i, cite) in response.citations.iter().enumerate() {
-                println!("  [{}] {} - {}", i + 1, cite.title, cite.url);
+                println!(
+                    "  {} {} - {}",
+                    theme.citation(&format!("[{}]", i + 1)),
+                    cite.title,
+                    theme.citation(&cite.url)
+                );
             }
         }
 
-        if args.has_context() {
-            let manager = ContextManager::with_ttl(config, args.context_ttl())?;
-            manager.add_message("user", query)?;
-            manager.add_message("assistant", &response_text)?;
+        if response.is_truncated() {
+            print_truncated_hint();
+        }
+
+        if args.cost {
+            cost::print_cost(config, provider.model(), response.usage);
+        }
+
+        // Commands are corrected at the confirm prompt; only prose is edited here
+        let saved_text = if args.edit_answer
+            && !args.ephemeral
+            && !is_likely_command(response_text.trim(), config.behavior.max_command_chars)
+        {
+            edit_answer::answer_to_save(&response_text)
+        } else {
+            response_text.clone()
+        };
+        save_exchange(config, args, query, &saved_text)?;
+
+        if args.copy && !args.json {
+            copy_response(config, &response_text);
         }
 
-        maybe_execute_command(config, args, &response_text).await?;
+        if !args.more && !args.apply && !args.preserve {
+            maybe_execute_command(config, args, &response_text).await?;
+        }
     }
 
     Ok(())
 }
 
+/// The answer stopped at the output token limit
+fn print_truncated_hint() {
+    eprintln!(
+        "{}",
+        crate::output::theme().hint("response truncated (hit max tokens); try --max-tokens higher")
+    );
+}
+
+/// `--preserve`: the answer exactly as received, no trailing newline added
+fn print_preserved(text: &str) -> Result<()> {
+    use std::io::Write;
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// `--raw-stream`: write chunks to stdout as they arrive, with no spinner,
+/// indicator, trailing hint or command detection
+async fn raw_stream_query(
+    config: &Config,
+    args: &Args,
+    provider: &dyn crate::providers::Provider,
+    query: &str,
+    messages: &[crate::providers::Message],
+    options: &crate::providers::ProviderOptions,
+) -> Result<()> {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    let started = std::time::Instant::now();
+    let full_response = Arc::new(Mutex::new(String::new()));
+    let response_clone = full_response.clone();
+
+    let callback: crate::providers::StreamCallback = Box::new(move |chunk: &str| {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(chunk.as_bytes()).ok();
+        stdout.flush().ok();
+        response_clone.lock().unwrap().push_str(chunk);
+    });
+
+    provider
+        .stream_with_options(messages, callback, options)
+        .await?;
+    stats::record_usage(config, provider.name(), provider.model(), None, started);
+
+    let response = full_response.lock().unwrap().clone();
+    save_exchange(config, args, query, &response)
+}
+
+/// `--json-stream`: one NDJSON `delta` event per chunk, then a `done` summary.
+/// Web search needs the complete response for citations, so it arrives as one delta.
+async fn json_stream_query(
+    config: &Config,
+    args: &Args,
+    provider: &dyn crate::providers::Provider,
+    query: &str,
+    messages: &[crate::providers::Message],
+    options: &crate::providers::ProviderOptions,
+) -> Result<()> {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    fn emit(line: &str) {
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", line).ok();
+        stdout.flush().ok();
+    }
+
+    let started = std::time::Instant::now();
+    let (response, usage, citations) = if options.web_search {
+        let response = provider.complete_with_options(messages, options).await?;
+        emit(&delta_event(&response.text));
+        (response.text, response.usage, response.citations)
+    } else {
+        let full_response = Arc::new(Mutex::new(String::new()));
+        let response_clone = full_response.clone();
+
+        let callback: crate::providers::StreamCallback = Box::new(move |chunk: &str| {
+            emit(&delta_event(chunk));
+            response_clone.lock().unwrap().push_str(chunk);
+        });
+
+        provider
+            .stream_with_options(messages, callback, options)
+            .await?;
+
+        let response = full_response.lock().unwrap().clone();
+        (response, None, Vec::new())
+    };
+    stats::record_usage(config, provider.name(), provider.model(), usage, started);
+
+    emit(&done_event(
+        provider.name(),
+        provider.model(),
+        usage,
+        &citations,
+    ));
+    save_exchange(config, args, query, &response)
+}
+
+fn delta_event(text: &str) -> String {
+    serde_json::json!({ "type": "delta", "text": text }).to_string()
+}
+
+/// Final `--json-stream` event; usage is null when the provider reports none (streaming)
+fn done_event(
+    provider: &str,
+    model: &str,
+    usage: Option<crate::providers::Usage>,
+    citations: &[crate::providers::Citation],
+) -> String {
+    serde_json::json!({
+        "type": "done",
+        "provider": provider,
+        "model": model,
+        "usage": usage_json(usage),
+        "citations": citations_json(citations),
+    })
+    .to_string()
+}
+
+/// `--json` output. The answer stays under `response`, the key it had before
+/// provider, model, usage and citations were added; `update` is only present
+/// right after ask updated itself.
+fn json_envelope(
+    provider: &str,
+    model: &str,
+    text: &str,
+    is_command: bool,
+    usage: Option<crate::providers::Usage>,
+    citations: &[crate::providers::Citation],
+    update: Option<&crate::update::UpdateNotification>,
+) -> String {
+    let mut envelope = serde_json::json!({
+        "response": text,
+        "success": true,
+        "provider": provider,
+        "model": model,
+        "is_command": is_command,
+        "usage": usage_json(usage),
+        "citations": citations_json(citations),
+    });
+    if let Some(update) = update {
+        envelope["update"] = serde_json::json!({
+            "from": update.old_version,
+            "to": update.new_version,
+            "changelog": update.changelog,
+        });
+    }
+    serde_json::to_string_pretty(&envelope).unwrap_or_default()
+}
+
+fn usage_json(usage: Option<crate::providers::Usage>) -> serde_json::Value {
+    usage.map_or(serde_json::Value::Null, |u| {
+        serde_json::json!({
+            "input_tokens": u.input_tokens,
+            "output_tokens": u.output_tokens,
+        })
+    })
+}
+
+fn citations_json(citations: &[crate::providers::Citation]) -> Vec<serde_json::Value> {
+    citations
+        .iter()
+        .map(|c| serde_json::json!({ "title": c.title, "url": c.url, "snippet": c.snippet }))
+        .collect()
+}
+
+/// Instruction sent for `--more`, with optional extra guidance from the query
+fn continue_query(extra: &str) -> String {
+    let instruction = "Continue your previous answer exactly where it stopped. \
+                       Do not repeat anything you already wrote.";
+    if extra.trim().is_empty() {
+        instruction.to_string()
+    } else {
+        format!("{}\n\n{}", instruction, extra.trim())
+    }
+}
+
+/// Drop the oldest messages until the total content fits in `max_chars` (0 = unlimited)
+fn trim_to_char_budget(
+    messages: Vec<crate::providers::Message>,
+    max_chars: usize,
+) -> Vec<crate::providers::Message> {
+    if max_chars == 0 {
+        return messages;
+    }
+
+    let mut total = 0;
+    let keep = messages
+        .iter()
+        .rev()
+        .take_while(|m| {
+            total += m.content.chars().count();
+            total <= max_chars
+        })
+        .count();
+
+    let skip = messages.len() - keep;
+    messages.into_iter().skip(skip).collect()
+}
+
+/// System prompt first, then few-shot examples, the context history and the new user message.
+/// System messages in the history (the rolling summary) join the system prompt, since
+/// providers keep only one
+fn assemble_messages(
+    system_prompt: String,
+    examples: &[crate::config::FewShotExample],
+    history: Vec<crate::providers::Message>,
+    query: &str,
+) -> Vec<crate::providers::Message> {
+    let message = |role: &str, content: &str| crate::providers::Message {
+        role: role.to_string(),
+        content: content.to_string(),
+        ..Default::default()
+    };
+
+    let (summaries, history): (Vec<_>, Vec<_>) =
+        history.into_iter().partition(|m| m.role == "system");
+    let system_prompt = summaries.iter().fold(system_prompt, |prompt, summary| {
+        append_prompt_fragment(prompt, Some(&summary.content))
+    });
+
+    let mut messages = Vec::with_capacity(examples.len() * 2 + history.len() + 2);
+    messages.push(message("system", &system_prompt));
+    for example in examples {
+        messages.push(message("user", &example.user));
+        messages.push(message("assistant", &example.assistant));
+    }
+    messages.extend(history);
+    messages.push(message("user", query));
+    messages
+}
+
+/// Reasoning trace on stderr, dimmed, so pipes only get the answer
+fn print_thinking(thinking: &str) {
+    let theme = crate::output::theme();
+    eprintln!("{}", theme.hint("Thinking:").bold());
+    for line in thinking.lines() {
+        eprintln!("{}", theme.hint(line));
+    }
+    eprintln!();
+}
+
+/// Context manager for this directory, scoped to `--session` when given,
+/// or for the explicit `--context-file`
+fn context_manager(config: &Config, args: &Args) -> Result<ContextManager> {
+    if let Some(ref prefix) = args.resume {
+        return ContextManager::open_by_id_prefix(config, prefix);
+    }
+    if let Some(ref path) = args.context_file {
+        return ContextManager::with_file(config, args.context_ttl(), path);
+    }
+    ContextManager::with_session(config, args.context_ttl(), args.session.as_deref())
+}
+
+/// Store the turn in the context. `--more` extends the last answer instead of adding a turn.
+fn save_exchange(config: &Config, args: &Args, query: &str, response: &str) -> Result<()> {
+    if !args.has_context() || args.ephemeral {
+        return Ok(());
+    }
+
+    let manager = context_manager(config, args)?;
+    if args.more {
+        manager.append_to_last_answer(response)
+    } else {
+        manager.add_message("user", query)?;
+        manager.add_message("assistant", response)
+    }
+}
+
 async fn maybe_execute_command(config: &Config, args: &Args, response: &str) -> Result<()> {
+    let max_chars = config.behavior.max_command_chars;
     let response =
-        normalize_command_response(response).unwrap_or_else(|| response.trim().to_string());
+        normalize_command_response(response, max_chars, config.behavior.flatten_commands)
+            .unwrap_or_else(|| response.trim().to_string());
 
-    let looks_like_command = is_likely_command(&response);
+    let looks_like_command = is_likely_command(&response, max_chars);
 
     if !looks_like_command {
         return Ok(());
     }
 
-    let executor = CommandExecutor::new(config);
+    let explain = args.explain_error || config.behavior.explain_failures;
+    let executor = CommandExecutor::new(config)
+        .with_timeout(
+            args.exec_timeout
+                .unwrap_or(config.behavior.exec_timeout_secs),
+        )
+        .with_stderr_tail(if explain { EXPLAIN_STDERR_CHARS } else { 0 });
 
-    if args.yes == Some(true) || (config.behavior.auto_execute && executor.is_safe(&response)) {
+    if should_auto_execute(args, config, executor.is_safe(&response)) {
         println!();
-        println!("{} {}", "Running:".green(), response.bright_white().bold());
+        println!(
+            "{} {}",
+            "Running:".green(),
+            crate::output::theme().command(&response)
+        );
         println!();
-        executor
-            .execute_with_sudo_retry(&response, args.follow != Some(false))
-            .await?;
-    } else if crate::executor::can_inject() {
-        match crate::executor::inject_command(&response)? {
-            None => {}
-            Some(edited_cmd) => {
-                println!(
-                    "{} {}",
-                    "Running:".green(),
-                    edited_cmd.bright_white().bold()
-                );
-                println!();
-                executor
-                    .execute_with_sudo_retry(&edited_cmd, args.follow != Some(false))
-                    .await?;
-            }
+        run_command(config, args, &executor, &response).await?;
+    } else {
+        let edit_in_editor = config.behavior.edit_in_editor;
+        let to_run = match command_path(
+            inject_mode(args, config),
+            crate::executor::can_inject(),
+            args.copy,
+        ) {
+            CommandPath::Inject => crate::executor::inject_command(
+                &response,
+                edit_in_editor,
+                args.safe_paste || config.behavior.confirm_paste,
+            )?,
+            CommandPath::Prompt => crate::executor::prompt_command(&response, edit_in_editor)?,
+            CommandPath::Confirm => confirm_command(&executor, &response).then(|| response.clone()),
+            CommandPath::Print => None,
+        };
+
+        if let Some(cmd) = to_run {
+            println!(
+                "{} {}",
+                "Running:".green(),
+                crate::output::theme().command(&cmd)
+            );
+            println!();
+            run_command(config, args, &executor, &cmd).await?;
         }
     }
 
     Ok(())
 }
 
-fn is_likely_command(text: &str) -> bool {
+/// Run a confirmed command, line by line when `behavior.flatten_commands` is off
+async fn run_command(
+    config: &Config,
+    args: &Args,
+    executor: &CommandExecutor,
+    command: &str,
+) -> Result<()> {
+    let follow = args.follow != Some(false);
+    let steps = command_steps(
+        command,
+        config.behavior.flatten_commands,
+        ShellFamily::from_env(),
+    );
+    let exit_code = if steps.len() > 1 {
+        executor
+            .execute_steps(&steps, follow, args.keep_going)
+            .await?
+    } else {
+        executor.execute_with_sudo_retry(command, follow).await?
+    };
+    maybe_explain_failure(config, args, command, exit_code, executor).await?;
+    if exit_code != 0 {
+        return Err(exit::command_failed(exit_code));
+    }
+    Ok(())
+}
+
+/// Put before a custom system prompt (template, `--prompt-file`, prompt file) with `-x`
+const COMMAND_MODE_DIRECTIVE: &str =
+    "IMPORTANT: User explicitly requested command mode. Return ONLY the shell command, nothing else.";
+
+/// Extra system instruction for `--format table`
+const TABLE_DIRECTIVE: &str = "IMPORTANT: Answer with a single Markdown table (pipe-delimited, \
+     with a header row and a |---| separator row). No prose before or after it, no code fences.";
+
+/// Most of a long error output is noise; the end usually holds the actual failure
+const EXPLAIN_STDERR_CHARS: usize = 4000;
+
+/// With `--explain-error` (or `behavior.explain_failures`), send a failed command
+/// and its stderr back to the model and print the explanation
+async fn maybe_explain_failure(
+    config: &Config,
+    args: &Args,
+    command: &str,
+    exit_code: i32,
+    executor: &CommandExecutor,
+) -> Result<()> {
+    if exit_code == 0 || !(args.explain_error || config.behavior.explain_failures) {
+        return Ok(());
+    }
+
+    let provider = create_provider(config)?;
+    let messages = vec![
+        crate::providers::Message {
+            role: "system".to_string(),
+            content: "You help users fix failed shell commands. Be brief: say what went wrong, \
+                      then give the corrected command or the next step."
+                .to_string(),
+            ..Default::default()
+        },
+        crate::providers::Message {
+            role: "user".to_string(),
+            content: explain_error_prompt(command, exit_code, &executor.last_stderr()),
+            ..Default::default()
+        },
+    ];
+
+    use std::io::IsTerminal;
+    println!();
+    let spinner = std::io::stdout()
+        .is_terminal()
+        .then(crate::output::Spinner::start);
+    let response = provider
+        .complete_with_options(&messages, &ProviderOptions::default())
+        .await;
+    drop(spinner);
+
+    let formatter =
+        OutputFormatter::new(args).with_wrap(args.wrap.unwrap_or(config.behavior.wrap_columns));
+    formatter.format(&response?.text);
+    Ok(())
+}
+
+/// Follow-up question for a failed command, keeping only the tail of long stderr
+fn explain_error_prompt(command: &str, exit_code: i32, stderr: &str) -> String {
+    let stderr = stderr.trim();
+    let chars = stderr.chars().count();
+    let stderr: String = if chars > EXPLAIN_STDERR_CHARS {
+        let tail: String = stderr.chars().skip(chars - EXPLAIN_STDERR_CHARS).collect();
+        format!("...{}", tail)
+    } else {
+        stderr.to_string()
+    };
+
+    let output = if stderr.is_empty() {
+        "It printed nothing on stderr.".to_string()
+    } else {
+        format!("Its stderr:\n```\n{}\n```", stderr)
+    };
+
+    format!(
+        "This shell command failed with exit code {}:\n```\n{}\n```\n{}\n\n\
+         Explain this error and suggest a fix.",
+        exit_code, command, output
+    )
+}
+
+/// How a suggested command is handed to the user
+#[derive(Debug, PartialEq)]
+enum CommandPath {
+    /// Paste into the terminal (GUI paste, tmux, screen)
+    Inject,
+    /// Editable prompt, run on Enter
+    Prompt,
+    /// Yes/no question before running as-is
+    Confirm,
+    /// Only show it
+    Print,
+}
+
+/// `--no-inject` wins over `behavior.inject`
+fn inject_mode(args: &Args, config: &Config) -> InjectMode {
+    if args.no_inject {
+        InjectMode::Off
+    } else {
+        config.behavior.inject
+    }
+}
+
+/// `--copy` replaces every path: the command goes to the clipboard instead
+fn command_path(mode: InjectMode, can_inject: bool, copy: bool) -> CommandPath {
+    if copy {
+        return CommandPath::Print;
+    }
+    match mode {
+        InjectMode::Auto if can_inject => CommandPath::Inject,
+        InjectMode::Auto => CommandPath::Print,
+        InjectMode::Prompt => CommandPath::Prompt,
+        InjectMode::Off => CommandPath::Confirm,
+    }
+}
+
+fn injects_commands(args: &Args, config: &Config) -> bool {
+    command_path(
+        inject_mode(args, config),
+        crate::executor::can_inject(),
+        args.copy,
+    ) == CommandPath::Inject
+}
+
+fn confirm_command(executor: &CommandExecutor, command: &str) -> bool {
+    executor.confirm(
+        &format!("Run {}?", crate::output::theme().command(command)),
+        command,
+    )
+}
+
+/// What `--copy` puts on the clipboard: the flattened command (pasted lines would
+/// run without stopping at a failure), or the full answer
+fn clipboard_text(response: &str, max_chars: usize) -> String {
+    normalize_command_response(response, max_chars, true)
+        .unwrap_or_else(|| response.trim().to_string())
+}
+
+fn copy_response(config: &Config, response: &str) {
+    let text = clipboard_text(response, config.behavior.max_command_chars);
+    match crate::executor::copy_to_clipboard(&text) {
+        Ok(()) => eprintln!("{}", "copied to clipboard".bright_black()),
+        Err(e) => eprintln!("{} {}", "Could not copy to clipboard:".yellow(), e),
+    }
+}
+
+/// Decide whether a command runs without prompting.
+/// `-y` runs anything; `--yes-safe` and `behavior.auto_execute` only run safe commands.
+fn should_auto_execute(args: &Args, config: &Config, is_safe: bool) -> bool {
+    if args.yes == Some(true) {
+        return true;
+    }
+
+    (args.yes_safe || config.behavior.auto_execute) && is_safe
+}
+
+/// Starts like a command and is short enough (`behavior.max_command_chars`, 0 = no limit).
+/// A longer answer still counts when it is a single line with no sentences in it,
+/// like a long `ffmpeg` or `awk` one-liner.
+fn is_likely_command(text: &str, max_chars: usize) -> bool {
     let text = text.trim();
 
     if text.is_empty() {
         return false;
     }
 
-    if text.len() > 500 {
-        return false;
-    }
+    !too_long_for_command(text, max_chars) && starts_like_command(text)
+}
+
+/// Past `max_chars`, multi-line or sentence-like text is prose
+fn too_long_for_command(text: &str, max_chars: usize) -> bool {
+    max_chars > 0
+        && text.chars().count() > max_chars
+        && (text.contains('\n') || has_sentence_break(text))
+}
 
+fn starts_like_command(text: &str) -> bool {
     let first_word = text.split_whitespace().next().unwrap_or("");
     crate::providers::COMMAND_STARTERS
         .iter()
         .any(|cmd| first_word.starts_with(cmd))
 }
 
+/// Prose tell: `.`, `!` or `?` followed by a space and a capital letter
+fn has_sentence_break(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .windows(3)
+        .any(|w| matches!(w[0], '.' | '!' | '?') && w[1] == ' ' && w[2].is_uppercase())
+}
+
+/// `ask alias [list | add NAME EXPANSION... | remove NAME]`
+fn run_alias_command(config: &Config, words: &[String]) -> Result<()> {
+    match words {
+        [] => crate::config::list_aliases(config),
+        [action] if action == "list" => crate::config::list_aliases(config),
+        [action, name, expansion @ ..] if action == "add" && !expansion.is_empty() => {
+            crate::config::add_alias(name, &expansion.join(" "))
+        }
+        [action, name] if action == "remove" || action == "rm" => crate::config::remove_alias(name),
+        _ => Err(exit::usage(
+            "Usage: ask alias [list | add NAME EXPANSION... | remove NAME]",
+        )),
+    }
+}
+
 fn list_profiles(config: &Config) -> Result<()> {
     let effective_default = config.effective_default_profile();
 
@@ -716,7 +2002,7 @@ fn list_profiles(config: &Config) -> Result<()> {
                 .thinking_level
                 .as_ref()
                 .map(|v| format!("think:{}", v)),
-            "openai" => profile
+            "openai" | "azure" => profile
                 .reasoning_effort
                 .as_ref()
                 .map(|v| format!("reason:{}", v)),
@@ -767,3 +2053,701 @@ fn list_profiles(config: &Config) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::CommandExecutor;
+
+    #[test]
+    fn test_assemble_messages_for_more() {
+        let history = vec![
+            crate::providers::Message {
+                role: "user".to_string(),
+                content: "write a long poem".to_string(),
+                ..Default::default()
+            },
+            crate::providers::Message {
+                role: "assistant".to_string(),
+                content: "Roses are red,".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let messages = assemble_messages("system".to_string(), &[], history, &continue_query(""));
+
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].role, "system");
+        assert_eq!(messages[2].role, "assistant");
+        assert_eq!(messages[2].content, "Roses are red,");
+        assert_eq!(messages[3].role, "user");
+        assert!(messages[3]
+            .content
+            .starts_with("Continue your previous answer"));
+    }
+
+    #[test]
+    fn test_ephemeral_reads_context_without_saving() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::default();
+        let args = Args {
+            context_file: Some(dir.path().join("thread.json")),
+            ..Default::default()
+        };
+
+        save_exchange(&config, &args, "set up nginx", "apt install nginx").unwrap();
+
+        let ephemeral = Args {
+            ephemeral: true,
+            ..args.clone()
+        };
+        let history = context_manager(&config, &ephemeral)
+            .unwrap()
+            .get_messages()
+            .unwrap();
+        assert_eq!(history.len(), 2, "earlier turns are still sent");
+
+        save_exchange(&config, &ephemeral, "the root password is hunter2", "ok").unwrap();
+        let saved = context_manager(&config, &args)
+            .unwrap()
+            .get_messages()
+            .unwrap();
+        assert_eq!(saved.len(), 2);
+        assert!(saved.iter().all(|m| !m.content.contains("hunter2")));
+    }
+
+    fn sized(role: &str, len: usize) -> crate::providers::Message {
+        crate::providers::Message {
+            role: role.to_string(),
+            content: "x".repeat(len),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_trim_to_char_budget_keeps_newest() {
+        let messages = vec![
+            sized("user", 100),
+            sized("assistant", 200),
+            sized("user", 50),
+            sized("assistant", 30),
+        ];
+
+        let trimmed = trim_to_char_budget(messages, 100);
+        let sizes: Vec<usize> = trimmed.iter().map(|m| m.content.len()).collect();
+        assert_eq!(sizes, vec![50, 30]);
+        assert_eq!(trimmed[0].role, "user");
+    }
+
+    #[test]
+    fn test_trim_to_char_budget_exact_fit_and_unlimited() {
+        let messages = vec![sized("user", 40), sized("assistant", 60)];
+        assert_eq!(trim_to_char_budget(messages.clone(), 100).len(), 2);
+        assert_eq!(trim_to_char_budget(messages.clone(), 99).len(), 1);
+        assert_eq!(trim_to_char_budget(messages, 0).len(), 2);
+    }
+
+    #[test]
+    fn test_trim_to_char_budget_drops_oversized_newest() {
+        let messages = vec![sized("user", 10), sized("assistant", 500)];
+        assert!(trim_to_char_budget(messages, 100).is_empty());
+    }
+
+    #[test]
+    fn test_trim_to_char_budget_counts_chars_not_bytes() {
+        let messages = vec![crate::providers::Message {
+            role: "user".to_string(),
+            content: "ção".to_string(),
+            ..Default::default()
+        }];
+        assert_eq!(trim_to_char_budget(messages, 3).len(), 1);
+    }
+
+    #[test]
+    fn test_assemble_messages_with_examples() {
+        let examples = vec![
+            crate::config::FewShotExample {
+                user: "diff 1".to_string(),
+                assistant: "feat: one".to_string(),
+            },
+            crate::config::FewShotExample {
+                user: "diff 2".to_string(),
+                assistant: "fix: two".to_string(),
+            },
+        ];
+        let history = vec![crate::providers::Message {
+            role: "user".to_string(),
+            content: "earlier question".to_string(),
+            ..Default::default()
+        }];
+
+        let messages = assemble_messages("system".to_string(), &examples, history, "real diff");
+        let turns: Vec<(&str, &str)> = messages
+            .iter()
+            .map(|m| (m.role.as_str(), m.content.as_str()))
+            .collect();
+
+        assert_eq!(
+            turns,
+            vec![
+                ("system", "system"),
+                ("user", "diff 1"),
+                ("assistant", "feat: one"),
+                ("user", "diff 2"),
+                ("assistant", "fix: two"),
+                ("user", "earlier question"),
+                ("user", "real diff"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_messages_folds_summary_into_system_prompt() {
+        let history = vec![
+            crate::providers::Message {
+                role: "system".to_string(),
+                content: "Summary of the earlier conversation:\n- user: set up nginx".to_string(),
+                ..Default::default()
+            },
+            crate::providers::Message {
+                role: "user".to_string(),
+                content: "and TLS?".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let messages = assemble_messages("system".to_string(), &[], history, "now caddy");
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].role, "system");
+        assert_eq!(
+            messages[0].content,
+            "system\n\nSummary of the earlier conversation:\n- user: set up nginx"
+        );
+        assert_eq!(messages[1].content, "and TLS?");
+        assert_eq!(messages[2].content, "now caddy");
+    }
+
+    #[test]
+    fn test_normalize_fenced_single_command() {
+        assert_eq!(
+            normalize_command_response("```bash\ngit status --short\n```", 500, true).as_deref(),
+            Some("git status --short")
+        );
+        assert_eq!(
+            normalize_command_response("```sh\nmkdir out\ncd out\n```", 500, true).as_deref(),
+            Some("mkdir out && cd out")
+        );
+    }
+
+    #[test]
+    fn test_normalize_keeps_fenced_multiline_code() {
+        let answer = "```python\ndef add(a, b):\n    return a + b\n```";
+        assert_eq!(normalize_command_response(answer, 500, true), None);
+
+        let two_blocks = "Use one of:\n```bash\nls\n```\n```bash\nls -a\n```";
+        assert_eq!(normalize_command_response(two_blocks, 500, true), None);
+    }
+
+    #[test]
+    fn test_normalize_without_fences() {
+        assert_eq!(
+            normalize_command_response("  ls -la  ", 500, true).as_deref(),
+            Some("ls -la")
+        );
+        assert_eq!(
+            normalize_command_response("Paris is the capital of France.", 500, true),
+            None
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_flatten_commands_toggle() {
+        let answer = "```sh\nmkdir out\ncd out\n```";
+        let config = Config::from_toml("[behavior]\nflatten_commands = false\n").unwrap();
+        assert!(Config::default().behavior.flatten_commands);
+
+        let kept = normalize_command_response(answer, 500, config.behavior.flatten_commands);
+        assert_eq!(kept.as_deref(), Some("mkdir out\ncd out"));
+        assert_eq!(
+            command_steps(kept.as_deref().unwrap(), false, ShellFamily::Posix),
+            vec!["mkdir out", "cd out"]
+        );
+        assert_eq!(
+            command_steps("mkdir out && cd out", true, ShellFamily::Posix),
+            vec!["mkdir out && cd out"]
+        );
+        // A heredoc cannot be split, so it runs as one script
+        assert_eq!(
+            command_steps("cat <<EOF\nhi\nEOF", false, ShellFamily::Posix).len(),
+            1
+        );
+        // fish and nushell run the answer as one script in their own syntax
+        assert_eq!(command_steps("ls\npwd", false, ShellFamily::Fish).len(), 1);
+    }
+
+    #[test]
+    fn test_long_single_line_command_is_likely() {
+        let inputs: String = (0..40).map(|i| format!("-i clip{:02}.mp4 ", i)).collect();
+        let command = format!(
+            "ffmpeg {}-filter_complex concat=n=40:v=1:a=1 -c:v libx264 -crf 20 out.mp4",
+            inputs
+        );
+        assert!(command.chars().count() > 600);
+        assert!(is_likely_command(&command, 500));
+    }
+
+    #[test]
+    fn test_long_prose_is_not_likely_command() {
+        let sentence = "find is the tool for this job. It walks the tree and matches names. ";
+        let prose = sentence.repeat(10);
+        assert!(prose.chars().count() > 600);
+        assert!(!is_likely_command(&prose, 500));
+        assert!(
+            is_likely_command(&prose, 0),
+            "0 disables the length check, leaving only the first word"
+        );
+
+        let short_prose = "find is the tool for this job. It walks the tree.";
+        assert!(
+            is_likely_command(short_prose, 500),
+            "under the limit as before"
+        );
+    }
+
+    #[test]
+    fn test_long_multiline_is_not_likely_command() {
+        let script = "echo step\n".repeat(80);
+        assert!(!is_likely_command(&script, 500));
+        assert!(is_likely_command(&script, 1000));
+    }
+
+    fn file_args(dir: &std::path::Path, names: &[(&str, &str)]) -> Vec<String> {
+        names
+            .iter()
+            .map(|(name, content)| {
+                let path = dir.join(name);
+                std::fs::write(&path, content).unwrap();
+                format!("@{}", path.display())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_multiple_file_args_get_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut query = vec!["explain".to_string()];
+        query.extend(file_args(
+            dir.path(),
+            &[("a.rs", "fn a() {}\n"), ("b.rs", "fn b() {}\n")],
+        ));
+        query.push("briefly".to_string());
+
+        let (words, files) = expand_file_args(&query).unwrap();
+        assert_eq!(words, vec!["explain", "briefly"]);
+        assert_eq!(files.len(), 2);
+
+        let a = dir.path().join("a.rs").display().to_string();
+        let b = dir.path().join("b.rs").display().to_string();
+        assert_eq!(
+            input_block(None, &files).unwrap(),
+            format!(
+                "Input:\n=== {} ===\n```\nfn a() {{}}\n```\n\n=== {} ===\n```\nfn b() {{}}\n```",
+                a, b
+            )
+        );
+    }
+
+    #[test]
+    fn test_stdin_is_labeled_alongside_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let query = file_args(dir.path(), &[("notes.md", "todo")]);
+        let (_, files) = expand_file_args(&query).unwrap();
+
+        let block = input_block(Some("piped"), &files).unwrap();
+        assert!(block.starts_with("Input:\n=== stdin ===\n```\npiped\n```\n\n=== "));
+        assert!(block.ends_with("notes.md ===\n```\ntodo\n```"));
+    }
+
+    #[test]
+    fn test_wrap_input_default_framing() {
+        assert_eq!(
+            wrap_input(None, Some("a\nb"), &[], "what is this").unwrap(),
+            "Input:\n```\na\nb\n```\n\nQuestion: what is this"
+        );
+        assert!(wrap_input(None, None, &[], "hi").is_none());
+    }
+
+    #[test]
+    fn test_wrap_input_custom_template() {
+        let template = "<document>\n{input}\n</document>\n\n{question}";
+        assert_eq!(
+            wrap_input(Some(template), Some("log line\n"), &[], "summarize").unwrap(),
+            "<document>\nlog line\n\n</document>\n\nsummarize"
+        );
+        assert!(wrap_input(Some(template), None, &[], "hi").is_none());
+
+        // Placeholders inside the piped text are not expanded
+        assert_eq!(
+            wrap_input(Some("{question}: {input}"), Some("{question}"), &[], "q").unwrap(),
+            "q: {question}"
+        );
+    }
+
+    #[test]
+    fn test_plain_stdin_keeps_single_fence() {
+        assert_eq!(
+            input_block(Some("a\nb"), &[]).unwrap(),
+            "Input:\n```\na\nb\n```"
+        );
+        assert!(input_block(None, &[]).is_none());
+    }
+
+    #[test]
+    fn test_text_stdin_is_accepted() {
+        let text = "line one\n\tindented\r\n\x1b[31mred\x1b[0m é\n";
+        assert_eq!(
+            stdin_text(text.as_bytes().to_vec(), false).unwrap(),
+            Some(text.to_string())
+        );
+        assert_eq!(stdin_text(Vec::new(), false).unwrap(), None);
+    }
+
+    #[test]
+    fn test_binary_stdin_is_refused() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        let err = stdin_text(png, false).unwrap_err();
+        assert!(err.to_string().contains("stdin appears to be binary"));
+
+        assert!(looks_binary(b"valid utf-8 with a \0 byte"));
+        assert!(looks_binary(b"\x01\x02\x03\x04 mostly control"));
+        assert!(!looks_binary(
+            "one\x07bell in a long enough line".as_bytes()
+        ));
+    }
+
+    #[test]
+    fn test_allow_binary_sends_base64() {
+        let text = stdin_text(vec![0, 159, 146, 150], true).unwrap().unwrap();
+        assert_eq!(text, "(binary data, 4 bytes, base64-encoded)\nAJ+Slg==");
+    }
+
+    #[test]
+    fn test_at_word_that_is_not_a_file_stays_in_query() {
+        let query = vec![
+            "ping".to_string(),
+            "@nobody-here.txt".to_string(),
+            "@".to_string(),
+        ];
+        let (words, files) = expand_file_args(&query).unwrap();
+        assert_eq!(words, query);
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn test_command_path_auto() {
+        assert_eq!(
+            command_path(InjectMode::Auto, true, false),
+            CommandPath::Inject
+        );
+        assert_eq!(
+            command_path(InjectMode::Auto, false, false),
+            CommandPath::Print
+        );
+    }
+
+    #[test]
+    fn test_command_path_prompt_never_pastes() {
+        assert_eq!(
+            command_path(InjectMode::Prompt, true, false),
+            CommandPath::Prompt
+        );
+        assert_eq!(
+            command_path(InjectMode::Prompt, false, false),
+            CommandPath::Prompt
+        );
+    }
+
+    #[test]
+    fn test_command_path_off_confirms() {
+        assert_eq!(
+            command_path(InjectMode::Off, true, false),
+            CommandPath::Confirm
+        );
+        assert_eq!(
+            command_path(InjectMode::Off, false, false),
+            CommandPath::Confirm
+        );
+    }
+
+    #[test]
+    fn test_command_path_copy_only_prints() {
+        for mode in [InjectMode::Auto, InjectMode::Prompt, InjectMode::Off] {
+            assert_eq!(command_path(mode, true, true), CommandPath::Print);
+        }
+    }
+
+    #[test]
+    fn test_no_inject_flag_overrides_config() {
+        let mut config = Config::default();
+        config.behavior.inject = InjectMode::Prompt;
+        let args = Args::default();
+        assert_eq!(inject_mode(&args, &config), InjectMode::Prompt);
+
+        let args = Args {
+            no_inject: true,
+            ..Default::default()
+        };
+        assert_eq!(inject_mode(&args, &config), InjectMode::Off);
+    }
+
+    #[test]
+    fn test_clipboard_text_command_is_flattened() {
+        assert_eq!(
+            clipboard_text("```bash\nmkdir build\ncd build\n```\n", 500),
+            "mkdir build && cd build"
+        );
+        assert_eq!(clipboard_text("ls -la\n", 500), "ls -la");
+    }
+
+    #[test]
+    fn test_clipboard_text_prose_is_kept_whole() {
+        let answer =
+            "Rust has no garbage collector.\n\nMemory is freed when owners go out of scope.";
+        assert_eq!(clipboard_text(&format!("{}\n", answer), 500), answer);
+    }
+
+    #[test]
+    fn test_explain_error_prompt() {
+        let prompt =
+            explain_error_prompt("cargo build", 101, "error[E0425]: cannot find value `x`\n");
+        assert!(prompt.contains("exit code 101"));
+        assert!(prompt.contains("```\ncargo build\n```"));
+        assert!(prompt.contains("```\nerror[E0425]: cannot find value `x`\n```"));
+        assert!(prompt.ends_with("Explain this error and suggest a fix."));
+
+        let silent = explain_error_prompt("false", 1, "  \n");
+        assert!(silent.contains("It printed nothing on stderr."));
+    }
+
+    #[test]
+    fn test_explain_error_prompt_keeps_stderr_tail() {
+        let stderr = format!("{}final error: é", "noise\n".repeat(2000));
+        let prompt = explain_error_prompt("make", 2, &stderr);
+        assert!(prompt.contains("```\n..."));
+        assert!(prompt.contains("final error: é"));
+        assert!(prompt.len() < stderr.len());
+    }
+
+    #[test]
+    fn test_json_stream_events() {
+        let delta: serde_json::Value = serde_json::from_str(&delta_event("a \"b\"\n")).unwrap();
+        assert_eq!(delta["type"], "delta");
+        assert_eq!(delta["text"], "a \"b\"\n");
+
+        let done: serde_json::Value =
+            serde_json::from_str(&done_event("openai", "gpt-5", None, &[])).unwrap();
+        assert_eq!(done["type"], "done");
+        assert_eq!(done["provider"], "openai");
+        assert_eq!(done["model"], "gpt-5");
+        assert!(done["usage"].is_null());
+        assert_eq!(done["citations"], serde_json::json!([]));
+
+        let usage = crate::providers::Usage {
+            input_tokens: 12,
+            output_tokens: 3,
+        };
+        let citation = crate::providers::Citation {
+            title: "Docs".to_string(),
+            url: "https://example.com".to_string(),
+            snippet: None,
+        };
+        let done: serde_json::Value = serde_json::from_str(&done_event(
+            "gemini",
+            "gemini-2.5",
+            Some(usage),
+            &[citation],
+        ))
+        .unwrap();
+        assert_eq!(done["usage"]["output_tokens"], 3);
+        assert_eq!(done["citations"][0]["url"], "https://example.com");
+    }
+
+    #[test]
+    fn test_json_envelope() {
+        let usage = crate::providers::Usage {
+            input_tokens: 20,
+            output_tokens: 4,
+        };
+        let envelope: serde_json::Value = serde_json::from_str(&json_envelope(
+            "anthropic",
+            "claude-sonnet-4-5",
+            "ls -la",
+            true,
+            Some(usage),
+            &[],
+            None,
+        ))
+        .unwrap();
+        assert_eq!(envelope["response"], "ls -la");
+        assert_eq!(envelope["success"], true);
+        assert_eq!(envelope["provider"], "anthropic");
+        assert_eq!(envelope["model"], "claude-sonnet-4-5");
+        assert_eq!(envelope["is_command"], true);
+        assert_eq!(envelope["usage"]["input_tokens"], 20);
+        assert_eq!(envelope["citations"], serde_json::json!([]));
+        assert!(envelope.get("update").is_none());
+
+        let update = crate::update::UpdateNotification {
+            old_version: "0.30.0".to_string(),
+            new_version: "0.31.0".to_string(),
+            changelog: "- faster".to_string(),
+            url: String::new(),
+            timestamp: 0,
+        };
+        let envelope: serde_json::Value = serde_json::from_str(&json_envelope(
+            "ollama",
+            "qwen3",
+            "hi",
+            false,
+            None,
+            &[],
+            Some(&update),
+        ))
+        .unwrap();
+        assert!(envelope["usage"].is_null());
+        assert_eq!(envelope["is_command"], false);
+        assert_eq!(
+            envelope["update"],
+            serde_json::json!({ "from": "0.30.0", "to": "0.31.0", "changelog": "- faster" })
+        );
+    }
+
+    #[test]
+    fn test_frame_query() {
+        let q = || "capital of France".to_string();
+        assert_eq!(frame_query(q(), None, None), "capital of France");
+        assert_eq!(
+            frame_query(q(), Some("in one word:"), None),
+            "in one word: capital of France"
+        );
+        assert_eq!(
+            frame_query(q(), None, Some("explain like I'm 5")),
+            "capital of France explain like I'm 5"
+        );
+        assert_eq!(
+            frame_query(q(), Some("in one word:"), Some("(no punctuation)")),
+            "in one word: capital of France (no punctuation)"
+        );
+        assert_eq!(frame_query(q(), Some(""), None), "capital of France");
+    }
+
+    #[test]
+    fn test_frame_query_after_stdin_assembly() {
+        let query = wrap_input(None, Some("error: E0502"), &[], "fix this").unwrap();
+        assert_eq!(
+            frame_query(query, Some("Be brief."), Some("Reply in Portuguese.")),
+            "Be brief.\n\nInput:\n```\nerror: E0502\n```\n\nQuestion: fix this\n\nReply in Portuguese."
+        );
+    }
+
+    #[test]
+    fn test_default_mode_applies_without_flags() {
+        let mut config = Config::default();
+        let args = Args::default();
+        assert_eq!(effective_command_mode(&args, &config), None);
+
+        config.behavior.default_mode = Some(crate::config::DefaultMode::Command);
+        assert_eq!(effective_command_mode(&args, &config), Some(true));
+
+        config.behavior.default_mode = Some(crate::config::DefaultMode::Question);
+        assert_eq!(effective_command_mode(&args, &config), Some(false));
+    }
+
+    #[test]
+    fn test_flags_override_default_mode() {
+        let mut config = Config::default();
+        config.behavior.default_mode = Some(crate::config::DefaultMode::Command);
+        let question = Args {
+            command_mode: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(effective_command_mode(&question, &config), Some(false));
+
+        config.behavior.default_mode = Some(crate::config::DefaultMode::Question);
+        let command = Args {
+            command_mode: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(effective_command_mode(&command, &config), Some(true));
+    }
+
+    #[test]
+    fn test_strip_command_preamble_only_in_command_mode() {
+        let answer = "Sure! Here's how:\n```bash\ndu -sh *\n```".to_string();
+        let command_mode = Args {
+            command_mode: Some(true),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            strip_command_preamble(&command_mode, answer.clone()),
+            "du -sh *"
+        );
+        assert_eq!(
+            strip_command_preamble(&Args::default(), answer.clone()),
+            answer
+        );
+    }
+
+    #[test]
+    fn test_continue_query_with_guidance() {
+        let query = continue_query("  in French ");
+        assert!(query.starts_with("Continue your previous answer"));
+        assert!(query.ends_with("\n\nin French"));
+        assert!(!continue_query("").contains('\n'));
+    }
+
+    #[test]
+    fn test_yes_safe_runs_safe_command() {
+        let config = Config::default();
+        let executor = CommandExecutor::new(&config);
+        let args = Args {
+            yes_safe: true,
+            ..Default::default()
+        };
+
+        assert!(should_auto_execute(
+            &args,
+            &config,
+            executor.is_safe("ls -la")
+        ));
+    }
+
+    #[test]
+    fn test_yes_safe_prompts_for_risky_command() {
+        let config = Config::default();
+        let executor = CommandExecutor::new(&config);
+        let args = Args {
+            yes_safe: true,
+            ..Default::default()
+        };
+
+        assert!(!should_auto_execute(
+            &args,
+            &config,
+            executor.is_safe("rm -rf ./build")
+        ));
+    }
+
+    #[test]
+    fn test_yes_runs_anything_and_default_prompts() {
+        let config = Config::default();
+        let yes = Args {
+            yes: Some(true),
+            ..Default::default()
+        };
+        assert!(should_auto_execute(&yes, &config, false));
+        assert!(!should_auto_execute(&Args::default(), &config, true));
+    }
+}
@@ -0,0 +1,161 @@
+//! Resolved provider, model and thinking parameters, without a call (`--model-info`)
+
+use super::{build_provider_options, Args};
+use crate::config::{detect_thinking_type, Config};
+use anyhow::Result;
+use colored::Colorize;
+
+/// `(label, value)` rows describing what a query with these flags would send
+pub fn model_info_rows(config: &Config, args: &Args) -> Result<Vec<(&'static str, String)>> {
+    let provider = crate::providers::inspect_provider(config)?;
+    let options = build_provider_options(args, config);
+    let thinking_type = detect_thinking_type(config.active_provider(), config.active_model());
+
+    let requested = if options.thinking_enabled {
+        options
+            .thinking_value
+            .as_deref()
+            .unwrap_or("on")
+            .to_string()
+    } else {
+        "off".to_string()
+    };
+    let sends = match provider.thinking_param(&options) {
+        Some((field, value)) => format!("{} = {}", field, value),
+        None => "nothing".to_string(),
+    };
+
+    let mut rows = vec![
+        (
+            "profile",
+            config
+                .active_profile(args)
+                .unwrap_or_else(|| "default".to_string()),
+        ),
+        ("provider", config.active_provider().to_string()),
+        ("model", config.active_model().to_string()),
+        ("thinking type", format!("{:?}", thinking_type)),
+        ("thinking", requested),
+        ("sends", sends),
+    ];
+    if let Some(note) = provider.reasoning_note(&options) {
+        rows.push(("note", note));
+    }
+    rows.push((
+        "web search",
+        if options.web_search { "on" } else { "off" }.to_string(),
+    ));
+    Ok(rows)
+}
+
+pub fn print_model_info(config: &Config, args: &Args) -> Result<()> {
+    println!("{}", "Model info".cyan().bold());
+    println!();
+    for (label, value) in model_info_rows(config, args)? {
+        println!("  {:<14} {}", format!("{}:", label).bright_black(), value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows_for(
+        profile: &str,
+        think: Option<bool>,
+        level: Option<&str>,
+    ) -> Vec<(&'static str, String)> {
+        let config = Config::from_toml(
+            r#"
+[profiles.gpt]
+provider = "openai"
+model = "gpt-5"
+reasoning_effort = "high"
+
+[profiles.gpt4]
+provider = "openai"
+model = "gpt-4o"
+
+[profiles.flash]
+provider = "gemini"
+model = "gemini-2.5-flash"
+thinking_budget = 4096
+
+[profiles.gemini3]
+provider = "gemini"
+model = "gemini-3-pro-preview"
+thinking_level = "high"
+web_search = true
+
+[profiles.claude]
+provider = "anthropic"
+model = "claude-sonnet-4-5"
+
+[profiles.local]
+provider = "ollama"
+model = "qwen3"
+"#,
+        )
+        .unwrap();
+        let args = Args {
+            profile: Some(profile.to_string()),
+            think,
+            think_level: level.map(str::to_string),
+            ..Default::default()
+        };
+        let config = config.with_cli_overrides(&args);
+        model_info_rows(&config, &args).unwrap()
+    }
+
+    fn row(rows: &[(&'static str, String)], label: &str) -> String {
+        rows.iter()
+            .find(|(l, _)| *l == label)
+            .map(|(_, v)| v.clone())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_model_info_openai() {
+        let rows = rows_for("gpt", None, None);
+        assert_eq!(row(&rows, "provider"), "openai");
+        assert_eq!(row(&rows, "thinking type"), "OpenAIEffort");
+        assert_eq!(row(&rows, "sends"), "reasoning_effort = high");
+
+        let rows = rows_for("gpt", Some(false), None);
+        assert_eq!(row(&rows, "sends"), "reasoning_effort = minimal");
+        assert!(row(&rows, "note").contains("not supported by gpt-5"));
+
+        let rows = rows_for("gpt4", None, None);
+        assert_eq!(row(&rows, "thinking type"), "NotSupported");
+        assert_eq!(row(&rows, "sends"), "nothing");
+    }
+
+    #[test]
+    fn test_model_info_gemini() {
+        let rows = rows_for("flash", None, None);
+        assert_eq!(row(&rows, "thinking type"), "GeminiBudget");
+        assert_eq!(row(&rows, "sends"), "thinkingConfig.thinkingBudget = 4096");
+        assert_eq!(row(&rows, "web search"), "off");
+
+        let rows = rows_for("gemini3", None, None);
+        assert_eq!(row(&rows, "thinking type"), "GeminiLevel");
+        assert_eq!(row(&rows, "sends"), "thinkingConfig.thinkingLevel = HIGH");
+        assert_eq!(row(&rows, "web search"), "on");
+    }
+
+    #[test]
+    fn test_model_info_anthropic_and_ollama() {
+        let rows = rows_for("claude", None, None);
+        assert_eq!(row(&rows, "thinking"), "off");
+        assert_eq!(row(&rows, "sends"), "nothing");
+
+        let rows = rows_for("claude", Some(true), Some("high"));
+        assert_eq!(row(&rows, "thinking"), "high");
+        assert_eq!(row(&rows, "sends"), "thinking.budget_tokens = 16384");
+
+        let rows = rows_for("local", Some(true), None);
+        assert_eq!(row(&rows, "thinking type"), "OllamaThink");
+        assert_eq!(row(&rows, "sends"), "think = true");
+    }
+}
@@ -0,0 +1,143 @@
+//! Models used by the configured profiles, with capability hints (`ask models`)
+
+use super::Args;
+use crate::config::{model_capabilities, Config, ModelCapabilities};
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+
+/// One provider/model pair and the profiles that use it
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelEntry {
+    pub provider: String,
+    pub model: String,
+    pub profiles: Vec<String>,
+    #[serde(flatten)]
+    pub capabilities: ModelCapabilities,
+}
+
+/// Distinct models across profiles, in profile-name order
+pub fn collect_models(config: &Config) -> Vec<ModelEntry> {
+    let mut names: Vec<&String> = config.profiles.keys().collect();
+    names.sort();
+
+    let mut entries: Vec<ModelEntry> = Vec::new();
+    for name in names {
+        let args = Args {
+            profile: Some(name.clone()),
+            ..Default::default()
+        };
+        let resolved = config.clone().with_cli_overrides(&args);
+        let provider = resolved.active_provider().to_string();
+        let model = resolved.active_model().to_string();
+
+        match entries
+            .iter_mut()
+            .find(|e| e.provider == provider && e.model == model)
+        {
+            Some(entry) => entry.profiles.push(name.clone()),
+            None => entries.push(ModelEntry {
+                capabilities: model_capabilities(&provider, &model),
+                provider,
+                model,
+                profiles: vec![name.clone()],
+            }),
+        }
+    }
+    entries
+}
+
+/// Print the models, or a JSON array with `--json`
+pub fn list_models(config: &Config, args: &Args) -> Result<()> {
+    let entries = collect_models(config);
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    println!("{}", "Models".cyan().bold());
+    println!();
+
+    if entries.is_empty() {
+        println!(
+            "  {}",
+            "No profiles configured. Run 'ask init' to create one.".bright_black()
+        );
+        return Ok(());
+    }
+
+    for entry in &entries {
+        println!(
+            "  {}/{}{} {}",
+            entry.provider.bright_black(),
+            entry.model.bright_white(),
+            capability_tags(&entry.capabilities).cyan(),
+            format!("({})", entry.profiles.join(", ")).bright_black()
+        );
+    }
+    Ok(())
+}
+
+/// ` [thinking:reasoning_effort] [vision]` style suffix
+fn capability_tags(caps: &ModelCapabilities) -> String {
+    let mut tags = String::new();
+    if let Some(param) = caps.thinking_param {
+        tags.push_str(&format!(" [thinking:{}]", param));
+    }
+    if caps.is_reasoning && caps.thinking_param.is_none() {
+        tags.push_str(" [reasoning]");
+    }
+    if caps.supports_vision {
+        tags.push_str(" [vision]");
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_models_groups_profiles() {
+        let config = Config::from_toml(
+            r#"
+[profiles.work]
+provider = "openai"
+model = "gpt-5-mini"
+
+[profiles.ci]
+provider = "openai"
+model = "gpt-5-mini"
+
+[profiles.local]
+provider = "ollama"
+model = "llava:13b"
+"#,
+        )
+        .unwrap();
+
+        let entries = collect_models(&config);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].model, "gpt-5-mini");
+        assert_eq!(entries[0].profiles, vec!["ci", "work"]);
+        assert_eq!(entries[1].model, "llava:13b");
+
+        let json = serde_json::to_value(&entries).unwrap();
+        assert_eq!(json[0]["provider"], "openai");
+        assert_eq!(json[0]["is_reasoning"], true);
+        assert_eq!(json[0]["thinking_param"], "reasoning_effort");
+        assert_eq!(json[1]["supports_vision"], true);
+        assert_eq!(json[1]["thinking_param"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_capability_tags() {
+        let tags = capability_tags(&model_capabilities("openai", "gpt-5"));
+        assert_eq!(tags, " [thinking:reasoning_effort] [vision]");
+        assert_eq!(
+            capability_tags(&model_capabilities("openai", "gpt-3.5")),
+            ""
+        );
+    }
+}
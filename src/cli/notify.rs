@@ -0,0 +1,91 @@
+//! Bell and desktop notification when a slow answer arrives (`behavior.notify_after_secs`)
+
+use crate::config::Config;
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Notify only when enabled, the answer took at least the threshold, and someone is watching
+pub fn should_notify(threshold_secs: u64, elapsed: Duration, interactive: bool) -> bool {
+    threshold_secs > 0 && interactive && elapsed >= Duration::from_secs(threshold_secs)
+}
+
+/// Ring the terminal bell (and show a desktop notification, if enabled) for a slow query
+pub fn notify_if_slow(config: &Config, started: Instant, answer: &str) {
+    let interactive = std::io::stdout().is_terminal();
+    if !should_notify(
+        config.behavior.notify_after_secs,
+        started.elapsed(),
+        interactive,
+    ) {
+        return;
+    }
+
+    let mut stderr = std::io::stderr();
+    let _ = stderr.write_all(b"\x07");
+    let _ = stderr.flush();
+
+    if config.behavior.notify_desktop {
+        desktop_notification(&notification_body(answer));
+    }
+}
+
+/// First non-empty line of the answer, short enough for a notification bubble
+fn notification_body(answer: &str) -> String {
+    let line = answer
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("Answer ready");
+    if line.chars().count() > 100 {
+        format!("{}...", line.chars().take(100).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn desktop_notification(body: &str) {
+    let _ = std::process::Command::new("notify-send")
+        .args(["ask", body])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+}
+
+#[cfg(target_os = "macos")]
+fn desktop_notification(body: &str) {
+    let script = format!(
+        "display notification \"{}\" with title \"ask\"",
+        body.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+    let _ = std::process::Command::new("osascript")
+        .args(["-e", &script])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn desktop_notification(_body: &str) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_notify_threshold() {
+        let secs = Duration::from_secs;
+        assert!(should_notify(10, secs(12), true));
+        assert!(should_notify(10, secs(10), true));
+        assert!(!should_notify(10, secs(9), true), "fast answers stay quiet");
+        assert!(!should_notify(10, secs(60), false), "piped output");
+        assert!(!should_notify(0, secs(600), true), "disabled by default");
+    }
+
+    #[test]
+    fn test_notification_body() {
+        assert_eq!(notification_body("\n  du -sh .\nmore"), "du -sh .");
+        assert_eq!(notification_body(""), "Answer ready");
+        assert_eq!(notification_body(&"x".repeat(150)).chars().count(), 103);
+    }
+}
@@ -2,6 +2,31 @@
 
 use std::env;
 
+/// `--format`: one switch for the output style
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Plain text (same as --no-markdown)
+    Text,
+    /// Rendered Markdown (same as --markdown)
+    Markdown,
+    /// Ask for a pipe-delimited table and render it
+    Table,
+    /// JSON envelope (same as --json)
+    Json,
+}
+
+impl OutputFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "text" | "plain" => Some(Self::Text),
+            "markdown" | "md" => Some(Self::Markdown),
+            "table" => Some(Self::Table),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Args {
     /// Use/create context for current directory (value = TTL in minutes, 0 = permanent)
@@ -16,6 +41,9 @@ pub struct Args {
     /// None = prompt, Some(true) = -y/--yes, Some(false) = --confirm
     pub yes: Option<bool>,
 
+    /// Auto-execute only commands deemed safe; prompt for anything else
+    pub yes_safe: bool,
+
     /// Override configured model
     pub model: Option<String>,
 
@@ -43,10 +71,25 @@ pub struct Args {
     /// Output raw text without formatting
     pub raw: bool,
 
+    /// Print the answer exactly as received: no trim, flattening or command detection
+    pub preserve: bool,
+
+    /// `--format text|markdown|table|json`; also sets the matching flag above
+    pub format: Option<OutputFormat>,
+
+    /// Stream raw chunks straight to stdout (no spinner, indicator or injection)
+    pub raw_stream: bool,
+
+    /// Stream NDJSON events to stdout: one `delta` per chunk, then `done`
+    pub json_stream: bool,
+
     /// Enable/disable colorized output
     /// None = default (enabled), Some(true) = --color, Some(false) = --no-color
     pub color: Option<bool>,
 
+    /// Color preset for this run (`--theme light`), over `[theme] preset`
+    pub theme: Option<String>,
+
     /// Enable/disable result echo after execution
     /// None = default (enabled), Some(true) = --follow, Some(false) = --no-follow
     pub follow: Option<bool>,
@@ -73,6 +116,18 @@ pub struct Args {
     /// Show version
     pub version: bool,
 
+    /// Run diagnostics (`ask doctor`)
+    pub doctor: bool,
+
+    /// Show usage and cost totals from the usage log (`ask stats`, `--stats`)
+    pub stats: bool,
+
+    /// Limit `ask stats` to a recent span, e.g. `7d` (`--since`)
+    pub since: Option<String>,
+
+    /// Ping this provider with only the flags' key/model/URL (`--health NAME`)
+    pub health: Option<String>,
+
     /// Initialize configuration
     pub init: bool,
 
@@ -82,9 +137,27 @@ pub struct Args {
     /// Show context history
     pub show_history: bool,
 
+    /// Continue the previous answer in the context (`--more`)
+    pub more: bool,
+
+    /// Send the context but don't save this exchange to it (`--ephemeral`)
+    pub ephemeral: bool,
+
+    /// Ask for a unified diff and apply it to the file after confirmation (`--apply`)
+    pub apply: bool,
+
+    /// Re-run the generated command whenever files in the cwd change (`--watch`)
+    pub watch: bool,
+
+    /// With `--watch`, ask the model for a fresh command before each run
+    pub watch_regenerate: bool,
+
     /// INTERNAL: Inject command via uinput (hidden)
     pub inject_raw: Option<String>,
 
+    /// INTERNAL: Keep stdin on the clipboard until it is replaced (hidden, used by `--copy`)
+    pub hold_clipboard: bool,
+
     /// Generate shell completions
     pub completions: Option<String>,
 
@@ -94,9 +167,45 @@ pub struct Args {
     /// Verbose mode - show profile and other debug info
     pub verbose: bool,
 
+    /// Print the argv before and after alias expansion, then stop (`--show-expansion`)
+    pub show_expansion: bool,
+
+    /// Run the previous query again with its flags (`--retry-last`)
+    pub retry_last: bool,
+
+    /// Correct a prose answer in $EDITOR before it is saved to the context (`--edit-answer`)
+    pub edit_answer: bool,
+
+    /// Run the remaining lines of a multi-line command after one fails (`--keep-going`)
+    pub keep_going: bool,
+
+    /// Send binary stdin base64-encoded instead of refusing it (`--allow-binary`)
+    pub allow_binary: bool,
+
+    /// The arguments this was parsed from, after alias expansion (saved for `--retry-last`)
+    pub argv: Vec<String>,
+
     /// List available profiles
     pub list_profiles: bool,
 
+    /// List saved prompt templates (`ask templates`)
+    pub list_templates: bool,
+
+    /// List the profiles' models with capability hints (`ask models`, `--list-models`)
+    pub list_models: bool,
+
+    /// Print the resolved provider, model and thinking parameters without a call
+    pub model_info: bool,
+
+    /// List the config file locations and which ones are in use (`--dump-config-path`)
+    pub dump_config_path: bool,
+
+    /// Print `export` lines for a profile, for `eval` in a shell (`--profile-env NAME`)
+    pub profile_env: Option<String>,
+
+    /// `ask alias [list|add NAME EXPANSION...|remove NAME]`: the words after `alias`
+    pub alias_command: Option<Vec<String>>,
+
     /// List all global history
     pub history_subcommand: bool,
 
@@ -109,6 +218,9 @@ pub struct Args {
     /// Prune history entries whose directories no longer exist
     pub history_prune: bool,
 
+    /// Browse history interactively
+    pub history_browse: bool,
+
     /// Global flag (used with history subcommand)
     pub global: bool,
 
@@ -121,14 +233,136 @@ pub struct Args {
     /// API key for non-interactive init
     pub api_key: Option<String>,
 
+    /// Read the API key for non-interactive init from stdin
+    pub key_from_stdin: bool,
+
+    /// Profile name for non-interactive init
+    pub profile_name: Option<String>,
+
+    /// Base URL for non-interactive init
+    pub base_url: Option<String>,
+
+    /// Thinking level/budget for non-interactive init
+    pub thinking: Option<String>,
+
+    /// Overwrite an existing config on non-interactive init
+    pub force: bool,
+
+    /// Kill executed commands after N seconds (overrides behavior.exec_timeout_secs)
+    pub exec_timeout: Option<u64>,
+
+    /// Character budget for context history (overrides context.max_chars)
+    pub max_context_chars: Option<usize>,
+
+    /// Cap on answer length in tokens (`--max-tokens`)
+    pub max_tokens: Option<u32>,
+
+    /// Sampling seed for reproducible answers, with temperature 0 (`--seed`)
+    pub seed: Option<u64>,
+
+    /// Ask for N candidate answers (`-N`/`--count`, 1-10)
+    pub count: Option<usize>,
+
+    /// Wrap prose at N columns (overrides behavior.wrap_columns; 0 = --no-wrap)
+    pub wrap: Option<usize>,
+
+    /// Explicit config file (skips discovery, like ASK_CONFIG)
+    pub config: Option<String>,
+
+    /// `key=value` to write into the config file (`--set`)
+    pub set: Option<String>,
+
+    /// File that gets a copy of the streamed answer (`--tee`)
+    pub tee: Option<std::path::PathBuf>,
+
+    /// Remote images sent with the question (`--image-url`, repeatable)
+    pub image_urls: Vec<String>,
+
+    /// Text put before the assembled question (`--prefix`)
+    pub prefix: Option<String>,
+
+    /// Text put after the assembled question (`--suffix`)
+    pub suffix: Option<String>,
+
+    /// System prompt file for this invocation (wins over ask.md and the built-in prompt)
+    pub prompt_file: Option<std::path::PathBuf>,
+
+    /// Saved prompt template used as the system prompt (`--template NAME`)
+    pub template: Option<String>,
+
+    /// Keep the context in this JSON file instead of the per-directory store
+    pub context_file: Option<std::path::PathBuf>,
+
+    /// Continue the saved context whose ID starts with this prefix (`--resume`)
+    pub resume: Option<String>,
+
+    /// Export context to a Markdown or JSON file
+    pub export: Option<String>,
+
+    /// Import a JSON transcript into the current context
+    pub import: Option<String>,
+
+    /// Named context session in the current directory (`--session`)
+    pub session: Option<String>,
+
+    /// Copy the current context into a new session and switch to it (`--fork`)
+    pub fork: Option<String>,
+
+    /// Benchmark the query across profiles
+    pub bench: bool,
+
+    /// Restrict --bench to these profiles (comma-separated)
+    pub bench_profiles: Option<Vec<String>>,
+
+    /// Review the piped diff and print structured comments (`--review`)
+    pub review: bool,
+
+    /// Rotate the starting profile through `provider_order` (`--round-robin`)
+    pub round_robin: bool,
+
+    /// Print an estimated cost from the reported token usage
+    pub cost: bool,
+
+    /// Never touch the network (`--offline`, or `ASK_OFFLINE=1`)
+    pub offline: bool,
+
+    /// Print provider request bodies to stderr (`--debug-request`)
+    pub debug_request: bool,
+
+    /// Print the model's reasoning trace above the answer (`--show-thinking`)
+    pub show_thinking: bool,
+
+    /// Copy the answer (or flattened command) to the clipboard instead of injecting it
+    pub copy: bool,
+
+    /// Never paste commands into the terminal (like `behavior.inject = "off"`)
+    pub no_inject: bool,
+
+    /// Ask before a GUI paste (like `behavior.confirm_paste`)
+    pub safe_paste: bool,
+
+    /// Ask the model why an executed command failed (like `behavior.explain_failures`)
+    pub explain_error: bool,
+
     /// The actual query text (all non-flag arguments concatenated)
     pub query: Vec<String>,
 }
 
 impl Args {
+    /// Apply `--format`, mapping it onto the individual output flags
+    fn set_format(&mut self, value: &str) {
+        self.format = OutputFormat::parse(value);
+        match self.format {
+            Some(OutputFormat::Text) => self.markdown = Some(false),
+            Some(OutputFormat::Markdown) | Some(OutputFormat::Table) => self.markdown = Some(true),
+            Some(OutputFormat::Json) => self.json = true,
+            None => {}
+        }
+    }
+
     /// Check if context is enabled
     pub fn has_context(&self) -> bool {
-        self.context.is_some()
+        self.context.is_some() || self.context_file.is_some() || self.resume.is_some()
     }
 
     /// Get context TTL in minutes (default 30)
@@ -139,30 +373,37 @@ impl Args {
     /// Parse arguments flexibly, allowing flags before or after text
     pub fn parse_flexible() -> Self {
         let raw_args: Vec<String> = env::args().skip(1).collect();
-        let args = Self::expand_aliases(raw_args);
-        Self::parse_args(args)
+
+        // Pin --config before anything loads the config (aliases included)
+        pin_config_path(&raw_args);
+
+        let args = Self::expand_aliases(raw_args.clone());
+        let parsed = Self::parse_args(args.clone());
+        if parsed.show_expansion || (parsed.verbose && args != raw_args) {
+            use colored::Colorize;
+            eprintln!(
+                "{} {}",
+                "[aliases]".bright_black(),
+                expansion_line(&raw_args, &args)
+            );
+        }
+        parsed
     }
 
     fn expand_aliases(args: Vec<String>) -> Vec<String> {
-        let aliases = crate::config::Config::load_aliases_only();
-        if aliases.is_empty() {
+        // `ask alias remove q` must see `q` itself
+        if args.first().map(String::as_str) == Some("alias") && is_alias_command(&args[1..]) {
             return args;
         }
 
-        let mut expanded = Vec::new();
-        for arg in args {
-            if let Some(expansion) = aliases.get(&arg) {
-                for part in expansion.split_whitespace() {
-                    expanded.push(part.to_string());
-                }
-            } else {
-                expanded.push(arg);
-            }
+        let aliases = crate::config::Config::load_aliases_only();
+        if aliases.is_empty() {
+            return args;
         }
-        expanded
+        expand_with(args, &aliases)
     }
 
-    fn parse_args(args: Vec<String>) -> Self {
+    pub(super) fn parse_args(args: Vec<String>) -> Self {
         let mut result = Args::default();
         let mut query_parts: Vec<String> = Vec::new();
         let mut i = 0;
@@ -198,15 +439,20 @@ impl Args {
                 "--command" => result.command_mode = Some(true),
                 "--question" => result.command_mode = Some(false),
                 "--yes" => result.yes = Some(true),
+                "--yes-safe" => result.yes_safe = true,
                 "--confirm" => result.yes = Some(false),
                 "--json" => result.json = true,
                 "--markdown" => result.markdown = Some(true),
                 "--no-markdown" => result.markdown = Some(false),
                 "--raw" => result.raw = true,
+                "--preserve" | "--no-trim" => result.preserve = true,
+                "--raw-stream" => result.raw_stream = true,
+                "--json-stream" => result.json_stream = true,
                 "--no-color" | "--color=false" => result.color = Some(false),
                 "--color" | "--color=true" => result.color = Some(true),
                 "--no-follow" => result.follow = Some(false),
                 "--follow" => result.follow = Some(true),
+                "--no-wrap" => result.wrap = Some(0),
                 "--no-fallback" => result.fallback = Some(false),
                 "--fallback" => result.fallback = Some(true),
                 "--stream" | "--stream=true" => result.stream = Some(true),
@@ -239,8 +485,13 @@ impl Args {
                 }
                 "--update" => result.update = true,
                 "--make-prompt" => result.make_prompt = true,
+                "--list-models" => result.list_models = true,
+                "--model-info" => result.model_info = true,
                 "--make-config" => result.make_config = true,
                 "--non-interactive" | "-n" => result.non_interactive = true,
+                "--key-from-stdin" => result.key_from_stdin = true,
+                "--force" => result.force = true,
+                "--init" => result.init = true,
                 "-v" | "--verbose" => result.verbose = true,
                 "--version" | "-V" => result.version = true,
                 "--help" | "-h" => {
@@ -255,6 +506,17 @@ impl Args {
                 // Subcommands
                 "init" | "config" if query_parts.is_empty() => result.init = true,
                 "profiles" if query_parts.is_empty() => result.list_profiles = true,
+                "templates" if query_parts.is_empty() => result.list_templates = true,
+                "models" if query_parts.is_empty() => result.list_models = true,
+                "version" if query_parts.is_empty() => result.version = true,
+                "doctor" if query_parts.is_empty() => result.doctor = true,
+                "stats" if query_parts.is_empty() => result.stats = true,
+                "--stats" => result.stats = true,
+                // Everything after `alias` is taken as-is, so expansions may contain flags
+                "alias" if query_parts.is_empty() && is_alias_command(&args[i + 1..]) => {
+                    result.alias_command = Some(args[i + 1..].to_vec());
+                    break;
+                }
                 "history" if query_parts.is_empty() => {
                     result.history_subcommand = true;
 
@@ -283,8 +545,34 @@ impl Args {
                         }
                     }
                 }
+                "--bench" => result.bench = true,
+                "--review" => result.review = true,
+                "--round-robin" => result.round_robin = true,
+                "--cost" => result.cost = true,
+                "--offline" => result.offline = true,
+                "--debug-request" | "--provider-raw-request" => result.debug_request = true,
+                "--show-expansion" => result.show_expansion = true,
+                "--retry-last" => result.retry_last = true,
+                "--allow-binary" => result.allow_binary = true,
+                "--keep-going" => result.keep_going = true,
+                "--edit-answer" | "--interactive-edit" => result.edit_answer = true,
+                "--dump-config-path" | "--where-config" => result.dump_config_path = true,
+                "--show-thinking" | "--thinking-output" => result.show_thinking = true,
+                "--no-inject" | "--no-injection" => result.no_inject = true,
+                "--safe-paste" => result.safe_paste = true,
+                "--explain-error" => result.explain_error = true,
+                "--copy" | "--clipboard" => result.copy = true,
                 "--clear" => result.clear_context = true,
                 "--history" => result.show_history = true,
+                "--more" | "--append" => result.more = true,
+                "--ephemeral" | "--no-history" => result.ephemeral = true,
+                "--apply" | "--diff-apply" => result.apply = true,
+                "--watch" => result.watch = true,
+                "--watch-regenerate" => {
+                    result.watch = true;
+                    result.watch_regenerate = true;
+                }
+                "--browse" => result.history_browse = true,
                 "--global" => result.global = true,
 
                 // Flags with values
@@ -313,6 +601,181 @@ impl Args {
                     }
                 }
 
+                "--profile-env" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.profile_env = Some(args[i].clone());
+                    }
+                }
+                "--profile-name" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.profile_name = Some(args[i].clone());
+                    }
+                }
+                "--base-url" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.base_url = Some(args[i].clone());
+                    }
+                }
+                "--thinking" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.thinking = Some(args[i].clone());
+                    }
+                }
+                "--config" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.config = Some(args[i].clone());
+                    }
+                }
+                "--set" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.set = Some(args[i].clone());
+                    }
+                }
+                "--tee" | "--stream-to-file" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.tee = Some(args[i].clone().into());
+                    }
+                }
+                "--image-url" | "--input-image-url" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.image_urls.push(args[i].clone());
+                    }
+                }
+                "--health" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.health = Some(args[i].clone());
+                    }
+                }
+                "--prefix" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.prefix = Some(args[i].clone());
+                    }
+                }
+                "--suffix" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.suffix = Some(args[i].clone());
+                    }
+                }
+                "--prompt-file" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.prompt_file = Some(args[i].clone().into());
+                    }
+                }
+                "--template" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.template = Some(args[i].clone());
+                    }
+                }
+                "--theme" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.theme = Some(args[i].clone());
+                    }
+                }
+                "--context-file" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.context_file = Some(args[i].clone().into());
+                    }
+                }
+                "--resume" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.resume = Some(args[i].clone());
+                    }
+                }
+                "--export" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.export = Some(args[i].clone());
+                    }
+                }
+                "--import" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.import = Some(args[i].clone());
+                    }
+                }
+                "--session" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.session = Some(args[i].clone());
+                    }
+                }
+                "--fork" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.fork = Some(args[i].clone());
+                    }
+                }
+                "--exec-timeout" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.exec_timeout = args[i].parse().ok();
+                    }
+                }
+                "-N" | "--count" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.count = parse_count(&args[i]);
+                    }
+                }
+                "--wrap" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.wrap = args[i].parse().ok();
+                    }
+                }
+                "--format" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.set_format(&args[i]);
+                    }
+                }
+                "--max-context-chars" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.max_context_chars = args[i].parse().ok();
+                    }
+                }
+                "--max-tokens" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.max_tokens = args[i].parse().ok();
+                    }
+                }
+                "--seed" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.seed = args[i].parse().ok();
+                    }
+                }
+                "--profiles" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.bench_profiles = Some(split_profile_list(&args[i]));
+                    }
+                }
+                "--since" => {
+                    i += 1;
+                    if i < args.len() {
+                        result.since = Some(args[i].clone());
+                    }
+                }
+
                 // Hidden internal flag for background injection
                 "--inject-raw" => {
                     i += 1;
@@ -320,6 +783,7 @@ impl Args {
                         result.inject_raw = Some(args[i].clone());
                     }
                 }
+                "--hold-clipboard" => result.hold_clipboard = true,
 
                 // Generate shell completions
                 "--completions" => {
@@ -347,58 +811,222 @@ impl Args {
                     result.profile = Some(value.to_string());
                 }
 
-                // Handle --markdown=true|false format
-                s if s.starts_with("--markdown=") => {
-                    let value = s.strip_prefix("--markdown=").unwrap();
-                    result.markdown = Some(value == "true" || value == "1");
+                s if s.starts_with("--api-key=") => {
+                    let value = s.strip_prefix("--api-key=").unwrap();
+                    result.api_key = Some(value.to_string());
                 }
 
-                arg if arg.starts_with('-') && !arg.starts_with("--") && arg.len() > 2 => {
-                    let chars: Vec<char> = arg.chars().skip(1).collect();
-                    let mut j = 0;
-                    while j < chars.len() {
-                        let c = chars[j];
-                        let remaining: String = chars[j + 1..].iter().collect();
+                // Handle --profile-name=, --base-url= and --thinking= formats
+                s if s.starts_with("--profile-env=") => {
+                    let value = s.strip_prefix("--profile-env=").unwrap();
+                    result.profile_env = Some(value.to_string());
+                }
+                s if s.starts_with("--profile-name=") => {
+                    let value = s.strip_prefix("--profile-name=").unwrap();
+                    result.profile_name = Some(value.to_string());
+                }
+                s if s.starts_with("--base-url=") => {
+                    let value = s.strip_prefix("--base-url=").unwrap();
+                    result.base_url = Some(value.to_string());
+                }
+                s if s.starts_with("--thinking=") => {
+                    let value = s.strip_prefix("--thinking=").unwrap();
+                    result.thinking = Some(value.to_string());
+                }
 
-                        match c {
-                            'c' => {
-                                if remaining.is_empty() {
-                                    result.context = Some(30);
-                                    break;
-                                } else if let Ok(minutes) = remaining.parse::<u64>() {
-                                    result.context = Some(minutes);
-                                    break; // POSIX: value consumes rest
-                                } else {
-                                    result.context = Some(30);
-                                    break;
-                                }
-                            }
-                            't' => {
-                                match remaining.as_str() {
-                                    s if s == "0" || s == "false" => {
-                                        result.think = Some(false);
-                                        break; // POSIX: value consumes rest
-                                    }
-                                    s if s == "1" || s == "true" => {
-                                        result.think = Some(true);
-                                        break;
-                                    }
-                                    s if s.starts_with('0') => {
-                                        result.think = Some(false);
-                                        break;
-                                    }
-                                    s if s.starts_with('1') => {
-                                        result.think = Some(true);
-                                        break;
-                                    }
-                                    s if is_think_level(s) => {
-                                        result.think = Some(true);
-                                        result.think_level = Some(s.to_string());
-                                        break;
-                                    }
-                                    _ => result.think = Some(true),
-                                }
-                            }
+                // Handle --config=PATH format
+                s if s.starts_with("--config=") => {
+                    let value = s.strip_prefix("--config=").unwrap();
+                    result.config = Some(value.to_string());
+                }
+
+                // Handle --set=key=value format
+                s if s.starts_with("--set=") => {
+                    let value = s.strip_prefix("--set=").unwrap();
+                    result.set = Some(value.to_string());
+                }
+
+                // Handle --tee=FILE format
+                s if s.starts_with("--tee=") => {
+                    let value = s.strip_prefix("--tee=").unwrap();
+                    result.tee = Some(value.into());
+                }
+                s if s.starts_with("--stream-to-file=") => {
+                    let value = s.strip_prefix("--stream-to-file=").unwrap();
+                    result.tee = Some(value.into());
+                }
+
+                // Handle --image-url=URL format
+                s if s.starts_with("--image-url=") => {
+                    let value = s.strip_prefix("--image-url=").unwrap();
+                    result.image_urls.push(value.to_string());
+                }
+                s if s.starts_with("--input-image-url=") => {
+                    let value = s.strip_prefix("--input-image-url=").unwrap();
+                    result.image_urls.push(value.to_string());
+                }
+
+                s if s.starts_with("--health=") => {
+                    result.health = Some(s.strip_prefix("--health=").unwrap().to_string());
+                }
+
+                // Handle --prefix=TEXT / --suffix=TEXT format
+                s if s.starts_with("--prefix=") => {
+                    result.prefix = Some(s.strip_prefix("--prefix=").unwrap().to_string());
+                }
+                s if s.starts_with("--suffix=") => {
+                    result.suffix = Some(s.strip_prefix("--suffix=").unwrap().to_string());
+                }
+
+                // Handle --prompt-file=FILE format
+                s if s.starts_with("--prompt-file=") => {
+                    let value = s.strip_prefix("--prompt-file=").unwrap();
+                    result.prompt_file = Some(value.into());
+                }
+
+                // Handle --template=NAME format
+                s if s.starts_with("--template=") => {
+                    let value = s.strip_prefix("--template=").unwrap();
+                    result.template = Some(value.to_string());
+                }
+                s if s.starts_with("--theme=") => {
+                    let value = s.strip_prefix("--theme=").unwrap();
+                    result.theme = Some(value.to_string());
+                }
+
+                // Handle --resume=ID format
+                s if s.starts_with("--resume=") => {
+                    let value = s.strip_prefix("--resume=").unwrap();
+                    result.resume = Some(value.to_string());
+                }
+
+                // Handle --context-file=FILE format
+                s if s.starts_with("--context-file=") => {
+                    let value = s.strip_prefix("--context-file=").unwrap();
+                    result.context_file = Some(value.into());
+                }
+
+                // Handle --export=PATH and --import=PATH formats
+                s if s.starts_with("--export=") => {
+                    let value = s.strip_prefix("--export=").unwrap();
+                    result.export = Some(value.to_string());
+                }
+                s if s.starts_with("--import=") => {
+                    let value = s.strip_prefix("--import=").unwrap();
+                    result.import = Some(value.to_string());
+                }
+
+                // Handle --session=NAME and --fork=NAME formats
+                s if s.starts_with("--session=") => {
+                    let value = s.strip_prefix("--session=").unwrap();
+                    result.session = Some(value.to_string());
+                }
+                s if s.starts_with("--fork=") => {
+                    let value = s.strip_prefix("--fork=").unwrap();
+                    result.fork = Some(value.to_string());
+                }
+
+                // Handle --exec-timeout=N format
+                s if s.starts_with("--exec-timeout=") => {
+                    let value = s.strip_prefix("--exec-timeout=").unwrap();
+                    result.exec_timeout = value.parse().ok();
+                }
+
+                // Handle --count=N format
+                s if s.starts_with("--count=") => {
+                    let value = s.strip_prefix("--count=").unwrap();
+                    result.count = parse_count(value);
+                }
+
+                // Handle --wrap=N format
+                s if s.starts_with("--wrap=") => {
+                    let value = s.strip_prefix("--wrap=").unwrap();
+                    result.wrap = value.parse().ok();
+                }
+
+                // Handle --max-context-chars=N format
+                s if s.starts_with("--max-context-chars=") => {
+                    let value = s.strip_prefix("--max-context-chars=").unwrap();
+                    result.max_context_chars = value.parse().ok();
+                }
+
+                // Handle --max-tokens=N format
+                s if s.starts_with("--max-tokens=") => {
+                    let value = s.strip_prefix("--max-tokens=").unwrap();
+                    result.max_tokens = value.parse().ok();
+                }
+                s if s.starts_with("--seed=") => {
+                    result.seed = s.strip_prefix("--seed=").unwrap().parse().ok();
+                }
+
+                // Handle --profiles=a,b,c format
+                s if s.starts_with("--profiles=") => {
+                    let value = s.strip_prefix("--profiles=").unwrap();
+                    result.bench_profiles = Some(split_profile_list(value));
+                }
+
+                s if s.starts_with("--since=") => {
+                    result.since = Some(s.strip_prefix("--since=").unwrap().to_string());
+                }
+
+                // Handle --format=VALUE format
+                s if s.starts_with("--format=") => {
+                    let value = s.strip_prefix("--format=").unwrap();
+                    result.set_format(value);
+                }
+
+                // Handle --markdown=true|false format
+                s if s.starts_with("--markdown=") => {
+                    let value = s.strip_prefix("--markdown=").unwrap();
+                    result.markdown = Some(value == "true" || value == "1");
+                }
+
+                arg if arg.starts_with('-') && !arg.starts_with("--") && arg.len() > 2 => {
+                    let chars: Vec<char> = arg.chars().skip(1).collect();
+                    let mut j = 0;
+                    while j < chars.len() {
+                        let c = chars[j];
+                        let remaining: String = chars[j + 1..].iter().collect();
+
+                        match c {
+                            'c' => {
+                                if remaining.is_empty() {
+                                    result.context = Some(30);
+                                    break;
+                                } else if let Ok(minutes) = remaining.parse::<u64>() {
+                                    result.context = Some(minutes);
+                                    break; // POSIX: value consumes rest
+                                } else {
+                                    result.context = Some(30);
+                                    break;
+                                }
+                            }
+                            't' => {
+                                match remaining.as_str() {
+                                    s if s == "0" || s == "false" => {
+                                        result.think = Some(false);
+                                        break; // POSIX: value consumes rest
+                                    }
+                                    s if s == "1" || s == "true" => {
+                                        result.think = Some(true);
+                                        break;
+                                    }
+                                    s if s.starts_with('0') => {
+                                        result.think = Some(false);
+                                        break;
+                                    }
+                                    s if s.starts_with('1') => {
+                                        result.think = Some(true);
+                                        break;
+                                    }
+                                    s if is_think_level(s) => {
+                                        result.think = Some(true);
+                                        result.think_level = Some(s.to_string());
+                                        break;
+                                    }
+                                    _ => result.think = Some(true),
+                                }
+                            }
                             's' => match remaining.as_str() {
                                 "0" | "false" => {
                                     result.search = Some(false);
@@ -418,6 +1046,17 @@ impl Args {
                                 }
                                 _ => result.search = Some(true),
                             },
+                            'N' => {
+                                if remaining.is_empty() {
+                                    i += 1;
+                                    if i < args.len() {
+                                        result.count = parse_count(&args[i]);
+                                    }
+                                } else {
+                                    result.count = parse_count(&remaining);
+                                }
+                                break; // POSIX: value consumes rest
+                            }
                             'x' => result.command_mode = Some(true),
                             'y' => result.yes = Some(true),
                             'v' => result.verbose = true,
@@ -449,6 +1088,7 @@ impl Args {
         }
 
         result.query = query_parts;
+        result.argv = args;
         result
     }
 }
@@ -461,6 +1101,8 @@ All configuration options can be set via environment variables with the ASK_ pre
 These override config file values but are overridden by CLI arguments.
 
 PROFILE & PROVIDER SELECTION:
+    ASK_CONFIG                Load only this config file (skips discovery)
+    ASK_PROMPT_MAX_DEPTH      Parent dirs searched for ask.md/ask.toml (default: 10)
     ASK_PROFILE               Select profile (like -p), mutually exclusive with ASK_PROVIDER
     ASK_PROVIDER              Ad-hoc mode provider (like -P), mutually exclusive with ASK_PROFILE
     ASK_MODEL                 Override model name
@@ -469,20 +1111,27 @@ API KEYS (used with ASK_PROVIDER or as fallback):
     ASK_GEMINI_API_KEY        Gemini API key
     ASK_OPENAI_API_KEY        OpenAI API key
     ASK_ANTHROPIC_API_KEY     Anthropic API key
+    ASK_AZURE_API_KEY         Azure OpenAI API key
 
 CUSTOM BASE URLS (for proxies or OpenAI-compatible APIs like Ollama):
     ASK_GEMINI_BASE_URL       Custom Gemini API endpoint
     ASK_OPENAI_BASE_URL       Custom OpenAI API endpoint (e.g., http://localhost:11434/v1)
     ASK_ANTHROPIC_BASE_URL    Custom Anthropic API endpoint
+    ASK_AZURE_BASE_URL        Azure OpenAI endpoint (e.g., https://myresource.openai.azure.com)
 
 BEHAVIOR:
     ASK_AUTO_EXECUTE          Auto-execute safe commands without prompting (true/false)
     ASK_CONFIRM_DESTRUCTIVE   Confirm before running destructive commands (true/false)
-    ASK_TIMEOUT               Request timeout in seconds (default: 30)
+    ASK_TIMEOUT               Request timeout in seconds, non-streaming (default: 30)
+    ASK_CONNECT_TIMEOUT       Connect timeout in seconds (default: 30)
+    ASK_EXEC_TIMEOUT          Kill executed commands after N seconds (default: 0 = no limit)
+    ASK_INJECT                Command injection: auto, prompt or off (default: auto)
+    ASK_EXPLAIN_FAILURES      Explain failed commands, like --explain-error (true/false)
 
 CONTEXT SETTINGS:
     ASK_CONTEXT_MAX_AGE       Context TTL in minutes (default: 30)
     ASK_CONTEXT_MAX_MESSAGES  Maximum messages to keep in context (default: 20)
+    ASK_CONTEXT_MAX_CHARS     Character budget for context sent to the provider (default: 0 = unlimited)
     ASK_CONTEXT_PATH          Custom path for context storage
 
 UPDATE SETTINGS:
@@ -490,9 +1139,22 @@ UPDATE SETTINGS:
     ASK_UPDATE_INTERVAL       Hours between update checks (default: 24)
     ASK_UPDATE_CHANNEL        Update channel (stable, beta, etc.)
     ASK_NO_UPDATE             Disable all update functionality (set to 1)
+    ASK_GITHUB_TOKEN          GitHub token for update checks (falls back to GITHUB_TOKEN)
+
+NETWORK:
+    HTTPS_PROXY, HTTP_PROXY   Proxy for HTTPS/HTTP requests (standard env vars)
+    ALL_PROXY                 Proxy for all requests
+    NO_PROXY                  Comma-separated hosts that bypass the proxy
+                              ([http] proxy in config overrides these)
+    ASK_OFFLINE               Never touch the network, same as --offline (set to 1)
 
 DISPLAY:
     NO_COLOR                  Disable colored output (standard env var)
+    TERM=dumb                 Plain output: no colors, spinner or cursor movement
+
+DEBUGGING:
+    RUST_LOG                  Diagnostic logs on stderr, e.g. ask=debug (API keys are redacted)
+    ASK_DEBUG_LOG             Append provider requests and responses to this file (keys redacted)
 
 EXAMPLES:
     # Use a specific profile
@@ -526,7 +1188,9 @@ OPTIONS:
                           Examples: -c (30 min), -c60 (60 min), --context=120 (2 hours)
     -x, --command         Force command mode (bypass auto-detection)
         --question        Force question mode (bypass auto-detection)
+    -N, --count <N>       Get N candidate answers (1-10); pick one to run in command mode
     -y, --yes             Auto-execute commands without confirmation
+        --yes-safe        Auto-execute only safe commands, prompt for the rest
         --confirm         Always prompt for confirmation (override -y/config)
     -t, --think[=LEVEL]   Enable thinking mode (levels: low, medium, high)
         --no-think        Disable thinking mode
@@ -535,40 +1199,107 @@ OPTIONS:
     -P, --provider <NAME> Override configured provider
     -k, --api-key <KEY>   API key (for use with init -n)
     -n, --non-interactive Non-interactive init (use with -P, -m, -k)
+        --key-from-stdin  Read the API key for init -n from stdin
+        --profile-name <NAME>  Profile to create on init -n (default: main)
+        --base-url <URL>  Base URL for the profile created on init -n
+        --thinking <VAL>  Thinking level/budget for the profile created on init -n
+        --force           Overwrite an existing config on init -n
         --stream          Enable streaming responses
         --no-stream       Disable streaming responses
     -s, --search          Enable web search for this query
         --no-search       Disable web search (override profile)
         --citations       Show citations from web search results
         --no-citations    Hide citations (override profile)
+        --cost            Print estimated cost from token usage (disables streaming)
+        --show-thinking   Print the reasoning trace above the answer (with -t)
+        --copy            Copy the answer or command to the clipboard (alias: --clipboard)
+        --no-inject       Never paste commands into the terminal; confirm before running
+        --safe-paste      Ask before pasting a command into the focused window
+        --explain-error   Ask the model to explain a failed command and suggest a fix
+        --offline         Never touch the network (no update checks, no provider calls)
+        --debug-request   Print each request sent to the provider (keys redacted) to stderr
+        --health <NAME>   Ping one provider using only -k/-m/--base-url (config ignored)
         --fallback        Enable fallback to other profiles (default)
         --no-fallback     Disable fallback to other profiles
+        --round-robin     Rotate the starting profile through provider_order
         --follow          Enable result echo after execution (default)
         --no-follow       Disable result echo after execution
+        --exec-timeout <SECS>  Kill executed commands after SECS seconds
+        --keep-going      Multi-line commands (flatten_commands = false): run the rest after a failure
+        --max-context-chars <N>  Drop oldest context messages beyond N characters
+        --max-tokens <N>  Cap the answer at N tokens (default depends on the model)
+        --seed <N>        Reproducible answers: send seed N (OpenAI, Ollama) and temperature 0
+        --wrap <N>        Wrap prose answers at N columns (turns off streaming)
+        --no-wrap         Do not wrap prose answers
         --json            Output in JSON format
-        --markdown        Enable markdown rendering
-        --no-markdown     Disable markdown rendering
+        --markdown        Always render markdown (default: only when the answer uses it)
+        --no-markdown     Never render markdown
+        --format <FMT>    Output format: text, markdown, table or json
         --raw             Output raw text without formatting
+        --preserve        Print the answer byte for byte (no trimming, formatting or commands)
+        --raw-stream      Stream plain chunks to stdout, for piping
+        --json-stream     Stream NDJSON events to stdout (delta per chunk, then done)
+        --tee <FILE>      Stream the answer as usual and save a copy to FILE
+        --edit-answer     Edit a prose answer in $EDITOR before saving it (use with -c)
         --color           Enable colorized output (default)
         --no-color        Disable colorized output
+        --theme <NAME>    Color preset: dark (default) or light; [theme] in config sets roles
         --make-prompt     Export default prompt template to stdout
         --make-config     Export example ask.toml to stdout
+        --set <KEY=VALUE> Set one config value, e.g. --set behavior.auto_execute=true
+        --config <FILE>   Load only this config file (skips discovery)
+        --prompt-file <FILE>  Use FILE as the system prompt for this query
+        --template <NAME> Use a saved template (~/.config/ask/templates/NAME.md) as the prompt
+        --image-url <URL> Attach a remote image to the question (repeatable)
+        --prefix <TEXT>   Put TEXT before the question, e.g. --prefix "in one word:"
+        --suffix <TEXT>   Put TEXT after the question, e.g. --suffix "explain like I'm 5"
+        --retry-last      Run the previous query again with the same flags
+        --allow-binary    Send binary stdin base64-encoded instead of refusing it
         --help-env        Show all environment variables
         --update          Check and install updates
+        --bench           Run the query on every profile and compare latency
+        --profiles <A,B>  Limit --bench to these profiles
+        --stats           Show usage totals (same as ask stats)
+        --since <SPAN>    Limit stats to the last SPAN, e.g. 24h, 7d, 2w
+        --review          Review a piped diff: summary plus comments grouped by file
         --completions <SHELL>  Generate shell completions (bash, zsh, fish, powershell, elvish)
     -v, --verbose         Show verbose output (profile, provider info)
-    -V, --version         Show version
+    --show-expansion      Print how aliases expanded the arguments, without running
+    --model-info          Show the thinking/reasoning parameters a query would send, without sending it
+    --profile-env <NAME>  Print export lines for a profile: eval "$(ask --profile-env work)"
+    --dump-config-path    List config file locations by precedence and which ones are used
+    -V, --version         Show version (add -v or --json for build info)
     -h, --help            Show this help
 
 SUBCOMMANDS:
     init, config          Initialize/manage configuration interactively
     profiles              List all available profiles
+    templates             List saved prompt templates
+    models [--json]       List the profiles' models with thinking/vision hints
+    version [--json]      Show version, build target, git commit and config files
+    doctor                Check config, API keys, connectivity and storage
+    stats [--since 7d]    Queries, tokens, estimated cost and latency over time
+    alias [list]          List command-line aliases
+    alias add <NAME> <EXPANSION>  Add or replace an alias (e.g. alias add q --raw --no-color)
+    alias remove <NAME>   Remove an alias
     history               List global context history
     history <TARGET>      Show one saved context by ID prefix or path
     history search <TERM> Search saved contexts by path or message content
     history prune         Delete saved contexts for missing directories
+    history --browse      Browse, filter and resume saved contexts interactively
     --clear               Clear current directory context (use with -c)
     --history             Show context history (use with -c)
+    --more                Continue the previous answer (use with -c)
+    --ephemeral           Use the context but don't save this exchange (use with -c)
+    --apply               Ask for a unified diff, preview it and patch the file (-y skips the prompt)
+    --watch               Run the generated command again whenever files here change
+    --watch-regenerate    Like --watch, but ask for a fresh command before each run
+    --export <FILE>       Export context to .md or .json (use with -c or history)
+    --import <FILE>       Import a JSON transcript into context (use with -c)
+    --session <NAME>      Use a named context session (use with -c)
+    --fork <NAME>         Copy the context into a new session (use with -c)
+    --context-file <FILE> Keep the context in FILE instead of per directory (implies -c)
+    --resume <ID>         Continue a saved context by ID prefix from 'ask history' (implies -c)
 
 EXAMPLES:
     ask how to list docker containers
@@ -584,16 +1315,31 @@ EXAMPLES:
     ask history                           # list saved contexts
     ask history .                         # show current directory context
     ask history search docker             # search saved contexts
+    ask history --browse                  # pick a context to view or resume
     ask -y history prune                  # prune orphaned contexts
+    ask -c --export session.md            # share the current conversation
+    ask --bench list files                # compare latency across profiles
+    git diff | ask --review               # structured code review of a diff
+    ask -N 3 compress this folder         # pick from three candidate commands
     git diff | ask cm
     cat main.rs | ask explain
 
+EXIT CODES:
+    0    success
+    1    generic error
+    2    invalid flags or flag combination
+    3    missing or rejected API key
+    4    rate limited / provider still failing after fallbacks
+    5    the command ran but exited non-zero
+    130  interrupted (Ctrl-C)
+
 CONFIGURATION:
     Run 'ask init' or 'ask config' to set up your API keys and preferences.
     Configuration files are loaded from:
       1. ./ask.toml or ./.ask.toml (project local)
       2. ~/ask.toml (home directory)
       3. ~/.config/ask/ask.toml (XDG config)
+    Set ASK_CONFIG or --config <FILE> to load a single file instead.
 
 CUSTOM PROMPTS:
     Create ask.md in the config search path to customize the system prompt.
@@ -604,6 +1350,79 @@ Run 'ask --help-env' for all environment variables.
     );
 }
 
+/// Replace each word that names an alias with the alias's words
+fn expand_with(
+    args: Vec<String>,
+    aliases: &std::collections::HashMap<String, String>,
+) -> Vec<String> {
+    let mut expanded = Vec::new();
+    for arg in args {
+        if let Some(expansion) = aliases.get(&arg) {
+            for part in expansion.split_whitespace() {
+                expanded.push(part.to_string());
+            }
+        } else {
+            expanded.push(arg);
+        }
+    }
+    expanded
+}
+
+/// `q foo → --raw --no-color foo`, for `-v` and `--show-expansion`
+fn expansion_line(before: &[String], after: &[String]) -> String {
+    if before == after {
+        format!("{} (no aliases)", before.join(" "))
+    } else {
+        format!("{} → {}", before.join(" "), after.join(" "))
+    }
+}
+
+/// `alias` alone or followed by an action is the subcommand; `alias in bash` is a question
+fn is_alias_command(rest: &[String]) -> bool {
+    rest.first()
+        .is_none_or(|action| matches!(action.as_str(), "list" | "add" | "remove" | "rm"))
+}
+
+/// Make `--config PATH` in these arguments the config every later load reads
+pub(super) fn pin_config_path(args: &[String]) {
+    if let Some(path) = find_config_flag(args) {
+        crate::config::Config::set_config_path(std::path::PathBuf::from(
+            shellexpand::tilde(&path).as_ref(),
+        ));
+    }
+}
+
+/// Find --config PATH / --config=PATH in raw arguments
+fn find_config_flag(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--config" {
+            return iter.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix("--config=") {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Candidate count for `-N`; out of range or not a number = None
+fn parse_count(value: &str) -> Option<usize> {
+    value
+        .parse()
+        .ok()
+        .filter(|n| (1..=super::candidates::MAX_CANDIDATES).contains(n))
+}
+
+fn split_profile_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
 fn is_think_level(s: &str) -> bool {
     let lower = s.to_lowercase();
     matches!(
@@ -703,6 +1522,486 @@ mod tests {
         assert_eq!(args.query, vec!["hello"]);
     }
 
+    #[test]
+    fn test_parse_round_robin() {
+        let args = Args::parse_args(vec!["--round-robin".into(), "hi".into()]);
+        assert!(args.round_robin);
+        assert_eq!(args.query, vec!["hi"]);
+    }
+
+    #[test]
+    fn test_parse_count() {
+        let args = Args::parse_args(vec!["-N".into(), "3".into(), "compress".into()]);
+        assert_eq!(args.count, Some(3));
+        assert_eq!(args.query, vec!["compress"]);
+
+        assert_eq!(Args::parse_args(vec!["--count=2".into()]).count, Some(2));
+        assert_eq!(Args::parse_args(vec!["-N4".into()]).count, Some(4));
+        assert_eq!(
+            Args::parse_args(vec!["-xN".into(), "5".into(), "hi".into()]).count,
+            Some(5)
+        );
+        assert_eq!(Args::parse_args(vec!["-N".into(), "0".into()]).count, None);
+        assert_eq!(Args::parse_args(vec!["--count=99".into()]).count, None);
+    }
+
+    #[test]
+    fn test_parse_review() {
+        let args = Args::parse_args(vec!["--review".into(), "security".into()]);
+        assert!(args.review);
+        assert_eq!(args.query, vec!["security"]);
+        assert!(!Args::parse_args(vec!["hi".into()]).review);
+    }
+
+    #[test]
+    fn test_parse_bench() {
+        let args = Args::parse_args(vec!["--bench".into(), "list".into(), "files".into()]);
+        assert!(args.bench);
+        assert!(args.bench_profiles.is_none());
+        assert_eq!(args.query, vec!["list", "files"]);
+    }
+
+    #[test]
+    fn test_parse_bench_profiles() {
+        let args = Args::parse_args(vec![
+            "--bench".into(),
+            "--profiles".into(),
+            "fast, work,".into(),
+            "hello".into(),
+        ]);
+        assert!(args.bench);
+        assert_eq!(
+            args.bench_profiles,
+            Some(vec!["fast".to_string(), "work".to_string()])
+        );
+        assert_eq!(args.query, vec!["hello"]);
+
+        let args = Args::parse_args(vec!["--profiles=a,b".into(), "--bench".into()]);
+        assert_eq!(
+            args.bench_profiles,
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_set() {
+        let args = Args::parse_args(vec!["--set".into(), "profiles.work.model=gpt-4o".into()]);
+        assert_eq!(args.set.as_deref(), Some("profiles.work.model=gpt-4o"));
+        assert!(args.query.is_empty());
+
+        let args = Args::parse_args(vec!["--set=behavior.timeout=60".into()]);
+        assert_eq!(args.set.as_deref(), Some("behavior.timeout=60"));
+    }
+
+    #[test]
+    fn test_parse_resume() {
+        let args = Args::parse_args(vec![
+            "--resume".into(),
+            "1a2b3c4d".into(),
+            "what".into(),
+            "next".into(),
+        ]);
+        assert_eq!(args.resume.as_deref(), Some("1a2b3c4d"));
+        assert!(args.has_context());
+        assert_eq!(args.query, vec!["what", "next"]);
+
+        let args = Args::parse_args(vec!["--resume=9f8e".into(), "hi".into()]);
+        assert_eq!(args.resume.as_deref(), Some("9f8e"));
+    }
+
+    #[test]
+    fn test_parse_context_file() {
+        let args = Args::parse_args(vec![
+            "--context-file".into(),
+            "./thread.json".into(),
+            "hi".into(),
+        ]);
+        assert_eq!(
+            args.context_file,
+            Some(std::path::PathBuf::from("./thread.json"))
+        );
+        assert!(args.has_context());
+        assert_eq!(args.query, vec!["hi"]);
+
+        let args = Args::parse_args(vec!["--context-file=t.json".into()]);
+        assert_eq!(args.context_file, Some(std::path::PathBuf::from("t.json")));
+        assert!(!Args::parse_args(vec!["hi".into()]).has_context());
+    }
+
+    #[test]
+    fn test_parse_template() {
+        let args = Args::parse_args(vec!["--template".into(), "debug".into(), "segfault".into()]);
+        assert_eq!(args.template.as_deref(), Some("debug"));
+        assert_eq!(args.query, vec!["segfault"]);
+
+        let args = Args::parse_args(vec!["--template=review".into()]);
+        assert_eq!(args.template.as_deref(), Some("review"));
+
+        assert!(Args::parse_args(vec!["templates".into()]).list_templates);
+
+        let args = Args::parse_args(vec!["--theme".into(), "light".into(), "hi".into()]);
+        assert_eq!(args.theme.as_deref(), Some("light"));
+        assert_eq!(args.query, vec!["hi"]);
+        let args = Args::parse_args(vec!["--theme=dark".into()]);
+        assert_eq!(args.theme.as_deref(), Some("dark"));
+        assert!(!Args::parse_args(vec!["my".into(), "templates".into()]).list_templates);
+    }
+
+    #[test]
+    fn test_parse_model_info() {
+        let args = Args::parse_args(vec!["--model-info".into(), "-p".into(), "work".into()]);
+        assert!(args.model_info);
+        assert_eq!(args.profile.as_deref(), Some("work"));
+        assert!(args.query.is_empty());
+    }
+
+    #[test]
+    fn test_parse_profile_env() {
+        let args = Args::parse_args(vec!["--profile-env".into(), "work".into()]);
+        assert_eq!(args.profile_env.as_deref(), Some("work"));
+        assert!(args.query.is_empty());
+
+        let args = Args::parse_args(vec!["--profile-env=local".into()]);
+        assert_eq!(args.profile_env.as_deref(), Some("local"));
+        assert_eq!(args.profile, None);
+    }
+
+    #[test]
+    fn test_expand_with_multi_token_alias() {
+        let aliases = std::collections::HashMap::from([
+            ("q".to_string(), "--raw --no-color".to_string()),
+            ("fast".to_string(), "-p fast  --no-fallback".to_string()),
+        ]);
+        let argv = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+
+        let expanded = expand_with(argv(&["q", "foo", "fast"]), &aliases);
+        assert_eq!(
+            expanded,
+            argv(&["--raw", "--no-color", "foo", "-p", "fast", "--no-fallback"])
+        );
+        assert_eq!(
+            expansion_line(&argv(&["q", "foo"]), &argv(&["--raw", "--no-color", "foo"])),
+            "q foo → --raw --no-color foo"
+        );
+        assert_eq!(
+            expansion_line(&argv(&["foo"]), &argv(&["foo"])),
+            "foo (no aliases)"
+        );
+    }
+
+    #[test]
+    fn test_parse_dump_config_path() {
+        assert!(Args::parse_args(vec!["--dump-config-path".into()]).dump_config_path);
+        assert!(Args::parse_args(vec!["--where-config".into()]).dump_config_path);
+    }
+
+    #[test]
+    fn test_parse_retry_last() {
+        let args = Args::parse_args(vec!["--retry-last".into(), "-v".into()]);
+        assert!(args.retry_last);
+        assert!(args.verbose);
+        assert!(args.query.is_empty());
+        assert_eq!(args.argv, vec!["--retry-last", "-v"]);
+    }
+
+    #[test]
+    fn test_parse_edit_answer() {
+        let parse =
+            |words: &[&str]| Args::parse_args(words.iter().map(|w| w.to_string()).collect());
+        assert!(parse(&["-c", "--edit-answer", "why"]).edit_answer);
+        assert!(parse(&["--interactive-edit", "why"]).edit_answer);
+        assert!(!parse(&["why"]).edit_answer);
+    }
+
+    #[test]
+    fn test_parse_show_expansion() {
+        let args = Args::parse_args(vec!["--show-expansion".into(), "q".into(), "hi".into()]);
+        assert!(args.show_expansion);
+        assert_eq!(args.query, vec!["q", "hi"]);
+    }
+
+    #[test]
+    fn test_parse_alias_subcommand() {
+        let args = Args::parse_args(vec![
+            "alias".into(),
+            "add".into(),
+            "q".into(),
+            "--raw".into(),
+            "--no-color".into(),
+        ]);
+        assert_eq!(
+            args.alias_command,
+            Some(vec![
+                "add".to_string(),
+                "q".to_string(),
+                "--raw".to_string(),
+                "--no-color".to_string()
+            ])
+        );
+        assert!(!args.raw, "flags after alias belong to the expansion");
+
+        let args = Args::parse_args(vec!["alias".into()]);
+        assert_eq!(args.alias_command, Some(vec![]));
+
+        let args = Args::parse_args(vec![
+            "what".into(),
+            "is".into(),
+            "an".into(),
+            "alias".into(),
+        ]);
+        assert!(args.alias_command.is_none());
+        assert_eq!(args.query, vec!["what", "is", "an", "alias"]);
+
+        let args = Args::parse_args(vec![
+            "alias".into(),
+            "ll".into(),
+            "in".into(),
+            "bash".into(),
+        ]);
+        assert!(
+            args.alias_command.is_none(),
+            "a question starting with alias"
+        );
+        assert_eq!(args.query, vec!["alias", "ll", "in", "bash"]);
+
+        let args = Args::parse_args(vec!["alias".into(), "rm".into(), "q".into()]);
+        assert_eq!(
+            args.alias_command,
+            Some(vec!["rm".to_string(), "q".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_list_models() {
+        let args = Args::parse_args(vec!["models".into(), "--json".into()]);
+        assert!(args.list_models);
+        assert!(args.json);
+        assert!(Args::parse_args(vec!["--list-models".into()]).list_models);
+        assert!(!Args::parse_args(vec!["list".into(), "models".into()]).list_models);
+    }
+
+    #[test]
+    fn test_parse_prompt_file() {
+        let args = Args::parse_args(vec![
+            "--prompt-file".into(),
+            "./experiment.md".into(),
+            "what".into(),
+            "is".into(),
+            "x".into(),
+        ]);
+        assert_eq!(
+            args.prompt_file,
+            Some(std::path::PathBuf::from("./experiment.md"))
+        );
+        assert_eq!(args.query, vec!["what", "is", "x"]);
+
+        let args = Args::parse_args(vec!["--prompt-file=p.md".into(), "hi".into()]);
+        assert_eq!(args.prompt_file, Some(std::path::PathBuf::from("p.md")));
+    }
+
+    #[test]
+    fn test_parse_prefix_suffix() {
+        let args = Args::parse_args(vec![
+            "--prefix".into(),
+            "in one word:".into(),
+            "--suffix=explain like I'm 5".into(),
+            "what".into(),
+            "is".into(),
+            "rust".into(),
+        ]);
+        assert_eq!(args.prefix.as_deref(), Some("in one word:"));
+        assert_eq!(args.suffix.as_deref(), Some("explain like I'm 5"));
+        assert_eq!(args.query, vec!["what", "is", "rust"]);
+
+        let args = Args::parse_args(vec!["--prefix=tl;dr".into(), "hi".into()]);
+        assert_eq!(args.prefix.as_deref(), Some("tl;dr"));
+        assert!(args.suffix.is_none());
+    }
+
+    #[test]
+    fn test_parse_image_url() {
+        let args = Args::parse_args(vec![
+            "--image-url".into(),
+            "https://example.com/a.jpg".into(),
+            "--input-image-url=https://example.com/b.png".into(),
+            "what".into(),
+            "is".into(),
+            "this".into(),
+        ]);
+        assert_eq!(
+            args.image_urls,
+            vec!["https://example.com/a.jpg", "https://example.com/b.png"]
+        );
+        assert_eq!(args.query, vec!["what", "is", "this"]);
+
+        let args = Args::parse_args(vec!["--image-url=https://x.io/c.gif".into(), "hi".into()]);
+        assert_eq!(args.image_urls, vec!["https://x.io/c.gif"]);
+    }
+
+    #[test]
+    fn test_parse_max_context_chars() {
+        let args = Args::parse_args(vec![
+            "-c".into(),
+            "--max-context-chars".into(),
+            "4000".into(),
+            "hi".into(),
+        ]);
+        assert_eq!(args.max_context_chars, Some(4000));
+        assert_eq!(args.query, vec!["hi"]);
+
+        let args = Args::parse_args(vec!["--max-context-chars=100".into()]);
+        assert_eq!(args.max_context_chars, Some(100));
+    }
+
+    #[test]
+    fn test_parse_max_tokens() {
+        let args = Args::parse_args(vec!["--max-tokens".into(), "8000".into(), "hi".into()]);
+        assert_eq!(args.max_tokens, Some(8000));
+        assert_eq!(args.query, vec!["hi"]);
+
+        let args = Args::parse_args(vec!["--max-tokens=512".into(), "hi".into()]);
+        assert_eq!(args.max_tokens, Some(512));
+        assert_eq!(Args::parse_args(vec!["hi".into()]).max_tokens, None);
+    }
+
+    #[test]
+    fn test_parse_seed() {
+        let args = Args::parse_args(vec!["--seed".into(), "42".into(), "hi".into()]);
+        assert_eq!(args.seed, Some(42));
+        assert_eq!(args.query, vec!["hi"]);
+
+        assert_eq!(Args::parse_args(vec!["--seed=7".into()]).seed, Some(7));
+        assert_eq!(Args::parse_args(vec!["hi".into()]).seed, None);
+    }
+
+    #[test]
+    fn test_parse_exec_timeout() {
+        let args = Args::parse_args(vec!["--exec-timeout".into(), "10".into(), "-x".into()]);
+        assert_eq!(args.exec_timeout, Some(10));
+        assert!(args.query.is_empty());
+
+        let args = Args::parse_args(vec!["--exec-timeout=5".into(), "hello".into()]);
+        assert_eq!(args.exec_timeout, Some(5));
+        assert_eq!(args.query, vec!["hello"]);
+    }
+
+    #[test]
+    fn test_parse_export_import() {
+        let args = Args::parse_args(vec!["--export".into(), "session.md".into(), "-c".into()]);
+        assert_eq!(args.export, Some("session.md".to_string()));
+        assert!(args.has_context());
+        assert!(args.query.is_empty());
+
+        let args = Args::parse_args(vec!["--import=session.json".into(), "-c".into()]);
+        assert_eq!(args.import, Some("session.json".to_string()));
+
+        let args = Args::parse_args(vec![
+            "history".into(),
+            "1234abcd".into(),
+            "--export".into(),
+            "out.json".into(),
+        ]);
+        assert!(args.history_subcommand);
+        assert_eq!(args.history_target, Some("1234abcd".to_string()));
+        assert_eq!(args.export, Some("out.json".to_string()));
+    }
+
+    #[test]
+    fn test_parse_wrap() {
+        let args = Args::parse_args(vec!["--wrap".into(), "80".into(), "hi".into()]);
+        assert_eq!(args.wrap, Some(80));
+        assert_eq!(args.query, vec!["hi"]);
+
+        let args = Args::parse_args(vec!["--wrap=72".into()]);
+        assert_eq!(args.wrap, Some(72));
+
+        let args = Args::parse_args(vec!["--no-wrap".into()]);
+        assert_eq!(args.wrap, Some(0));
+    }
+
+    #[test]
+    fn test_parse_session_and_fork() {
+        let args = Args::parse_args(vec![
+            "-c".into(),
+            "--fork".into(),
+            "experiment".into(),
+            "try".into(),
+            "caddy".into(),
+        ]);
+        assert_eq!(args.fork, Some("experiment".to_string()));
+        assert!(args.has_context());
+        assert_eq!(args.query, vec!["try", "caddy"]);
+
+        let args = Args::parse_args(vec!["-c".into(), "--session=experiment".into()]);
+        assert_eq!(args.session, Some("experiment".to_string()));
+        assert!(args.fork.is_none());
+    }
+
+    #[test]
+    fn test_parse_config_flag() {
+        let args = Args::parse_args(vec!["--config".into(), "/etc/ask/ci.toml".into()]);
+        assert_eq!(args.config, Some("/etc/ask/ci.toml".to_string()));
+        assert!(args.query.is_empty());
+
+        let args = Args::parse_args(vec!["--config=ci.toml".into(), "hello".into()]);
+        assert_eq!(args.config, Some("ci.toml".to_string()));
+        assert_eq!(args.query, vec!["hello"]);
+    }
+
+    #[test]
+    fn test_find_config_flag() {
+        let raw = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            find_config_flag(&raw(&["q", "--config", "a.toml"])),
+            Some("a.toml".to_string())
+        );
+        assert_eq!(
+            find_config_flag(&raw(&["--config=b.toml", "q"])),
+            Some("b.toml".to_string())
+        );
+        assert_eq!(find_config_flag(&raw(&["hello", "world"])), None);
+    }
+
+    #[test]
+    fn test_parse_yes_safe() {
+        let args = Args::parse_args(vec!["--yes-safe".into(), "list".into(), "files".into()]);
+        assert!(args.yes_safe);
+        assert_eq!(args.yes, None);
+        assert_eq!(args.query, vec!["list", "files"]);
+    }
+
+    #[test]
+    fn test_parse_non_interactive_init_flags() {
+        let args = Args::parse_args(vec![
+            "init".into(),
+            "-n".into(),
+            "-P".into(),
+            "openai".into(),
+            "--profile-name".into(),
+            "local".into(),
+            "--base-url=http://localhost:11434/v1".into(),
+            "--thinking".into(),
+            "low".into(),
+            "--no-stream".into(),
+            "--force".into(),
+            "--key-from-stdin".into(),
+        ]);
+        assert!(args.init);
+        assert!(args.non_interactive);
+        assert!(args.force);
+        assert!(args.key_from_stdin);
+        assert_eq!(args.provider.as_deref(), Some("openai"));
+        assert_eq!(args.profile_name.as_deref(), Some("local"));
+        assert_eq!(args.base_url.as_deref(), Some("http://localhost:11434/v1"));
+        assert_eq!(args.thinking.as_deref(), Some("low"));
+        assert_eq!(args.stream, Some(false));
+        assert!(args.query.is_empty());
+
+        let args = Args::parse_args(vec!["--init".into(), "--force".into()]);
+        assert!(args.init);
+        assert!(args.force);
+    }
+
     #[test]
     fn test_is_think_level() {
         assert!(is_think_level("minimal"));
@@ -728,6 +2027,223 @@ mod tests {
         assert!(args.query.is_empty());
     }
 
+    #[test]
+    fn test_parse_version_subcommand() {
+        let args = Args::parse_args(vec!["version".into(), "--json".into()]);
+        assert!(args.version);
+        assert!(args.json);
+        assert!(args.query.is_empty());
+
+        // Only a subcommand in first position
+        let args = Args::parse_args(vec!["what".into(), "version".into()]);
+        assert!(!args.version);
+    }
+
+    #[test]
+    fn test_parse_show_thinking() {
+        let args = Args::parse_args(vec!["-t".into(), "--show-thinking".into(), "why".into()]);
+        assert!(args.show_thinking);
+        assert_eq!(args.think, Some(true));
+        assert_eq!(args.query, vec!["why"]);
+
+        let args = Args::parse_args(vec!["--thinking-output".into()]);
+        assert!(args.show_thinking);
+    }
+
+    #[test]
+    fn test_parse_debug_request() {
+        assert!(Args::parse_args(vec!["--debug-request".into(), "hi".into()]).debug_request);
+        assert!(Args::parse_args(vec!["--provider-raw-request".into(), "hi".into()]).debug_request);
+        assert!(!Args::parse_args(vec!["hi".into()]).debug_request);
+    }
+
+    #[test]
+    fn test_parse_offline() {
+        let args = Args::parse_args(vec!["--offline".into(), "hi".into()]);
+        assert!(args.offline);
+        assert_eq!(args.query, vec!["hi"]);
+        assert!(!Args::parse_args(vec!["hi".into()]).offline);
+    }
+
+    #[test]
+    fn test_parse_no_inject() {
+        assert!(!Args::parse_args(vec!["list".into(), "files".into()]).no_inject);
+        assert!(Args::parse_args(vec!["--no-inject".into()]).no_inject);
+        assert!(Args::parse_args(vec!["--no-injection".into()]).no_inject);
+    }
+
+    #[test]
+    fn test_parse_safe_paste() {
+        assert!(!Args::parse_args(vec!["list".into(), "files".into()]).safe_paste);
+        assert!(Args::parse_args(vec!["--safe-paste".into(), "list".into()]).safe_paste);
+    }
+
+    #[test]
+    fn test_parse_explain_error() {
+        assert!(!Args::parse_args(vec!["list".into()]).explain_error);
+        let args = Args::parse_args(vec!["-y".into(), "--explain-error".into(), "build".into()]);
+        assert!(args.explain_error);
+        assert_eq!(args.query, vec!["build"]);
+    }
+
+    #[test]
+    fn test_parse_health() {
+        let args = Args::parse_args(vec![
+            "--health".into(),
+            "openai".into(),
+            "-k".into(),
+            "sk-test".into(),
+        ]);
+        assert_eq!(args.health.as_deref(), Some("openai"));
+        assert_eq!(args.api_key.as_deref(), Some("sk-test"));
+        assert!(args.query.is_empty());
+
+        let args = Args::parse_args(vec!["--health=ollama".into()]);
+        assert_eq!(args.health.as_deref(), Some("ollama"));
+    }
+
+    #[test]
+    fn test_parse_doctor_subcommand() {
+        let args = Args::parse_args(vec!["doctor".into()]);
+        assert!(args.doctor);
+        assert!(args.query.is_empty());
+
+        let args = Args::parse_args(vec!["ask".into(), "the".into(), "doctor".into()]);
+        assert!(!args.doctor);
+    }
+
+    #[test]
+    fn test_parse_stats() {
+        let args = Args::parse_args(vec!["stats".into(), "--since".into(), "7d".into()]);
+        assert!(args.stats);
+        assert_eq!(args.since.as_deref(), Some("7d"));
+        assert!(args.query.is_empty());
+
+        let args = Args::parse_args(vec!["--stats".into(), "--since=24h".into()]);
+        assert!(args.stats);
+        assert_eq!(args.since.as_deref(), Some("24h"));
+
+        assert!(!Args::parse_args(vec!["show".into(), "stats".into()]).stats);
+    }
+
+    #[test]
+    fn test_parse_more() {
+        let args = Args::parse_args(vec!["-c".into(), "--more".into()]);
+        assert!(args.more);
+        assert!(args.has_context());
+        assert!(args.query.is_empty());
+
+        let args = Args::parse_args(vec!["-c".into(), "--append".into()]);
+        assert!(args.more);
+    }
+
+    #[test]
+    fn test_parse_apply() {
+        let args = Args::parse_args(vec![
+            "--apply".into(),
+            "fix".into(),
+            "the".into(),
+            "off-by-one".into(),
+        ]);
+        assert!(args.apply);
+        assert_eq!(args.query, vec!["fix", "the", "off-by-one"]);
+        assert!(Args::parse_args(vec!["--diff-apply".into()]).apply);
+        assert!(!Args::parse_args(vec!["fix".into()]).apply);
+    }
+
+    #[test]
+    fn test_parse_watch() {
+        let args = Args::parse_args(vec!["--watch".into(), "run".into(), "the tests".into()]);
+        assert!(args.watch);
+        assert!(!args.watch_regenerate);
+        assert_eq!(args.query, vec!["run", "the tests"]);
+
+        let args = Args::parse_args(vec!["--watch-regenerate".into(), "lint".into()]);
+        assert!(args.watch, "--watch-regenerate implies --watch");
+        assert!(args.watch_regenerate);
+    }
+
+    #[test]
+    fn test_parse_ephemeral() {
+        let args = Args::parse_args(vec!["-c".into(), "--ephemeral".into(), "secret".into()]);
+        assert!(args.ephemeral);
+        assert!(args.has_context());
+        assert_eq!(args.query, vec!["secret"]);
+
+        assert!(Args::parse_args(vec!["--no-history".into()]).ephemeral);
+        assert!(!Args::parse_args(vec!["-c".into(), "hi".into()]).ephemeral);
+    }
+
+    #[test]
+    fn test_parse_format() {
+        let args = Args::parse_args(vec![
+            "--format".into(),
+            "table".into(),
+            "largest".into(),
+            "files".into(),
+        ]);
+        assert_eq!(args.format, Some(OutputFormat::Table));
+        assert_eq!(args.markdown, Some(true));
+        assert_eq!(args.query, vec!["largest", "files"]);
+
+        let args = Args::parse_args(vec!["--format=json".into()]);
+        assert_eq!(args.format, Some(OutputFormat::Json));
+        assert!(args.json);
+
+        let args = Args::parse_args(vec!["--format=text".into()]);
+        assert_eq!(args.markdown, Some(false));
+
+        let args = Args::parse_args(vec!["--format=bogus".into()]);
+        assert_eq!(args.format, None);
+        assert_eq!(args.markdown, None);
+    }
+
+    #[test]
+    fn test_parse_json_stream() {
+        let args = Args::parse_args(vec!["--json-stream".into(), "hello".into()]);
+        assert!(args.json_stream);
+        assert!(!args.json);
+        assert_eq!(args.query, vec!["hello"]);
+    }
+
+    #[test]
+    fn test_parse_preserve() {
+        let args = Args::parse_args(vec!["--preserve".into(), "yaml".into()]);
+        assert!(args.preserve);
+        assert!(!args.raw);
+        assert!(Args::parse_args(vec!["--no-trim".into(), "yaml".into()]).preserve);
+    }
+
+    #[test]
+    fn test_parse_raw_stream() {
+        let args = Args::parse_args(vec!["--raw-stream".into(), "hello".into()]);
+        assert!(args.raw_stream);
+        assert!(!args.raw);
+        assert_eq!(args.query, vec!["hello"]);
+    }
+
+    #[test]
+    fn test_parse_tee() {
+        let args = Args::parse_args(vec!["--tee".into(), "doc.md".into(), "write docs".into()]);
+        assert_eq!(args.tee, Some(std::path::PathBuf::from("doc.md")));
+        assert_eq!(args.query, vec!["write docs"]);
+
+        let args = Args::parse_args(vec!["--tee=out.md".into(), "hi".into()]);
+        assert_eq!(args.tee, Some(std::path::PathBuf::from("out.md")));
+
+        let args = Args::parse_args(vec!["--stream-to-file".into(), "a.md".into(), "hi".into()]);
+        assert_eq!(args.tee, Some(std::path::PathBuf::from("a.md")));
+    }
+
+    #[test]
+    fn test_parse_history_browse() {
+        let args = Args::parse_args(vec!["history".into(), "--browse".into()]);
+        assert!(args.history_subcommand);
+        assert!(args.history_browse);
+        assert!(args.history_target.is_none());
+        assert!(args.query.is_empty());
+    }
+
     #[test]
     fn test_parse_history_subcommand_global() {
         let args = Args::parse_args(vec!["history".into(), "--global".into()]);
@@ -0,0 +1,104 @@
+//! Export a profile's settings for the current shell (`eval "$(ask --profile-env work)"`)
+
+use crate::config::{ActiveConfig, Config};
+use crate::executor::shell_quote;
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+/// `export` lines reproducing the profile through ad-hoc mode (`ASK_PROVIDER` and friends).
+/// `ASK_PROFILE` is unset first, since ask refuses it alongside `ASK_PROVIDER`.
+pub fn profile_env_lines(active: &ActiveConfig) -> Vec<String> {
+    let prefix = format!("ASK_{}", active.provider.to_uppercase().replace('-', "_"));
+
+    let mut lines = vec![
+        "unset ASK_PROFILE".to_string(),
+        export("ASK_PROVIDER", &active.provider),
+        export("ASK_MODEL", &active.model),
+    ];
+    if let Some(ref key) = active.api_key {
+        lines.push(export(&format!("{}_API_KEY", prefix), key));
+    }
+    if let Some(ref url) = active.base_url {
+        lines.push(export(&format!("{}_BASE_URL", prefix), url));
+    }
+    lines
+}
+
+pub fn print_profile_env(config: &Config, name: &str) -> Result<()> {
+    let Some(active) = config.resolve_profile(name, None) else {
+        bail!(
+            "Profile '{}' not found. Run 'ask profiles' to list them.",
+            name
+        );
+    };
+
+    if active.api_key.is_some() {
+        eprintln!(
+            "{}",
+            "Note: the API key will be in this shell's environment, visible to every program it starts"
+                .yellow()
+        );
+    }
+    for line in profile_env_lines(&active) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+fn export(name: &str, value: &str) -> String {
+    format!("export {}={}", name, shell_quote(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config::from_toml(
+            r#"
+[profiles.work]
+provider = "openai"
+model = "gpt-5"
+api_key = "sk-work"
+base_url = "https://llm.example.com/v1"
+
+[profiles.local]
+provider = "ollama"
+model = "qwen3"
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_profile_env_lines() {
+        let active = config().resolve_profile("work", None).unwrap();
+        assert_eq!(
+            profile_env_lines(&active),
+            vec![
+                "unset ASK_PROFILE",
+                "export ASK_PROVIDER='openai'",
+                "export ASK_MODEL='gpt-5'",
+                "export ASK_OPENAI_API_KEY='sk-work'",
+                "export ASK_OPENAI_BASE_URL='https://llm.example.com/v1'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_profile_env_lines_without_key() {
+        let active = ActiveConfig {
+            provider: "ollama".to_string(),
+            model: "qwen3".to_string(),
+            ..Default::default()
+        };
+        let lines = profile_env_lines(&active);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2], "export ASK_MODEL='qwen3'");
+    }
+
+    #[test]
+    fn test_unknown_profile() {
+        assert!(print_profile_env(&config(), "missing").is_err());
+    }
+}
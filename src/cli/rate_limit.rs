@@ -0,0 +1,142 @@
+//! Client-side spacing between API calls (`behavior.min_interval_ms`)
+
+use crate::config::Config;
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// File holding the time of the last (or next reserved) call to `provider`, in ms since the epoch
+pub fn stamp_path(provider: &str) -> PathBuf {
+    let name: String = provider
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ask")
+        .join("rate_limit")
+        .join(name)
+}
+
+/// Calls that may be queued behind each other; a stamp further ahead than this
+/// many intervals means the clock moved back
+const MAX_QUEUED_CALLS: u64 = 8;
+
+/// How long to sleep so that `min_interval_ms` separates this call from the last
+/// one, which may be a slot another run reserved in the future
+pub fn wait_duration(last_ms: Option<u64>, now_ms: u64, min_interval_ms: u64) -> Option<Duration> {
+    let wait = last_ms?
+        .saturating_add(min_interval_ms)
+        .checked_sub(now_ms)
+        .filter(|&wait| wait > 0)?;
+    if wait > min_interval_ms.saturating_mul(MAX_QUEUED_CALLS) {
+        return Some(Duration::from_millis(min_interval_ms));
+    }
+    Some(Duration::from_millis(wait))
+}
+
+/// Reserve the next slot for the active provider and sleep until it comes
+pub async fn throttle(config: &Config) {
+    let min_interval_ms = config.behavior.min_interval_ms;
+    if min_interval_ms == 0 {
+        return;
+    }
+
+    let path = stamp_path(config.active_provider());
+    match reserve_slot(&path, now_ms(), min_interval_ms) {
+        Ok(Some(wait)) => {
+            tracing::debug!(
+                wait_ms = wait.as_millis() as u64,
+                "waiting for behavior.min_interval_ms"
+            );
+            tokio::time::sleep(wait).await;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::debug!(path = %path.display(), error = %e, "could not use rate-limit stamp")
+        }
+    }
+}
+
+/// Read the last stamp and write this call's time under an exclusive lock, so
+/// concurrent runs each get their own slot instead of all reading the same stamp.
+/// Returns how long to wait for the slot.
+fn reserve_slot(
+    path: &Path,
+    now_ms: u64,
+    min_interval_ms: u64,
+) -> std::io::Result<Option<Duration>> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    file.lock()?;
+
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    let wait = wait_duration(text.trim().parse().ok(), now_ms, min_interval_ms);
+    let slot_ms = now_ms + wait.map_or(0, |w| w.as_millis() as u64);
+
+    file.set_len(0)?;
+    file.rewind()?;
+    file.write_all(slot_ms.to_string().as_bytes())?;
+    // Dropping the file releases the lock
+    Ok(wait)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wait_duration_after_recent_call() {
+        assert_eq!(
+            wait_duration(Some(10_000), 10_300, 1_000),
+            Some(Duration::from_millis(700))
+        );
+        assert_eq!(wait_duration(Some(10_000), 11_000, 1_000), None);
+        assert_eq!(wait_duration(Some(10_000), 15_000, 1_000), None);
+        assert_eq!(wait_duration(None, 10_000, 1_000), None);
+        // A stamp from the future (clock change) waits at most one interval
+        assert_eq!(
+            wait_duration(Some(20_000), 10_000, 1_000),
+            Some(Duration::from_millis(1_000))
+        );
+    }
+
+    #[test]
+    fn test_reserve_slot_spaces_concurrent_calls() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rate_limit").join("openai");
+
+        assert_eq!(reserve_slot(&path, 10_000, 1_000).unwrap(), None);
+        // Two more runs starting at the same moment queue up behind each other
+        assert_eq!(
+            reserve_slot(&path, 10_200, 1_000).unwrap(),
+            Some(Duration::from_millis(800))
+        );
+        assert_eq!(
+            reserve_slot(&path, 10_200, 1_000).unwrap(),
+            Some(Duration::from_millis(1_800))
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "12000");
+    }
+
+    #[test]
+    fn test_stamp_path_is_per_provider() {
+        assert_ne!(stamp_path("openai"), stamp_path("anthropic"));
+        assert_eq!(stamp_path("a/b").file_name().unwrap(), "a_b");
+    }
+}
@@ -0,0 +1,198 @@
+//! Re-run the previous query (`ask --retry-last`)
+
+use super::{exit, pin_config_path, Args};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What `--retry-last` needs to send a query again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LastQuery {
+    /// The question as typed, shown when retrying
+    pub query: String,
+    /// Every argument of that run after alias expansion, question included
+    pub args: Vec<String>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl LastQuery {
+    /// The API key (`-k`/`--api-key`) is left out; a retry falls back to the config's key
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            query: args.query.join(" "),
+            args: without_api_key(&args.argv),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+}
+
+fn without_api_key(argv: &[String]) -> Vec<String> {
+    let mut kept = Vec::with_capacity(argv.len());
+    let mut args = argv.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-k" | "--api-key" => {
+                args.next();
+            }
+            _ if arg.starts_with("--api-key=") => {}
+            _ => kept.push(arg.clone()),
+        }
+    }
+    kept
+}
+
+/// Where the most recent query is kept between invocations
+pub fn last_query_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ask")
+        .join("last_query.json")
+}
+
+/// Record this query for `--retry-last`. Saved before the request goes out, so a
+/// failed run can be retried too; a write error never fails the query itself.
+pub fn save_last_query(path: &Path, args: &Args) {
+    if let Err(e) = write_last_query(path, &LastQuery::from_args(args)) {
+        tracing::debug!(path = %path.display(), error = %e, "could not save last query");
+    }
+}
+
+/// Written readable by the owner only, since the question may be private
+fn write_last_query(path: &Path, last: &LastQuery) -> Result<()> {
+    use std::io::Write;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // The mode only applies on creation; tighten a file left by an older version
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    let mut file = options.open(path)?;
+    file.write_all(serde_json::to_string_pretty(last)?.as_bytes())?;
+    Ok(())
+}
+
+pub fn load_last_query(path: &Path) -> Result<LastQuery> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            anyhow::bail!("No previous query to retry. Ask something first: ask <question>")
+        }
+        Err(e) => return Err(e).with_context(|| format!("Could not read {}", path.display())),
+    };
+    serde_json::from_str(&text).with_context(|| format!("Could not parse {}", path.display()))
+}
+
+/// The saved arguments, then any flags given alongside `--retry-last` (later flags win)
+pub fn retry_argv(last: &LastQuery, current: &[String]) -> Vec<String> {
+    last.args
+        .iter()
+        .chain(current.iter().filter(|arg| *arg != "--retry-last"))
+        .cloned()
+        .collect()
+}
+
+/// Arguments of the previous query, to run in place of this one
+pub fn retry_args(args: &Args) -> Result<Args> {
+    if !args.query.is_empty() {
+        return Err(exit::usage(
+            "--retry-last re-runs the previous question; drop the new one",
+        ));
+    }
+
+    let last = load_last_query(&last_query_path())?;
+    if !args.json && !args.raw && !args.raw_stream && !args.json_stream {
+        eprintln!("{} {}", "Retrying:".bright_black(), last.query);
+    }
+    let argv = retry_argv(&last, &args.argv);
+    pin_config_path(&argv);
+    Ok(Args::parse_args(argv))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_last_query_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("last_query.json");
+        let args = Args::parse_args(strings(&["-p", "work", "--think", "why", "is", "it"]));
+
+        save_last_query(&path, &args);
+        let last = load_last_query(&path).unwrap();
+        assert_eq!(last.query, "why is it");
+        assert_eq!(
+            last.args,
+            strings(&["-p", "work", "--think", "why", "is", "it"])
+        );
+
+        let again = Args::parse_args(last.args);
+        assert_eq!(again.query, args.query);
+        assert_eq!(again.profile.as_deref(), Some("work"));
+        assert_eq!(again.think, Some(true));
+    }
+
+    #[test]
+    fn test_last_query_leaves_out_api_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("last_query.json");
+        let args = Args::parse_args(strings(&[
+            "-k",
+            "sk-secret",
+            "--api-key",
+            "sk-other",
+            "--api-key=sk-third",
+            "-v",
+            "why",
+        ]));
+
+        save_last_query(&path, &args);
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(!text.contains("sk-"), "{}", text);
+        assert_eq!(
+            load_last_query(&path).unwrap().args,
+            strings(&["-v", "why"])
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_missing_last_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = load_last_query(&dir.path().join("last_query.json")).unwrap_err();
+        assert!(err.to_string().contains("No previous query"));
+    }
+
+    #[test]
+    fn test_retry_argv_appends_new_flags() {
+        let last = LastQuery {
+            query: "list files".to_string(),
+            args: strings(&["-x", "list", "files"]),
+            timestamp: chrono::Utc::now(),
+        };
+        assert_eq!(
+            retry_argv(&last, &strings(&["--retry-last", "-v"])),
+            strings(&["-x", "list", "files", "-v"])
+        );
+    }
+}
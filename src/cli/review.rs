@@ -0,0 +1,265 @@
+//! Structured code review of a piped diff (`git diff | ask --review`)
+
+use super::Args;
+use crate::config::Config;
+use crate::output::Spinner;
+use crate::providers::{create_provider, strip_code_fences, Message};
+use anyhow::Result;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+
+/// System prompt for `--review`; the JSON shape is what `Review` deserializes
+const REVIEW_PROMPT: &str = r#"You are a careful senior code reviewer. Review the diff the user sends.
+Only comment on lines the diff adds or changes. Prefer a few important findings over many nitpicks.
+
+Reply with ONLY a JSON object, no prose and no code fences, in exactly this shape:
+{
+  "summary": "one or two sentences on what the change does and its overall quality",
+  "comments": [
+    {"file": "path/in/diff.rs", "line": 42, "severity": "error", "comment": "what is wrong and how to fix it"}
+  ]
+}
+
+"severity" is one of "error" (bugs, security, data loss), "warning" (likely problems, missing
+handling) or "info" (style, readability, suggestions). "line" is the line number in the new
+file, or null when the comment is about the whole file. Use an empty "comments" array when the
+change looks good."#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[serde(alias = "critical", alias = "high", alias = "bug")]
+    Error,
+    #[serde(alias = "medium", alias = "warn")]
+    Warning,
+    #[serde(other, alias = "low", alias = "nit", alias = "suggestion")]
+    Info,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewComment {
+    pub file: String,
+    #[serde(default)]
+    pub line: Option<u64>,
+    pub severity: Severity,
+    pub comment: String,
+}
+
+/// The model's review of a diff
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Review {
+    pub summary: String,
+    #[serde(default)]
+    pub comments: Vec<ReviewComment>,
+}
+
+/// Review the diff on stdin and print it grouped by file
+pub async fn run_review(config: &Config, args: &Args) -> Result<()> {
+    let diff = super::read_stdin_if_available(args.allow_binary)?.unwrap_or_default();
+    if diff.trim().is_empty() {
+        anyhow::bail!("Nothing to review. Pipe a diff in, e.g.: git diff | ask --review");
+    }
+
+    let focus = args.query.join(" ");
+    let mut request = format!("Review this diff:\n```diff\n{}\n```", diff.trim_end());
+    if !focus.trim().is_empty() {
+        request.push_str(&format!("\n\nFocus on: {}", focus.trim()));
+    }
+
+    let messages = vec![
+        Message {
+            role: "system".to_string(),
+            content: REVIEW_PROMPT.to_string(),
+            ..Default::default()
+        },
+        Message {
+            role: "user".to_string(),
+            content: request,
+            ..Default::default()
+        },
+    ];
+
+    let provider = create_provider(config)?;
+    let options = super::build_provider_options(args, config);
+    let spinner = (!args.json && std::io::stdout().is_terminal()).then(Spinner::start);
+    let response = provider.complete_with_options(&messages, &options).await;
+    drop(spinner);
+    let text = response?.text;
+
+    let Some(review) = parse_review(&text) else {
+        eprintln!(
+            "{}",
+            "The model did not return a structured review; showing its answer as is.".yellow()
+        );
+        println!("{}", text.trim());
+        return Ok(());
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&review)?);
+    } else {
+        print!("{}", render_review(&review));
+    }
+    Ok(())
+}
+
+/// Parse the model's JSON, tolerating code fences or stray text around the object
+pub fn parse_review(text: &str) -> Option<Review> {
+    let text = strip_code_fences(text);
+    if let Ok(review) = serde_json::from_str(&text) {
+        return Some(review);
+    }
+
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    serde_json::from_str(text.get(start..=end)?).ok()
+}
+
+/// Summary, then comments grouped by file (in diff order) and sorted by line
+pub fn render_review(review: &Review) -> String {
+    let mut out = format!("{}\n  {}\n", "Summary".cyan().bold(), review.summary.trim());
+
+    if review.comments.is_empty() {
+        out.push_str(&format!("\n{}\n", "No issues found.".green()));
+        return out;
+    }
+
+    let mut files: Vec<&str> = Vec::new();
+    for comment in &review.comments {
+        if !files.contains(&comment.file.as_str()) {
+            files.push(&comment.file);
+        }
+    }
+
+    for file in files {
+        out.push_str(&format!("\n{}\n", file.bright_white().bold()));
+
+        let mut comments: Vec<&ReviewComment> =
+            review.comments.iter().filter(|c| c.file == file).collect();
+        comments.sort_by_key(|c| (c.line.unwrap_or(0), c.severity));
+
+        for comment in comments {
+            let line = comment
+                .line
+                .map(|l| format!("L{}", l))
+                .unwrap_or_else(|| "-".to_string());
+            out.push_str(&format!(
+                "  {} {} {}\n",
+                severity_label(comment.severity),
+                format!("{:>5}", line).bright_black(),
+                comment.comment.trim()
+            ));
+        }
+    }
+
+    let count = |s: Severity| review.comments.iter().filter(|c| c.severity == s).count();
+    out.push_str(&format!(
+        "\n{}\n",
+        format!(
+            "{} comment(s): {} error, {} warning, {} info",
+            review.comments.len(),
+            count(Severity::Error),
+            count(Severity::Warning),
+            count(Severity::Info)
+        )
+        .bright_black()
+    ));
+    out
+}
+
+fn severity_label(severity: Severity) -> String {
+    match severity {
+        Severity::Error => format!("{:<7}", "error").red().bold().to_string(),
+        Severity::Warning => format!("{:<7}", "warning").yellow().to_string(),
+        Severity::Info => format!("{:<7}", "info").blue().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(file: &str, line: Option<u64>, severity: Severity, text: &str) -> ReviewComment {
+        ReviewComment {
+            file: file.to_string(),
+            line,
+            severity,
+            comment: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_review_plain_and_fenced() {
+        let json = r#"{"summary": "Adds login", "comments": [
+            {"file": "src/auth.rs", "line": 12, "severity": "critical", "comment": "SQL injection"},
+            {"file": "src/auth.rs", "line": null, "severity": "nit", "comment": "Rename"}
+        ]}"#;
+
+        let review = parse_review(json).unwrap();
+        assert_eq!(review.summary, "Adds login");
+        assert_eq!(review.comments[0].severity, Severity::Error);
+        assert_eq!(review.comments[1].severity, Severity::Info);
+        assert_eq!(review.comments[1].line, None);
+
+        let fenced = format!("Here you go:\n```json\n{}\n```", json);
+        assert_eq!(parse_review(&fenced).unwrap().comments.len(), 2);
+
+        let no_comments = parse_review(r#"{"summary": "Looks good"}"#).unwrap();
+        assert!(no_comments.comments.is_empty());
+
+        assert!(parse_review("The diff looks fine to me.").is_none());
+    }
+
+    #[test]
+    fn test_render_review_groups_by_file_in_order() {
+        let review = Review {
+            summary: "Refactors the parser".to_string(),
+            comments: vec![
+                comment(
+                    "src/parser.rs",
+                    Some(40),
+                    Severity::Info,
+                    "Extract a helper",
+                ),
+                comment("src/main.rs", Some(3), Severity::Warning, "Unused import"),
+                comment(
+                    "src/parser.rs",
+                    Some(7),
+                    Severity::Error,
+                    "Index out of bounds",
+                ),
+            ],
+        };
+
+        let out = render_review(&review);
+        assert!(out.contains("Refactors the parser"));
+
+        let parser = out.find("src/parser.rs").unwrap();
+        let main = out.find("src/main.rs").unwrap();
+        assert!(parser < main, "files keep the order of the review");
+        assert_eq!(out.matches("src/parser.rs").count(), 1);
+
+        let bounds = out.find("Index out of bounds").unwrap();
+        let helper = out.find("Extract a helper").unwrap();
+        assert!(
+            parser < bounds && bounds < helper,
+            "sorted by line within a file"
+        );
+        assert!(helper < main);
+
+        assert!(out.contains("L7"));
+        assert!(out.contains("3 comment(s): 1 error, 1 warning, 1 info"));
+    }
+
+    #[test]
+    fn test_render_review_without_comments() {
+        let review = Review {
+            summary: "Small docs fix".to_string(),
+            comments: Vec::new(),
+        };
+        let out = render_review(&review);
+        assert!(out.contains("Small docs fix"));
+        assert!(out.contains("No issues found."));
+    }
+}
@@ -0,0 +1,116 @@
+//! Spread queries across profiles (`ask --round-robin`)
+
+use crate::config::Config;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Where the next starting position is kept between invocations
+pub fn index_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ask")
+        .join("round_robin")
+}
+
+/// Profiles listed in `provider_order`, all of which must exist
+pub fn profile_order(config: &Config) -> Result<Vec<String>> {
+    if config.provider_order.is_empty() {
+        anyhow::bail!(
+            "--round-robin needs a profile list in the config, e.g.:\n\
+             provider_order = [\"work\", \"personal\", \"local\"]"
+        );
+    }
+    if let Some(unknown) = config
+        .provider_order
+        .iter()
+        .find(|name| !config.profiles.contains_key(*name))
+    {
+        anyhow::bail!("Unknown profile in provider_order: {}", unknown);
+    }
+    Ok(config.provider_order.clone())
+}
+
+/// Position to start from on this run; the following one is saved for the next run
+pub fn advance_index(path: &Path, len: usize) -> usize {
+    let current = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| s.trim().parse::<usize>().ok())
+        .unwrap_or(0)
+        % len;
+
+    let saved = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(path, ((current + 1) % len).to_string()));
+    if let Err(e) = saved {
+        tracing::debug!(path = %path.display(), error = %e, "could not save round-robin index");
+    }
+
+    current
+}
+
+/// The order to try profiles in this run: the list rotated to begin at `start`
+pub fn rotation(order: &[String], start: usize) -> Vec<String> {
+    order[start..]
+        .iter()
+        .chain(&order[..start])
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_advance_index_advances_and_wraps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("round_robin");
+
+        assert_eq!(advance_index(&path, 3), 0);
+        assert_eq!(advance_index(&path, 3), 1);
+        assert_eq!(advance_index(&path, 3), 2);
+        assert_eq!(advance_index(&path, 3), 0);
+    }
+
+    #[test]
+    fn test_advance_index_survives_shorter_list_and_garbage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("round_robin");
+
+        std::fs::write(&path, "5").unwrap();
+        assert_eq!(advance_index(&path, 2), 1);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "0");
+
+        std::fs::write(&path, "not a number").unwrap();
+        assert_eq!(advance_index(&path, 2), 0);
+    }
+
+    #[test]
+    fn test_rotation_starts_at_index() {
+        let order = names(&["work", "personal", "local"]);
+        assert_eq!(rotation(&order, 0), order);
+        assert_eq!(rotation(&order, 2), names(&["local", "work", "personal"]));
+    }
+
+    #[test]
+    fn test_profile_order_validates_names() {
+        let config = Config::from_toml(
+            r#"
+provider_order = ["work", "missing"]
+
+[profiles.work]
+provider = "openai"
+"#,
+        )
+        .unwrap();
+        let err = profile_order(&config).unwrap_err();
+        assert!(err.to_string().contains("missing"));
+
+        assert!(profile_order(&Config::default()).is_err());
+    }
+}
@@ -0,0 +1,432 @@
+//! Usage log and totals over time (`ask stats`, `--since 7d`)
+
+use super::cost;
+use super::exit;
+use crate::config::{Config, ModelPrice};
+use crate::providers::Usage;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// One answered query, as appended to the usage log. No question or answer text is kept.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    pub provider: String,
+    pub model: String,
+    /// Token counts, when the provider reported them (streamed answers usually don't)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<UsageTokens>,
+    /// From sending the request to the last byte of the answer
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UsageTokens {
+    pub input: u64,
+    pub output: u64,
+}
+
+impl From<Usage> for UsageTokens {
+    fn from(usage: Usage) -> Self {
+        Self {
+            input: usage.input_tokens,
+            output: usage.output_tokens,
+        }
+    }
+}
+
+/// Where answered queries are logged, one JSON object per line
+pub fn usage_log_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ask")
+        .join("usage.jsonl")
+}
+
+/// Append this query to the usage log (`behavior.log_usage`). A write error never fails the query.
+pub fn record_usage(
+    config: &Config,
+    provider: &str,
+    model: &str,
+    usage: Option<Usage>,
+    started: std::time::Instant,
+) {
+    if !config.behavior.log_usage {
+        return;
+    }
+    let record = UsageRecord {
+        timestamp: Utc::now(),
+        provider: provider.to_string(),
+        model: model.to_string(),
+        usage: usage.map(UsageTokens::from),
+        latency_ms: started.elapsed().as_millis() as u64,
+    };
+    let path = usage_log_path();
+    if let Err(e) = append_record(&path, &record) {
+        tracing::debug!(path = %path.display(), error = %e, "could not log usage");
+    }
+}
+
+fn append_record(path: &Path, record: &UsageRecord) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Every readable record in the log; a missing log is empty, broken lines are skipped
+pub fn load_records(path: &Path) -> Result<Vec<UsageRecord>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                tracing::debug!(error = %e, "skipping unreadable usage log line");
+                None
+            }
+        })
+        .collect())
+}
+
+/// `--since` span: a number followed by `h`, `d` or `w` (`24h`, `7d`, `2w`)
+pub fn parse_since(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let span = value
+        .char_indices()
+        .last()
+        .and_then(|(i, unit)| Some((value[..i].parse::<i64>().ok()?, unit)))
+        .and_then(|(n, unit)| match unit {
+            'h' => Duration::try_hours(n),
+            'd' => Duration::try_days(n),
+            'w' => Duration::try_weeks(n),
+            _ => None,
+        })
+        .filter(|span| *span > Duration::zero());
+    span.ok_or_else(|| {
+        exit::usage(format!(
+            "Invalid --since '{}'. Use a number and h, d or w, e.g. 24h, 7d, 2w",
+            value
+        ))
+    })
+}
+
+/// Sums over a set of records
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Totals {
+    pub queries: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Queries whose provider reported no token counts
+    pub without_usage: u64,
+    /// Estimated USD, for the queries with usage and a known price
+    pub cost: f64,
+    /// Queries with usage but no price for their model
+    pub unpriced: u64,
+    latency_ms: u64,
+}
+
+impl Totals {
+    fn add(&mut self, record: &UsageRecord, pricing: &HashMap<String, ModelPrice>) {
+        self.queries += 1;
+        self.latency_ms += record.latency_ms;
+        let Some(tokens) = record.usage else {
+            self.without_usage += 1;
+            return;
+        };
+        self.input_tokens += tokens.input;
+        self.output_tokens += tokens.output;
+        let usage = Usage {
+            input_tokens: tokens.input,
+            output_tokens: tokens.output,
+        };
+        match cost::estimate_cost(&record.model, &usage, pricing) {
+            Some(cost) => self.cost += cost,
+            None => self.unpriced += 1,
+        }
+    }
+
+    pub fn average_latency_ms(&self) -> Option<u64> {
+        (self.queries > 0).then(|| self.latency_ms / self.queries)
+    }
+}
+
+/// Totals for one period, overall and per `(provider, model)`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    pub total: Totals,
+    pub by_model: BTreeMap<(String, String), Totals>,
+}
+
+/// Aggregate the records at or after `since` (all of them when `None`)
+pub fn aggregate(
+    records: &[UsageRecord],
+    since: Option<DateTime<Utc>>,
+    pricing: &HashMap<String, ModelPrice>,
+) -> Stats {
+    let mut stats = Stats::default();
+    for record in records
+        .iter()
+        .filter(|r| since.is_none_or(|since| r.timestamp >= since))
+    {
+        stats.total.add(record, pricing);
+        stats
+            .by_model
+            .entry((record.provider.clone(), record.model.clone()))
+            .or_default()
+            .add(record, pricing);
+    }
+    stats
+}
+
+fn format_latency(ms: Option<u64>) -> String {
+    match ms {
+        Some(ms) => format!("{:.1}s", ms as f64 / 1000.0),
+        None => "-".to_string(),
+    }
+}
+
+/// A row of the stats table: its label and how to show a period's totals
+type Row = (&'static str, fn(&Totals) -> String);
+
+/// One row per measure, one column per period
+pub fn format_table(periods: &[(String, Stats)]) -> String {
+    let rows: [Row; 5] = [
+        ("Queries", |t| t.queries.to_string()),
+        ("Input tokens", |t| t.input_tokens.to_string()),
+        ("Output tokens", |t| t.output_tokens.to_string()),
+        ("Est. cost", |t| cost::format_cost(t.cost)),
+        ("Avg latency", |t| format_latency(t.average_latency_ms())),
+    ];
+
+    let mut out = format!("{:<14}", "");
+    for (label, _) in periods {
+        out.push_str(&format!("{:>12}", label));
+    }
+    out.push('\n');
+    for (name, value) in rows {
+        out.push_str(&format!("{:<14}", name));
+        for (_, stats) in periods {
+            out.push_str(&format!("{:>12}", value(&stats.total)));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Per provider/model lines, most used first
+pub fn format_by_model(stats: &Stats) -> String {
+    let mut models: Vec<_> = stats.by_model.iter().collect();
+    models.sort_by_key(|(_, totals)| std::cmp::Reverse(totals.queries));
+
+    let width = models
+        .iter()
+        .map(|((provider, model), _)| provider.len() + model.len() + 1)
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for ((provider, model), totals) in models {
+        out.push_str(&format!(
+            "  {:width$}  {:>5} queries  {:>9} tokens  {}\n",
+            format!("{}/{}", provider, model),
+            totals.queries,
+            totals.input_tokens + totals.output_tokens,
+            cost::format_cost(totals.cost),
+        ));
+    }
+    out
+}
+
+/// `ask stats`: last day, last week and all time, or just the `--since` span
+pub fn print_stats(config: &Config, since: Option<&str>) -> Result<()> {
+    let now = Utc::now();
+    let spans = match since {
+        Some(value) => vec![(format!("last {}", value.trim()), Some(parse_since(value)?))],
+        None => vec![
+            ("last day".to_string(), Some(Duration::days(1))),
+            ("last week".to_string(), Some(Duration::weeks(1))),
+            ("all time".to_string(), None),
+        ],
+    };
+
+    let path = usage_log_path();
+    let records = load_records(&path)?;
+    if records.is_empty() {
+        let hint = if config.behavior.log_usage {
+            "No usage logged yet. Each answered query is added to"
+        } else {
+            "No usage logged; behavior.log_usage is off. When on, queries are added to"
+        };
+        println!("{} {}", hint.bright_black(), path.display());
+        return Ok(());
+    }
+
+    let periods: Vec<(String, Stats)> = spans
+        .into_iter()
+        .map(|(label, span)| {
+            let stats = aggregate(&records, span.map(|span| now - span), &config.pricing);
+            (label, stats)
+        })
+        .collect();
+
+    print!("{}", format_table(&periods));
+
+    // The widest period: all time, or the --since span
+    let (label, widest) = periods.last().expect("at least one period");
+    if !widest.by_model.is_empty() {
+        println!();
+        println!(
+            "{}",
+            crate::output::theme().heading(&format!("By model ({}):", label))
+        );
+        print!("{}", format_by_model(widest));
+    }
+    let notes = [
+        (
+            widest.total.without_usage,
+            "reported no token counts (streamed answers usually don't)",
+        ),
+        (
+            widest.total.unpriced,
+            "have no price for their model; add it under [pricing]",
+        ),
+    ];
+    for (count, note) in notes {
+        if count > 0 {
+            eprintln!(
+                "{}",
+                crate::output::theme().hint(&format!("{} queries {}", count, note))
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        hours_ago: i64,
+        provider: &str,
+        model: &str,
+        tokens: Option<(u64, u64)>,
+        latency_ms: u64,
+    ) -> UsageRecord {
+        UsageRecord {
+            timestamp: Utc::now() - Duration::hours(hours_ago),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            usage: tokens.map(|(input, output)| UsageTokens { input, output }),
+            latency_ms,
+        }
+    }
+
+    fn pricing() -> HashMap<String, ModelPrice> {
+        HashMap::from([(
+            "priced".to_string(),
+            ModelPrice {
+                input_per_mtok: 2.0,
+                output_per_mtok: 8.0,
+            },
+        )])
+    }
+
+    fn records() -> Vec<UsageRecord> {
+        vec![
+            record(1, "openai", "priced", Some((500, 250)), 1000),
+            record(2, "openai", "priced", None, 3000),
+            record(30, "gemini", "free", Some((100, 100)), 2000),
+            record(24 * 30, "openai", "priced", Some((1_000_000, 0)), 4000),
+        ]
+    }
+
+    #[test]
+    fn test_aggregate_all_time() {
+        let stats = aggregate(&records(), None, &pricing());
+        assert_eq!(stats.total.queries, 4);
+        assert_eq!(stats.total.input_tokens, 1_000_600);
+        assert_eq!(stats.total.output_tokens, 350);
+        assert_eq!(stats.total.without_usage, 1);
+        assert_eq!(stats.total.unpriced, 1);
+        // 0.001 + 0.002 for the first, 2.0 for the million input tokens
+        assert!((stats.total.cost - 2.003).abs() < 1e-9);
+        assert_eq!(stats.total.average_latency_ms(), Some(2500));
+
+        let openai = &stats.by_model[&("openai".to_string(), "priced".to_string())];
+        assert_eq!(openai.queries, 3);
+        let gemini = &stats.by_model[&("gemini".to_string(), "free".to_string())];
+        assert_eq!((gemini.queries, gemini.cost), (1, 0.0));
+    }
+
+    #[test]
+    fn test_aggregate_since() {
+        let day = aggregate(&records(), Some(Utc::now() - Duration::days(1)), &pricing());
+        assert_eq!(day.total.queries, 2);
+        assert_eq!(day.total.average_latency_ms(), Some(2000));
+        assert_eq!(day.by_model.len(), 1);
+
+        let week = aggregate(
+            &records(),
+            Some(Utc::now() - Duration::weeks(1)),
+            &pricing(),
+        );
+        assert_eq!(week.total.queries, 3);
+
+        let none = aggregate(&[], None, &pricing());
+        assert_eq!(none.total.average_latency_ms(), None);
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(parse_since("24h").unwrap(), Duration::hours(24));
+        assert_eq!(parse_since("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_since(" 2w ").unwrap(), Duration::weeks(2));
+        for bad in ["", "d", "7", "7y", "-1d", "0d", "1.5d"] {
+            assert!(parse_since(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_log_round_trip_skips_broken_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ask").join("usage.jsonl");
+        assert!(load_records(&path).unwrap().is_empty());
+
+        let saved = records();
+        for r in &saved {
+            append_record(&path, r).unwrap();
+        }
+        let mut text = std::fs::read_to_string(&path).unwrap();
+        text.push_str("not json\n");
+        std::fs::write(&path, text).unwrap();
+
+        assert_eq!(load_records(&path).unwrap(), saved);
+    }
+
+    #[test]
+    fn test_format_table() {
+        let all = aggregate(&records(), None, &pricing());
+        let table = format_table(&[("all time".to_string(), all)]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].ends_with("all time"));
+        assert!(lines[1].starts_with("Queries") && lines[1].ends_with(" 4"));
+        assert!(lines[4].ends_with("~$2.0030"));
+        assert!(lines[5].ends_with("2.5s"));
+    }
+}
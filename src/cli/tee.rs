@@ -0,0 +1,78 @@
+//! Save a copy of the answer to a file while it is shown (`--tee`)
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// File receiving the streamed chunks. A write error is kept and reported by
+/// `finish`, since the stream callback has no way to return it.
+pub struct TeeFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    error: Option<std::io::Error>,
+}
+
+impl TeeFile {
+    /// Create (or truncate) the file up front, so a bad path fails before the request
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Cannot create --tee file {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            error: None,
+        })
+    }
+
+    pub fn write_chunk(&mut self, chunk: &str) {
+        if self.error.is_none() {
+            if let Err(e) = self.writer.write_all(chunk.as_bytes()) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Flush the file, surfacing the first write error
+    pub fn finish(mut self) -> Result<()> {
+        let result = match self.error.take() {
+            Some(e) => Err(e),
+            None => self.writer.flush(),
+        };
+        result.with_context(|| format!("Cannot write --tee file {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tee_file_writes_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("answer.md");
+        std::fs::write(&path, "old contents").unwrap();
+
+        let mut tee = TeeFile::create(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "",
+            "truncated up front"
+        );
+        for chunk in ["# Title", "\n\n", "body ✓"] {
+            tee.write_chunk(chunk);
+        }
+        tee.finish().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Title\n\nbody ✓");
+    }
+
+    #[test]
+    fn test_tee_file_bad_path_fails_early() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = TeeFile::create(&dir.path().join("missing/answer.md"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Cannot create --tee file"));
+    }
+}
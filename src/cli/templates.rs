@@ -0,0 +1,131 @@
+//! Saved prompt templates (`ask --template <name>`, `ask templates`)
+
+use crate::providers::{expand_prompt_variables, PromptContext};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+
+/// `~/.config/ask/templates` (`%APPDATA%\ask\templates` on Windows)
+pub fn templates_dir() -> PathBuf {
+    crate::config::Config::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("templates")
+}
+
+/// Read `<dir>/<name>.md`; names are plain file stems, never paths
+pub fn load_template(dir: &Path, name: &str) -> Result<String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        anyhow::bail!("Invalid template name: {}", name);
+    }
+
+    let path = dir.join(format!("{}.md", name));
+    if !path.exists() {
+        anyhow::bail!(
+            "Template '{}' not found in {}\nRun 'ask templates' to list them",
+            name,
+            dir.display()
+        );
+    }
+    std::fs::read_to_string(&path)
+        .with_context(|| format!("Could not read template {}", path.display()))
+}
+
+/// System prompt from a template: the usual `{os}`/`{cwd}`/... variables, then
+/// `{input}` replaced by the query
+pub fn apply_template(template: &str, query: &str, ctx: &PromptContext) -> String {
+    expand_prompt_variables(template, ctx).replace("{input}", query)
+}
+
+/// Names of the `*.md` files in `dir`, sorted
+pub fn list_templates(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// `ask templates`
+pub fn print_templates() -> Result<()> {
+    let dir = templates_dir();
+    let names = list_templates(&dir);
+
+    println!("{}", "Templates".cyan().bold());
+    println!();
+    if names.is_empty() {
+        println!(
+            "  {}",
+            format!("No templates yet. Add <name>.md files to {}", dir.display()).bright_black()
+        );
+        return Ok(());
+    }
+    for name in names {
+        println!("  {}", name);
+    }
+    println!();
+    println!(
+        "{}",
+        format!(
+            "Use with: ask --template <name> <input>  ({})",
+            dir.display()
+        )
+        .bright_black()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_template_and_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("debug.md"), "Debug this: {input}").unwrap();
+
+        assert_eq!(
+            load_template(dir.path(), "debug").unwrap(),
+            "Debug this: {input}"
+        );
+
+        let err = load_template(dir.path(), "nope").unwrap_err().to_string();
+        assert!(err.contains("Template 'nope' not found"));
+
+        assert!(load_template(dir.path(), "../debug").is_err());
+        assert!(load_template(dir.path(), "").is_err());
+    }
+
+    #[test]
+    fn test_apply_template_substitutes_input_and_variables() {
+        let ctx = PromptContext {
+            os: "linux".to_string(),
+            ..Default::default()
+        };
+        let prompt = apply_template(
+            "You debug {os} errors.\nError: {input}\nAgain: {input}",
+            "segfault at {os}",
+            &ctx,
+        );
+        assert_eq!(
+            prompt,
+            "You debug linux errors.\nError: segfault at {os}\nAgain: segfault at {os}"
+        );
+    }
+
+    #[test]
+    fn test_list_templates_only_markdown_sorted() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("review.md"), "").unwrap();
+        std::fs::write(dir.path().join("debug.md"), "").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+        std::fs::create_dir(dir.path().join("dir.md")).unwrap();
+
+        assert_eq!(list_templates(dir.path()), vec!["debug", "review"]);
+        assert!(list_templates(&dir.path().join("missing")).is_empty());
+    }
+}
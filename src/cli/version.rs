@@ -0,0 +1,73 @@
+//! Version and build information (`ask --version`, `ask version --json`)
+
+use super::Args;
+use crate::config::Config;
+use colored::Colorize;
+use serde::Serialize;
+
+/// Build metadata embedded by `build.rs`, plus the config files in use
+#[derive(Debug, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_hash: &'static str,
+    pub target: &'static str,
+    pub profile: &'static str,
+    pub config_files: Vec<String>,
+}
+
+impl BuildInfo {
+    pub fn collect() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_hash: env!("GIT_HASH"),
+            target: env!("BUILD_TARGET"),
+            profile: env!("BUILD_PROFILE"),
+            config_files: Config::config_files()
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Print the version; `--verbose` adds build info, `--json` prints it all as JSON
+pub fn print_version(args: &Args) -> anyhow::Result<()> {
+    if !args.json && !args.verbose {
+        println!("ask {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+
+    let info = BuildInfo::collect();
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    println!("ask {} ({})", info.version, info.git_hash);
+    println!("{} {}", "target:".cyan(), info.target);
+    println!("{} {}", "profile:".cyan(), info.profile);
+    if info.config_files.is_empty() {
+        println!("{} {}", "config:".cyan(), "none (defaults)".bright_black());
+    } else {
+        for file in &info.config_files {
+            println!("{} {}", "config:".cyan(), file);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info_json_fields() {
+        let json = serde_json::to_value(BuildInfo::collect()).unwrap();
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert!(!json["target"].as_str().unwrap().is_empty());
+        assert!(json["git_hash"].is_string());
+        assert!(json["config_files"].is_array());
+    }
+}
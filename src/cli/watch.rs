@@ -0,0 +1,285 @@
+//! Re-run a generated command whenever files change (`--watch`)
+
+use super::{
+    confirm_command, is_likely_command, normalize_command_response, save_exchange,
+    should_auto_execute, strip_command_preamble, Args,
+};
+use crate::config::Config;
+use crate::executor::CommandExecutor;
+use crate::output::Spinner;
+use crate::providers::{Message, Provider, ProviderOptions};
+use anyhow::{bail, Result};
+use colored::Colorize;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::io::IsTerminal;
+use std::path::{Component, Path};
+use std::time::{Duration, Instant};
+
+/// Quiet time after the last change before the command runs again
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// After a run, changes reported this soon are the command's own writes still
+/// coming through the watcher
+const COMMAND_SETTLE: Duration = Duration::from_millis(250);
+
+/// Changes under these directories (VCS data, build output) never trigger a run,
+/// or a build command would keep re-triggering itself
+const IGNORED_DIRS: &[&str] = &[".git", "target", "node_modules"];
+
+/// Fires once the changes have stopped for `quiet`
+#[derive(Debug)]
+pub struct Debounce {
+    quiet: Duration,
+    last_change: Option<Instant>,
+    ignore_until: Option<Instant>,
+}
+
+impl Debounce {
+    pub fn new(quiet: Duration) -> Self {
+        Self {
+            quiet,
+            last_change: None,
+            ignore_until: None,
+        }
+    }
+
+    pub fn record(&mut self, now: Instant) {
+        if self.ignore_until.is_some_and(|until| now < until) {
+            return;
+        }
+        self.last_change = Some(now);
+    }
+
+    /// Forget pending changes and drop those recorded before `until`
+    pub fn ignore_until(&mut self, until: Instant) {
+        self.last_change = None;
+        self.ignore_until = Some(until);
+    }
+
+    /// Time left before firing, `None` when nothing changed
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.last_change
+            .map(|at| self.quiet.saturating_sub(now.saturating_duration_since(at)))
+    }
+
+    /// True once per burst of changes, when the quiet time has passed
+    pub fn fire(&mut self, now: Instant) -> bool {
+        if self.remaining(now) == Some(Duration::ZERO) {
+            self.last_change = None;
+            return true;
+        }
+        false
+    }
+}
+
+/// Whether a filesystem event under the watched `root` should re-run the command
+fn is_relevant(event: &notify::Event, root: &Path) -> bool {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return false;
+    }
+    event.paths.iter().any(|path| !is_ignored(path, root))
+}
+
+/// Only the part below `root` counts, so watching inside e.g. `~/target/app` still works
+fn is_ignored(path: &Path, root: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative.components().any(|c| match c {
+        Component::Normal(name) => IGNORED_DIRS.iter().any(|d| name == *d),
+        _ => false,
+    })
+}
+
+/// Ask once for a command, then run it now and after every debounced change in the
+/// cwd. `--watch-regenerate` asks the model again before each run. Ctrl-C is handled
+/// by the interrupt handler in main, which stops a running command and exits with 130.
+pub async fn watch_query(
+    config: &Config,
+    args: &Args,
+    provider: &dyn Provider,
+    query: &str,
+    messages: &[Message],
+    options: &ProviderOptions,
+) -> Result<()> {
+    let executor = CommandExecutor::new(config).with_timeout(
+        args.exec_timeout
+            .unwrap_or(config.behavior.exec_timeout_secs),
+    );
+
+    let mut command = generate_command(config, args, provider, messages, options).await?;
+    save_exchange(config, args, query, &command)?;
+    if !approved(config, args, &executor, &command) {
+        return Ok(());
+    }
+
+    let cwd = std::env::current_dir()?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })?;
+    watcher.watch(&cwd, RecursiveMode::Recursive)?;
+
+    eprintln!(
+        "{} {} {}",
+        "Watching".cyan().bold(),
+        cwd.display(),
+        "(Ctrl+C to stop)".bright_black()
+    );
+
+    let mut debounce = Debounce::new(WATCH_DEBOUNCE);
+    let mut first_run = true;
+    loop {
+        if first_run || debounce.fire(Instant::now()) {
+            if !first_run && args.watch_regenerate {
+                let fresh = generate_command(config, args, provider, messages, options).await?;
+                if fresh != command && !approved(config, args, &executor, &fresh) {
+                    return Ok(());
+                }
+                command = fresh;
+            }
+            first_run = false;
+
+            println!("{} {}", "Running:".green(), command.bright_white().bold());
+            let exit_code = executor
+                .execute_with_sudo_retry(&command, args.follow != Some(false))
+                .await?;
+            if exit_code != 0 {
+                eprintln!("{}", format!("exited with {}", exit_code).bright_black());
+            }
+
+            // Changes made by the command itself must not trigger another run
+            debounce.ignore_until(Instant::now() + COMMAND_SETTLE);
+            eprintln!("{}", "Waiting for changes...".bright_black());
+        }
+
+        let wait = debounce
+            .remaining(Instant::now())
+            .unwrap_or(Duration::from_secs(3600));
+        tokio::select! {
+            event = rx.recv() => match event {
+                Some(Ok(event)) if is_relevant(&event, &cwd) => debounce.record(Instant::now()),
+                Some(_) => {}
+                None => bail!("File watcher stopped"),
+            },
+            _ = tokio::time::sleep(wait) => {}
+        }
+    }
+}
+
+/// The model's answer, which must be a command
+async fn generate_command(
+    config: &Config,
+    args: &Args,
+    provider: &dyn Provider,
+    messages: &[Message],
+    options: &ProviderOptions,
+) -> Result<String> {
+    let spinner = std::io::stdout().is_terminal().then(Spinner::start);
+    let response = provider.complete_with_options(messages, options).await;
+    drop(spinner);
+
+    let max_chars = config.behavior.max_command_chars;
+    let text = strip_command_preamble(args, response?.text);
+    let command = normalize_command_response(&text, max_chars, config.behavior.flatten_commands)
+        .unwrap_or(text);
+    let command = command.trim().to_string();
+    if !is_likely_command(&command, max_chars) {
+        bail!(
+            "--watch needs a command to run, but the answer was:\n{}",
+            command
+        );
+    }
+    Ok(command)
+}
+
+/// Same rules as a single run: `-y`/`--yes-safe`/`auto_execute` skip the confirmation
+fn approved(config: &Config, args: &Args, executor: &CommandExecutor, command: &str) -> bool {
+    should_auto_execute(args, config, executor.is_safe(command))
+        || confirm_command(executor, command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, ModifyKind};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_debounce_waits_for_quiet() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut debounce = Debounce::new(ms(500));
+
+        assert!(!debounce.fire(start), "nothing changed yet");
+        assert_eq!(debounce.remaining(start), None);
+
+        debounce.record(start);
+        assert!(!debounce.fire(start + ms(100)));
+        debounce.record(start + ms(300));
+        assert!(!debounce.fire(start + ms(700)), "quiet time restarts");
+        assert_eq!(debounce.remaining(start + ms(700)), Some(ms(100)));
+
+        assert!(debounce.fire(start + ms(800)));
+        assert!(!debounce.fire(start + ms(900)), "fires once per burst");
+    }
+
+    #[test]
+    fn test_debounce_ignores_changes_right_after_a_run() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut debounce = Debounce::new(ms(500));
+
+        debounce.record(start);
+        debounce.ignore_until(start + ms(250));
+        assert_eq!(debounce.remaining(start), None, "pending change dropped");
+
+        debounce.record(start + ms(100));
+        assert_eq!(
+            debounce.remaining(start + ms(100)),
+            None,
+            "command's own write"
+        );
+
+        debounce.record(start + ms(300));
+        assert!(debounce.fire(start + ms(800)));
+    }
+
+    #[test]
+    fn test_is_relevant_skips_access_and_build_dirs() {
+        let event = |kind, path: &str| notify::Event::new(kind).add_path(PathBuf::from(path));
+        let is_relevant = |event: &notify::Event| is_relevant(event, Path::new("/proj"));
+
+        assert!(is_relevant(&event(
+            EventKind::Modify(ModifyKind::Any),
+            "/proj/src/main.rs"
+        )));
+        assert!(is_relevant(&event(
+            EventKind::Create(CreateKind::File),
+            "/proj/new.txt"
+        )));
+        assert!(!is_relevant(&event(
+            EventKind::Access(AccessKind::Any),
+            "/proj/src/main.rs"
+        )));
+        assert!(!is_relevant(&event(
+            EventKind::Modify(ModifyKind::Any),
+            "/proj/target/debug/app"
+        )));
+        assert!(!is_relevant(&event(
+            EventKind::Modify(ModifyKind::Any),
+            "/proj/.git/index"
+        )));
+
+        // An ignored name above the watched directory does not count
+        let root = Path::new("/home/me/target/app");
+        let change = event(
+            EventKind::Modify(ModifyKind::Any),
+            "/home/me/target/app/src/main.rs",
+        );
+        assert!(super::is_relevant(&change, root));
+        let build = event(
+            EventKind::Modify(ModifyKind::Any),
+            "/home/me/target/app/target/debug/app",
+        );
+        assert!(!super::is_relevant(&build, root));
+    }
+}
@@ -24,6 +24,13 @@ fn build_cli() -> Command {
                 .help("Force command mode (bypass auto-detection)")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("count")
+                .short('N')
+                .long("count")
+                .help("Get N candidate answers (1-10); pick one to run in command mode")
+                .value_name("N"),
+        )
         .arg(
             Arg::new("yes")
                 .short('y')
@@ -31,6 +38,12 @@ fn build_cli() -> Command {
                 .help("Auto-execute commands without confirmation")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("yes-safe")
+                .long("yes-safe")
+                .help("Auto-execute only safe commands, prompt for the rest")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("think")
                 .short('t')
@@ -75,6 +88,36 @@ fn build_cli() -> Command {
                 .help("Non-interactive init (use with -P, -m, -k)")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("key-from-stdin")
+                .long("key-from-stdin")
+                .help("Read the API key for init -n from stdin")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("profile-name")
+                .long("profile-name")
+                .help("Profile to create on init -n (default: main)")
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("base-url")
+                .long("base-url")
+                .help("Base URL for the profile created on init -n")
+                .value_name("URL"),
+        )
+        .arg(
+            Arg::new("thinking")
+                .long("thinking")
+                .help("Thinking level/budget for the profile created on init -n")
+                .value_name("VALUE"),
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .help("Overwrite an existing config on init -n")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("json")
                 .long("json")
@@ -87,30 +130,121 @@ fn build_cli() -> Command {
                 .help("Output rendered in Markdown")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("Output format: text, markdown, table or json")
+                .value_name("FMT")
+                .value_parser(["text", "markdown", "table", "json"]),
+        )
         .arg(
             Arg::new("raw")
                 .long("raw")
                 .help("Output raw text without formatting")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("preserve")
+                .long("preserve")
+                .alias("no-trim")
+                .help("Print the answer byte for byte (no trimming, formatting or commands)")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("raw-stream")
+                .long("raw-stream")
+                .help("Stream plain chunks to stdout, for piping")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("json-stream")
+                .long("json-stream")
+                .help("Stream NDJSON events to stdout (delta per chunk, then done)")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("tee")
+                .long("tee")
+                .alias("stream-to-file")
+                .help("Stream the answer as usual and save a copy to FILE")
+                .value_name("FILE")
+                .value_hint(clap::ValueHint::FilePath),
+        )
         .arg(
             Arg::new("no-color")
                 .long("no-color")
                 .help("Disable colorized output")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("theme")
+                .long("theme")
+                .help("Color preset: dark (default) or light")
+                .value_name("NAME")
+                .value_parser(["dark", "light"]),
+        )
         .arg(
             Arg::new("no-follow")
                 .long("no-follow")
                 .help("Disable result echo after execution")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("exec-timeout")
+                .long("exec-timeout")
+                .help("Kill executed commands after SECS seconds")
+                .value_name("SECS"),
+        )
+        .arg(
+            Arg::new("keep-going")
+                .long("keep-going")
+                .help(
+                    "Multi-line commands (flatten_commands = false): run the rest after a failure",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("max-context-chars")
+                .long("max-context-chars")
+                .help("Drop oldest context messages beyond N characters")
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new("max-tokens")
+                .long("max-tokens")
+                .help("Cap the answer at N tokens (default depends on the model)")
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .help("Send seed N and temperature 0 for reproducible answers")
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new("wrap")
+                .long("wrap")
+                .help("Wrap prose answers at N columns (turns off streaming)")
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new("no-wrap")
+                .long("no-wrap")
+                .help("Do not wrap prose answers")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("no-fallback")
                 .long("no-fallback")
                 .help("Disable fallback to other profiles on error")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("round-robin")
+                .long("round-robin")
+                .help("Rotate the starting profile through provider_order")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("search")
                 .short('s')
@@ -124,12 +258,126 @@ fn build_cli() -> Command {
                 .help("Show citations from web search results")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("cost")
+                .long("cost")
+                .help("Print estimated cost from token usage")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("show-thinking")
+                .long("show-thinking")
+                .visible_alias("thinking-output")
+                .help("Print the reasoning trace above the answer (with -t)")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("copy")
+                .long("copy")
+                .visible_alias("clipboard")
+                .help("Copy the answer or command to the clipboard")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-inject")
+                .long("no-inject")
+                .visible_alias("no-injection")
+                .help("Never paste commands into the terminal; confirm before running")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("safe-paste")
+                .long("safe-paste")
+                .help("Ask before pasting a command into the focused window")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("offline")
+                .long("offline")
+                .help("Never touch the network (no update checks, no provider calls)")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("debug-request")
+                .long("debug-request")
+                .alias("provider-raw-request")
+                .help("Print each request sent to the provider (keys redacted) to stderr")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("health")
+                .long("health")
+                .help("Ping one provider using only -k/-m/--base-url")
+                .value_name("NAME")
+                .value_parser([
+                    "openai",
+                    "anthropic",
+                    "gemini",
+                    "ollama",
+                    "azure",
+                    "openai_compatible",
+                ]),
+        )
+        .arg(
+            Arg::new("explain-error")
+                .long("explain-error")
+                .help("Ask the model to explain a failed command and suggest a fix")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("update")
                 .long("update")
                 .help("Check and install updates")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("bench")
+                .long("bench")
+                .help("Run the query on every profile and compare latency")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("profiles")
+                .long("profiles")
+                .help("Limit --bench to these profiles (comma-separated)")
+                .value_name("NAMES"),
+        )
+        .arg(
+            Arg::new("stats")
+                .long("stats")
+                .help("Show queries, tokens, estimated cost and latency over time")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("since")
+                .long("since")
+                .help("Limit stats to the last SPAN, e.g. 24h, 7d, 2w")
+                .value_name("SPAN"),
+        )
+        .arg(
+            Arg::new("model-info")
+                .long("model-info")
+                .help("Show the thinking/reasoning parameters a query would send")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("profile-env")
+                .long("profile-env")
+                .help("Print export lines for a profile, for eval in a shell")
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("list-models")
+                .long("list-models")
+                .help("List the profiles' models with thinking/vision hints")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("review")
+                .long("review")
+                .help("Review a piped diff: summary plus comments grouped by file")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("make-prompt")
                 .long("make-prompt")
@@ -142,6 +390,52 @@ fn build_cli() -> Command {
                 .help("Export example ask.toml to stdout")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("set")
+                .long("set")
+                .help("Set one config value (key=value)")
+                .value_name("KEY=VALUE"),
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .help("Load only this config file (skips discovery)")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("prompt-file")
+                .long("prompt-file")
+                .help("Use FILE as the system prompt for this query")
+                .value_name("FILE")
+                .value_hint(clap::ValueHint::FilePath),
+        )
+        .arg(
+            Arg::new("image-url")
+                .long("image-url")
+                .alias("input-image-url")
+                .help("Attach a remote image to the question (repeatable)")
+                .value_name("URL")
+                .value_hint(clap::ValueHint::Url)
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("prefix")
+                .long("prefix")
+                .help("Put TEXT before the question")
+                .value_name("TEXT"),
+        )
+        .arg(
+            Arg::new("suffix")
+                .long("suffix")
+                .help("Put TEXT after the question")
+                .value_name("TEXT"),
+        )
+        .arg(
+            Arg::new("template")
+                .long("template")
+                .help("Use a saved template (~/.config/ask/templates/NAME.md) as the prompt")
+                .value_name("NAME"),
+        )
         .arg(
             Arg::new("verbose")
                 .short('v')
@@ -149,6 +443,38 @@ fn build_cli() -> Command {
                 .help("Show verbose output (profile, provider info)")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("show-expansion")
+                .long("show-expansion")
+                .help("Print how aliases expanded the arguments, without running")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dump-config-path")
+                .long("dump-config-path")
+                .visible_alias("where-config")
+                .help("List config file locations by precedence and which ones are used")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("retry-last")
+                .long("retry-last")
+                .help("Run the previous query again with the same flags")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("allow-binary")
+                .long("allow-binary")
+                .help("Send binary stdin base64-encoded instead of refusing it")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("edit-answer")
+                .long("edit-answer")
+                .visible_alias("interactive-edit")
+                .help("Edit a prose answer in $EDITOR before saving it (use with -c)")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("clear")
                 .long("clear")
@@ -161,6 +487,81 @@ fn build_cli() -> Command {
                 .help("Show context history (use with -c)")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("more")
+                .long("more")
+                .help("Continue the previous answer (use with -c)")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ephemeral")
+                .long("ephemeral")
+                .alias("no-history")
+                .help("Use the context but don't save this exchange (use with -c)")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("apply")
+                .long("apply")
+                .alias("diff-apply")
+                .help("Ask for a unified diff, preview it and patch the file")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .help("Run the generated command again whenever files here change")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("watch-regenerate")
+                .long("watch-regenerate")
+                .help("Like --watch, but ask for a fresh command before each run")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("browse")
+                .long("browse")
+                .help("Browse saved contexts interactively (use with history)")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("export")
+                .long("export")
+                .help("Export context to a .md or .json file")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("import")
+                .long("import")
+                .help("Import a JSON transcript into the current context")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("session")
+                .long("session")
+                .help("Use a named context session (use with -c)")
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("fork")
+                .long("fork")
+                .help("Copy the context into a new session (use with -c)")
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("context-file")
+                .long("context-file")
+                .help("Keep the context in FILE instead of per directory (implies -c)")
+                .value_name("FILE")
+                .value_hint(clap::ValueHint::FilePath),
+        )
+        .arg(
+            Arg::new("resume")
+                .long("resume")
+                .help("Continue a saved context by ID prefix from 'ask history' (implies -c)")
+                .value_name("ID"),
+        )
         .arg(
             Arg::new("completions")
                 .long("completions")
@@ -177,6 +578,74 @@ fn build_cli() -> Command {
         .subcommand(Command::new("init").about("Initialize configuration interactively"))
         .subcommand(Command::new("config").about("Initialize configuration interactively"))
         .subcommand(Command::new("profiles").about("List available profiles"))
+        .subcommand(Command::new("templates").about("List saved prompt templates"))
+        .subcommand(
+            Command::new("models").about("List the profiles' models with thinking/vision hints"),
+        )
+        .subcommand(Command::new("version").about("Show version and build info"))
+        .subcommand(
+            Command::new("doctor").about("Check config, API keys, connectivity and storage"),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Queries, tokens, estimated cost and latency over time")
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .help("Only the last SPAN, e.g. 24h, 7d, 2w")
+                        .value_name("SPAN"),
+                ),
+        )
+        .subcommand(Command::new("history").about("List, search or prune saved contexts"))
+        .subcommand(
+            Command::new("alias")
+                .about("Manage command-line aliases")
+                .subcommand(Command::new("list").about("List aliases"))
+                .subcommand(
+                    Command::new("add")
+                        .about("Add or replace an alias")
+                        .arg(Arg::new("name").required(true))
+                        .arg(Arg::new("expansion").required(true).num_args(1..)),
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("Remove an alias")
+                        .arg(Arg::new("name").required(true)),
+                ),
+        )
+}
+
+/// Whether `word` is already an ask flag (`--raw`, `-p`) or subcommand, so an alias would shadow it
+pub fn is_reserved_word(word: &str) -> bool {
+    let cmd = build_cli();
+
+    if let Some(long) = word.strip_prefix("--") {
+        let long = long.split('=').next().unwrap_or(long);
+        return cmd.get_arguments().any(|arg| {
+            arg.get_long() == Some(long)
+                || arg
+                    .get_all_aliases()
+                    .is_some_and(|aliases| aliases.contains(&long))
+        });
+    }
+
+    if let Some(short) = word.strip_prefix('-') {
+        let mut chars = short.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) => cmd.get_arguments().any(|arg| {
+                arg.get_short() == Some(c)
+                    || arg
+                        .get_all_short_aliases()
+                        .is_some_and(|aliases| aliases.contains(&c))
+            }),
+            _ => false,
+        };
+    }
+
+    let is_subcommand = cmd
+        .get_subcommands()
+        .any(|sub| sub.get_name() == word || sub.get_all_aliases().any(|a| a == word));
+    is_subcommand
 }
 
 /// Generate shell completions and print to stdout
@@ -0,0 +1,210 @@
+//! `ask alias add/remove/list`: manage the `[aliases]` table
+
+use super::set::{read_doc, target_config_path, write_doc};
+use super::Config;
+use anyhow::{bail, Result};
+use colored::Colorize;
+use std::path::PathBuf;
+
+/// Add or replace an alias in the config file that wins the merge
+pub fn add_alias(name: &str, expansion: &str) -> Result<()> {
+    validate_alias(name, expansion)?;
+
+    let path = target_config_path()?;
+    let mut doc = read_doc(&path)?;
+    let previous = insert_alias(&mut doc, name, expansion)?;
+    write_doc(&path, &doc)?;
+
+    let verb = if previous.is_some() {
+        "Updated"
+    } else {
+        "Added"
+    };
+    println!(
+        "{} alias {} = \"{}\" {}",
+        verb.green(),
+        name.bright_white(),
+        expansion,
+        format!("in {}", path.display()).bright_black()
+    );
+    Ok(())
+}
+
+/// Remove an alias from the highest-precedence config file that defines it
+pub fn remove_alias(name: &str) -> Result<()> {
+    // Aliases from every file are merged, so look in each, winning file first
+    let defining: Vec<PathBuf> = Config::config_files()
+        .into_iter()
+        .rev()
+        .filter(|path| read_doc(path).is_ok_and(|doc| defines_alias(&doc, name)))
+        .collect();
+    let Some(path) = defining.first() else {
+        bail!("No alias '{}' in any config file", name);
+    };
+
+    let mut doc = read_doc(path)?;
+    delete_alias(&mut doc, name);
+    write_doc(path, &doc)?;
+
+    println!(
+        "{} alias {} {}",
+        "Removed".green(),
+        name.bright_white(),
+        format!("from {}", path.display()).bright_black()
+    );
+    if let Some(next) = defining.get(1) {
+        println!(
+            "  {}",
+            format!(
+                "'{}' is still defined in {}; run again to remove it there too",
+                name,
+                next.display()
+            )
+            .bright_black()
+        );
+    }
+    Ok(())
+}
+
+/// Print the aliases in effect, sorted by name
+pub fn list_aliases(config: &Config) -> Result<()> {
+    println!("{}", "Aliases".cyan().bold());
+    println!();
+
+    if config.aliases.is_empty() {
+        println!(
+            "  {}",
+            "No aliases. Add one with: ask alias add q \"--raw --no-color\"".bright_black()
+        );
+        return Ok(());
+    }
+
+    let mut names: Vec<&String> = config.aliases.keys().collect();
+    names.sort();
+    let width = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+    for name in names {
+        println!(
+            "  {:<width$}  {}",
+            name.bright_white(),
+            config.aliases[name],
+            width = width
+        );
+    }
+    Ok(())
+}
+
+/// An alias must be one word and must not shadow a real flag or subcommand
+fn validate_alias(name: &str, expansion: &str) -> Result<()> {
+    if name.is_empty() || name.chars().any(char::is_whitespace) {
+        bail!("Alias name must be a single word, got '{}'", name);
+    }
+    if crate::completions::is_reserved_word(name) {
+        bail!(
+            "'{}' is already an ask flag or subcommand; pick another alias name",
+            name
+        );
+    }
+    if expansion.trim().is_empty() {
+        bail!(
+            "Alias '{}' needs an expansion, e.g. \"--raw --no-color\"",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Set `aliases.<name>`, creating the table; returns the expansion it replaced
+fn insert_alias(doc: &mut toml::Value, name: &str, expansion: &str) -> Result<Option<String>> {
+    let root = doc
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("Config is not a table"))?;
+    let aliases = root
+        .entry("aliases")
+        .or_insert_with(|| toml::Value::Table(toml::map::Map::new()))
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("'aliases' is not a table"))?;
+
+    let previous = aliases.insert(
+        name.to_string(),
+        toml::Value::String(expansion.trim().to_string()),
+    );
+    Ok(previous.and_then(|v| v.as_str().map(str::to_string)))
+}
+
+/// Whether the document has `aliases.<name>`
+fn defines_alias(doc: &toml::Value, name: &str) -> bool {
+    doc.get("aliases")
+        .and_then(|a| a.as_table())
+        .is_some_and(|aliases| aliases.contains_key(name))
+}
+
+/// Drop `aliases.<name>`; false if it wasn't there
+fn delete_alias(doc: &mut toml::Value, name: &str) -> bool {
+    doc.get_mut("aliases")
+        .and_then(|a| a.as_table_mut())
+        .is_some_and(|aliases| aliases.remove(name).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(doc: &toml::Value) -> Config {
+        Config::from_toml(&toml::to_string_pretty(doc).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_add_alias_round_trips() {
+        let mut doc: toml::Value =
+            toml::from_str("[behavior]\nauto_execute = false\n\n[aliases]\nfast = \"-p fast\"\n")
+                .unwrap();
+
+        let previous = insert_alias(&mut doc, "q", "--raw --no-color").unwrap();
+        assert_eq!(previous, None);
+
+        let config = round_trip(&doc);
+        assert_eq!(config.aliases["q"], "--raw --no-color");
+        assert_eq!(config.aliases["fast"], "-p fast", "other aliases are kept");
+        assert!(!config.behavior.auto_execute, "other sections are kept");
+
+        let previous = insert_alias(&mut doc, "q", "--raw").unwrap();
+        assert_eq!(previous.as_deref(), Some("--raw --no-color"));
+        assert_eq!(round_trip(&doc).aliases["q"], "--raw");
+    }
+
+    #[test]
+    fn test_add_alias_creates_table() {
+        let mut doc = toml::Value::Table(toml::map::Map::new());
+        insert_alias(&mut doc, "deep", "-t --search").unwrap();
+        assert_eq!(round_trip(&doc).aliases["deep"], "-t --search");
+    }
+
+    #[test]
+    fn test_remove_alias_round_trips() {
+        let mut doc: toml::Value =
+            toml::from_str("[aliases]\nq = \"--raw\"\nfast = \"-p fast\"\n").unwrap();
+
+        assert!(defines_alias(&doc, "q"));
+        assert!(delete_alias(&mut doc, "q"));
+        assert!(!defines_alias(&doc, "q"));
+        assert!(!delete_alias(&mut doc, "q"), "already gone");
+
+        let config = round_trip(&doc);
+        assert!(!config.aliases.contains_key("q"));
+        assert_eq!(config.aliases["fast"], "-p fast");
+
+        let mut empty = toml::Value::Table(toml::map::Map::new());
+        assert!(!defines_alias(&empty, "q"));
+        assert!(!delete_alias(&mut empty, "q"));
+    }
+
+    #[test]
+    fn test_validate_alias_rejects_flags() {
+        assert!(validate_alias("q", "--raw").is_ok());
+        assert!(validate_alias("--raw", "--json").is_err());
+        assert!(validate_alias("-p", "--json").is_err());
+        assert!(validate_alias("history", "--json").is_err());
+        assert!(validate_alias("two words", "--raw").is_err());
+        assert!(validate_alias("q", "  ").is_err());
+    }
+}
@@ -5,12 +5,83 @@ pub const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
 pub const DEFAULT_ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com";
 pub const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
 pub const DEFAULT_OLLAMA_MODEL: &str = "phi4-mini";
+pub const DEFAULT_AZURE_API_VERSION: &str = "2024-10-21";
 pub const DEFAULT_PROVIDER: &str = "gemini";
 pub const DEFAULT_MODEL: &str = "gemini-flash-lite-latest";
 pub const DEFAULT_OPENAI_MODEL: &str = "gpt-5-nano";
 pub const DEFAULT_ANTHROPIC_MODEL: &str = "claude-haiku-4-5";
 pub const DEFAULT_TIMEOUT: u64 = 30;
 
+// OpenAI-compatible local servers offered by `ask init` (label, base_url).
+// They are written as `provider = "openai"` with the placeholder key below,
+// since most local servers ignore the key but the provider requires one.
+pub const LOCAL_SERVER_PRESETS: &[(&str, &str)] = &[
+    ("LM Studio", "http://localhost:1234/v1"),
+    ("vLLM", "http://localhost:8000/v1"),
+    ("Ollama (OpenAI API)", "http://localhost:11434/v1"),
+];
+pub const LOCAL_SERVER_API_KEY: &str = "local";
+
+// Built-in USD prices per million tokens (model, input, output) for `--cost`.
+// Approximate list prices; `[pricing]` in the config takes precedence.
+pub const DEFAULT_PRICING: &[(&str, f64, f64)] = &[
+    ("gpt-5", 1.25, 10.00),
+    ("gpt-5-mini", 0.25, 2.00),
+    ("gpt-5-nano", 0.05, 0.40),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("claude-opus-4-1", 15.00, 75.00),
+    ("claude-sonnet-4-5", 3.00, 15.00),
+    ("claude-sonnet-4-20250514", 3.00, 15.00),
+    ("claude-haiku-4-5", 1.00, 5.00),
+    ("gemini-2.5-pro", 1.25, 10.00),
+    ("gemini-2.5-flash", 0.30, 2.50),
+    ("gemini-2.5-flash-lite", 0.10, 0.40),
+    ("gemini-flash-latest", 0.30, 2.50),
+    ("gemini-flash-lite-latest", 0.10, 0.40),
+];
+
+// Answer length cap per model family when --max-tokens is not given
+// (provider, model name prefix, tokens); the first matching prefix wins.
+// Reasoning models get more room, since their thinking counts against the cap.
+pub const DEFAULT_MAX_TOKENS: &[(&str, &str, u32)] = &[
+    ("openai", "gpt-5-nano", 16384),
+    ("openai", "gpt-5", 32768),
+    ("openai", "o1", 32768),
+    ("openai", "o3", 32768),
+    ("openai", "o4", 32768),
+    ("openai", "gpt-4.1-nano", 16384),
+    ("openai", "gpt-4.1", 32768),
+    ("openai", "gpt-4o", 16384),
+    ("anthropic", "claude-3-7", 64000),
+    ("anthropic", "claude-3-5", 8192),
+    ("anthropic", "claude-3", 4096),
+    ("anthropic", "claude-opus-4", 32000),
+    ("anthropic", "claude", 64000),
+    ("gemini", "gemini-1.", 8192),
+    ("gemini", "gemini-2.0", 8192),
+];
+
+/// Default answer cap for a model: its `DEFAULT_MAX_TOKENS` family, else the
+/// provider's fallback. Ollama doesn't ask, it leaves the length to the model.
+pub fn default_max_tokens_for(provider: &str, model: &str) -> u32 {
+    // "openai/gpt-5" on routers and gateways
+    let name = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+
+    let family = DEFAULT_MAX_TOKENS
+        .iter()
+        .find(|(p, prefix, _)| *p == provider && name.starts_with(prefix))
+        .map(|(_, _, tokens)| *tokens);
+
+    match provider {
+        "gemini" => family.unwrap_or(65536),
+        _ => family.unwrap_or(4096),
+    }
+}
+
 // Free built-in profiles (no signup required)
 pub const FREE_PROFILE_NAMES: &[&str] = &["talker", "coder", "vision", "faster"];
 
@@ -91,6 +162,10 @@ pub const DEFAULT_CONFIG_TEMPLATE: &str = r##"# ask - Configuration File
 # Built-in free profiles: talker (ch.at), coder, vision, faster (llm7.io)
 # Default selection: default_profile, then first non-built-in profile, then "talker"
 # Switch profiles with: ask -p <profile_name>
+
+# Profiles for --round-robin: each run starts at the next one, errors fall through the list
+# provider_order = ["main", "work", "local"]
+
 [profiles.main]
 provider = "gemini"           # gemini, openai, anthropic
 model = "gemini-3-flash-preview"
@@ -100,6 +175,7 @@ stream = true                 # Stream responses token by token
 # thinking_budget = 1024      # For Gemini 2.5: 0 (off), 1024-32768, -1 (dynamic)
 # web_search = false          # Enable web search by default
 # fallback = "none"           # Profile to use on errors: "any", "none", or profile name
+# prompt_append = "..."       # Extra system prompt instructions for this profile
 
 # Example: Work profile with OpenAI
 # [profiles.work]
@@ -107,6 +183,16 @@ stream = true                 # Stream responses token by token
 # model = "gpt-5"
 # api_key = "sk-..."
 # reasoning_effort = "medium" # For o1/o3/gpt-5: none, minimal, low, medium, high, xhigh
+# supports_reasoning = true   # Send reasoning params to a non-OpenAI base_url
+
+# Example: Azure OpenAI (model is used as the deployment unless set)
+# [profiles.azure]
+# provider = "azure"
+# base_url = "https://myresource.openai.azure.com"
+# model = "gpt-4o"
+# deployment = "my-gpt4o-deployment"
+# api_version = "2024-10-21"
+# api_key = "..."             # Or set ASK_AZURE_API_KEY
 # fallback = "main"
 
 # Example: Local profile with Ollama
@@ -128,20 +214,80 @@ stream = true                 # Stream responses token by token
 [behavior]
 auto_execute = false          # Auto-execute safe commands without prompting
 confirm_destructive = true    # Confirm before running destructive commands
-timeout = 30                  # Request timeout in seconds
+# confirm_default = "no"      # Answer when Enter is pressed at "Run ...?" (yes/no)
+# confirm_full_word = false   # Destructive commands need "yes" typed in full
+timeout = 30                  # Request timeout in seconds (non-streaming)
+connect_timeout = 30          # Connect timeout in seconds (streaming has no total limit)
+# exec_timeout_secs = 300     # Kill executed commands after N seconds (0 = no limit)
+# edit_in_editor = false      # Edit suggested commands in $EDITOR
+# inject = "auto"             # auto (paste into terminal), prompt (editable prompt), off (confirm only)
+# explain_failures = false    # Ask the model why an executed command failed
+# clipboard_restore_ms = 500  # Restore the clipboard this long after a GUI paste (0 = never)
+# confirm_paste = false       # Ask before pasting into the focused window (GUI paste)
+# default_mode = "auto"       # "command" or "question" when no -x/--question (e.g. in a project .ask.toml)
+# min_interval_ms = 0         # Wait at least this long between calls to a provider (0 = off)
+# notify_after_secs = 0       # Bell when an answer took this long (0 = off)
+# notify_desktop = false      # Also show a desktop notification (notify-send / macOS)
+# wrap_columns = 100          # Wrap non-streamed prose answers (capped at terminal width, 0 = off)
+# max_command_chars = 500     # Longer answers run only if one line with no sentences (0 = no limit)
+# flatten_commands = true     # Join multi-line commands with &&; false runs them line by line
+# log_usage = true            # Log provider, model, tokens and latency (no text) for `ask stats`
 
 # Context/history settings (global)
 [context]
 max_age_minutes = 30          # Context TTL (0 = permanent)
 max_messages = 20             # Maximum messages to keep
+# max_chars = 12000           # Character budget sent to the provider (0 = unlimited)
+# rolling_summary = false     # Summarize trimmed messages instead of forgetting them
 # storage_path = "~/.local/share/ask/contexts"  # Custom storage path
 
+# Shell hooks around executed commands ($CMD = command, $EXIT = exit code, after only)
+# [hooks]
+# before_execute = 'echo "about to run: $CMD"'
+# after_execute = 'notify-send "ask" "$CMD exited with $EXIT"'
+
+# Prices for --cost in USD per million tokens (overrides the built-in table)
+# [pricing]
+# "gpt-4o" = { input_per_mtok = 2.50, output_per_mtok = 10.00 }
+
+# HTTP settings (HTTPS_PROXY, HTTP_PROXY, ALL_PROXY and NO_PROXY are honored by default)
+# [http]
+# proxy = "http://proxy.corp:3128"  # Override env proxies for all requests
+
+# Replace secrets in piped input and @files with [REDACTED] before sending
+# (AWS keys, bearer tokens, password=/api_key=, PEM private keys)
+# [privacy]
+# redact = true
+# redact_patterns = ['ghp_[A-Za-z0-9]{36}']  # Extra regexes; a capture group limits the replacement
+
+# Output colors: a preset ("dark" or "light", also --theme light), then any role overridden
+# with a color name ("blue", "bright black") or "#rrggbb"
+# [theme]
+# preset = "light"
+# command = "blue"     # Commands before they run
+# heading = "magenta"  # Markdown headers, "Sources:", numbered candidates
+# hint = "#808080"     # Hints, verbose notes, thinking, the spinner
+# error = "red"
+# citation = "cyan"
+
+# Cache the system prompt on Anthropic (cheaper repeats with a long ask.md)
+# [providers.anthropic]
+# prompt_cache = true
+
+# Extra instructions appended to the system prompt (default or ask.md)
+# [prompt]
+# append = "Always prefer ripgrep over grep. Never suggest sudo unless asked."
+# stdin_template = "<input>\n{input}\n</input>\n\n{question}"  # Framing for piped input
+# merge = false               # Combine every ask.md found (config dir, home, local) instead of the first
+
 # Auto-update settings
 [update]
 auto_check = true             # Check for updates in background
 aggressive = true             # Check every execution (not every 24h)
 check_interval_hours = 24     # Hours between checks (when aggressive=false)
 channel = "stable"            # stable, beta
+# user_agent = "ask (ops@example.com)"  # User-Agent for update requests (default: ask/<version>)
+# allow_self_update = true    # false = never replace the binary (package-manager installs)
 
 # Custom commands - use with: ask <command_name> or pipe: git diff | ask cm
 [commands.cm]
@@ -149,6 +295,11 @@ system = "Generate a concise git commit message based on the diff provided. Outp
 type = "command"
 auto_execute = false
 
+# Few-shot examples are sent as user/assistant turns before your input
+# [[commands.cm.examples]]
+# user = "+ fn login(user: &str) -> Result<Session>"
+# assistant = "feat(auth): add login"
+
 [commands.explain]
 system = "Explain this code in detail, including what it does and how it works."
 inherit_flags = true
@@ -164,3 +315,55 @@ inherit_flags = true
 # fast = "-p fast --no-fallback"
 # deep = "-t --search"
 "##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_max_tokens_openai() {
+        assert_eq!(default_max_tokens_for("openai", "gpt-5"), 32768);
+        assert_eq!(default_max_tokens_for("openai", "gpt-5-mini"), 32768);
+        assert_eq!(default_max_tokens_for("openai", "gpt-5-nano"), 16384);
+        assert_eq!(default_max_tokens_for("openai", "o4-mini"), 32768);
+        assert_eq!(default_max_tokens_for("openai", "gpt-4.1-nano"), 16384);
+        assert_eq!(default_max_tokens_for("openai", "gpt-4o-mini"), 16384);
+        assert_eq!(default_max_tokens_for("openai", "openai/GPT-5"), 32768);
+        // Unknown models on compatible servers keep the old default
+        assert_eq!(default_max_tokens_for("openai", "qwen2.5-coder"), 4096);
+    }
+
+    #[test]
+    fn test_default_max_tokens_anthropic() {
+        assert_eq!(
+            default_max_tokens_for("anthropic", "claude-sonnet-4-5"),
+            64000
+        );
+        assert_eq!(
+            default_max_tokens_for("anthropic", "claude-haiku-4-5"),
+            64000
+        );
+        assert_eq!(
+            default_max_tokens_for("anthropic", "claude-opus-4-1"),
+            32000
+        );
+        assert_eq!(
+            default_max_tokens_for("anthropic", "claude-3-5-haiku-latest"),
+            8192
+        );
+        assert_eq!(
+            default_max_tokens_for("anthropic", "claude-3-haiku-20240307"),
+            4096
+        );
+    }
+
+    #[test]
+    fn test_default_max_tokens_gemini() {
+        assert_eq!(default_max_tokens_for("gemini", "gemini-2.5-pro"), 65536);
+        assert_eq!(
+            default_max_tokens_for("gemini", "gemini-flash-lite-latest"),
+            65536
+        );
+        assert_eq!(default_max_tokens_for("gemini", "gemini-2.0-flash"), 8192);
+    }
+}
@@ -1,16 +1,42 @@
 //! Configuration loader - handles TOML config hierarchy
 
-use super::Config;
-use anyhow::Result;
-use std::path::PathBuf;
+use super::{
+    AnthropicConfig, BehaviorConfig, Config, HooksConfig, HttpConfig, PrivacyConfig, PromptConfig,
+    ProvidersConfig, ThemeConfig,
+};
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Config file pinned with --config (takes precedence over ASK_CONFIG)
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
 
 impl Config {
+    /// Pin the config file for this process (from --config)
+    pub fn set_config_path(path: PathBuf) {
+        let _ = CONFIG_PATH_OVERRIDE.set(path);
+    }
+
+    /// Explicit config file from --config or ASK_CONFIG, if any
+    fn pinned_config_path() -> Option<PathBuf> {
+        CONFIG_PATH_OVERRIDE.get().cloned().or_else(|| {
+            std::env::var("ASK_CONFIG")
+                .ok()
+                .filter(|p| !p.is_empty())
+                .map(|p| PathBuf::from(shellexpand::tilde(&p).as_ref()))
+        })
+    }
+
     /// Load only aliases from config (fast, for early argument expansion)
     pub fn load_aliases_only() -> std::collections::HashMap<String, String> {
-        if let Some(path) = Self::find_local_config()
-            .or_else(Self::find_home_config)
-            .or_else(Self::find_xdg_config)
-        {
+        let path = match Self::pinned_config_path() {
+            Some(pinned) => Some(pinned),
+            None => Self::find_local_config()
+                .or_else(Self::find_home_config)
+                .or_else(Self::find_xdg_config),
+        };
+
+        if let Some(path) = path {
             if let Ok(content) = std::fs::read_to_string(&path) {
                 if let Ok(config) = toml::from_str::<Config>(&content) {
                     return config.aliases;
@@ -27,7 +53,13 @@ impl Config {
     /// 4. ~/ask.toml (home directory)
     /// 5. ~/.config/ask/ask.toml (XDG config)
     /// 6. Defaults (hardcoded)
+    ///
+    /// When ASK_CONFIG or --config is set, only that file is loaded (plus env overrides).
     pub fn load() -> Result<Self> {
+        if let Some(pinned) = Self::pinned_config_path() {
+            return Self::load_pinned(&pinned);
+        }
+
         let mut config = Config::default();
 
         // Load in reverse precedence order (lowest first, higher overwrites)
@@ -61,49 +93,90 @@ impl Config {
         Ok(config)
     }
 
-    /// Find XDG config file
-    /// On Linux/macOS: ~/.config/ask/ask.toml
-    /// On Windows: C:\Users\<user>\AppData\Roaming\ask\ask.toml
-    fn find_xdg_config() -> Option<PathBuf> {
+    /// Config files `load()` reads, lowest precedence first
+    pub fn config_files() -> Vec<PathBuf> {
+        if let Some(pinned) = Self::pinned_config_path() {
+            return vec![pinned];
+        }
+
+        [
+            Self::find_xdg_config(),
+            Self::find_home_config(),
+            Self::find_local_config(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Load exactly one config file, skipping the discovery chain
+    fn load_pinned(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            bail!(
+                "Config file not found: {} (set via ASK_CONFIG or --config)",
+                path.display()
+            );
+        }
+
+        let loaded = Self::load_from_file(&path.to_path_buf())
+            .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+        let config = Self::merge(Config::default(), loaded);
+        let config = Self::apply_env_overrides(config);
+        Ok(config.ensure_default_profiles())
+    }
+
+    /// The per-user config directory, which also holds templates
+    /// On Linux/macOS: ~/.config/ask
+    /// On Windows: C:\Users\<user>\AppData\Roaming\ask
+    pub(crate) fn config_dir() -> Option<PathBuf> {
         #[cfg(windows)]
         {
             // Windows: use AppData\Roaming
-            if let Some(config_dir) = dirs::config_dir() {
-                let path = config_dir.join("ask").join("ask.toml");
-                if path.exists() {
-                    return Some(path);
-                }
-            }
+            dirs::config_dir().map(|dir| dir.join("ask"))
         }
 
         #[cfg(not(windows))]
         {
             // Linux/macOS: use ~/.config for Unix consistency
-            if let Some(home) = dirs::home_dir() {
-                let path = home.join(".config").join("ask").join("ask.toml");
-                if path.exists() {
-                    return Some(path);
-                }
-            }
+            dirs::home_dir().map(|home| home.join(".config").join("ask"))
         }
+    }
+
+    /// Where the XDG config file goes, whether or not it exists
+    pub(crate) fn xdg_config_path() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("ask.toml"))
+    }
 
-        None
+    /// Find XDG config file
+    pub(crate) fn find_xdg_config() -> Option<PathBuf> {
+        Self::xdg_config_path().filter(|path| path.exists())
+    }
+
+    /// Where the home directory config goes, whether or not it exists
+    pub(crate) fn home_config_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join("ask.toml"))
     }
 
     /// Find home directory config
-    fn find_home_config() -> Option<PathBuf> {
-        let home = dirs::home_dir()?;
-        let path = home.join("ask.toml");
-        if path.exists() {
-            Some(path)
-        } else {
-            None
-        }
+    pub(crate) fn find_home_config() -> Option<PathBuf> {
+        Self::home_config_path().filter(|path| path.exists())
     }
 
     /// Find project local config by searching upwards from current directory
-    fn find_local_config() -> Option<PathBuf> {
-        find_recursive_file(&["ask.toml", ".ask.toml"])
+    pub(crate) fn find_local_config() -> Option<PathBuf> {
+        find_recursive_file(LOCAL_CONFIG_NAMES)
+    }
+
+    /// Every place a config file is looked for, highest precedence first, with
+    /// whether it exists and whether `load()` merged it (`--dump-config-path`)
+    pub fn config_sources() -> Vec<ConfigSource> {
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        config_sources_from(
+            Self::pinned_config_path(),
+            local_config_candidate(&cwd, dirs::home_dir().as_deref()),
+            Self::home_config_path(),
+            Self::xdg_config_path(),
+        )
     }
 
     /// Load config from a specific file
@@ -123,9 +196,57 @@ impl Config {
                 profiles
             },
             default_profile: overlay.default_profile.or(base.default_profile),
-            behavior: overlay.behavior,
+            provider_order: if overlay.provider_order.is_empty() {
+                base.provider_order
+            } else {
+                overlay.provider_order
+            },
+            // Other behavior keys still come whole from the overlay
+            behavior: BehaviorConfig {
+                default_mode: overlay.behavior.default_mode.or(base.behavior.default_mode),
+                ..overlay.behavior
+            },
             context: overlay.context,
             update: overlay.update,
+            prompt: PromptConfig {
+                append: overlay.prompt.append.or(base.prompt.append),
+                stdin_template: overlay.prompt.stdin_template.or(base.prompt.stdin_template),
+                merge: overlay.prompt.merge || base.prompt.merge,
+            },
+            http: HttpConfig {
+                proxy: overlay.http.proxy.or(base.http.proxy),
+            },
+            hooks: HooksConfig {
+                before_execute: overlay.hooks.before_execute.or(base.hooks.before_execute),
+                after_execute: overlay.hooks.after_execute.or(base.hooks.after_execute),
+            },
+            providers: ProvidersConfig {
+                anthropic: AnthropicConfig {
+                    prompt_cache: overlay
+                        .providers
+                        .anthropic
+                        .prompt_cache
+                        .or(base.providers.anthropic.prompt_cache),
+                },
+            },
+            // A project config can add patterns but never switch off redaction set at home
+            privacy: PrivacyConfig {
+                redact: overlay.privacy.redact || base.privacy.redact,
+                redact_patterns: base
+                    .privacy
+                    .redact_patterns
+                    .into_iter()
+                    .chain(overlay.privacy.redact_patterns)
+                    .collect(),
+            },
+            theme: ThemeConfig {
+                preset: overlay.theme.preset.or(base.theme.preset),
+                command: overlay.theme.command.or(base.theme.command),
+                heading: overlay.theme.heading.or(base.theme.heading),
+                hint: overlay.theme.hint.or(base.theme.hint),
+                error: overlay.theme.error.or(base.theme.error),
+                citation: overlay.theme.citation.or(base.theme.citation),
+            },
             commands: {
                 let mut commands = base.commands;
                 for (k, v) in overlay.commands {
@@ -140,6 +261,11 @@ impl Config {
                 }
                 aliases
             },
+            pricing: {
+                let mut pricing = base.pricing;
+                pricing.extend(overlay.pricing);
+                pricing
+            },
             active: Default::default(),
         }
     }
@@ -156,6 +282,24 @@ impl Config {
                 config.behavior.timeout = timeout;
             }
         }
+        if let Ok(val) = std::env::var("ASK_CONNECT_TIMEOUT") {
+            if let Ok(timeout) = val.parse() {
+                config.behavior.connect_timeout = timeout;
+            }
+        }
+        if let Ok(val) = std::env::var("ASK_EXEC_TIMEOUT") {
+            if let Ok(secs) = val.parse() {
+                config.behavior.exec_timeout_secs = secs;
+            }
+        }
+        if let Ok(val) = std::env::var("ASK_INJECT") {
+            if let Some(mode) = super::InjectMode::parse(&val) {
+                config.behavior.inject = mode;
+            }
+        }
+        if let Ok(val) = std::env::var("ASK_EXPLAIN_FAILURES") {
+            config.behavior.explain_failures = parse_bool(&val);
+        }
 
         if let Ok(val) = std::env::var("ASK_CONTEXT_MAX_AGE") {
             if let Ok(age) = val.parse() {
@@ -167,6 +311,11 @@ impl Config {
                 config.context.max_messages = max;
             }
         }
+        if let Ok(val) = std::env::var("ASK_CONTEXT_MAX_CHARS") {
+            if let Ok(max) = val.parse() {
+                config.context.max_chars = max;
+            }
+        }
         if let Ok(path) = std::env::var("ASK_CONTEXT_PATH") {
             config.context.storage_path = Some(path);
         }
@@ -190,12 +339,96 @@ impl Config {
     }
 }
 
-/// Find a file by searching upwards from current directory
+/// Default number of parent directories searched for `ask.toml`/`ask.md`
+const DEFAULT_MAX_SEARCH_DEPTH: usize = 10;
+
+/// Names a project config may have, tried in this order in each directory
+const LOCAL_CONFIG_NAMES: &[&str] = &["ask.toml", ".ask.toml"];
+
+/// One config location and what became of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSource {
+    /// `pinned` (--config / ASK_CONFIG), `project`, `home` or `xdg`
+    pub kind: &'static str,
+    pub path: PathBuf,
+    pub exists: bool,
+    /// Merged into the config; false when missing, unparsable or replaced by a pinned file
+    pub contributes: bool,
+    /// Why an existing file was left out
+    pub note: Option<String>,
+}
+
+/// The project config found from `start` upwards, or `start/ask.toml` when there is none
+fn local_config_candidate(start: &Path, home: Option<&Path>) -> PathBuf {
+    find_file_upwards(start, LOCAL_CONFIG_NAMES, home, max_search_depth())
+        .unwrap_or_else(|| start.join(LOCAL_CONFIG_NAMES[0]))
+}
+
+fn config_sources_from(
+    pinned: Option<PathBuf>,
+    local: PathBuf,
+    home: Option<PathBuf>,
+    xdg: Option<PathBuf>,
+) -> Vec<ConfigSource> {
+    let discovered = [("project", Some(local)), ("home", home), ("xdg", xdg)]
+        .into_iter()
+        .filter_map(|(kind, path)| Some((kind, path?)));
+
+    let mut sources = Vec::new();
+    let is_pinned = pinned.is_some();
+    if let Some(path) = pinned {
+        sources.push(config_source("pinned", path, None));
+    }
+    for (kind, path) in discovered {
+        let skipped = is_pinned.then(|| "skipped: --config / ASK_CONFIG is set".to_string());
+        sources.push(config_source(kind, path, skipped));
+    }
+    sources
+}
+
+fn config_source(kind: &'static str, path: PathBuf, skipped: Option<String>) -> ConfigSource {
+    let exists = path.is_file();
+    let note = match (skipped, exists) {
+        (_, false) => None,
+        (Some(skipped), true) => Some(skipped),
+        (None, true) => Config::load_from_file(&path)
+            .err()
+            .map(|e| format!("ignored: {}", e.to_string().lines().next().unwrap_or(""))),
+    };
+    ConfigSource {
+        kind,
+        contributes: exists && note.is_none(),
+        path,
+        exists,
+        note,
+    }
+}
+
+/// Parent directories to traverse (`ASK_PROMPT_MAX_DEPTH`, default 10)
+fn max_search_depth() -> usize {
+    std::env::var("ASK_PROMPT_MAX_DEPTH")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_SEARCH_DEPTH)
+}
+
+/// Search upwards from the current directory for the first of `names`.
+/// Stops at the home directory, a `.git` boundary, or after `ASK_PROMPT_MAX_DEPTH` parents.
 pub fn find_recursive_file(names: &[&str]) -> Option<PathBuf> {
     let cwd = std::env::current_dir().ok()?;
-    let mut current = cwd.as_path();
+    let home = dirs::home_dir();
+    find_file_upwards(&cwd, names, home.as_deref(), max_search_depth())
+}
+
+fn find_file_upwards(
+    start: &Path,
+    names: &[&str],
+    home: Option<&Path>,
+    max_depth: usize,
+) -> Option<PathBuf> {
+    let mut current = start;
 
-    loop {
+    for _ in 0..=max_depth {
         for name in names {
             let path = current.join(name);
             if path.exists() {
@@ -203,6 +436,11 @@ pub fn find_recursive_file(names: &[&str]) -> Option<PathBuf> {
             }
         }
 
+        // Don't pick up files from ancestors of the project root or home
+        if Some(current) == home || current.join(".git").exists() {
+            break;
+        }
+
         // Move to parent directory
         match current.parent() {
             Some(parent) => current = parent,
@@ -230,6 +468,120 @@ impl Config {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_config_sources_report_local_config() {
+        let temp = tempfile::tempdir().unwrap();
+        let project = temp.path().join("project");
+        let nested = project.join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(project.join("ask.toml"), "default_profile = \"work\"\n").unwrap();
+        let home = temp.path().join("home");
+
+        let local = local_config_candidate(&nested, None);
+        assert_eq!(local, project.join("ask.toml"));
+
+        let sources = config_sources_from(None, local, Some(home.join("ask.toml")), None);
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].kind, "project");
+        assert_eq!(sources[0].path, project.join("ask.toml"));
+        assert!(sources[0].exists && sources[0].contributes);
+        assert_eq!(sources[1].kind, "home");
+        assert!(!sources[1].exists && !sources[1].contributes);
+    }
+
+    #[test]
+    fn test_config_sources_flag_broken_and_skipped_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let broken = temp.path().join("ask.toml");
+        std::fs::write(&broken, "not = [valid").unwrap();
+        let pinned = temp.path().join("pinned.toml");
+        std::fs::write(&pinned, "").unwrap();
+
+        let sources = config_sources_from(None, broken.clone(), None, None);
+        assert!(sources[0].exists && !sources[0].contributes);
+        assert!(sources[0].note.as_deref().unwrap().starts_with("ignored:"));
+
+        let sources = config_sources_from(Some(pinned), broken, None, None);
+        assert_eq!(sources[0].kind, "pinned");
+        assert!(sources[0].contributes);
+        assert!(!sources[1].contributes);
+        assert!(sources[1].note.as_deref().unwrap().starts_with("skipped"));
+    }
+
+    #[test]
+    fn test_local_config_candidate_defaults_to_cwd() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp.path().join(".git")).unwrap();
+        assert_eq!(
+            local_config_candidate(temp.path(), None),
+            temp.path().join("ask.toml")
+        );
+    }
+
+    #[test]
+    fn test_find_file_upwards_stops_at_git_boundary() {
+        let temp = tempfile::tempdir().unwrap();
+        let outer = temp.path();
+        let repo = outer.join("repo");
+        let deep = repo.join("src/nested");
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::create_dir(repo.join(".git")).unwrap();
+
+        // Outside the repository: must not be picked up
+        std::fs::write(outer.join("ask.md"), "outer").unwrap();
+        assert_eq!(find_file_upwards(&deep, &["ask.md"], None, 10), None);
+
+        // At the repository root: found
+        std::fs::write(repo.join("ask.md"), "repo").unwrap();
+        assert_eq!(
+            find_file_upwards(&deep, &["ask.md"], None, 10),
+            Some(repo.join("ask.md"))
+        );
+    }
+
+    #[test]
+    fn test_find_file_upwards_stops_at_home() {
+        let temp = tempfile::tempdir().unwrap();
+        let home = temp.path().join("home");
+        let project = home.join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(temp.path().join("ask.md"), "above home").unwrap();
+
+        assert_eq!(
+            find_file_upwards(&project, &["ask.md"], Some(&home), 10),
+            None
+        );
+
+        std::fs::write(home.join(".ask.md"), "home").unwrap();
+        assert_eq!(
+            find_file_upwards(&project, &["ask.md", ".ask.md"], Some(&home), 10),
+            Some(home.join(".ask.md"))
+        );
+    }
+
+    #[test]
+    fn test_find_file_upwards_depth_cap() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let deep = root.join("a/b/c");
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::write(root.join("ask.toml"), "").unwrap();
+
+        // root is three parents above a/b/c
+        assert_eq!(find_file_upwards(&deep, &["ask.toml"], None, 2), None);
+        assert_eq!(
+            find_file_upwards(&deep, &["ask.toml"], None, 3),
+            Some(root.join("ask.toml"))
+        );
+
+        // Depth 0 only checks the start directory
+        std::fs::write(deep.join("ask.toml"), "").unwrap();
+        assert_eq!(
+            find_file_upwards(&deep, &["ask.toml"], None, 0),
+            Some(deep.join("ask.toml"))
+        );
+    }
+
     #[test]
     fn test_default_config() {
         let config = Config::default();
@@ -238,6 +590,7 @@ mod tests {
         assert!(!config.behavior.auto_execute);
         assert!(config.behavior.confirm_destructive);
         assert_eq!(config.behavior.timeout, 30);
+        assert_eq!(config.behavior.connect_timeout, 30);
         assert_eq!(config.context.max_age_minutes, 30);
         assert_eq!(config.context.max_messages, 20);
         assert!(config.update.auto_check);
@@ -277,6 +630,8 @@ timeout = 60
 [context]
 max_age_minutes = 60
 max_messages = 50
+max_chars = 8000
+rolling_summary = true
 
 [update]
 auto_check = false
@@ -298,6 +653,8 @@ auto_execute = false
         assert_eq!(config.behavior.timeout, 60);
         assert_eq!(config.context.max_age_minutes, 60);
         assert_eq!(config.context.max_messages, 50);
+        assert_eq!(config.context.max_chars, 8000);
+        assert!(config.context.rolling_summary);
         assert!(!config.update.auto_check);
         assert_eq!(config.update.check_interval_hours, 48);
         assert_eq!(config.update.channel, "beta");
@@ -334,6 +691,166 @@ model = "claude-3-opus"
         assert_eq!(fix.model.as_deref(), Some("claude-3-opus"));
     }
 
+    #[test]
+    fn test_parse_pricing() {
+        let toml = r#"
+[pricing]
+"gpt-4o" = { input_per_mtok = 2.5, output_per_mtok = 10.0 }
+"#;
+        let config = Config::from_toml(toml).unwrap();
+        let price = config.pricing.get("gpt-4o").unwrap();
+        assert_eq!(price.input_per_mtok, 2.5);
+        assert_eq!(price.output_per_mtok, 10.0);
+    }
+
+    #[test]
+    fn test_parse_inject_mode() {
+        let config = Config::from_toml("[behavior]\ninject = \"off\"\n").unwrap();
+        assert_eq!(config.behavior.inject, crate::config::InjectMode::Off);
+
+        let config = Config::from_toml("[behavior]\nauto_execute = true\n").unwrap();
+        assert_eq!(config.behavior.inject, crate::config::InjectMode::Auto);
+
+        assert!(Config::from_toml("[behavior]\ninject = \"sometimes\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_confirm_settings() {
+        let config =
+            Config::from_toml("[behavior]\nconfirm_default = \"yes\"\nconfirm_full_word = true\n")
+                .unwrap();
+        assert_eq!(
+            config.behavior.confirm_default,
+            crate::config::ConfirmDefault::Yes
+        );
+        assert!(config.behavior.confirm_full_word);
+
+        let config = Config::default();
+        assert_eq!(
+            config.behavior.confirm_default,
+            crate::config::ConfirmDefault::No
+        );
+        assert!(!config.behavior.confirm_full_word);
+        assert!(Config::from_toml("[behavior]\nconfirm_default = \"maybe\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_allow_self_update() {
+        let config = Config::from_toml("[update]\nallow_self_update = false\n").unwrap();
+        assert!(!config.update.allow_self_update);
+        assert!(Config::default().update.allow_self_update);
+    }
+
+    #[test]
+    fn test_parse_notify_settings() {
+        let config =
+            Config::from_toml("[behavior]\nnotify_after_secs = 10\nnotify_desktop = true\n")
+                .unwrap();
+        assert_eq!(config.behavior.notify_after_secs, 10);
+        assert!(config.behavior.notify_desktop);
+
+        let config = Config::from_toml("").unwrap();
+        assert_eq!(config.behavior.notify_after_secs, 0);
+        assert!(!config.behavior.notify_desktop);
+    }
+
+    #[test]
+    fn test_parse_max_command_chars() {
+        let config = Config::from_toml("[behavior]\nmax_command_chars = 2000\n").unwrap();
+        assert_eq!(config.behavior.max_command_chars, 2000);
+        assert_eq!(Config::default().behavior.max_command_chars, 500);
+    }
+
+    #[test]
+    fn test_parse_min_interval_ms() {
+        let config = Config::from_toml("[behavior]\nmin_interval_ms = 1000\n").unwrap();
+        assert_eq!(config.behavior.min_interval_ms, 1000);
+
+        let config = Config::from_toml("[behavior]\nauto_execute = true\n").unwrap();
+        assert_eq!(config.behavior.min_interval_ms, 0);
+    }
+
+    #[test]
+    fn test_parse_clipboard_restore_ms() {
+        let config = Config::from_toml("[behavior]\nclipboard_restore_ms = 1500\n").unwrap();
+        assert_eq!(config.behavior.clipboard_restore_ms, 1500);
+
+        let config = Config::from_toml("[behavior]\nauto_execute = true\n").unwrap();
+        assert_eq!(config.behavior.clipboard_restore_ms, 500);
+    }
+
+    #[test]
+    fn test_parse_default_mode() {
+        use crate::config::DefaultMode;
+
+        let config = Config::from_toml("[behavior]\ndefault_mode = \"command\"\n").unwrap();
+        assert_eq!(config.behavior.default_mode, Some(DefaultMode::Command));
+        assert_eq!(DefaultMode::Command.command_mode(), Some(true));
+
+        let config = Config::from_toml("[behavior]\ndefault_mode = \"question\"\n").unwrap();
+        assert_eq!(config.behavior.default_mode, Some(DefaultMode::Question));
+        assert_eq!(DefaultMode::Question.command_mode(), Some(false));
+
+        let config = Config::from_toml("[behavior]\ndefault_mode = \"auto\"\n").unwrap();
+        assert_eq!(config.behavior.default_mode, Some(DefaultMode::Auto));
+        assert_eq!(DefaultMode::Auto.command_mode(), None);
+
+        let config = Config::from_toml("").unwrap();
+        assert_eq!(config.behavior.default_mode, None);
+
+        assert!(Config::from_toml("[behavior]\ndefault_mode = \"shell\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_confirm_paste() {
+        let config = Config::from_toml("[behavior]\nconfirm_paste = true\n").unwrap();
+        assert!(config.behavior.confirm_paste);
+
+        let config = Config::from_toml("[behavior]\nauto_execute = true\n").unwrap();
+        assert!(!config.behavior.confirm_paste);
+    }
+
+    #[test]
+    fn test_custom_command_examples() {
+        let toml = r#"
+[commands.cm]
+system = "Write a conventional commit message for the diff"
+
+[[commands.cm.examples]]
+user = "+ fn login()"
+assistant = "feat(auth): add login"
+
+[[commands.cm.examples]]
+user = "- unused import"
+assistant = "chore: remove unused import"
+
+[commands.explain]
+system = "Explain this code"
+"#;
+        let config = Config::from_toml(toml).unwrap();
+
+        let cm = config.commands.get("cm").unwrap();
+        assert_eq!(cm.examples.len(), 2);
+        assert_eq!(cm.examples[0].user, "+ fn login()");
+        assert_eq!(cm.examples[0].assistant, "feat(auth): add login");
+        assert_eq!(cm.examples[1].assistant, "chore: remove unused import");
+
+        let explain = config.commands.get("explain").unwrap();
+        assert!(explain.examples.is_empty());
+    }
+
+    #[test]
+    fn test_custom_command_example_requires_both_roles() {
+        let toml = r#"
+[commands.cm]
+system = "x"
+
+[[commands.cm.examples]]
+user = "only a question"
+"#;
+        assert!(Config::from_toml(toml).is_err());
+    }
+
     #[test]
     fn test_parse_profiles() {
         let toml = r#"
@@ -577,4 +1094,165 @@ model = "gpt-5"
         assert_eq!(applied.api_key(), Some("ad-hoc-key".to_string()));
         assert!(applied.active.profile_name.is_none());
     }
+
+    #[test]
+    fn test_prompt_append_global_and_profile() {
+        use crate::cli::Args;
+
+        let toml = r#"
+[prompt]
+append = "Always prefer ripgrep over grep."
+
+[profiles.work]
+provider = "openai"
+model = "gpt-5"
+prompt_append = "Never suggest sudo unless asked."
+
+[profiles.home]
+provider = "gemini"
+"#;
+        let config = Config::from_toml(toml).unwrap();
+
+        let work = config.clone().with_cli_overrides(&Args {
+            profile: Some("work".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            work.get_prompt_append().as_deref(),
+            Some("Always prefer ripgrep over grep.\n\nNever suggest sudo unless asked.")
+        );
+
+        let home = config.with_cli_overrides(&Args {
+            profile: Some("home".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            home.get_prompt_append().as_deref(),
+            Some("Always prefer ripgrep over grep.")
+        );
+    }
+
+    #[test]
+    fn test_prompt_append_unset() {
+        let config = Config::default();
+        assert!(config.get_prompt_append().is_none());
+    }
+
+    #[test]
+    fn test_merge_prompt_append_keeps_base() {
+        let base = Config::from_toml("[prompt]\nappend = \"base rule\"\n").unwrap();
+        let overlay = Config::from_toml("").unwrap();
+        let merged = Config::merge(base, overlay);
+        assert_eq!(merged.prompt.append.as_deref(), Some("base rule"));
+    }
+
+    #[test]
+    fn test_parse_prompt_merge() {
+        let config = Config::from_toml("[prompt]\nmerge = true\n").unwrap();
+        assert!(config.prompt.merge);
+        assert!(!Config::default().prompt.merge);
+
+        let merged = Config::merge(
+            config,
+            Config::from_toml("[prompt]\nappend = \"x\"\n").unwrap(),
+        );
+        assert!(
+            merged.prompt.merge,
+            "a later file without the key keeps it on"
+        );
+    }
+
+    #[test]
+    fn test_parse_anthropic_prompt_cache() {
+        let base = Config::from_toml("[providers.anthropic]\nprompt_cache = true\n").unwrap();
+        assert_eq!(base.providers.anthropic.prompt_cache, Some(true));
+
+        let merged = Config::merge(base, Config::from_toml("").unwrap());
+        assert_eq!(merged.providers.anthropic.prompt_cache, Some(true));
+
+        let unset = Config::from_toml("").unwrap();
+        assert_eq!(unset.providers.anthropic.prompt_cache, None);
+    }
+
+    #[test]
+    fn test_parse_privacy() {
+        let home =
+            Config::from_toml("[privacy]\nredact = true\nredact_patterns = ['ghp_[A-Za-z0-9]+']\n")
+                .unwrap();
+        assert!(home.privacy.redact);
+
+        let project = Config::from_toml("[privacy]\nredact_patterns = ['corp-[0-9]+']\n").unwrap();
+        let merged = Config::merge(home, project);
+        assert!(
+            merged.privacy.redact,
+            "a project config cannot switch it off"
+        );
+        assert_eq!(
+            merged.privacy.redact_patterns,
+            vec!["ghp_[A-Za-z0-9]+", "corp-[0-9]+"]
+        );
+
+        assert!(!Config::from_toml("").unwrap().privacy.redact);
+    }
+
+    #[test]
+    fn test_merge_theme_by_role() {
+        let home = Config::from_toml("[theme]\npreset = \"light\"\nhint = \"blue\"\n").unwrap();
+        let project = Config::from_toml("[theme]\nhint = \"magenta\"\n").unwrap();
+        let merged = Config::merge(home, project);
+        assert_eq!(merged.theme.preset.as_deref(), Some("light"));
+        assert_eq!(merged.theme.hint.as_deref(), Some("magenta"));
+        assert_eq!(merged.theme.command, None);
+    }
+
+    #[test]
+    fn test_merge_default_mode() {
+        use crate::config::DefaultMode;
+
+        let home = Config::from_toml("[behavior]\ndefault_mode = \"command\"\n").unwrap();
+        let project = Config::from_toml("[behavior]\nauto_execute = true\n").unwrap();
+        let merged = Config::merge(home.clone(), project);
+        assert_eq!(merged.behavior.default_mode, Some(DefaultMode::Command));
+        assert!(merged.behavior.auto_execute);
+
+        let project = Config::from_toml("[behavior]\ndefault_mode = \"question\"\n").unwrap();
+        let merged = Config::merge(home, project);
+        assert_eq!(merged.behavior.default_mode, Some(DefaultMode::Question));
+    }
+
+    #[test]
+    fn test_load_pinned_config_exclusively() {
+        let temp = tempfile::tempdir().unwrap();
+        let pinned = temp.path().join("ci.toml");
+        std::fs::write(
+            &pinned,
+            "default_profile = \"ci\"\n\n[profiles.ci]\nprovider = \"openai\"\nmodel = \"gpt-ci\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load_pinned(&pinned).unwrap();
+        assert_eq!(config.default_profile.as_deref(), Some("ci"));
+        assert_eq!(
+            config.profiles.get("ci").unwrap().model.as_deref(),
+            Some("gpt-ci")
+        );
+
+        // Only the pinned file plus built-in free profiles
+        let user_profiles: Vec<_> = config
+            .profiles
+            .keys()
+            .filter(|name| !super::super::FREE_PROFILE_NAMES.contains(&name.as_str()))
+            .collect();
+        assert_eq!(user_profiles, vec!["ci"]);
+    }
+
+    #[test]
+    fn test_load_pinned_config_missing_errors() {
+        let temp = tempfile::tempdir().unwrap();
+        let missing = temp.path().join("missing.toml");
+
+        let err = Config::load_pinned(&missing).unwrap_err();
+        assert!(err.to_string().contains("Config file not found"));
+        assert!(err.to_string().contains("missing.toml"));
+    }
 }
@@ -1,11 +1,18 @@
 //! Configuration module - handles loading and merging configs
 
+mod alias;
 mod defaults;
 pub(crate) mod loader;
+mod set;
 mod thinking;
 
+pub use alias::{add_alias, list_aliases, remove_alias};
 pub use defaults::*;
-pub use thinking::{format_thinking_config, select_thinking_config};
+pub use set::set_config_value;
+pub use thinking::{
+    detect_thinking_type, format_thinking_config, model_capabilities, select_thinking_config,
+    ModelCapabilities,
+};
 
 use crate::cli::Args;
 use anyhow::Result;
@@ -26,6 +33,10 @@ pub struct Config {
     #[serde(default)]
     pub default_profile: Option<String>,
 
+    /// Profiles `--round-robin` rotates through, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub provider_order: Vec<String>,
+
     #[serde(default)]
     pub behavior: BehaviorConfig,
 
@@ -35,6 +46,24 @@ pub struct Config {
     #[serde(default)]
     pub update: UpdateConfig,
 
+    #[serde(default)]
+    pub prompt: PromptConfig,
+
+    #[serde(default)]
+    pub http: HttpConfig,
+
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
+    #[serde(default)]
+    pub providers: ProvidersConfig,
+
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+
+    #[serde(default)]
+    pub theme: ThemeConfig,
+
     #[serde(default)]
     pub commands: HashMap<String, CustomCommand>,
 
@@ -42,6 +71,10 @@ pub struct Config {
     #[serde(default)]
     pub aliases: HashMap<String, String>,
 
+    /// Per-model prices for `--cost`, checked before the built-in table
+    #[serde(default)]
+    pub pricing: HashMap<String, ModelPrice>,
+
     /// Active profile data (set after profile resolution, not from TOML)
     #[serde(skip)]
     pub active: ActiveConfig,
@@ -66,8 +99,134 @@ pub struct BehaviorConfig {
     #[serde(default = "default_true")]
     pub confirm_destructive: bool,
 
+    /// Answer taken when Enter is pressed at the run confirmation
+    #[serde(default)]
+    pub confirm_default: ConfirmDefault,
+
+    /// Destructive commands need `yes` typed in full, not just `y`
+    #[serde(default)]
+    pub confirm_full_word: bool,
+
     #[serde(default = "default_timeout")]
     pub timeout: u64,
+
+    /// Seconds allowed to connect to a provider (0 = no limit)
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: u64,
+
+    /// Kill executed commands after this many seconds (0 = no limit)
+    #[serde(default)]
+    pub exec_timeout_secs: u64,
+
+    /// Edit suggested commands in $EDITOR instead of the inline prompt
+    #[serde(default)]
+    pub edit_in_editor: bool,
+
+    /// Wrap prose answers at this many columns, capped at the terminal width (0 = off)
+    #[serde(default = "default_wrap_columns")]
+    pub wrap_columns: usize,
+
+    /// How suggested commands reach the terminal
+    #[serde(default)]
+    pub inject: InjectMode,
+
+    /// Ask the model to explain a failed command (same as `--explain-error`)
+    #[serde(default)]
+    pub explain_failures: bool,
+
+    /// After a GUI paste, put the previous clipboard back after this many ms (0 = never)
+    #[serde(default = "default_clipboard_restore_ms")]
+    pub clipboard_restore_ms: u64,
+
+    /// Ask in the terminal before a GUI paste (same as `--safe-paste`)
+    #[serde(default)]
+    pub confirm_paste: bool,
+
+    /// Mode used when neither `-x` nor `--question` is given; unset here means
+    /// a lower-precedence config decides, then auto
+    #[serde(default)]
+    pub default_mode: Option<DefaultMode>,
+
+    /// Minimum gap between API calls to the same provider, across runs (0 = off)
+    #[serde(default)]
+    pub min_interval_ms: u64,
+
+    /// Ring the bell when an answer took at least this many seconds (0 = off)
+    #[serde(default)]
+    pub notify_after_secs: u64,
+
+    /// Also show a desktop notification for slow answers
+    #[serde(default)]
+    pub notify_desktop: bool,
+
+    /// Longer answers are only offered to run when they are a single line
+    /// without sentences (0 = no limit)
+    #[serde(default = "default_max_command_chars")]
+    pub max_command_chars: usize,
+
+    /// Join multi-line commands into one `&&` line; when false they are shown
+    /// one per line and run in order, stopping at the first failure
+    #[serde(default = "default_true")]
+    pub flatten_commands: bool,
+
+    /// Log provider, model, tokens and latency of each answer for `ask stats`
+    #[serde(default = "default_true")]
+    pub log_usage: bool,
+}
+
+/// `behavior.confirm_default`: what Enter means at a run confirmation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmDefault {
+    Yes,
+    #[default]
+    No,
+}
+
+/// `behavior.inject`: paste suggested commands, prompt for them, or only confirm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InjectMode {
+    /// Paste into the terminal when possible (GUI, tmux, screen)
+    #[default]
+    Auto,
+    /// Always show the editable prompt, never paste
+    Prompt,
+    /// Never paste or prompt for edits; ask before running
+    Off,
+}
+
+impl InjectMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "prompt" => Some(Self::Prompt),
+            "off" | "false" | "0" => Some(Self::Off),
+            _ => None,
+        }
+    }
+}
+
+/// `behavior.default_mode`: force command or question mode, e.g. in a project's `.ask.toml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultMode {
+    /// Detect commands from the answer
+    #[default]
+    Auto,
+    Command,
+    Question,
+}
+
+impl DefaultMode {
+    /// The `command_mode` this stands for when no flag chose one
+    pub fn command_mode(self) -> Option<bool> {
+        match self {
+            Self::Auto => None,
+            Self::Command => Some(true),
+            Self::Question => Some(false),
+        }
+    }
 }
 
 /// Context/history settings
@@ -79,6 +238,14 @@ pub struct ContextConfig {
     #[serde(default = "default_max_messages")]
     pub max_messages: usize,
 
+    /// Character budget for history sent to the provider (0 = unlimited)
+    #[serde(default)]
+    pub max_chars: usize,
+
+    /// Keep a summary of trimmed messages and send it ahead of the recent turns
+    #[serde(default)]
+    pub rolling_summary: bool,
+
     #[serde(default)]
     pub storage_path: Option<String>,
 }
@@ -97,6 +264,97 @@ pub struct UpdateConfig {
 
     #[serde(default = "default_true")]
     pub aggressive: bool,
+
+    /// User-Agent for update requests (default: ask/<version>)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+
+    /// Let `--update` and background updates replace the binary
+    #[serde(default = "default_true")]
+    pub allow_self_update: bool,
+}
+
+/// System prompt settings
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PromptConfig {
+    /// Extra instructions appended to the system prompt (unified or ask.md)
+    #[serde(default)]
+    pub append: Option<String>,
+
+    /// How piped input is framed in the question (`{input}`, `{question}`)
+    #[serde(default)]
+    pub stdin_template: Option<String>,
+
+    /// Concatenate every ask.md found (config dir, home, local) instead of the first
+    #[serde(default)]
+    pub merge: bool,
+}
+
+/// HTTP client settings
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HttpConfig {
+    /// Proxy URL for all requests (overrides HTTP(S)_PROXY/ALL_PROXY)
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+/// Shell hooks around executed commands; `$CMD` and `$EXIT` are set in their environment
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HooksConfig {
+    /// Runs before a suggested command is executed
+    #[serde(default)]
+    pub before_execute: Option<String>,
+
+    /// Runs after the command finishes, with `$EXIT` set to its exit code
+    #[serde(default)]
+    pub after_execute: Option<String>,
+}
+
+/// Secret redaction for piped input and `@file` attachments
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PrivacyConfig {
+    /// Replace secrets with `[REDACTED]` before the input is sent
+    #[serde(default)]
+    pub redact: bool,
+
+    /// Extra regexes on top of the built-in ones (a capture group limits what is replaced)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact_patterns: Vec<String>,
+}
+
+/// `[theme]`: output colors by role, on top of a preset
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ThemeConfig {
+    /// `dark` (default) or `light`
+    #[serde(default)]
+    pub preset: Option<String>,
+
+    /// Color names (`blue`, `bright black`) or `#rrggbb`; unset roles keep the preset's color
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub heading: Option<String>,
+    #[serde(default)]
+    pub hint: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub citation: Option<String>,
+}
+
+/// Settings for one provider's API, shared by every profile using it
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProvidersConfig {
+    #[serde(default)]
+    pub anthropic: AnthropicConfig,
+}
+
+/// `[providers.anthropic]`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AnthropicConfig {
+    /// Send the system prompt with `cache_control` so repeats bill at the cache rate
+    #[serde(default)]
+    pub prompt_cache: Option<bool>,
 }
 
 /// Custom command definition
@@ -121,6 +379,24 @@ pub struct CustomCommand {
 
     #[serde(default)]
     pub model: Option<String>,
+
+    /// Few-shot examples sent as user/assistant turns before the query
+    #[serde(default)]
+    pub examples: Vec<FewShotExample>,
+}
+
+/// USD price per million tokens for a model (`[pricing]`)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+/// A sample exchange for a custom command (`[[commands.<name>.examples]]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FewShotExample {
+    pub user: String,
+    pub assistant: String,
 }
 
 /// Named profile configuration - all settings for a profile
@@ -177,6 +453,51 @@ pub struct ProfileConfig {
     /// Blocked domains for web search (Anthropic only)
     #[serde(default)]
     pub blocked_domains: Option<Vec<String>>,
+
+    /// Extra instructions appended to the system prompt for this profile
+    #[serde(default)]
+    pub prompt_append: Option<String>,
+
+    /// Send `reasoning_effort`/`max_completion_tokens` (OpenAI only).
+    /// Unset: only for api.openai.com, since compatible servers often reject them.
+    #[serde(default)]
+    pub supports_reasoning: Option<bool>,
+
+    /// Azure OpenAI deployment name (defaults to the model)
+    #[serde(default)]
+    pub deployment: Option<String>,
+
+    /// Azure OpenAI API version (default: 2024-10-21)
+    #[serde(default)]
+    pub api_version: Option<String>,
+}
+
+/// Guess the provider from a well-known model name prefix
+pub fn infer_provider_from_model(model: &str) -> Option<&'static str> {
+    let model = model.trim().to_lowercase();
+
+    if model.starts_with("gpt-")
+        || ["o1", "o3", "o4"]
+            .iter()
+            .any(|p| model == *p || model.starts_with(&format!("{}-", p)))
+    {
+        Some("openai")
+    } else if model.starts_with("claude-") {
+        Some("anthropic")
+    } else if model.starts_with("gemini-") {
+        Some("gemini")
+    } else {
+        None
+    }
+}
+
+/// Canonical provider name for aliases ("claude" -> "anthropic")
+fn provider_family(provider: &str) -> &str {
+    match provider {
+        "claude" => "anthropic",
+        "openai_compatible" => "openai",
+        other => other,
+    }
 }
 
 // Default value functions
@@ -192,6 +513,14 @@ fn default_timeout() -> u64 {
     30
 }
 
+fn default_connect_timeout() -> u64 {
+    30
+}
+
+fn default_clipboard_restore_ms() -> u64 {
+    500
+}
+
 fn default_max_age() -> u64 {
     30
 }
@@ -204,6 +533,14 @@ fn default_check_interval() -> u64 {
     24
 }
 
+fn default_wrap_columns() -> usize {
+    100
+}
+
+fn default_max_command_chars() -> usize {
+    500
+}
+
 fn default_channel() -> String {
     "stable".to_string()
 }
@@ -213,7 +550,24 @@ impl Default for BehaviorConfig {
         Self {
             auto_execute: false,
             confirm_destructive: true,
+            confirm_default: ConfirmDefault::No,
+            confirm_full_word: false,
             timeout: default_timeout(),
+            connect_timeout: default_connect_timeout(),
+            exec_timeout_secs: 0,
+            edit_in_editor: false,
+            wrap_columns: default_wrap_columns(),
+            inject: InjectMode::Auto,
+            explain_failures: false,
+            clipboard_restore_ms: default_clipboard_restore_ms(),
+            confirm_paste: false,
+            default_mode: None,
+            min_interval_ms: 0,
+            notify_after_secs: 0,
+            notify_desktop: false,
+            max_command_chars: default_max_command_chars(),
+            flatten_commands: true,
+            log_usage: true,
         }
     }
 }
@@ -223,6 +577,8 @@ impl Default for ContextConfig {
         Self {
             max_age_minutes: default_max_age(),
             max_messages: default_max_messages(),
+            max_chars: 0,
+            rolling_summary: false,
             storage_path: None,
         }
     }
@@ -235,6 +591,8 @@ impl Default for UpdateConfig {
             check_interval_hours: default_check_interval(),
             channel: default_channel(),
             aggressive: true,
+            user_agent: None,
+            allow_self_update: true,
         }
     }
 }
@@ -310,42 +668,81 @@ impl Config {
         }
 
         // Profile mode: resolve profile (CLI -p > ENV > default_profile > first non-free > first)
-        let profile_name = args
+        let explicit_profile = args
             .profile
             .clone()
-            .or_else(|| std::env::var("ASK_PROFILE").ok())
-            .or_else(|| self.effective_default_profile());
+            .or_else(|| std::env::var("ASK_PROFILE").ok());
 
-        if let Some(ref name) = profile_name {
-            if let Some(profile) = self.profiles.get(name) {
-                let provider = profile.provider.clone().unwrap_or_else(default_provider);
+        // Without -p/-P, a recognizable -m picks a profile (or ad-hoc provider) that can serve it
+        let inferred_provider = if explicit_profile.is_none() {
+            args.model.as_deref().and_then(infer_provider_from_model)
+        } else {
+            None
+        };
+
+        let mut profile_name = explicit_profile.or_else(|| self.effective_default_profile());
+
+        if let Some(inferred) = inferred_provider {
+            let matches = |name: &String| {
+                self.profiles
+                    .get(name)
+                    .map(|p| provider_family(p.provider.as_deref().unwrap_or("gemini")) == inferred)
+                    .unwrap_or(false)
+            };
+
+            if !profile_name.as_ref().map(matches).unwrap_or(false) {
+                profile_name = self.sorted_profile_names().into_iter().find(|n| matches(n));
+            }
+
+            if profile_name.is_none() {
                 self.active = ActiveConfig {
-                    provider: provider.clone(),
-                    model: args
-                        .model
-                        .clone()
-                        .or_else(|| profile.model.clone())
-                        .unwrap_or_else(|| self.default_model_for_provider(&provider)),
-                    api_key: profile
-                        .api_key
-                        .clone()
-                        .or_else(|| self.env_api_key(&provider)),
-                    base_url: profile
-                        .base_url
-                        .clone()
-                        .or_else(|| self.env_base_url(&provider)),
-                    stream: profile.stream.unwrap_or(true),
-                    profile_name: Some(name.clone()),
+                    provider: inferred.to_string(),
+                    model: args.model.clone().unwrap_or_default(),
+                    api_key: self.env_api_key(inferred),
+                    base_url: self.env_base_url(inferred),
+                    stream: true,
+                    profile_name: None,
                 };
+                return self;
             }
         }
 
+        if let Some(active) = profile_name
+            .as_deref()
+            .and_then(|name| self.resolve_profile(name, args.model.clone()))
+        {
+            self.active = active;
+        }
+
         self
     }
 
+    /// Provider, model, key and URL a profile runs with, filling gaps from the
+    /// provider defaults and `ASK_<PROVIDER>_*` variables. `None` if there is no such profile.
+    pub fn resolve_profile(&self, name: &str, model: Option<String>) -> Option<ActiveConfig> {
+        let profile = self.profiles.get(name)?;
+        let provider = profile.provider.clone().unwrap_or_else(default_provider);
+        Some(ActiveConfig {
+            provider: provider.clone(),
+            model: model
+                .or_else(|| profile.model.clone())
+                .unwrap_or_else(|| self.default_model_for_provider(&provider)),
+            api_key: profile
+                .api_key
+                .clone()
+                .or_else(|| self.env_api_key(&provider)),
+            base_url: profile
+                .base_url
+                .clone()
+                .or_else(|| self.env_base_url(&provider)),
+            stream: profile.stream.unwrap_or(true),
+            profile_name: Some(name.to_string()),
+        })
+    }
+
     fn default_model_for_provider(&self, provider: &str) -> String {
         match provider {
-            "openai" => defaults::DEFAULT_OPENAI_MODEL.to_string(),
+            "openai" | "azure" => defaults::DEFAULT_OPENAI_MODEL.to_string(),
             "anthropic" => defaults::DEFAULT_ANTHROPIC_MODEL.to_string(),
             "ollama" => defaults::DEFAULT_OLLAMA_MODEL.to_string(),
             _ => defaults::DEFAULT_MODEL.to_string(),
@@ -367,6 +764,11 @@ impl Config {
             return None; // Ad-hoc mode has no profile
         }
 
+        // Once overrides are applied, trust the resolved profile (it may come from -m inference)
+        if !self.active.provider.is_empty() {
+            return self.active.profile_name.clone();
+        }
+
         args.profile
             .clone()
             .or_else(|| std::env::var("ASK_PROFILE").ok())
@@ -446,6 +848,55 @@ impl Config {
         (None, None)
     }
 
+    /// Azure deployment name and API version for the active profile
+    pub fn get_azure_deployment(&self) -> (String, String) {
+        let profile = self
+            .active
+            .profile_name
+            .as_ref()
+            .and_then(|name| self.profiles.get(name));
+
+        let deployment = profile
+            .and_then(|p| p.deployment.clone())
+            .unwrap_or_else(|| self.active.model.clone());
+        let api_version = profile
+            .and_then(|p| p.api_version.clone())
+            .unwrap_or_else(|| defaults::DEFAULT_AZURE_API_VERSION.to_string());
+
+        (deployment, api_version)
+    }
+
+    pub fn get_profile_supports_reasoning(&self) -> Option<bool> {
+        self.active
+            .profile_name
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+            .and_then(|profile| profile.supports_reasoning)
+    }
+
+    /// Prompt fragments to append: global `[prompt] append` first, then the profile's `prompt_append`
+    pub fn get_prompt_append(&self) -> Option<String> {
+        let profile_append = self
+            .active
+            .profile_name
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+            .and_then(|profile| profile.prompt_append.clone());
+
+        let fragments: Vec<String> = [self.prompt.append.clone(), profile_append]
+            .into_iter()
+            .flatten()
+            .map(|f| f.trim().to_string())
+            .filter(|f| !f.is_empty())
+            .collect();
+
+        if fragments.is_empty() {
+            None
+        } else {
+            Some(fragments.join("\n\n"))
+        }
+    }
+
     pub fn get_thinking_level(&self) -> Option<String> {
         if let Some(ref name) = self.active.profile_name {
             if let Some(profile) = self.profiles.get(name) {
@@ -487,7 +938,7 @@ impl Config {
                     (false, None)
                 }
             }
-            "openai" | "openai_compatible" => {
+            "openai" | "openai_compatible" | "azure" => {
                 if let Some(effort) = self.get_reasoning_effort() {
                     let enabled = effort.to_lowercase() != "none";
                     (enabled, Some(effort))
@@ -507,7 +958,10 @@ impl Config {
                 }
             }
             "ollama" => {
-                if let Some(budget) = self.get_thinking_budget() {
+                if let Some(level) = self.get_thinking_level() {
+                    let enabled = level.to_lowercase() != "none" && level != "0";
+                    (enabled, enabled.then(|| "1".to_string()))
+                } else if let Some(budget) = self.get_thinking_budget() {
                     let enabled = budget > 0;
                     (enabled, if enabled { Some("1".to_string()) } else { None })
                 } else {
@@ -694,6 +1148,9 @@ fn merge_profile_into_doc(doc: &mut toml::Value, profile_toml: &str) -> Result<(
 /// Uses block_in_place to run async code safely from a sync function called within
 /// an async Tokio context (e.g. during `ask init`).
 fn fetch_ollama_models(base_url: &str) -> Option<Vec<String>> {
+    if crate::http::is_offline() {
+        return None;
+    }
     let url = format!("{}/api/tags", base_url);
 
     tokio::task::block_in_place(|| {
@@ -718,6 +1175,98 @@ fn fetch_ollama_models(base_url: &str) -> Option<Vec<String>> {
     })
 }
 
+/// Fetch model ids from an OpenAI-compatible server (`GET {base_url}/models`).
+///
+/// Returns None if the server is unreachable or answers with something else.
+fn fetch_openai_compatible_models(base_url: &str) -> Option<Vec<String>> {
+    if crate::http::is_offline() {
+        return None;
+    }
+    let url = format!("{}/models", base_url.trim_end_matches('/'));
+
+    tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(async {
+            let client = crate::http::create_client_builder()
+                .timeout(Duration::from_secs(3))
+                .build()
+                .ok()?;
+            let response = client.get(&url).send().await.ok()?;
+            if !response.status().is_success() {
+                return None;
+            }
+            let body: serde_json::Value = response.json().await.ok()?;
+            let models = body
+                .get("data")?
+                .as_array()?
+                .iter()
+                .filter_map(|m| m.get("id")?.as_str().map(|s| s.to_string()))
+                .collect::<Vec<_>>();
+            Some(models)
+        })
+    })
+}
+
+/// Base URL for a local server preset index; None means "Custom URL"
+fn local_preset_base_url(idx: usize) -> Option<&'static str> {
+    defaults::LOCAL_SERVER_PRESETS.get(idx).map(|(_, url)| *url)
+}
+
+/// Preset index matching an existing base_url, if any
+fn local_preset_index(base_url: &str) -> Option<usize> {
+    let base_url = base_url.trim_end_matches('/');
+    defaults::LOCAL_SERVER_PRESETS
+        .iter()
+        .position(|(_, url)| *url == base_url)
+}
+
+/// Let the user pick a discovered model, or type one when discovery failed
+fn prompt_discovered_model(
+    models: Option<Vec<String>>,
+    existing_model_name: &str,
+    server: &str,
+    base_url: &str,
+) -> Result<String> {
+    let manual = || -> Result<String> {
+        let question = Question::input("model_manual")
+            .message("Model name")
+            .default(existing_model_name)
+            .build();
+        Ok(requestty::prompt_one(question)?
+            .as_string()
+            .unwrap_or_default()
+            .to_string())
+    };
+
+    match models {
+        Some(models) if !models.is_empty() => {
+            let mut choices: Vec<String> = models.clone();
+            choices.push("Type manually...".to_string());
+            let default_idx = models
+                .iter()
+                .position(|m| m == existing_model_name)
+                .unwrap_or(0);
+            let idx = numbered_select("Select model", &choices, default_idx)?;
+            if idx == choices.len() - 1 {
+                manual()
+            } else {
+                Ok(models[idx].clone())
+            }
+        }
+        _ => {
+            // Server not reachable or no models — fall back to manual input
+            println!(
+                "{}",
+                format!(
+                    "{} not reachable at {} — please type the model name",
+                    server, base_url
+                )
+                .yellow()
+            );
+            manual()
+        }
+    }
+}
+
 /// Configure a single profile
 fn configure_profile(mgr: &ConfigManager, profile_name: Option<&str>) -> Result<Option<String>> {
     let name: String = if let Some(n) = profile_name {
@@ -744,16 +1293,23 @@ fn configure_profile(mgr: &ConfigManager, profile_name: Option<&str>) -> Result<
         "OpenAI",
         "Anthropic Claude",
         "Ollama (local/remote)",
+        "Azure OpenAI",
+        "OpenAI-compatible local server",
     ];
     providers.push("Back");
 
     let existing_provider = mgr.get_str(&["profiles", &name, "provider"]);
+    let existing_local_preset = mgr
+        .get_str(&["profiles", &name, "base_url"])
+        .and_then(|url| local_preset_index(&url));
 
     let existing_idx = match existing_provider.as_deref() {
         Some("gemini") => 0,
+        Some("openai") if existing_local_preset.is_some() => 5,
         Some("openai") => 1,
         Some("anthropic") => 2,
         Some("ollama") => 3,
+        Some("azure") => 4,
         _ => 0,
     };
 
@@ -775,10 +1331,16 @@ fn configure_profile(mgr: &ConfigManager, profile_name: Option<&str>) -> Result<
         1 => ("openai", defaults::DEFAULT_OPENAI_MODEL),
         2 => ("anthropic", defaults::DEFAULT_ANTHROPIC_MODEL),
         3 => ("ollama", defaults::DEFAULT_OLLAMA_MODEL),
+        4 => ("azure", defaults::DEFAULT_OPENAI_MODEL),
+        5 => ("openai", ""),
         _ => ("gemini", defaults::DEFAULT_MODEL),
     };
+    let local_server = provider_idx == 5;
 
     // Ollama: guided setup with URL discovery and model listing
+    // Azure routes by deployment name; empty means "same as model"
+    let mut deployment = String::new();
+
     let (model, api_key, base_url, web_search) = if provider == "ollama" {
         let location_options = ["Local (http://localhost:11434)", "Remote (custom URL)"];
         let existing_base_url = mgr.get_str(&["profiles", &name, "base_url"]);
@@ -811,42 +1373,38 @@ fn configure_profile(mgr: &ConfigManager, profile_name: Option<&str>) -> Result<
             .get_str(&["profiles", &name, "model"])
             .unwrap_or_else(|| default_model.to_string());
 
-        let ollama_model = match fetch_ollama_models(&ollama_base_url) {
-            Some(models) if !models.is_empty() => {
-                let mut choices: Vec<String> = models.clone();
-                choices.push("Type manually...".to_string());
-                let default_idx = models
-                    .iter()
-                    .position(|m| m == &existing_model_name)
-                    .unwrap_or(0);
-                let idx = numbered_select("Select model", &choices, default_idx)?;
-                if idx == choices.len() - 1 {
-                    // Manual input
-                    let question = Question::input("ollama_model_manual")
-                        .message("Model name")
-                        .default(existing_model_name.as_str())
-                        .build();
-                    requestty::prompt_one(question)?
-                        .as_string()
-                        .unwrap_or_default()
-                        .to_string()
-                } else {
-                    models[idx].clone()
-                }
+        let ollama_model = prompt_discovered_model(
+            fetch_ollama_models(&ollama_base_url),
+            &existing_model_name,
+            "Ollama",
+            &ollama_base_url,
+        )?;
+
+        (ollama_model, "ollama".to_string(), ollama_base_url, false)
+    } else if local_server {
+        // OpenAI-compatible local server: preset URL, placeholder key, model discovery
+        let mut preset_options: Vec<&str> = defaults::LOCAL_SERVER_PRESETS
+            .iter()
+            .map(|(label, _)| *label)
+            .collect();
+        preset_options.push("Custom URL");
+
+        let existing_base_url = mgr.get_str(&["profiles", &name, "base_url"]);
+        let preset_default = match (&existing_base_url, existing_local_preset) {
+            (_, Some(idx)) => idx,
+            (Some(_), None) if existing_provider.as_deref() == Some("openai") => {
+                preset_options.len() - 1
             }
-            _ => {
-                // Ollama not reachable or no models — fall back to manual input
-                println!(
-                    "{}",
-                    format!(
-                        "Ollama not reachable at {} — please type the model name",
-                        ollama_base_url
-                    )
-                    .yellow()
-                );
-                let question = Question::input("ollama_model_manual")
-                    .message("Model name")
-                    .default(existing_model_name.as_str())
+            _ => 0,
+        };
+        let preset_idx = numbered_select("Local server", &preset_options, preset_default)?;
+
+        let local_base_url = match local_preset_base_url(preset_idx) {
+            Some(url) => url.to_string(),
+            None => {
+                let question = Question::input("local_server_url")
+                    .message("Server URL (e.g., http://localhost:5000/v1)")
+                    .default(existing_base_url.as_deref().unwrap_or(""))
                     .build();
                 requestty::prompt_one(question)?
                     .as_string()
@@ -855,7 +1413,22 @@ fn configure_profile(mgr: &ConfigManager, profile_name: Option<&str>) -> Result<
             }
         };
 
-        (ollama_model, "ollama".to_string(), ollama_base_url, false)
+        let existing_model_name = mgr
+            .get_str(&["profiles", &name, "model"])
+            .unwrap_or_default();
+        let local_model = prompt_discovered_model(
+            fetch_openai_compatible_models(&local_base_url),
+            &existing_model_name,
+            preset_options[preset_idx],
+            &local_base_url,
+        )?;
+
+        (
+            local_model,
+            defaults::LOCAL_SERVER_API_KEY.to_string(),
+            local_base_url,
+            false,
+        )
     } else {
         // Standard provider flow: model → api_key → base_url → web_search
 
@@ -905,8 +1478,13 @@ fn configure_profile(mgr: &ConfigManager, profile_name: Option<&str>) -> Result<
 
         let existing_base_url = mgr.get_str(&["profiles", &name, "base_url"]);
         let base_url: String = {
+            let message = if provider == "azure" {
+                "Azure endpoint (e.g., https://myresource.openai.azure.com)"
+            } else {
+                "Base URL (Enter for default, or custom like http://localhost:11434/v1)"
+            };
             let question = Question::input("profile_base_url")
-                .message("Base URL (Enter for default, or custom like http://localhost:11434/v1)")
+                .message(message)
                 .default(existing_base_url.as_deref().unwrap_or(""))
                 .build();
             requestty::prompt_one(question)?
@@ -915,6 +1493,21 @@ fn configure_profile(mgr: &ConfigManager, profile_name: Option<&str>) -> Result<
                 .to_string()
         };
 
+        if provider == "azure" {
+            let existing_deployment = mgr
+                .get_str(&["profiles", &name, "deployment"])
+                .unwrap_or_default();
+            let question = Question::input("profile_deployment")
+                .message("Deployment name (Enter to use the model name)")
+                .default(existing_deployment.as_str())
+                .build();
+            deployment = requestty::prompt_one(question)?
+                .as_string()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+        }
+
         let existing_web_search = mgr.get_bool(&["profiles", &name, "web_search"], false);
         let web_search = {
             let question = Question::confirm("profile_web_search")
@@ -1005,6 +1598,10 @@ model = "{}""#,
         profile_toml.push_str(&format!("\nbase_url = \"{}\"", base_url));
     }
 
+    if !deployment.is_empty() {
+        profile_toml.push_str(&format!("\ndeployment = \"{}\"", deployment));
+    }
+
     if web_search {
         profile_toml.push_str("\nweb_search = true");
     }
@@ -1509,20 +2106,145 @@ channel = "stable"
     Ok(())
 }
 
-pub fn init_config_non_interactive(
-    provider: Option<&str>,
-    model: Option<&str>,
+/// Options for `ask init -n`
+#[derive(Debug, Clone, Default)]
+pub struct NonInteractiveInit<'a> {
+    pub provider: Option<&'a str>,
+    pub model: Option<&'a str>,
+    pub api_key: Option<&'a str>,
+    /// Read the API key from stdin (`--key-from-stdin`), keeping it out of `ps` and history
+    pub key_from_stdin: bool,
+    /// Profile name to create (default: "main")
+    pub profile_name: Option<&'a str>,
+    pub base_url: Option<&'a str>,
+    pub stream: Option<bool>,
+    /// Thinking level/budget, mapped to the provider's setting
+    pub thinking: Option<&'a str>,
+    /// Overwrite an existing config file
+    pub force: bool,
+}
+
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+/// Profile key that holds a thinking value for the given provider
+fn thinking_key(provider: &str, value: &str) -> &'static str {
+    let numeric = value.parse::<i64>().is_ok();
+    match provider {
+        "openai" | "openai_compatible" => "reasoning_effort",
+        _ if numeric => "thinking_budget",
+        _ => "thinking_level",
+    }
+}
+
+/// Render the config written by `ask init -n`
+fn render_non_interactive_config(
+    profile_name: &str,
+    provider: &str,
+    model: &str,
     api_key: Option<&str>,
-) -> Result<()> {
-    let provider = provider.unwrap_or("gemini");
-    let model = model.unwrap_or(match provider {
+    opts: &NonInteractiveInit,
+) -> String {
+    let mut profile = format!(
+        "[profiles.{}]\nprovider = {}\nmodel = {}\n",
+        profile_name,
+        toml_string(provider),
+        toml_string(model)
+    );
+    if let Some(key) = api_key {
+        profile.push_str(&format!("api_key = {}\n", toml_string(key)));
+    }
+    if let Some(url) = opts.base_url {
+        profile.push_str(&format!("base_url = {}\n", toml_string(url)));
+    }
+    profile.push_str(&format!("stream = {}\n", opts.stream.unwrap_or(true)));
+    if let Some(thinking) = opts.thinking {
+        let key = thinking_key(provider, thinking);
+        if key == "thinking_budget" {
+            profile.push_str(&format!("{} = {}\n", key, thinking));
+        } else {
+            profile.push_str(&format!("{} = {}\n", key, toml_string(thinking)));
+        }
+    }
+
+    format!(
+        r#"# ask configuration (generated by --non-interactive)
+
+default_profile = {default}
+
+{profile}
+[behavior]
+auto_execute = false
+confirm_destructive = true
+timeout = 30
+
+[context]
+max_age_minutes = 30
+max_messages = 20
+
+[update]
+auto_check = true
+check_interval_hours = 24
+channel = "stable"
+"#,
+        default = toml_string(profile_name),
+    )
+}
+
+/// The API key piped to `init -n --key-from-stdin`, trimmed
+fn read_key_from(mut input: impl std::io::Read) -> Result<String> {
+    let mut key = String::new();
+    input.read_to_string(&mut key)?;
+    let key = key.trim();
+    if key.is_empty() {
+        anyhow::bail!("--key-from-stdin got an empty key");
+    }
+    Ok(key.to_string())
+}
+
+fn key_from_stdin() -> Result<String> {
+    use std::io::IsTerminal;
+
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        anyhow::bail!(
+            "--key-from-stdin needs the key piped in, e.g. pass show openai | ask init -n -P openai --key-from-stdin"
+        );
+    }
+    read_key_from(stdin.lock())
+}
+
+pub fn init_config_non_interactive(opts: NonInteractiveInit) -> Result<()> {
+    let provider = opts.provider.unwrap_or("gemini");
+    let model = opts.model.unwrap_or(match provider {
         "openai" => "gpt-4o",
         "anthropic" => "claude-sonnet-4-20250514",
+        "ollama" => DEFAULT_OLLAMA_MODEL,
         _ => "gemini-2.5-flash-preview-05-20",
     });
+    let profile_name = opts.profile_name.unwrap_or("main");
+
+    if profile_name.is_empty()
+        || !profile_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!(
+            "Invalid profile name '{}'. Use letters, digits, '-' or '_'.",
+            profile_name
+        );
+    }
 
-    let api_key = match api_key {
-        Some(k) => k.to_string(),
+    if opts.key_from_stdin && opts.api_key.is_some() {
+        anyhow::bail!("Use either --api-key or --key-from-stdin, not both");
+    }
+
+    let api_key = match opts.api_key {
+        Some(k) => Some(k.to_string()),
+        None if opts.key_from_stdin => Some(key_from_stdin()?),
+        // Local Ollama servers do not need a key
+        None if provider == "ollama" => None,
         None => {
             let env_key =
                 match provider {
@@ -1533,53 +2255,31 @@ pub fn init_config_non_interactive(
                     _ => std::env::var("GEMINI_API_KEY")
                         .or_else(|_| std::env::var("ASK_GEMINI_API_KEY")),
                 };
-            env_key.map_err(|_| {
+            Some(env_key.map_err(|_| {
                 anyhow::anyhow!(
                     "No API key provided. Use --api-key or set {}_API_KEY environment variable",
                     provider.to_uppercase()
                 )
-            })?
+            })?)
         }
     };
 
     // Platform-specific config directory
-    #[cfg(windows)]
-    let config_dir = dirs::config_dir()
-        .map(|p| p.join("ask"))
-        .unwrap_or_else(|| std::path::PathBuf::from("."));
-
-    #[cfg(not(windows))]
-    let config_dir = dirs::home_dir()
-        .map(|p| p.join(".config").join("ask"))
-        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    let config_dir = Config::config_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
 
-    std::fs::create_dir_all(&config_dir)?;
     let config_path = config_dir.join("ask.toml");
 
-    let config_content = format!(
-        r#"# ask configuration (generated by --non-interactive)
-
-[profiles.main]
-provider = "{provider}"
-model = "{model}"
-api_key = "{api_key}"
-stream = true
-
-[behavior]
-auto_execute = false
-confirm_destructive = true
-timeout = 30
+    if config_path.exists() && !opts.force {
+        anyhow::bail!(
+            "Config already exists at {}\nUse --force to overwrite it.",
+            config_path.display()
+        );
+    }
 
-[context]
-max_age_minutes = 30
-max_messages = 20
+    std::fs::create_dir_all(&config_dir)?;
 
-[update]
-auto_check = true
-check_interval_hours = 24
-channel = "stable"
-"#
-    );
+    let config_content =
+        render_non_interactive_config(profile_name, provider, model, api_key.as_deref(), &opts);
 
     std::fs::write(&config_path, config_content)?;
 
@@ -1589,8 +2289,9 @@ channel = "stable"
         config_path.display().to_string().bright_white()
     );
     println!(
-        "{} provider={}, model={}",
+        "{} profile={}, provider={}, model={}",
         "Configured:".green(),
+        profile_name.cyan(),
         provider.cyan(),
         model.cyan()
     );
@@ -1602,6 +2303,36 @@ channel = "stable"
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_read_key_from_stdin_content() {
+        assert_eq!(
+            read_key_from("sk-test-123\n".as_bytes()).unwrap(),
+            "sk-test-123"
+        );
+        assert_eq!(read_key_from("  sk-abc \r\n".as_bytes()).unwrap(), "sk-abc");
+        assert!(read_key_from("\n".as_bytes()).is_err());
+        assert!(read_key_from("".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_local_preset_base_url() {
+        assert_eq!(local_preset_base_url(0), Some("http://localhost:1234/v1"));
+        assert_eq!(local_preset_base_url(1), Some("http://localhost:8000/v1"));
+        assert_eq!(local_preset_base_url(2), Some("http://localhost:11434/v1"));
+        // The entry after the presets is "Custom URL"
+        assert_eq!(local_preset_base_url(LOCAL_SERVER_PRESETS.len()), None);
+    }
+
+    #[test]
+    fn test_local_preset_index_roundtrip() {
+        for (idx, (_, url)) in LOCAL_SERVER_PRESETS.iter().enumerate() {
+            assert_eq!(local_preset_index(url), Some(idx));
+        }
+        assert_eq!(local_preset_index("http://localhost:8000/v1/"), Some(1));
+        assert_eq!(local_preset_index("http://localhost:11434"), None);
+        assert_eq!(local_preset_index("https://api.openai.com/v1"), None);
+    }
+
     #[test]
     fn test_cli_overrides_precedence() {
         let mut config = Config::default();
@@ -1633,6 +2364,211 @@ mod tests {
         assert_eq!(cfg2.active_model(), "claude-3");
     }
 
+    #[test]
+    fn test_azure_deployment_defaults_to_model() {
+        let config: Config = toml::from_str(
+            r#"
+[profiles.az]
+provider = "azure"
+model = "gpt-4o"
+base_url = "https://myres.openai.azure.com"
+
+[profiles.az2]
+provider = "azure"
+model = "gpt-4o"
+deployment = "prod"
+api_version = "2025-01-01-preview"
+"#,
+        )
+        .unwrap();
+
+        let args = Args {
+            profile: Some("az".to_string()),
+            ..Default::default()
+        };
+        let az = config.clone().with_cli_overrides(&args);
+        assert_eq!(
+            az.get_azure_deployment(),
+            ("gpt-4o".to_string(), "2024-10-21".to_string())
+        );
+
+        let args = Args {
+            profile: Some("az2".to_string()),
+            ..Default::default()
+        };
+        let az2 = config.with_cli_overrides(&args);
+        assert_eq!(
+            az2.get_azure_deployment(),
+            ("prod".to_string(), "2025-01-01-preview".to_string())
+        );
+    }
+
+    #[test]
+    fn test_render_non_interactive_config_with_profile_and_base_url() {
+        let opts = NonInteractiveInit {
+            base_url: Some("http://localhost:11434/v1"),
+            stream: Some(false),
+            thinking: Some("medium"),
+            ..Default::default()
+        };
+        let toml = render_non_interactive_config("local", "openai", "llama3", Some("k\"ey"), &opts);
+
+        let config: Config = toml::from_str(&toml).unwrap();
+        assert_eq!(config.default_profile.as_deref(), Some("local"));
+        let profile = config.profiles.get("local").unwrap();
+        assert_eq!(profile.provider.as_deref(), Some("openai"));
+        assert_eq!(profile.model.as_deref(), Some("llama3"));
+        assert_eq!(profile.api_key.as_deref(), Some("k\"ey"));
+        assert_eq!(
+            profile.base_url.as_deref(),
+            Some("http://localhost:11434/v1")
+        );
+        assert_eq!(profile.stream, Some(false));
+        assert_eq!(profile.reasoning_effort.as_deref(), Some("medium"));
+    }
+
+    #[test]
+    fn test_render_non_interactive_config_thinking_keys() {
+        let opts = NonInteractiveInit {
+            thinking: Some("8000"),
+            ..Default::default()
+        };
+        let toml = render_non_interactive_config("main", "anthropic", "claude", Some("k"), &opts);
+        let config: Config = toml::from_str(&toml).unwrap();
+        let profile = config.profiles.get("main").unwrap();
+        assert_eq!(profile.thinking_budget, Some(8000));
+        assert_eq!(profile.stream, Some(true));
+
+        let opts = NonInteractiveInit {
+            thinking: Some("low"),
+            ..Default::default()
+        };
+        let toml = render_non_interactive_config("main", "gemini", "gemini-3", None, &opts);
+        let config: Config = toml::from_str(&toml).unwrap();
+        let profile = config.profiles.get("main").unwrap();
+        assert_eq!(profile.thinking_level.as_deref(), Some("low"));
+        assert!(profile.api_key.is_none());
+
+        // Ollama only switches thinking on or off; a level must still load
+        for (thinking, enabled) in [("high", true), ("none", false), ("4096", true)] {
+            let opts = NonInteractiveInit {
+                thinking: Some(thinking),
+                ..Default::default()
+            };
+            let toml = render_non_interactive_config("main", "ollama", "qwen3", None, &opts);
+            let mut config: Config = toml::from_str(&toml).unwrap();
+            config.active = ActiveConfig {
+                provider: "ollama".to_string(),
+                profile_name: Some("main".to_string()),
+                ..Default::default()
+            };
+            assert_eq!(config.get_thinking_config().0, enabled, "{}", thinking);
+        }
+    }
+
+    #[test]
+    fn test_infer_provider_from_model() {
+        assert_eq!(infer_provider_from_model("gpt-5"), Some("openai"));
+        assert_eq!(infer_provider_from_model("gpt-4o-mini"), Some("openai"));
+        assert_eq!(infer_provider_from_model("o1"), Some("openai"));
+        assert_eq!(infer_provider_from_model("o3-mini"), Some("openai"));
+        assert_eq!(infer_provider_from_model("o4-mini"), Some("openai"));
+        assert_eq!(
+            infer_provider_from_model("claude-sonnet-4"),
+            Some("anthropic")
+        );
+        assert_eq!(
+            infer_provider_from_model("Claude-Haiku-4-5"),
+            Some("anthropic")
+        );
+        assert_eq!(
+            infer_provider_from_model("gemini-3-flash-preview"),
+            Some("gemini")
+        );
+    }
+
+    #[test]
+    fn test_infer_provider_unknown_model() {
+        assert_eq!(infer_provider_from_model("llama3"), None);
+        assert_eq!(infer_provider_from_model("codestral-latest"), None);
+        assert_eq!(infer_provider_from_model("o1x"), None);
+        assert_eq!(infer_provider_from_model("gemini"), None);
+    }
+
+    #[test]
+    fn test_model_inference_switches_to_matching_profile() {
+        let mut config = Config::default();
+        config.profiles.insert(
+            "main".to_string(),
+            ProfileConfig {
+                provider: Some("gemini".to_string()),
+                api_key: Some("gemini-key".to_string()),
+                ..Default::default()
+            },
+        );
+        config.profiles.insert(
+            "work".to_string(),
+            ProfileConfig {
+                provider: Some("anthropic".to_string()),
+                api_key: Some("anthropic-key".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let args = Args {
+            model: Some("claude-sonnet-4".to_string()),
+            ..Default::default()
+        };
+        let cfg = config.with_cli_overrides(&args);
+        assert_eq!(cfg.active_provider(), "anthropic");
+        assert_eq!(cfg.active_model(), "claude-sonnet-4");
+        assert_eq!(cfg.api_key(), Some("anthropic-key".to_string()));
+        assert_eq!(cfg.active.profile_name.as_deref(), Some("work"));
+        assert_eq!(cfg.active_profile(&args).as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn test_model_inference_without_matching_profile_uses_ad_hoc() {
+        let mut config = Config::default();
+        config.profiles.insert(
+            "main".to_string(),
+            ProfileConfig {
+                provider: Some("gemini".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let args = Args {
+            model: Some("gpt-5".to_string()),
+            ..Default::default()
+        };
+        let cfg = config.with_cli_overrides(&args);
+        assert_eq!(cfg.active_provider(), "openai");
+        assert_eq!(cfg.active_model(), "gpt-5");
+        assert!(cfg.active.profile_name.is_none());
+    }
+
+    #[test]
+    fn test_model_inference_keeps_default_for_unknown_model() {
+        let mut config = Config::default();
+        config.profiles.insert(
+            "main".to_string(),
+            ProfileConfig {
+                provider: Some("gemini".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let args = Args {
+            model: Some("llama3".to_string()),
+            ..Default::default()
+        };
+        let cfg = config.with_cli_overrides(&args);
+        assert_eq!(cfg.active_provider(), "gemini");
+        assert_eq!(cfg.active_model(), "llama3");
+        assert_eq!(cfg.active.profile_name.as_deref(), Some("main"));
+    }
+
     #[test]
     fn test_thinking_config_logic() {
         let mut config = Config::default();
@@ -0,0 +1,290 @@
+//! Quick scalar edits: `ask --set behavior.auto_execute=true`
+
+use super::{Config, ConfigManager};
+use anyhow::{bail, Result};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+
+/// Top-level keys `--set` may touch (`default` is shorthand for the default profile)
+const SETTABLE_SECTIONS: &[&str] = &[
+    "default",
+    "default_profile",
+    "provider_order",
+    "profiles",
+    "behavior",
+    "context",
+    "update",
+    "prompt",
+    "http",
+    "hooks",
+    "providers",
+    "privacy",
+    "theme",
+    "commands",
+    "aliases",
+    "pricing",
+];
+
+/// Apply `key=value` to the highest-precedence config file, backing it up first
+pub fn set_config_value(assignment: &str) -> Result<()> {
+    let Some((key, raw_value)) = assignment.split_once('=') else {
+        bail!("Expected key=value, e.g.: ask --set behavior.auto_execute=true");
+    };
+
+    let path = target_config_path()?;
+    let mut doc = read_doc(&path)?;
+
+    let value = list_value(key.trim(), parse_value(raw_value));
+    let resolved = set_path(&mut doc, key.trim(), value.clone())?;
+
+    write_doc(&path, &doc)?;
+
+    println!(
+        "{} {} = {} {}",
+        "Set".green(),
+        resolved.bright_white(),
+        value,
+        format!("in {}", path.display()).bright_black()
+    );
+    Ok(())
+}
+
+/// The file that wins when configs are merged; the user config if none exists yet
+pub(super) fn target_config_path() -> Result<PathBuf> {
+    match Config::config_files().pop() {
+        Some(path) => Ok(path),
+        None => Ok(ConfigManager::new()?.config_path),
+    }
+}
+
+/// The config file as a TOML document (empty if it doesn't exist yet)
+pub(super) fn read_doc(path: &Path) -> Result<toml::Value> {
+    if !path.exists() {
+        return Ok(toml::Value::Table(toml::map::Map::new()));
+    }
+    let content = std::fs::read_to_string(path)?;
+    toml::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Write the document back, keeping the previous file as `ask.toml.bak`
+pub(super) fn write_doc(path: &Path, doc: &toml::Value) -> Result<()> {
+    if path.exists() {
+        std::fs::copy(path, path.with_extension("toml.bak"))?;
+    } else if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, toml::to_string_pretty(doc)?)?;
+    Ok(())
+}
+
+/// Infer bool, integer or float; anything else (or a quoted value) is a string
+fn parse_value(raw: &str) -> toml::Value {
+    let raw = raw.trim();
+
+    for quote in ['"', '\''] {
+        if raw.len() >= 2 && raw.starts_with(quote) && raw.ends_with(quote) {
+            return toml::Value::String(raw[1..raw.len() - 1].to_string());
+        }
+    }
+
+    match raw {
+        "true" => return toml::Value::Boolean(true),
+        "false" => return toml::Value::Boolean(false),
+        _ => {}
+    }
+    if let Ok(i) = raw.parse::<i64>() {
+        return toml::Value::Integer(i);
+    }
+    if let Ok(f) = raw.parse::<f64>() {
+        if f.is_finite() {
+            return toml::Value::Float(f);
+        }
+    }
+    toml::Value::String(raw.to_string())
+}
+
+/// `provider_order` is a list: `work,personal` becomes `["work", "personal"]`
+fn list_value(key: &str, value: toml::Value) -> toml::Value {
+    match value {
+        toml::Value::String(s) if key == "provider_order" => toml::Value::Array(
+            s.split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| toml::Value::String(name.to_string()))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Set a dotted path, creating intermediate tables. Returns the resolved path.
+fn set_path(doc: &mut toml::Value, key: &str, value: toml::Value) -> Result<String> {
+    let mut parts: Vec<String> = key.split('.').map(|p| p.trim().to_string()).collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        bail!("Invalid key '{}'", key);
+    }
+
+    if !SETTABLE_SECTIONS.contains(&parts[0].as_str()) {
+        bail!(
+            "Unknown config section '{}'. Expected one of: {}",
+            parts[0],
+            SETTABLE_SECTIONS.join(", ")
+        );
+    }
+
+    if parts[0] == "default" {
+        if parts.len() < 2 {
+            bail!("Use default.<key>, e.g.: ask --set default.model=gpt-4o");
+        }
+        let Some(profile) = doc.get("default_profile").and_then(|v| v.as_str()) else {
+            bail!("No default_profile set; use profiles.<name>.<key> instead");
+        };
+        let profile = profile.to_string();
+        parts.splice(0..1, ["profiles".to_string(), profile]);
+    }
+
+    let (last, tables) = parts.split_last().expect("key has at least one part");
+    let mut current = doc;
+    for (i, part) in tables.iter().enumerate() {
+        let table = current
+            .as_table_mut()
+            .ok_or_else(|| anyhow::anyhow!("'{}' is not a table", parts[..i].join(".")))?;
+        current = table
+            .entry(part.clone())
+            .or_insert_with(|| toml::Value::Table(toml::map::Map::new()));
+    }
+
+    let table = current
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("'{}' is not a table", tables.join(".")))?;
+    table.insert(last.clone(), value);
+
+    Ok(parts.join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(toml: &str) -> toml::Value {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_parse_value_infers_types() {
+        assert_eq!(parse_value("true"), toml::Value::Boolean(true));
+        assert_eq!(parse_value("false"), toml::Value::Boolean(false));
+        assert_eq!(parse_value("30"), toml::Value::Integer(30));
+        assert_eq!(parse_value("-1"), toml::Value::Integer(-1));
+        assert_eq!(parse_value("2.5"), toml::Value::Float(2.5));
+        assert_eq!(parse_value("gpt-4o"), toml::Value::String("gpt-4o".into()));
+        assert_eq!(parse_value("\"42\""), toml::Value::String("42".into()));
+        assert_eq!(parse_value("'true'"), toml::Value::String("true".into()));
+        assert_eq!(parse_value("nan"), toml::Value::String("nan".into()));
+    }
+
+    #[test]
+    fn test_set_path_creates_nested_tables() {
+        let mut d = doc("");
+        set_path(&mut d, "profiles.work.model", parse_value("gpt-4o")).unwrap();
+        set_path(&mut d, "behavior.auto_execute", parse_value("true")).unwrap();
+
+        assert_eq!(d["profiles"]["work"]["model"].as_str(), Some("gpt-4o"));
+        assert_eq!(d["behavior"]["auto_execute"].as_bool(), Some(true));
+    }
+
+    #[test]
+    fn test_set_path_keeps_siblings() {
+        let mut d = doc(r#"
+[profiles.work]
+provider = "openai"
+model = "gpt-4o-mini"
+"#);
+        set_path(&mut d, "profiles.work.model", parse_value("gpt-4o")).unwrap();
+
+        assert_eq!(d["profiles"]["work"]["provider"].as_str(), Some("openai"));
+        assert_eq!(d["profiles"]["work"]["model"].as_str(), Some("gpt-4o"));
+    }
+
+    #[test]
+    fn test_set_path_default_targets_default_profile() {
+        let mut d = doc(r#"
+default_profile = "main"
+
+[profiles.main]
+model = "old"
+"#);
+        let resolved = set_path(&mut d, "default.model", parse_value("gpt-4o")).unwrap();
+
+        assert_eq!(resolved, "profiles.main.model");
+        assert_eq!(d["profiles"]["main"]["model"].as_str(), Some("gpt-4o"));
+    }
+
+    #[test]
+    fn test_set_path_default_without_default_profile() {
+        let mut d = doc("");
+        assert!(set_path(&mut d, "default.model", parse_value("x")).is_err());
+    }
+
+    #[test]
+    fn test_set_path_rejects_unknown_section() {
+        let mut d = doc("");
+        let err = set_path(&mut d, "bogus.key", parse_value("1")).unwrap_err();
+        assert!(err.to_string().contains("Unknown config section 'bogus'"));
+    }
+
+    #[test]
+    fn test_set_path_rejects_scalar_parent() {
+        let mut d = doc("default_profile = \"main\"");
+        assert!(set_path(&mut d, "default_profile.x", parse_value("1")).is_err());
+    }
+
+    #[test]
+    fn test_set_result_loads_as_config() {
+        let mut d = doc("");
+        set_path(&mut d, "context.max_messages", parse_value("50")).unwrap();
+        set_path(&mut d, "update.channel", parse_value("beta")).unwrap();
+
+        let config = Config::from_toml(&toml::to_string_pretty(&d).unwrap()).unwrap();
+        assert_eq!(config.context.max_messages, 50);
+        assert_eq!(config.update.channel, "beta");
+    }
+
+    #[test]
+    fn test_set_provider_settings() {
+        let mut d = doc("");
+        let resolved = set_path(
+            &mut d,
+            "providers.anthropic.prompt_cache",
+            parse_value("true"),
+        )
+        .unwrap();
+        assert_eq!(resolved, "providers.anthropic.prompt_cache");
+
+        let config = Config::from_toml(&toml::to_string_pretty(&d).unwrap()).unwrap();
+        assert_eq!(config.providers.anthropic.prompt_cache, Some(true));
+    }
+
+    #[test]
+    fn test_set_provider_order_list() {
+        let mut d = doc("");
+        let value = list_value("provider_order", parse_value("work, personal,local"));
+        set_path(&mut d, "provider_order", value).unwrap();
+        let config = Config::from_toml(&toml::to_string_pretty(&d).unwrap()).unwrap();
+        assert_eq!(config.provider_order, vec!["work", "personal", "local"]);
+
+        assert_eq!(
+            list_value("behavior.timeout", parse_value("a,b")),
+            toml::Value::String("a,b".into())
+        );
+    }
+
+    #[test]
+    fn test_set_privacy_settings() {
+        let mut d = doc("");
+        set_path(&mut d, "privacy.redact", parse_value("true")).unwrap();
+        let config = Config::from_toml(&toml::to_string_pretty(&d).unwrap()).unwrap();
+        assert!(config.privacy.redact);
+    }
+}
@@ -8,6 +8,7 @@
 
 use anyhow::Result;
 use requestty::Question;
+use serde::Serialize;
 
 use super::numbered_select;
 
@@ -42,7 +43,7 @@ pub fn detect_thinking_type(provider: &str, model: &str) -> ThinkingType {
                 ThinkingType::NotSupported
             }
         }
-        "openai" => {
+        "openai" | "azure" => {
             let model_lower = model.to_lowercase();
             if model_lower.starts_with("o1")
                 || model_lower.starts_with("o3")
@@ -60,6 +61,94 @@ pub fn detect_thinking_type(provider: &str, model: &str) -> ThinkingType {
     }
 }
 
+impl ThinkingType {
+    /// Profile key that sets this kind of thinking (None = not supported)
+    pub fn config_key(self) -> Option<&'static str> {
+        match self {
+            ThinkingType::GeminiBudget
+            | ThinkingType::AnthropicBudget
+            | ThinkingType::OllamaThink => Some("thinking_budget"),
+            ThinkingType::GeminiLevel => Some("thinking_level"),
+            ThinkingType::OpenAIEffort => Some("reasoning_effort"),
+            ThinkingType::NotSupported => None,
+        }
+    }
+}
+
+/// What a model can do, guessed from its name (for `ask models --json`)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ModelCapabilities {
+    pub supports_thinking: bool,
+    pub supports_vision: bool,
+    pub is_reasoning: bool,
+    /// Profile key that controls thinking, e.g. "reasoning_effort"
+    pub thinking_param: Option<&'static str>,
+}
+
+/// Capability hints for a model, extending `detect_thinking_type` with name heuristics
+pub fn model_capabilities(provider: &str, model: &str) -> ModelCapabilities {
+    let thinking_type = detect_thinking_type(provider, model);
+    let m = model.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| m.contains(n));
+
+    let is_reasoning = match provider {
+        "openai" | "azure" | "openai_compatible" => {
+            crate::providers::is_reasoning_model(model) || has(&["gpt-oss", "deepseek-r1"])
+        }
+        "gemini" => has(&["2.5", "2-5", "gemini-3", "thinking"]),
+        "anthropic" => has(&["3-7", "3.7", "opus-4", "sonnet-4", "haiku-4", "claude-4"]),
+        "ollama" => has(&[
+            "deepseek-r1",
+            "qwq",
+            "qwen3",
+            "gpt-oss",
+            "magistral",
+            "think",
+        ]),
+        _ => false,
+    };
+
+    // Anthropic and Ollama accept the parameter for any model, but only reasoning models use it
+    let supports_thinking = match thinking_type {
+        ThinkingType::NotSupported => false,
+        ThinkingType::AnthropicBudget | ThinkingType::OllamaThink => is_reasoning,
+        _ => true,
+    };
+
+    let supports_vision = match provider {
+        "gemini" => !has(&["embedding", "tts", "aqa"]),
+        "openai" | "azure" => {
+            has(&[
+                "gpt-4o",
+                "gpt-4.1",
+                "gpt-4-turbo",
+                "gpt-5",
+                "o1",
+                "o3",
+                "o4",
+            ]) && !has(&["o1-mini", "o3-mini"])
+        }
+        "anthropic" => !has(&["claude-2", "claude-instant"]),
+        _ => has(&[
+            "llava",
+            "vision",
+            "moondream",
+            "gemma3",
+            "minicpm-v",
+            "qwen2.5vl",
+            "qwen2.5-vl",
+            "-vl",
+        ]),
+    };
+
+    ModelCapabilities {
+        supports_thinking,
+        supports_vision,
+        is_reasoning,
+        thinking_param: thinking_type.config_key().filter(|_| supports_thinking),
+    }
+}
+
 pub struct ThinkingOption {
     pub label: String,
     pub config_value: String,
@@ -278,6 +367,73 @@ pub fn format_thinking_config(key: &str, value: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_model_capabilities_gemini() {
+        let caps = model_capabilities("gemini", "gemini-3-flash-preview");
+        assert!(caps.supports_thinking && caps.supports_vision && caps.is_reasoning);
+        assert_eq!(caps.thinking_param, Some("thinking_level"));
+
+        let caps = model_capabilities("gemini", "gemini-2.5-pro");
+        assert_eq!(caps.thinking_param, Some("thinking_budget"));
+        assert!(caps.is_reasoning);
+
+        let caps = model_capabilities("gemini", "text-embedding-004");
+        assert!(!caps.supports_thinking && !caps.supports_vision);
+        assert_eq!(caps.thinking_param, None);
+    }
+
+    #[test]
+    fn test_model_capabilities_openai() {
+        let caps = model_capabilities("openai", "gpt-5-mini");
+        assert!(caps.supports_thinking && caps.supports_vision && caps.is_reasoning);
+        assert_eq!(caps.thinking_param, Some("reasoning_effort"));
+
+        let caps = model_capabilities("openai", "gpt-4o");
+        assert!(!caps.supports_thinking && !caps.is_reasoning);
+        assert!(caps.supports_vision);
+
+        let caps = model_capabilities("openai", "o3-mini");
+        assert!(caps.is_reasoning && !caps.supports_vision);
+
+        let caps = model_capabilities("openai", "gpt-3.5-turbo");
+        assert_eq!(
+            caps,
+            ModelCapabilities {
+                supports_thinking: false,
+                supports_vision: false,
+                is_reasoning: false,
+                thinking_param: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_model_capabilities_anthropic() {
+        let caps = model_capabilities("anthropic", "claude-sonnet-4-5");
+        assert!(caps.supports_thinking && caps.supports_vision && caps.is_reasoning);
+        assert_eq!(caps.thinking_param, Some("thinking_budget"));
+
+        let caps = model_capabilities("anthropic", "claude-3-7-sonnet-latest");
+        assert!(caps.supports_thinking);
+
+        let caps = model_capabilities("anthropic", "claude-3-5-haiku-latest");
+        assert!(!caps.supports_thinking && caps.supports_vision);
+        assert_eq!(caps.thinking_param, None);
+    }
+
+    #[test]
+    fn test_model_capabilities_ollama() {
+        let caps = model_capabilities("ollama", "deepseek-r1:8b");
+        assert!(caps.supports_thinking && caps.is_reasoning && !caps.supports_vision);
+        assert_eq!(caps.thinking_param, Some("thinking_budget"));
+
+        let caps = model_capabilities("ollama", "llava:13b");
+        assert!(caps.supports_vision && !caps.supports_thinking);
+
+        let caps = model_capabilities("ollama", "phi4-mini");
+        assert!(!caps.supports_vision && !caps.supports_thinking);
+    }
+
     #[test]
     fn test_detect_gemini_3() {
         assert_eq!(
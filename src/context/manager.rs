@@ -28,8 +28,10 @@ impl ContextStats {
 pub struct ContextManager {
     storage: ContextStorage,
     context_id: String,
+    session: Option<String>,
     max_messages: usize,
     max_age_minutes: u64,
+    rolling_summary: bool,
 }
 
 fn sort_contexts_by_recent(contexts: &mut [ContextEntry]) {
@@ -45,6 +47,48 @@ fn truncate_chars(text: &str, max_chars: usize) -> String {
     }
 }
 
+/// Longest line one trimmed message adds to the rolling summary
+const SUMMARY_LINE_CHARS: usize = 200;
+
+/// Budget for the whole rolling summary; the oldest lines go first
+const SUMMARY_MAX_CHARS: usize = 4000;
+
+/// Fold messages trimmed from the context into the rolling summary, one line each
+fn fold_into_summary(summary: Option<String>, dropped: &[StoredMessage]) -> Option<String> {
+    let mut lines: Vec<String> = summary
+        .iter()
+        .flat_map(|s| s.lines())
+        .map(str::to_string)
+        .collect();
+
+    for message in dropped {
+        let text = message
+            .content
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !text.is_empty() {
+            lines.push(format!(
+                "- {}: {}",
+                message.role,
+                truncate_chars(&text, SUMMARY_LINE_CHARS)
+            ));
+        }
+    }
+
+    let mut total: usize = lines.iter().map(|l| l.chars().count() + 1).sum();
+    while total > SUMMARY_MAX_CHARS && !lines.is_empty() {
+        total -= lines.remove(0).chars().count() + 1;
+    }
+
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// First 8 characters of a context id; ids read from disk are not guaranteed to be hex
+fn short_id(id: &str) -> String {
+    id.chars().take(8).collect()
+}
+
 fn excerpt_around(text: &str, start: usize, match_len: usize, max_chars: usize) -> String {
     let chars: Vec<char> = text.chars().collect();
     let char_start = text[..start].chars().count();
@@ -81,6 +125,190 @@ fn first_matching_snippet(text: &str, query: &str) -> Option<String> {
     None
 }
 
+/// Case-insensitive subsequence match. Lower scores are tighter matches.
+fn fuzzy_score(haystack: &str, needle: &str) -> Option<usize> {
+    let haystack: Vec<char> = haystack.to_lowercase().chars().collect();
+    let mut start = None;
+    let mut pos = 0;
+
+    for n in needle.to_lowercase().chars() {
+        let offset = haystack[pos..].iter().position(|&c| c == n)?;
+        start.get_or_insert(pos + offset);
+        pos += offset + 1;
+    }
+
+    Some(pos - start.unwrap_or(0))
+}
+
+/// Filter contexts for the history browser, best matches first.
+/// Directories match fuzzily; message content matches by substring.
+fn filter_contexts<'a>(contexts: &'a [ContextEntry], filter: &str) -> Vec<&'a ContextEntry> {
+    let filter = filter.trim();
+    if filter.is_empty() {
+        return contexts.iter().collect();
+    }
+
+    let lowercase_filter = filter.to_lowercase();
+    let mut scored: Vec<(usize, usize, &ContextEntry)> = contexts
+        .iter()
+        .enumerate()
+        .filter_map(|(order, ctx)| {
+            let score = if ctx.pwd.to_lowercase().contains(&lowercase_filter) {
+                Some(0)
+            } else if let Some(score) = fuzzy_score(&ctx.pwd, filter) {
+                Some(score)
+            } else if ctx
+                .messages
+                .iter()
+                .any(|m| m.content.to_lowercase().contains(&lowercase_filter))
+            {
+                Some(usize::MAX)
+            } else {
+                None
+            };
+            score.map(|s| (s, order, ctx))
+        })
+        .collect();
+
+    scored.sort_by_key(|(score, order, _)| (*score, *order));
+    scored.into_iter().map(|(_, _, ctx)| ctx).collect()
+}
+
+fn find_context(contexts: Vec<ContextEntry>, target: &str) -> (Option<ContextEntry>, String) {
+    let current_dir = std::env::current_dir()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let search_target = if target == "." {
+        current_dir
+    } else if let Ok(abs_path) = std::fs::canonicalize(target) {
+        abs_path.to_string_lossy().to_string()
+    } else {
+        target.to_string()
+    };
+
+    let matching_ctx = contexts.into_iter().find(|ctx| {
+        ctx.id.starts_with(&search_target) || (ctx.pwd == search_target && ctx.session.is_none())
+    });
+
+    (matching_ctx, search_target)
+}
+
+/// The one context whose ID starts with `prefix`; none or several is an error
+fn find_by_id_prefix(contexts: Vec<ContextEntry>, prefix: &str) -> Result<ContextEntry> {
+    if prefix.is_empty() {
+        bail!("Context ID prefix is empty; see 'ask history' for IDs");
+    }
+
+    let mut matches: Vec<ContextEntry> = contexts
+        .into_iter()
+        .filter(|ctx| ctx.id.starts_with(prefix))
+        .collect();
+
+    match matches.len() {
+        0 => bail!(
+            "No context found matching: '{}' (see 'ask history' for IDs)",
+            prefix
+        ),
+        1 => Ok(matches.remove(0)),
+        _ => {
+            sort_contexts_by_recent(&mut matches);
+            let candidates: Vec<String> = matches
+                .iter()
+                .map(|ctx| format!("{} ({})", short_id(&ctx.id), ctx.pwd))
+                .collect();
+            bail!(
+                "Context ID '{}' is ambiguous, it matches: {}. Use more characters.",
+                prefix,
+                candidates.join(", ")
+            )
+        }
+    }
+}
+
+fn validate_session_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        bail!(
+            "Invalid session name '{}'. Use letters, digits, '-', '_' or '.'.",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Single-line assistant replies that start like a shell command
+fn is_command_response(text: &str) -> bool {
+    let text = text.trim();
+    if text.is_empty() || text.contains('\n') || text.starts_with("```") {
+        return false;
+    }
+
+    let first_word = text.split_whitespace().next().unwrap_or("");
+    crate::providers::COMMAND_STARTERS
+        .iter()
+        .any(|cmd| first_word.starts_with(cmd))
+}
+
+/// Render a context as a readable Markdown transcript
+fn render_markdown_transcript(entry: &ContextEntry) -> String {
+    let mut out = String::new();
+    out.push_str("# ask session\n\n");
+    out.push_str(&format!("- **Directory:** `{}`\n", entry.pwd));
+    out.push_str(&format!(
+        "- **Created:** {}\n",
+        entry.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+    ));
+    out.push_str(&format!(
+        "- **Last used:** {}\n",
+        entry.last_used.format("%Y-%m-%d %H:%M:%S UTC")
+    ));
+
+    for msg in &entry.messages {
+        let role = match msg.role.as_str() {
+            "user" => "User",
+            "assistant" => "Assistant",
+            "system" => "System",
+            other => other,
+        };
+        out.push_str(&format!(
+            "\n### {} ({})\n\n",
+            role,
+            msg.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+
+        if msg.role == "assistant" && is_command_response(&msg.content) {
+            out.push_str(&format!("```sh\n{}\n```\n", msg.content.trim()));
+        } else {
+            out.push_str(msg.content.trim_end());
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/// Write a context to `path` as JSON (`.json`) or Markdown (anything else)
+fn export_entry(entry: &ContextEntry, path: &Path) -> Result<()> {
+    let is_json = path
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+
+    let content = if is_json {
+        serde_json::to_string_pretty(entry)?
+    } else {
+        render_markdown_transcript(entry)
+    };
+
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
 fn load_all_contexts(config: &Config) -> Result<Vec<ContextEntry>> {
     let storage_path = config.context_storage_path();
     let storage = ContextStorage::new(storage_path)?;
@@ -95,12 +323,21 @@ impl ContextManager {
 
     /// Create with custom TTL (0 = permanent, no cleanup)
     pub fn with_ttl(config: &Config, ttl_minutes: u64) -> Result<Self> {
+        Self::with_session(config, ttl_minutes, None)
+    }
+
+    /// Create for a named session of the current directory (None = main context)
+    pub fn with_session(config: &Config, ttl_minutes: u64, session: Option<&str>) -> Result<Self> {
+        if let Some(name) = session {
+            validate_session_name(name)?;
+        }
+
         let storage_path = config.context_storage_path();
         let storage = ContextStorage::new(storage_path)?;
 
-        // Create context ID from current directory
+        // Create context ID from current directory (and session, if any)
         let pwd = std::env::current_dir()?.to_string_lossy().to_string();
-        let context_id = Self::hash_pwd(&pwd);
+        let context_id = Self::context_id_for(&pwd, session);
 
         // Run cleanup only if TTL > 0 (not permanent)
         if ttl_minutes > 0 {
@@ -110,11 +347,54 @@ impl ContextManager {
         Ok(Self {
             storage,
             context_id,
+            session: session.map(str::to_string),
+            max_messages: config.context.max_messages,
+            max_age_minutes: ttl_minutes,
+            rolling_summary: config.context.rolling_summary,
+        })
+    }
+
+    /// Keep the context in one explicit JSON file (`--context-file`) instead of the storage dir
+    pub fn with_file(config: &Config, ttl_minutes: u64, path: &Path) -> Result<Self> {
+        let storage = ContextStorage::single_file(path)?;
+
+        if ttl_minutes > 0 {
+            let _ = storage.cleanup(ttl_minutes);
+        }
+
+        Ok(Self {
+            storage,
+            context_id: Self::hash_pwd(&path.to_string_lossy()),
+            session: None,
             max_messages: config.context.max_messages,
             max_age_minutes: ttl_minutes,
+            rolling_summary: config.context.rolling_summary,
         })
     }
 
+    /// Open a saved context by ID prefix (`--resume`), for both reading and saving,
+    /// wherever it was created. It is not expired while resumed.
+    pub fn open_by_id_prefix(config: &Config, prefix: &str) -> Result<Self> {
+        let entry = find_by_id_prefix(load_all_contexts(config)?, prefix)?;
+
+        Ok(Self {
+            storage: ContextStorage::new(config.context_storage_path())?,
+            context_id: entry.id,
+            session: entry.session,
+            max_messages: config.context.max_messages,
+            max_age_minutes: 0,
+            rolling_summary: config.context.rolling_summary,
+        })
+    }
+
+    /// Context ID for a directory; sessions hash the name in so they get their own entry
+    fn context_id_for(pwd: &str, session: Option<&str>) -> String {
+        match session {
+            Some(name) => Self::hash_pwd(&format!("{}#{}", pwd, name)),
+            None => Self::hash_pwd(pwd),
+        }
+    }
+
     /// Create a hash of the directory path
     fn hash_pwd(pwd: &str) -> String {
         let mut hasher = Sha256::new();
@@ -142,21 +422,40 @@ impl ContextManager {
             }))
     }
 
-    /// Get messages from the current context
+    /// Get messages from the current context, led by the rolling summary when enabled
     pub fn get_messages(&self) -> Result<Vec<Message>> {
-        let entry = self.storage.load(&self.context_id)?;
+        let Some(entry) = self.storage.load(&self.context_id)? else {
+            return Ok(Vec::new());
+        };
 
-        Ok(entry
-            .map(|e| {
-                e.messages
-                    .into_iter()
-                    .map(|m| Message {
-                        role: m.role,
-                        content: m.content,
-                    })
-                    .collect()
-            })
-            .unwrap_or_default())
+        let summary = entry
+            .summary
+            .filter(|_| self.rolling_summary)
+            .map(|summary| Message {
+                role: "system".to_string(),
+                content: format!("Summary of the earlier conversation:\n{}", summary),
+                ..Default::default()
+            });
+
+        Ok(summary
+            .into_iter()
+            .chain(entry.messages.into_iter().map(|m| Message {
+                role: m.role,
+                content: m.content,
+                tool_call_id: m.tool_call_id,
+                name: m.name,
+                ..Default::default()
+            }))
+            .collect())
+    }
+
+    /// Drop the oldest messages beyond `max_messages`, keeping their gist with `rolling_summary`
+    fn trim(&self, entry: &mut ContextEntry) {
+        let excess = entry.messages.len().saturating_sub(self.max_messages);
+        let dropped: Vec<StoredMessage> = entry.messages.drain(..excess).collect();
+        if self.rolling_summary && !dropped.is_empty() {
+            entry.summary = fold_into_summary(entry.summary.take(), &dropped);
+        }
     }
 
     /// Add a message to the current context
@@ -169,7 +468,9 @@ impl ContextManager {
             .unwrap_or_else(|| ContextEntry {
                 id: self.context_id.clone(),
                 pwd: pwd.clone(),
+                session: self.session.clone(),
                 messages: Vec::new(),
+                summary: None,
                 created_at: Utc::now(),
                 last_used: Utc::now(),
             });
@@ -178,11 +479,39 @@ impl ContextManager {
             role: role.to_string(),
             content: content.to_string(),
             timestamp: Utc::now(),
+            tool_call_id: None,
+            name: None,
         });
 
-        // Trim to max messages
-        while entry.messages.len() > self.max_messages {
-            entry.messages.remove(0);
+        self.trim(&mut entry);
+
+        entry.last_used = Utc::now();
+        self.storage.save(&entry)?;
+
+        Ok(())
+    }
+
+    /// Whether the current context ends with an assistant answer that can be continued
+    pub fn has_last_answer(&self) -> Result<bool> {
+        Ok(self
+            .storage
+            .load(&self.context_id)?
+            .and_then(|entry| entry.messages.last().map(|m| m.role == "assistant"))
+            .unwrap_or(false))
+    }
+
+    /// Extend the last assistant message in place (used by `--more`)
+    pub fn append_to_last_answer(&self, content: &str) -> Result<()> {
+        let Some(mut entry) = self.storage.load(&self.context_id)? else {
+            bail!("No context found for current directory.");
+        };
+
+        match entry.messages.last_mut() {
+            Some(last) if last.role == "assistant" => {
+                last.content.push_str(content);
+                last.timestamp = Utc::now();
+            }
+            _ => bail!("No previous answer to continue in this context."),
         }
 
         entry.last_used = Utc::now();
@@ -196,6 +525,116 @@ impl ContextManager {
         self.storage.delete(&self.context_id)
     }
 
+    /// Export the current context to a Markdown or JSON file
+    pub fn export(&self, path: &Path) -> Result<usize> {
+        let Some(entry) = self.storage.load(&self.context_id)? else {
+            bail!("No context found for current directory.");
+        };
+
+        export_entry(&entry, path)?;
+        Ok(entry.messages.len())
+    }
+
+    /// Export a saved context (by ID prefix or path) to a Markdown or JSON file
+    pub fn export_specific(config: &Config, target: &str, path: &Path) -> Result<usize> {
+        let contexts = load_all_contexts(config)?;
+        let (matching_ctx, search_target) = find_context(contexts, target);
+
+        let Some(entry) = matching_ctx else {
+            bail!("No context found matching: '{}'", search_target);
+        };
+
+        export_entry(&entry, path)?;
+        Ok(entry.messages.len())
+    }
+
+    /// Append messages from a JSON transcript (as written by `export`) to the current context
+    pub fn import(&self, path: &Path) -> Result<usize> {
+        let content = std::fs::read_to_string(path)?;
+        let imported: ContextEntry = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid JSON transcript {}: {}", path.display(), e))?;
+
+        self.append_messages(imported.messages)
+    }
+
+    /// Copy the messages of a saved context (by ID prefix) into the current context
+    pub fn resume(&self, config: &Config, id: &str) -> Result<usize> {
+        let contexts = load_all_contexts(config)?;
+        let Some(source) = contexts.into_iter().find(|ctx| ctx.id.starts_with(id)) else {
+            bail!("No context found matching: '{}'", id);
+        };
+
+        if source.id == self.context_id {
+            return Ok(0);
+        }
+
+        self.append_messages(source.messages)
+    }
+
+    /// Copy the current conversation into session `name` and continue there.
+    /// The original context is left untouched.
+    pub fn fork(&mut self, name: &str) -> Result<usize> {
+        validate_session_name(name)?;
+
+        let Some(source) = self.storage.load(&self.context_id)? else {
+            bail!("No conversation to fork in this directory. Start one with 'ask -c ...'.");
+        };
+
+        let fork_id = Self::context_id_for(&source.pwd, Some(name));
+        if fork_id == self.context_id {
+            bail!("Already in session '{}'", name);
+        }
+        if self.storage.load(&fork_id)?.is_some() {
+            bail!(
+                "Session '{}' already exists. Use --session {} to continue it.",
+                name,
+                name
+            );
+        }
+
+        let count = source.messages.len();
+        let now = Utc::now();
+        self.storage.save(&ContextEntry {
+            id: fork_id.clone(),
+            pwd: source.pwd,
+            session: Some(name.to_string()),
+            messages: source.messages,
+            summary: source.summary,
+            created_at: now,
+            last_used: now,
+        })?;
+
+        self.context_id = fork_id;
+        self.session = Some(name.to_string());
+        Ok(count)
+    }
+
+    fn append_messages(&self, messages: Vec<StoredMessage>) -> Result<usize> {
+        let pwd = std::env::current_dir()?.to_string_lossy().to_string();
+        let mut entry = self
+            .storage
+            .load(&self.context_id)?
+            .unwrap_or_else(|| ContextEntry {
+                id: self.context_id.clone(),
+                pwd,
+                session: self.session.clone(),
+                messages: Vec::new(),
+                summary: None,
+                created_at: Utc::now(),
+                last_used: Utc::now(),
+            });
+
+        let count = messages.len();
+        entry.messages.extend(messages);
+
+        self.trim(&mut entry);
+
+        entry.last_used = Utc::now();
+        self.storage.save(&entry)?;
+
+        Ok(count)
+    }
+
     /// Get max age in minutes (0 = permanent)
     pub fn max_age_minutes(&self) -> u64 {
         self.max_age_minutes
@@ -265,22 +704,7 @@ impl ContextManager {
             return Ok(());
         }
 
-        let current_dir = std::env::current_dir()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-
-        let search_target = if target == "." {
-            current_dir.clone()
-        } else if let Ok(abs_path) = std::fs::canonicalize(target) {
-            abs_path.to_string_lossy().to_string()
-        } else {
-            target.to_string()
-        };
-
-        let matching_ctx = contexts
-            .into_iter()
-            .find(|ctx| ctx.id.starts_with(&search_target) || ctx.pwd == search_target);
+        let (matching_ctx, search_target) = find_context(contexts, target);
 
         match matching_ctx {
             Some(ctx) => {
@@ -365,13 +789,19 @@ impl ContextManager {
             };
 
             let marker = if is_current { "* " } else { "  " };
+            let session = ctx
+                .session
+                .as_deref()
+                .map(|name| format!(" [{}]", name).magenta().to_string())
+                .unwrap_or_default();
 
             println!(
-                "{}{} {} {} {}",
+                "{}{} {} {}{} {}",
                 marker.green(),
-                ctx.id[..8].bright_black(),
+                short_id(&ctx.id).bright_black(),
                 time_str.blue(),
                 pwd_display,
+                session,
                 format!("({} msgs, {} chars)", msg_count, total_chars).bright_black(),
             );
         }
@@ -385,6 +815,88 @@ impl ContextManager {
         Ok(())
     }
 
+    /// Interactive history browser. Returns the ID of a context to resume, if any.
+    pub fn browse_contexts(config: &Config) -> Result<Option<String>> {
+        let mut contexts = load_all_contexts(config)?;
+
+        if contexts.is_empty() {
+            println!("{}", "No global context history found.".yellow());
+            return Ok(None);
+        }
+
+        sort_contexts_by_recent(&mut contexts);
+
+        loop {
+            let question = Question::input("history_filter")
+                .message("Filter (directory or content, Enter for all)")
+                .build();
+            let filter = requestty::prompt_one(question)?
+                .as_string()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+
+            let matches = filter_contexts(&contexts, &filter);
+            if matches.is_empty() {
+                println!(
+                    "{} '{}'",
+                    "No history matches found for:".yellow(),
+                    filter.bright_white()
+                );
+                continue;
+            }
+
+            let mut choices: Vec<String> = matches
+                .iter()
+                .map(|ctx| {
+                    format!(
+                        "{} {} {} ({} msgs)",
+                        short_id(&ctx.id),
+                        ctx.last_used.format("%Y-%m-%d %H:%M"),
+                        ctx.pwd,
+                        ctx.messages.len()
+                    )
+                })
+                .collect();
+            choices.push("Change filter".to_string());
+            choices.push("Quit".to_string());
+
+            let question = Question::select("history_context")
+                .message(format!("Contexts ({})", matches.len()))
+                .choices(choices)
+                .page_size(15)
+                .build();
+            let index = requestty::prompt_one(question)?
+                .as_list_item()
+                .map(|item| item.index)
+                .unwrap_or(matches.len() + 1);
+
+            if index == matches.len() {
+                continue;
+            }
+            if index > matches.len() {
+                return Ok(None);
+            }
+
+            let id = matches[index].id.clone();
+            println!();
+            Self::show_specific_history(config, &id)?;
+
+            let question = Question::select("history_action")
+                .message("Action")
+                .choices(vec!["Resume in current directory", "Back to list", "Quit"])
+                .build();
+            match requestty::prompt_one(question)?
+                .as_list_item()
+                .map(|item| item.index)
+            {
+                Some(0) => return Ok(Some(id)),
+                Some(1) => continue,
+                _ => return Ok(None),
+            }
+        }
+    }
+
     /// Search all saved contexts by path or message content.
     pub fn search_global(config: &Config, query: &str) -> Result<()> {
         let query = query.trim();
@@ -451,7 +963,7 @@ impl ContextManager {
             println!(
                 "{}{} {} {} {}",
                 marker.green(),
-                ctx.id[..8].bright_black(),
+                short_id(&ctx.id).bright_black(),
                 ctx.last_used.format("%Y-%m-%d %H:%M:%S").to_string().blue(),
                 pwd_display,
                 format!("({} msgs)", ctx.messages.len()).bright_black(),
@@ -503,7 +1015,7 @@ impl ContextManager {
         for ctx in &orphaned {
             println!(
                 "  {} {} {}",
-                ctx.id[..8].bright_black(),
+                short_id(&ctx.id).bright_black(),
                 ctx.last_used.format("%Y-%m-%d %H:%M:%S").to_string().blue(),
                 ctx.pwd.white(),
             );
@@ -558,3 +1070,415 @@ impl ContextManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_manager(dir: &Path, context_id: &str) -> ContextManager {
+        ContextManager {
+            storage: ContextStorage::new(dir.to_path_buf()).unwrap(),
+            context_id: context_id.to_string(),
+            session: None,
+            max_messages: 20,
+            max_age_minutes: 0,
+            rolling_summary: false,
+        }
+    }
+
+    #[test]
+    fn test_truncate_chars_multibyte() {
+        let emoji = "🦀".repeat(250);
+        let truncated = truncate_chars(&emoji, 200);
+        assert_eq!(truncated.chars().count(), 203);
+        assert!(truncated.ends_with("🦀..."));
+
+        // Byte 200 falls inside a 3-byte character here
+        let cjk = format!("a{}", "漢字".repeat(150));
+        let truncated = truncate_chars(&cjk, 200);
+        assert!(truncated.starts_with("a漢字"));
+        assert!(truncated.ends_with("..."));
+        assert_eq!(truncated.trim_end_matches("...").chars().count(), 200);
+
+        assert_eq!(truncate_chars("héllo", 200), "héllo");
+    }
+
+    #[test]
+    fn test_short_id_is_char_safe() {
+        assert_eq!(short_id("0123456789abcdef"), "01234567");
+        assert_eq!(short_id("abc"), "abc");
+        assert_eq!(short_id("日本語のコンテキストID"), "日本語のコンテキ");
+    }
+
+    fn entry(id: &str, pwd: &str, content: &str) -> ContextEntry {
+        ContextEntry {
+            id: id.to_string(),
+            pwd: pwd.to_string(),
+            session: None,
+            messages: vec![StoredMessage {
+                role: "user".to_string(),
+                content: content.to_string(),
+                timestamp: Utc::now(),
+                tool_call_id: None,
+                name: None,
+            }],
+            summary: None,
+            created_at: Utc::now(),
+            last_used: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_append_to_last_answer() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = test_manager(dir.path(), "more");
+
+        assert!(!manager.has_last_answer().unwrap());
+        assert!(manager.append_to_last_answer("x").is_err());
+
+        manager.add_message("user", "write a poem").unwrap();
+        assert!(!manager.has_last_answer().unwrap());
+        assert!(manager.append_to_last_answer("x").is_err());
+
+        manager.add_message("assistant", "Roses are red,").unwrap();
+        assert!(manager.has_last_answer().unwrap());
+        manager
+            .append_to_last_answer("\nviolets are blue.")
+            .unwrap();
+
+        let messages = manager.get_messages().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].role, "assistant");
+        assert_eq!(messages[1].content, "Roses are red,\nviolets are blue.");
+    }
+
+    #[test]
+    fn test_fork_is_an_independent_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = test_manager(dir.path(), "main");
+        manager.add_message("user", "set up nginx").unwrap();
+        manager
+            .add_message("assistant", "apt install nginx")
+            .unwrap();
+        let original = manager.context_id.clone();
+
+        assert_eq!(manager.fork("experiment").unwrap(), 2);
+        assert_ne!(manager.context_id, original);
+        assert_eq!(manager.session.as_deref(), Some("experiment"));
+
+        manager.add_message("user", "what about caddy?").unwrap();
+        assert_eq!(manager.get_messages().unwrap().len(), 3);
+
+        let source = manager.storage.load(&original).unwrap().unwrap();
+        assert_eq!(source.messages.len(), 2);
+        assert!(source.session.is_none());
+
+        let forked = manager.storage.load(&manager.context_id).unwrap().unwrap();
+        assert_eq!(forked.session.as_deref(), Some("experiment"));
+        assert_eq!(forked.pwd, source.pwd);
+    }
+
+    #[test]
+    fn test_find_by_id_prefix_unique() {
+        let contexts = vec![
+            entry("1a2b3c4d00000000", "/srv/api", "deploy"),
+            entry("1a2bffff00000000", "/srv/web", "build"),
+            entry("9f8e7d6c00000000", "/home/u", "hello"),
+        ];
+
+        let found = find_by_id_prefix(contexts.clone(), "1a2b3").unwrap();
+        assert_eq!(found.pwd, "/srv/api");
+        let found = find_by_id_prefix(contexts.clone(), "9f").unwrap();
+        assert_eq!(found.pwd, "/home/u");
+        let found = find_by_id_prefix(contexts, "1a2b3c4d00000000").unwrap();
+        assert_eq!(found.id, "1a2b3c4d00000000");
+    }
+
+    #[test]
+    fn test_find_by_id_prefix_ambiguous_or_missing() {
+        let contexts = vec![
+            entry("1a2b3c4d00000000", "/srv/api", "deploy"),
+            entry("1a2bffff00000000", "/srv/web", "build"),
+        ];
+
+        let err = find_by_id_prefix(contexts.clone(), "1a2b")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("ambiguous"), "{}", err);
+        assert!(err.contains("1a2b3c4d (/srv/api)"), "{}", err);
+        assert!(err.contains("1a2bffff (/srv/web)"), "{}", err);
+
+        let err = find_by_id_prefix(contexts.clone(), "ffff")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("No context found"), "{}", err);
+        assert!(find_by_id_prefix(contexts, "").is_err());
+    }
+
+    #[test]
+    fn test_open_by_id_prefix_reads_and_writes_that_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = ContextStorage::new(dir.path().to_path_buf()).unwrap();
+        storage
+            .save(&entry("1a2b3c4d00000000", "/srv/api", "deploy the api"))
+            .unwrap();
+        storage
+            .save(&entry("9f8e7d6c00000000", "/home/u", "hello"))
+            .unwrap();
+
+        let mut config = Config::default();
+        config.context.storage_path = Some(dir.path().to_string_lossy().to_string());
+
+        let manager = ContextManager::open_by_id_prefix(&config, "1a2b").unwrap();
+        assert_eq!(manager.get_messages().unwrap()[0].content, "deploy the api");
+
+        manager
+            .add_message("assistant", "kubectl apply -f api.yaml")
+            .unwrap();
+        let saved = storage.load("1a2b3c4d00000000").unwrap().unwrap();
+        assert_eq!(saved.messages.len(), 2);
+        assert_eq!(saved.pwd, "/srv/api", "the context keeps its directory");
+        assert_eq!(
+            storage
+                .load("9f8e7d6c00000000")
+                .unwrap()
+                .unwrap()
+                .messages
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_fork_requires_messages_and_new_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = test_manager(dir.path(), "empty");
+        assert!(manager.fork("experiment").is_err());
+
+        manager.add_message("user", "hi").unwrap();
+        assert!(manager.fork("bad name").is_err());
+        assert!(manager.fork("").is_err());
+
+        let mut other = test_manager(dir.path(), "empty");
+        manager.fork("experiment").unwrap();
+        assert!(other.fork("experiment").is_err());
+    }
+
+    #[test]
+    fn test_session_context_ids_differ() {
+        let main = ContextManager::context_id_for("/srv/app", None);
+        let a = ContextManager::context_id_for("/srv/app", Some("a"));
+        let b = ContextManager::context_id_for("/srv/app", Some("b"));
+        assert_eq!(main, ContextManager::hash_pwd("/srv/app"));
+        assert_ne!(main, a);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_filter_contexts_fuzzy() {
+        let contexts = vec![
+            entry("a", "/home/me/projects/web-app", "npm install"),
+            entry("b", "/home/me/work/api", "cargo build"),
+            entry("c", "/srv/docker", "list running containers"),
+        ];
+
+        let ids = |filter: &str| -> Vec<String> {
+            filter_contexts(&contexts, filter)
+                .iter()
+                .map(|c| c.id.clone())
+                .collect()
+        };
+
+        assert_eq!(ids(""), vec!["a", "b", "c"]);
+        // Exact substring of the path
+        assert_eq!(ids("DOCKER"), vec!["c"]);
+        // Subsequence of the path
+        assert_eq!(ids("wbap"), vec!["a"]);
+        // Message content
+        assert_eq!(ids("cargo"), vec!["b"]);
+        assert!(ids("zzz").is_empty());
+        // Substring path matches rank before looser fuzzy matches
+        assert_eq!(ids("api")[0], "b");
+    }
+
+    #[test]
+    fn test_fuzzy_score_prefers_tight_matches() {
+        assert_eq!(fuzzy_score("web-app", "web"), Some(3));
+        assert!(fuzzy_score("w-e-b", "web").unwrap() > 3);
+        assert_eq!(fuzzy_score("abc", "abd"), None);
+    }
+
+    #[test]
+    fn test_export_import_json_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = test_manager(&temp.path().join("a"), "source");
+        source.add_message("user", "list files").unwrap();
+        source.add_message("assistant", "ls -la").unwrap();
+
+        let export_path = temp.path().join("session.json");
+        assert_eq!(source.export(&export_path).unwrap(), 2);
+
+        let target = test_manager(&temp.path().join("b"), "target");
+        assert_eq!(target.import(&export_path).unwrap(), 2);
+
+        let original = source.storage.load("source").unwrap().unwrap();
+        let restored = target.storage.load("target").unwrap().unwrap();
+        assert_eq!(restored.messages.len(), original.messages.len());
+        for (a, b) in original.messages.iter().zip(&restored.messages) {
+            assert_eq!(a.role, b.role);
+            assert_eq!(a.content, b.content);
+            assert_eq!(a.timestamp, b.timestamp);
+        }
+    }
+
+    #[test]
+    fn test_context_file_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("threads").join("thread.json");
+        let config = Config::default();
+
+        let manager = ContextManager::with_file(&config, 0, &path).unwrap();
+        manager.add_message("user", "list files").unwrap();
+        manager.add_message("assistant", "ls -la").unwrap();
+        assert!(path.exists());
+
+        let reopened = ContextManager::with_file(&config, 0, &path).unwrap();
+        let messages = reopened.get_messages().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].content, "ls -la");
+
+        let saved: ContextEntry =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.messages.len(), 2);
+
+        reopened.clear_current().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_context_file_ttl_cleanup() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("thread.json");
+        let mut stale = entry("old", "/tmp", "hello");
+        stale.last_used = Utc::now() - chrono::Duration::minutes(90);
+        std::fs::write(&path, serde_json::to_string(&stale).unwrap()).unwrap();
+
+        let manager = ContextManager::with_file(&Config::default(), 30, &path).unwrap();
+        assert!(!path.exists());
+        assert!(manager.get_messages().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_export_markdown_transcript() {
+        let temp = tempfile::tempdir().unwrap();
+        let manager = test_manager(&temp.path().join("ctx"), "md");
+        manager.add_message("user", "how do I list files?").unwrap();
+        manager.add_message("assistant", "ls -la").unwrap();
+        manager
+            .add_message("assistant", "Kubernetes orchestrates containers.")
+            .unwrap();
+
+        let export_path = temp.path().join("session.md");
+        manager.export(&export_path).unwrap();
+        let markdown = std::fs::read_to_string(&export_path).unwrap();
+
+        assert!(markdown.starts_with("# ask session"));
+        assert!(markdown.contains("### User ("));
+        assert!(markdown.contains("how do I list files?"));
+        assert!(markdown.contains("### Assistant ("));
+        assert!(markdown.contains("```sh\nls -la\n```"));
+        assert!(!markdown.contains("```sh\nKubernetes"));
+    }
+
+    #[test]
+    fn test_export_without_context_fails() {
+        let temp = tempfile::tempdir().unwrap();
+        let manager = test_manager(&temp.path().join("ctx"), "missing");
+        assert!(manager.export(&temp.path().join("out.md")).is_err());
+    }
+
+    #[test]
+    fn test_rolling_summary_keeps_trimmed_messages() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut manager = test_manager(temp.path(), "rolling");
+        manager.max_messages = 2;
+        manager.rolling_summary = true;
+
+        manager.add_message("user", "set up nginx").unwrap();
+        manager
+            .add_message("assistant", "apt install\n  nginx")
+            .unwrap();
+        manager.add_message("user", "and TLS?").unwrap();
+        manager.add_message("assistant", "certbot --nginx").unwrap();
+
+        let entry = manager.storage.load("rolling").unwrap().unwrap();
+        assert_eq!(entry.messages.len(), 2);
+        assert_eq!(
+            entry.summary.as_deref(),
+            Some("- user: set up nginx\n- assistant: apt install nginx")
+        );
+
+        let messages = manager.get_messages().unwrap();
+        let turns: Vec<(&str, &str)> = messages
+            .iter()
+            .map(|m| (m.role.as_str(), m.content.as_str()))
+            .collect();
+        assert_eq!(
+            turns,
+            vec![
+                (
+                    "system",
+                    "Summary of the earlier conversation:\n- user: set up nginx\n- assistant: apt install nginx"
+                ),
+                ("user", "and TLS?"),
+                ("assistant", "certbot --nginx"),
+            ]
+        );
+
+        manager.rolling_summary = false;
+        assert_eq!(manager.get_messages().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_trim_without_rolling_summary_drops_messages() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut manager = test_manager(temp.path(), "plain");
+        manager.max_messages = 1;
+
+        manager.add_message("user", "first").unwrap();
+        manager.add_message("user", "second").unwrap();
+
+        let entry = manager.storage.load("plain").unwrap().unwrap();
+        assert!(entry.summary.is_none());
+        assert_eq!(manager.get_messages().unwrap()[0].content, "second");
+    }
+
+    #[test]
+    fn test_fold_into_summary_caps_length() {
+        let long = StoredMessage {
+            role: "user".to_string(),
+            content: "x".repeat(1000),
+            timestamp: Utc::now(),
+            tool_call_id: None,
+            name: None,
+        };
+        let mut summary = None;
+        for _ in 0..50 {
+            summary = fold_into_summary(summary, std::slice::from_ref(&long));
+        }
+
+        let summary = summary.unwrap();
+        assert!(summary.chars().count() <= SUMMARY_MAX_CHARS);
+        assert!(summary.lines().all(|l| l.starts_with("- user: ")));
+        assert_eq!(fold_into_summary(None, &[]), None);
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_json() {
+        let temp = tempfile::tempdir().unwrap();
+        let manager = test_manager(&temp.path().join("ctx"), "bad");
+        let path = temp.path().join("bad.json");
+        std::fs::write(&path, "not json").unwrap();
+        assert!(manager.import(&path).is_err());
+    }
+}
@@ -3,14 +3,20 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// A stored context entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContextEntry {
     pub id: String,
     pub pwd: String,
+    /// Named session (`--session`/`--fork`); None for the directory's main context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
     pub messages: Vec<StoredMessage>,
+    /// Rolling summary of messages trimmed away (`context.rolling_summary`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
     pub created_at: DateTime<Utc>,
     pub last_used: DateTime<Utc>,
 }
@@ -21,22 +27,48 @@ pub struct StoredMessage {
     pub role: String,
     pub content: String,
     pub timestamp: DateTime<Utc>,
+    /// Tool call id and tool name, kept for `tool` messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 /// Context storage backend
 pub struct ContextStorage {
     storage_path: PathBuf,
+    /// Set by `--context-file`: every ID maps to this one file
+    single_file: Option<PathBuf>,
 }
 
 impl ContextStorage {
     pub fn new(storage_path: PathBuf) -> Result<Self> {
         std::fs::create_dir_all(&storage_path)?;
-        Ok(Self { storage_path })
+        Ok(Self {
+            storage_path,
+            single_file: None,
+        })
+    }
+
+    /// Storage backed by a single context file instead of the storage directory
+    pub fn single_file(path: &Path) -> Result<Self> {
+        let parent = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        std::fs::create_dir_all(parent)?;
+        Ok(Self {
+            storage_path: parent.to_path_buf(),
+            single_file: Some(path.to_path_buf()),
+        })
     }
 
     /// Get the file path for a context ID
     fn context_file(&self, id: &str) -> PathBuf {
-        self.storage_path.join(format!("{}.json", id))
+        match self.single_file {
+            Some(ref path) => path.clone(),
+            None => self.storage_path.join(format!("{}.json", id)),
+        }
     }
 
     /// Load a context by ID
@@ -70,6 +102,10 @@ impl ContextStorage {
 
     /// List all contexts
     pub fn list(&self) -> Result<Vec<ContextEntry>> {
+        if self.single_file.is_some() {
+            return Ok(self.load("")?.into_iter().collect());
+        }
+
         let mut entries = Vec::new();
 
         for entry in std::fs::read_dir(&self.storage_path)? {
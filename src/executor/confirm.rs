@@ -0,0 +1,101 @@
+//! Yes/no confirmation before running a suggested command
+
+use crate::config::{BehaviorConfig, ConfirmDefault};
+use colored::Colorize;
+
+/// How the run confirmation behaves (`behavior.confirm_default`, `behavior.confirm_full_word`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConfirmPolicy {
+    /// Answer given by pressing Enter
+    pub default: ConfirmDefault,
+    /// Destructive commands only run after typing `yes` in full
+    pub full_word: bool,
+}
+
+impl ConfirmPolicy {
+    pub fn from_behavior(behavior: &BehaviorConfig) -> Self {
+        Self {
+            default: behavior.confirm_default,
+            full_word: behavior.confirm_full_word,
+        }
+    }
+
+    /// Whether `y` is not enough for this command
+    pub fn requires_full_word(&self, destructive: bool) -> bool {
+        destructive && self.full_word
+    }
+
+    /// Answer for an empty reply; a full-word prompt never defaults to yes
+    pub fn default_answer(&self, destructive: bool) -> bool {
+        !self.requires_full_word(destructive) && self.default == ConfirmDefault::Yes
+    }
+
+    /// Ask before running `command`; a failed prompt counts as no
+    pub fn confirm(&self, message: &str, destructive: bool) -> bool {
+        if self.requires_full_word(destructive) {
+            let question = requestty::Question::input("confirm_full_word")
+                .message(format!("{} {}", message, "Type 'yes' to confirm:".yellow()))
+                .build();
+            return requestty::prompt_one(question)
+                .ok()
+                .and_then(|answer| answer.as_string().map(is_full_yes))
+                .unwrap_or(false);
+        }
+
+        let question = requestty::Question::confirm("confirm_run")
+            .message(message)
+            .default(self.default_answer(destructive))
+            .build();
+        requestty::prompt_one(question)
+            .ok()
+            .and_then(|answer| answer.as_bool())
+            .unwrap_or(false)
+    }
+}
+
+/// Only the whole word `yes` (any case) accepts; `y`, `ye` or `yes please` do not
+pub fn is_full_yes(reply: &str) -> bool {
+    reply.trim().eq_ignore_ascii_case("yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_answer_follows_config() {
+        let no = ConfirmPolicy::default();
+        assert!(!no.default_answer(false));
+        assert!(!no.default_answer(true));
+
+        let yes = ConfirmPolicy {
+            default: ConfirmDefault::Yes,
+            full_word: false,
+        };
+        assert!(yes.default_answer(false));
+        assert!(yes.default_answer(true));
+    }
+
+    #[test]
+    fn test_full_word_only_for_destructive_commands() {
+        let policy = ConfirmPolicy {
+            default: ConfirmDefault::Yes,
+            full_word: true,
+        };
+        assert!(policy.requires_full_word(true));
+        assert!(!policy.requires_full_word(false));
+
+        assert!(!policy.default_answer(true), "Enter never runs it");
+        assert!(policy.default_answer(false));
+    }
+
+    #[test]
+    fn test_is_full_yes() {
+        assert!(is_full_yes("yes"));
+        assert!(is_full_yes(" YES\n"));
+        assert!(!is_full_yes("y"));
+        assert!(!is_full_yes("ye"));
+        assert!(!is_full_yes("yes please"));
+        assert!(!is_full_yes(""));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::providers::ShellFamily;
 use anyhow::Result;
 use std::process::Command;
 
@@ -50,6 +51,68 @@ pub fn detect_injection_method() -> InjectionMethod {
     InjectionMethod::Fallback
 }
 
+/// Put text on the system clipboard (`--copy`).
+///
+/// On Linux the clipboard belongs to the process that set it and is gone when
+/// that process exits, so a `--hold-clipboard` background process keeps serving
+/// it until something else is copied.
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::io::Write;
+        use std::os::unix::process::CommandExt;
+        use std::process::Stdio;
+
+        // Fail here, not silently in the background, when there is no clipboard
+        drop(arboard::Clipboard::new().map_err(|e| anyhow::anyhow!("{}", e))?);
+        let mut child = Command::new(std::env::current_exe()?)
+            .arg("--hold-clipboard")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            // Out of the terminal's job, so closing the shell doesn't take the clipboard along
+            .process_group(0)
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let mut clipboard = arboard::Clipboard::new().map_err(|e| anyhow::anyhow!("{}", e))?;
+        clipboard
+            .set_text(text)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    }
+}
+
+/// `--hold-clipboard`: set the clipboard to stdin and serve it until it is replaced
+pub fn hold_clipboard() -> Result<()> {
+    use std::io::Read;
+
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text)?;
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    #[cfg(target_os = "linux")]
+    {
+        use arboard::SetExtLinux;
+        clipboard
+            .set()
+            .wait()
+            .text(text)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        clipboard
+            .set_text(text)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    }
+}
+
 /// Save current clipboard content
 fn save_clipboard() -> Option<String> {
     arboard::Clipboard::new()
@@ -57,15 +120,25 @@ fn save_clipboard() -> Option<String> {
         .and_then(|mut cb| cb.get_text().ok())
 }
 
-/// Restore clipboard content after a delay (spawns a thread)
-fn restore_clipboard_delayed(previous: Option<String>, delay_ms: u64) {
-    if let Some(text) = previous {
-        std::thread::spawn(move || {
-            std::thread::sleep(std::time::Duration::from_millis(delay_ms));
-            if let Ok(mut cb) = arboard::Clipboard::new() {
-                let _ = cb.set_text(&text);
-            }
-        });
+/// Longest `behavior.clipboard_restore_ms` honoured, so the paste process never lingers
+const MAX_CLIPBOARD_RESTORE_MS: u64 = 10_000;
+
+/// How long to wait after pasting before restoring the clipboard (None = never restore)
+pub fn clipboard_restore_delay(ms: u64) -> Option<std::time::Duration> {
+    (ms > 0).then(|| std::time::Duration::from_millis(ms.min(MAX_CLIPBOARD_RESTORE_MS)))
+}
+
+/// Restore clipboard content once the paste has landed.
+///
+/// This blocks on purpose: it runs in the `--inject-raw` background process, and a
+/// detached thread would die with that process before the clipboard was put back.
+fn restore_clipboard_after(previous: Option<String>, delay: Option<std::time::Duration>) {
+    let (Some(text), Some(delay)) = (previous, delay) else {
+        return;
+    };
+    std::thread::sleep(delay);
+    if let Ok(mut cb) = arboard::Clipboard::new() {
+        let _ = cb.set_text(&text);
     }
 }
 
@@ -80,7 +153,7 @@ fn can_use_accessibility() -> bool {
 }
 
 #[cfg(target_os = "linux")]
-fn try_clipboard_paste(command: &str) -> Result<()> {
+fn try_clipboard_paste(command: &str, restore: Option<std::time::Duration>) -> Result<()> {
     use mouse_keyboard_input::key_codes::*;
     use mouse_keyboard_input::VirtualDevice;
     use std::thread;
@@ -119,14 +192,14 @@ fn try_clipboard_paste(command: &str) -> Result<()> {
         .release(KEY_LEFTCTRL)
         .map_err(|e| anyhow::anyhow!("{}", e))?;
 
-    // Restore clipboard after delay
-    restore_clipboard_delayed(previous_clipboard, 500);
+    // Restore clipboard once the paste has landed
+    restore_clipboard_after(previous_clipboard, restore);
 
     Ok(())
 }
 
 #[cfg(target_os = "macos")]
-fn try_clipboard_paste(command: &str) -> Result<()> {
+fn try_clipboard_paste(command: &str, restore: Option<std::time::Duration>) -> Result<()> {
     use enigo::{Direction, Enigo, Key, Keyboard, Settings};
     use std::thread;
     use std::time::Duration;
@@ -160,14 +233,14 @@ fn try_clipboard_paste(command: &str) -> Result<()> {
         .key(Key::Meta, Direction::Release)
         .map_err(|e| anyhow::anyhow!("{}", e))?;
 
-    // Restore clipboard after delay
-    restore_clipboard_delayed(previous_clipboard, 500);
+    // Restore clipboard once the paste has landed
+    restore_clipboard_after(previous_clipboard, restore);
 
     Ok(())
 }
 
 #[cfg(target_os = "windows")]
-fn try_clipboard_paste(command: &str) -> Result<()> {
+fn try_clipboard_paste(command: &str, restore: Option<std::time::Duration>) -> Result<()> {
     use enigo::{Direction, Enigo, Key, Keyboard, Settings};
     use std::thread;
     use std::time::Duration;
@@ -201,35 +274,135 @@ fn try_clipboard_paste(command: &str) -> Result<()> {
         .key(Key::Control, Direction::Release)
         .map_err(|e| anyhow::anyhow!("{}", e))?;
 
-    // Restore clipboard after delay
-    restore_clipboard_delayed(previous_clipboard, 500);
+    // Restore clipboard once the paste has landed
+    restore_clipboard_after(previous_clipboard, restore);
 
     Ok(())
 }
 
-fn interactive_prompt(command: &str) -> Result<Option<String>> {
+/// Joins chained commands for the user's shell (`&&`, fish `; and`, nushell `;`)
+fn chain_separator() -> &'static str {
+    ShellFamily::from_env().separator()
+}
+
+/// Split a chain joined with `separator` into one command per line, ignoring separators inside quotes
+fn split_command_chain(command: &str, separator: &str) -> Vec<String> {
+    let token = separator.trim();
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut rest = command;
+
+    while let Some(c) = rest.chars().next() {
+        if quote.is_none() && rest.starts_with(token) {
+            parts.push(current.trim().to_string());
+            current.clear();
+            rest = &rest[token.len()..];
+            continue;
+        }
+        match c {
+            '\'' | '"' if quote.is_none() => quote = Some(c),
+            q if quote == Some(q) => quote = None,
+            _ => {}
+        }
+        current.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    parts.push(current.trim().to_string());
+
+    parts.into_iter().filter(|p| !p.is_empty()).collect()
+}
+
+/// Rejoin edited lines into a single chain, dropping blank lines
+fn join_command_chain(lines: &str, separator: &str) -> String {
+    lines
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn non_empty(cmd: String) -> Option<String> {
+    if cmd.trim().is_empty() {
+        None
+    } else {
+        Some(cmd)
+    }
+}
+
+/// Edit the command in $EDITOR, one chained command per line
+fn editor_prompt(command: &str) -> Result<Option<String>> {
+    use requestty::Question;
+
+    let mut content = split_command_chain(command, chain_separator()).join("\n");
+    content.push('\n');
+
+    let question = Question::editor("command")
+        .message("Command (opens $EDITOR, one command per line)")
+        .default(content)
+        .extension(".sh")
+        .build();
+
+    match requestty::prompt_one(question) {
+        Ok(answer) => Ok(non_empty(join_command_chain(
+            answer.as_string().unwrap_or_default(),
+            chain_separator(),
+        ))),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Edit an `&&` chain one step at a time; clearing a step removes it
+fn chain_prompt(steps: &[String]) -> Result<Option<String>> {
     use requestty::Question;
 
+    let mut edited = Vec::with_capacity(steps.len());
+    for (i, step) in steps.iter().enumerate() {
+        let question = Question::input(format!("step_{}", i))
+            .message(format!("Step {}/{}", i + 1, steps.len()))
+            .default(step.as_str())
+            .build();
+
+        match requestty::prompt_one(question) {
+            Ok(answer) => edited.push(answer.as_string().unwrap_or_default().to_string()),
+            Err(_) => return Ok(None),
+        }
+    }
+
+    Ok(non_empty(join_command_chain(
+        &edited.join("\n"),
+        chain_separator(),
+    )))
+}
+
+fn interactive_prompt(command: &str, edit_in_editor: bool) -> Result<Option<String>> {
+    use requestty::Question;
+
+    if edit_in_editor {
+        return editor_prompt(command);
+    }
+
+    let steps = split_command_chain(command, chain_separator());
+    if steps.len() > 1 {
+        return chain_prompt(&steps);
+    }
+
     let question = Question::input("command")
         .message("Command")
         .default(command)
         .build();
 
     match requestty::prompt_one(question) {
-        Ok(answer) => {
-            let cmd = answer.as_string().unwrap_or_default();
-            if cmd.is_empty() {
-                Ok(None)
-            } else {
-                Ok(Some(cmd.to_string()))
-            }
-        }
+        Ok(answer) => Ok(non_empty(
+            answer.as_string().unwrap_or_default().to_string(),
+        )),
         Err(_) => Ok(None),
     }
 }
 
 /// Try to inject command using tmux send-keys
-fn try_tmux_inject(command: &str) -> Result<Option<String>> {
+fn try_tmux_inject(command: &str, edit_in_editor: bool) -> Result<Option<String>> {
     // Use send-keys -l for literal text (no key name interpretation)
     let status = Command::new("tmux")
         .args(["send-keys", "-l", "--", command])
@@ -242,17 +415,17 @@ fn try_tmux_inject(command: &str) -> Result<Option<String>> {
         Ok(s) if s.success() => Ok(None),
         Ok(_) => {
             // tmux failed, fall back to enhanced fallback
-            enhanced_fallback(command)
+            enhanced_fallback(command, edit_in_editor)
         }
         Err(_) => {
             // tmux not available, fall back
-            enhanced_fallback(command)
+            enhanced_fallback(command, edit_in_editor)
         }
     }
 }
 
 /// Try to inject command using GNU screen
-fn try_screen_inject(command: &str) -> Result<Option<String>> {
+fn try_screen_inject(command: &str, edit_in_editor: bool) -> Result<Option<String>> {
     // screen -X stuff sends literal characters to the current window
     let status = Command::new("screen")
         .args(["-X", "stuff", command])
@@ -265,21 +438,20 @@ fn try_screen_inject(command: &str) -> Result<Option<String>> {
         Ok(s) if s.success() => Ok(None),
         Ok(_) => {
             // screen failed, fall back to enhanced fallback
-            enhanced_fallback(command)
+            enhanced_fallback(command, edit_in_editor)
         }
         Err(_) => {
             // screen not available, fall back
-            enhanced_fallback(command)
+            enhanced_fallback(command, edit_in_editor)
         }
     }
 }
 
 /// Enhanced fallback for headless terminals: print command with visual highlight, then prompt
-fn enhanced_fallback(command: &str) -> Result<Option<String>> {
+fn enhanced_fallback(command: &str, edit_in_editor: bool) -> Result<Option<String>> {
     use std::io::{self, Write};
 
-    // Check if terminal supports colors (not "dumb")
-    let use_colors = std::env::var("TERM").map(|t| t != "dumb").unwrap_or(true);
+    let use_colors = crate::output::supports_ansi();
 
     let stdout = io::stdout();
     let mut handle = stdout.lock();
@@ -320,11 +492,43 @@ fn enhanced_fallback(command: &str) -> Result<Option<String>> {
     handle.flush()?;
 
     // Now show the interactive prompt
-    interactive_prompt(command)
+    interactive_prompt(command, edit_in_editor)
+}
+
+/// Ask in the terminal before a GUI paste (`--safe-paste`, `behavior.confirm_paste`),
+/// since the keystrokes land in whatever window has focus. Only a bare Enter confirms;
+/// Esc, any other input or end of input cancels.
+fn confirm_gui_paste(
+    input: &mut impl std::io::BufRead,
+    out: &mut impl std::io::Write,
+) -> Result<bool> {
+    write!(
+        out,
+        "About to paste into the active window — press Enter to confirm, Esc to cancel "
+    )?;
+    out.flush()?;
+
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        writeln!(out)?;
+        return Ok(false);
+    }
+    let confirmed = answer.trim_end_matches(['\r', '\n']).is_empty();
+    if !confirmed {
+        writeln!(out, "Paste cancelled")?;
+    }
+    Ok(confirmed)
 }
 
 /// Try GUI paste injection (spawns background process)
-fn try_gui_paste_inject(command: &str) -> Result<Option<String>> {
+fn try_gui_paste_inject(
+    command: &str,
+    edit_in_editor: bool,
+    confirm_paste: bool,
+) -> Result<Option<String>> {
+    if confirm_paste && !confirm_gui_paste(&mut std::io::stdin().lock(), &mut std::io::stderr())? {
+        return Ok(None);
+    }
     if let Ok(exe) = std::env::current_exe() {
         use std::process::Stdio;
         let child = Command::new(exe)
@@ -340,35 +544,52 @@ fn try_gui_paste_inject(command: &str) -> Result<Option<String>> {
         }
     }
     // If spawn failed, use enhanced fallback
-    enhanced_fallback(command)
+    enhanced_fallback(command, edit_in_editor)
 }
 
-pub fn inject_raw_only(command: &str) -> Result<()> {
-    let clean_command = command.replace('\n', " && ").replace('\r', "");
+/// Paste `command` and put the previous clipboard back after `restore_ms` (0 = keep the command)
+pub fn inject_raw_only(command: &str, restore_ms: u64) -> Result<()> {
+    let clean_command = join_command_chain(&command.replace('\r', ""), chain_separator());
 
     // For raw injection, we only support GUI paste (used by background process)
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
     {
-        try_clipboard_paste(&clean_command)
+        try_clipboard_paste(&clean_command, clipboard_restore_delay(restore_ms))
     }
 
     #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
     {
+        let _ = restore_ms;
         anyhow::bail!("Unsupported platform for raw injection")
     }
 }
 
-pub fn inject_command(command: &str) -> Result<Option<String>> {
-    let clean_command = command.replace('\n', " && ").replace('\r', "");
+/// Inject the command for editing. When the user edits it in a prompt, returns the edited command.
+/// `edit_in_editor` (`behavior.edit_in_editor`) edits through $EDITOR instead of the inline prompt;
+/// `confirm_paste` asks before a GUI paste.
+pub fn inject_command(
+    command: &str,
+    edit_in_editor: bool,
+    confirm_paste: bool,
+) -> Result<Option<String>> {
+    let clean_command = join_command_chain(&command.replace('\r', ""), chain_separator());
 
     match detect_injection_method() {
-        InjectionMethod::TmuxSendKeys => try_tmux_inject(&clean_command),
-        InjectionMethod::ScreenStuff => try_screen_inject(&clean_command),
-        InjectionMethod::GuiPaste => try_gui_paste_inject(&clean_command),
-        InjectionMethod::Fallback => enhanced_fallback(&clean_command),
+        InjectionMethod::TmuxSendKeys => try_tmux_inject(&clean_command, edit_in_editor),
+        InjectionMethod::ScreenStuff => try_screen_inject(&clean_command, edit_in_editor),
+        InjectionMethod::GuiPaste => {
+            try_gui_paste_inject(&clean_command, edit_in_editor, confirm_paste)
+        }
+        InjectionMethod::Fallback => enhanced_fallback(&clean_command, edit_in_editor),
     }
 }
 
+/// Editable prompt without trying to paste (`behavior.inject = "prompt"`)
+pub fn prompt_command(command: &str, edit_in_editor: bool) -> Result<Option<String>> {
+    let clean_command = join_command_chain(&command.replace('\r', ""), chain_separator());
+    enhanced_fallback(&clean_command, edit_in_editor)
+}
+
 pub fn can_inject() -> bool {
     // We can always "inject" now - either via GUI, tmux/screen, or enhanced fallback
     // This function now indicates if automatic injection (without user interaction) is possible
@@ -378,8 +599,87 @@ pub fn can_inject() -> bool {
     )
 }
 
-/// Returns true if injection happens asynchronously (background process)
-/// This is used to decide whether to print the command before injecting
-pub fn is_async_injection() -> bool {
-    matches!(detect_injection_method(), InjectionMethod::GuiPaste)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clipboard_restore_delay() {
+        assert_eq!(clipboard_restore_delay(0), None);
+        assert_eq!(
+            clipboard_restore_delay(500),
+            Some(std::time::Duration::from_millis(500))
+        );
+        assert_eq!(
+            clipboard_restore_delay(u64::MAX),
+            Some(std::time::Duration::from_millis(MAX_CLIPBOARD_RESTORE_MS))
+        );
+    }
+
+    #[test]
+    fn test_restore_without_previous_or_delay_returns_at_once() {
+        let start = std::time::Instant::now();
+        restore_clipboard_after(None, Some(std::time::Duration::from_secs(5)));
+        restore_clipboard_after(Some("old".to_string()), None);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_confirm_gui_paste() {
+        let confirm = |input: &str| {
+            let mut out = Vec::new();
+            let answer = confirm_gui_paste(&mut input.as_bytes(), &mut out).unwrap();
+            (answer, String::from_utf8(out).unwrap())
+        };
+
+        let (answer, shown) = confirm("\n");
+        assert!(answer, "Enter confirms");
+        assert!(shown.starts_with("About to paste into the active window"));
+        assert!(confirm("\r\n").0);
+
+        let (answer, shown) = confirm("\x1b\n");
+        assert!(!answer, "Esc cancels");
+        assert!(shown.contains("Paste cancelled"));
+        assert!(!confirm("n\n").0);
+        assert!(!confirm("").0, "end of input cancels");
+    }
+
+    #[test]
+    fn test_command_chain_round_trip() {
+        let command = "mkdir -p build && cd build && cmake .. && make -j4";
+        let lines = split_command_chain(command, " && ");
+        assert_eq!(
+            lines,
+            vec!["mkdir -p build", "cd build", "cmake ..", "make -j4"]
+        );
+        assert_eq!(join_command_chain(&lines.join("\n"), " && "), command);
+    }
+
+    #[test]
+    fn test_command_chain_for_fish_and_nushell() {
+        let fish = "mkdir build; and cd build; and echo 'a; and b'";
+        let lines = split_command_chain(fish, "; and ");
+        assert_eq!(lines, vec!["mkdir build", "cd build", "echo 'a; and b'"]);
+        assert_eq!(join_command_chain(&lines.join("\n"), "; and "), fish);
+        assert_eq!(join_command_chain("ls\ncd src\n", "; "), "ls; cd src");
+    }
+
+    #[test]
+    fn test_split_command_chain_respects_quotes() {
+        let command = r#"echo "a && b" && grep 'x&&y' file"#;
+        assert_eq!(
+            split_command_chain(command, " && "),
+            vec![r#"echo "a && b""#, "grep 'x&&y' file"]
+        );
+        assert_eq!(split_command_chain("ls -la", " && "), vec!["ls -la"]);
+    }
+
+    #[test]
+    fn test_join_command_chain_drops_blank_lines() {
+        assert_eq!(
+            join_command_chain("  apt update \n\n apt upgrade -y\n", " && "),
+            "apt update && apt upgrade -y"
+        );
+        assert_eq!(join_command_chain("\n  \n", " && "), "");
+    }
 }
@@ -1,9 +1,11 @@
 //! Command executor module - handles safe command execution
 
+mod confirm;
 mod injector;
 mod runner;
 mod safety;
 
+pub use confirm::*;
 pub use injector::*;
 pub use runner::*;
 pub use safety::*;
@@ -1,19 +1,83 @@
 //! Command execution with output capture
 
-use super::SafetyAnalyzer;
-use crate::config::Config;
+use super::{ConfirmPolicy, SafetyAnalyzer};
+use crate::config::{Config, HooksConfig};
+use crate::output::theme;
+use crate::providers::ShellFamily;
 use anyhow::Result;
 use colored::Colorize;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Duration;
 use tokio::io::AsyncReadExt;
-use tokio::process::Command;
+use tokio::process::{Child, Command};
+
+/// Exit code reported when a command exceeds its time limit (same as timeout(1))
+const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// PID of the command being run and whether it leads its own process group,
+/// so an interrupt can stop it before ask exits
+static RUNNING_COMMAND: Mutex<Option<(u32, bool)>> = Mutex::new(None);
+
+/// Clears `RUNNING_COMMAND` however `execute` returns
+struct RunningGuard;
+
+impl RunningGuard {
+    fn register(child: &Child, own_group: bool) -> Self {
+        if let Ok(mut running) = RUNNING_COMMAND.lock() {
+            *running = child.id().map(|pid| (pid, own_group));
+        }
+        Self
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        if let Ok(mut running) = RUNNING_COMMAND.lock() {
+            *running = None;
+        }
+    }
+}
+
+/// Terminate the command being run, if any, so it does not outlive ask after
+/// Ctrl-C. A command in the terminal's group got the SIGINT already; one in a
+/// group of its own (or an interrupt sent with kill) would otherwise be orphaned.
+pub fn stop_running_command() {
+    let Some((pid, own_group)) = RUNNING_COMMAND.lock().ok().and_then(|mut r| r.take()) else {
+        return;
+    };
+    #[cfg(unix)]
+    {
+        let target = if own_group {
+            format!("-{}", pid)
+        } else {
+            pid.to_string()
+        };
+        let _ = std::process::Command::new("kill")
+            .args(["-TERM", "--", &target])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+    #[cfg(not(unix))]
+    let _ = (pid, own_group);
+}
 
 /// Command executor with safety checks
 pub struct CommandExecutor {
     analyzer: SafetyAnalyzer,
     #[allow(dead_code)]
     confirm_destructive: bool,
+    confirm_policy: ConfirmPolicy,
+    exec_timeout: Option<Duration>,
+    hooks: HooksConfig,
+    /// End of the last command's stderr, kept for `--explain-error`
+    last_stderr: Mutex<String>,
+    /// How many bytes of stderr to keep (0 = none)
+    stderr_tail: usize,
+    /// Interactive children (sudo, less, read) must stay in the terminal's foreground group
+    stdin_is_terminal: bool,
 }
 
 impl CommandExecutor {
@@ -21,7 +85,14 @@ impl CommandExecutor {
         Self {
             analyzer: SafetyAnalyzer::new(),
             confirm_destructive: config.behavior.confirm_destructive,
+            confirm_policy: ConfirmPolicy::from_behavior(&config.behavior),
+            exec_timeout: None,
+            hooks: config.hooks.clone(),
+            last_stderr: Mutex::new(String::new()),
+            stderr_tail: 0,
+            stdin_is_terminal: std::io::stdin().is_terminal(),
         }
+        .with_timeout(config.behavior.exec_timeout_secs)
     }
 
     /// Check if command is safe for auto-execution
@@ -34,26 +105,102 @@ impl CommandExecutor {
         self.analyzer.is_destructive(command)
     }
 
+    /// Ask before running `command`, typing `yes` in full when the policy
+    /// requires it for destructive commands
+    pub fn confirm(&self, message: &str, command: &str) -> bool {
+        self.confirm_policy
+            .confirm(message, self.is_destructive(command))
+    }
+
+    /// The end of what the last executed command wrote to stderr, when captured
+    pub fn last_stderr(&self) -> String {
+        self.last_stderr.lock().unwrap().clone()
+    }
+
+    /// Keep the last `bytes` of each command's stderr for `last_stderr` (0 = keep nothing)
+    pub fn with_stderr_tail(mut self, bytes: usize) -> Self {
+        self.stderr_tail = bytes;
+        self
+    }
+
+    /// Limit execution time (0 = no limit)
+    pub fn with_timeout(mut self, secs: u64) -> Self {
+        self.exec_timeout = if secs > 0 {
+            Some(Duration::from_secs(secs))
+        } else {
+            None
+        };
+        self
+    }
+
     /// Execute a command with optional output following
     pub async fn execute(&self, command: &str, follow: bool) -> Result<i32> {
         println!("{}", "Executing...".cyan());
+        self.last_stderr.lock().unwrap().clear();
 
-        // Determine shell
-        let shell = if cfg!(windows) { "cmd" } else { "sh" };
-        let shell_arg = if cfg!(windows) { "/C" } else { "-c" };
+        // The shell the answer was written for: sh (cmd on Windows), fish or nu
+        let (shell, shell_arg) = ShellFamily::from_env().exec_shell();
 
-        let mut child = Command::new(shell)
-            .arg(shell_arg)
+        let mut cmd = Command::new(shell);
+        cmd.arg(shell_arg)
             .arg(command)
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
+            .stderr(Stdio::piped());
 
-        let exit_code = if follow {
-            // Stream output in real-time
-            let mut stdout = child.stdout.take().unwrap();
-            let mut stderr = child.stderr.take().unwrap();
+        // With a time limit and no terminal to read from, run in a process group of its
+        // own so the timeout can kill the whole tree. Otherwise stay in the terminal's
+        // foreground group: prompts keep working and Ctrl-C reaches the command.
+        let own_group = self.exec_timeout.is_some() && !self.stdin_is_terminal;
+        #[cfg(unix)]
+        if own_group {
+            cmd.process_group(0);
+        }
+
+        let mut child = cmd.spawn()?;
+        let _running = RunningGuard::register(&child, own_group);
 
+        let (exit_code, stderr) = match self.exec_timeout {
+            Some(limit) => {
+                let wait = Self::wait_for_child(&mut child, follow, self.stderr_tail);
+                match tokio::time::timeout(limit, wait).await {
+                    Ok(result) => result?,
+                    Err(_) => {
+                        kill_child(&mut child, own_group).await;
+                        println!(
+                            "{}",
+                            theme().error(&format!("command timed out after {}s", limit.as_secs()))
+                        );
+                        return Ok(TIMEOUT_EXIT_CODE);
+                    }
+                }
+            }
+            None => Self::wait_for_child(&mut child, follow, self.stderr_tail).await?,
+        };
+        *self.last_stderr.lock().unwrap() = String::from_utf8_lossy(&stderr).into_owned();
+
+        // Show result
+        if exit_code == 0 {
+            println!("{}", "Done".green());
+        } else {
+            println!("{} (exit code: {})", theme().error("Failed"), exit_code);
+        }
+
+        Ok(exit_code)
+    }
+
+    /// Drain output (streaming it if following) and wait for the child to exit.
+    /// Returns the exit code and the last `tail` bytes the command wrote to stderr.
+    async fn wait_for_child(
+        child: &mut Child,
+        follow: bool,
+        tail: usize,
+    ) -> Result<(i32, Vec<u8>)> {
+        let mut stdout = child.stdout.take().unwrap();
+        let mut stderr = child.stderr.take().unwrap();
+        let mut captured = Vec::new();
+
+        if follow {
+            // Stream output in real-time
             let mut stdout_buf = [0u8; 1024];
             let mut stderr_buf = [0u8; 1024];
             let mut stdout_done = false;
@@ -70,7 +217,7 @@ impl CommandExecutor {
                                 std::io::stdout().flush().unwrap_or(());
                             }
                             Err(e) => {
-                                eprintln!("{}: {}", "Error".red(), e);
+                                eprintln!("{}: {}", theme().error("Error"), e);
                                 stdout_done = true;
                             }
                         }
@@ -79,41 +226,97 @@ impl CommandExecutor {
                         match res {
                             Ok(0) => stderr_done = true,
                             Ok(n) => {
-                                // We print stderr in red but without trailing newline if not present
+                                keep_tail(&mut captured, &stderr_buf[..n], tail);
+                                // We print stderr in the error color but without trailing newline if not present
                                 let text = String::from_utf8_lossy(&stderr_buf[..n]);
-                                eprint!("{}", text.red());
+                                eprint!("{}", theme().error(&text));
                                 std::io::stderr().flush().unwrap_or(());
                             }
                             Err(e) => {
-                                eprintln!("{}: {}", "Error".red(), e);
+                                eprintln!("{}: {}", theme().error("Error"), e);
                                 stderr_done = true;
                             }
                         }
                     }
                 }
             }
-
-            // Wait for process to complete
-            let status = child.wait().await?;
-            status.code().unwrap_or(1)
         } else {
-            // Just wait for completion
-            let output = child.wait_with_output().await?;
-            output.status.code().unwrap_or(1)
-        };
-
-        // Show result
-        if exit_code == 0 {
-            println!("{}", "Done".green());
-        } else {
-            println!("{} (exit code: {})", "Failed".red(), exit_code);
+            // Just drain the pipes so the child never blocks on a full buffer
+            let drain_stderr = async {
+                let mut buf = [0u8; 1024];
+                while let Ok(n @ 1..) = stderr.read(&mut buf).await {
+                    keep_tail(&mut captured, &buf[..n], tail);
+                }
+            };
+            let mut sink = tokio::io::sink();
+            let _ = tokio::join!(tokio::io::copy(&mut stdout, &mut sink), drain_stderr);
         }
 
-        Ok(exit_code)
+        // Wait for process to complete
+        let status = child.wait().await?;
+        Ok((status.code().unwrap_or(1), captured))
     }
 
-    /// Execute a command and suggest sudo retry on permission denied
+    /// Execute a command and suggest sudo retry on permission denied.
+    /// `[hooks]` run around it; `after_execute` sees the final exit code.
     pub async fn execute_with_sudo_retry(&self, command: &str, follow: bool) -> Result<i32> {
+        run_hook(
+            "before_execute",
+            self.hooks.before_execute.as_deref(),
+            command,
+            None,
+        )
+        .await;
+
+        let exit_code = self.execute_with_sudo_prompt(command, follow).await?;
+
+        run_hook(
+            "after_execute",
+            self.hooks.after_execute.as_deref(),
+            command,
+            Some(exit_code),
+        )
+        .await;
+
+        Ok(exit_code)
+    }
+
+    /// Run one command per line of a multi-line answer, in order, in a single
+    /// POSIX shell session so `cd`, `export` and variables carry over to later lines.
+    /// Stops at the first failure unless `keep_going`; hooks run once around the whole
+    /// script. Returns the exit code of the first failing line, 0 if all succeeded.
+    /// Only for POSIX shells: fish and nushell answers are run whole.
+    pub async fn execute_steps(
+        &self,
+        steps: &[String],
+        follow: bool,
+        keep_going: bool,
+    ) -> Result<i32> {
+        let command = steps.join("\n");
+        run_hook(
+            "before_execute",
+            self.hooks.before_execute.as_deref(),
+            &command,
+            None,
+        )
+        .await;
+
+        let exit_code = self
+            .execute(&step_script(steps, keep_going), follow)
+            .await?;
+
+        run_hook(
+            "after_execute",
+            self.hooks.after_execute.as_deref(),
+            &command,
+            Some(exit_code),
+        )
+        .await;
+
+        Ok(exit_code)
+    }
+
+    async fn execute_with_sudo_prompt(&self, command: &str, follow: bool) -> Result<i32> {
         let exit_code = self.execute(command, follow).await?;
 
         // Check if it looks like a permission error (common exit codes)
@@ -215,20 +418,9 @@ impl CommandExecutor {
                 "Warning:".yellow().bold(),
                 "This command may be destructive!".yellow()
             );
-            println!("{}", command.bright_white());
-
-            // Ask for confirmation
-            let confirm = {
-                let question = requestty::Question::confirm("execute_destructive")
-                    .message("Execute anyway?")
-                    .default(false)
-                    .build();
-                requestty::prompt_one(question)
-                    .map(|a| a.as_bool().unwrap_or(false))
-                    .unwrap_or(false)
-            };
+            println!("{}", theme().command(command));
 
-            if !confirm {
+            if !self.confirm("Execute anyway?", command) {
                 println!("{}", "Cancelled.".yellow());
                 return Ok(1);
             }
@@ -237,3 +429,347 @@ impl CommandExecutor {
         self.execute(command, follow).await
     }
 }
+
+/// A POSIX script running `steps` in order, each announced as `[i/n] step`. The
+/// status is checked on the line after each step, so a trailing `# comment` can't hide it.
+fn step_script(steps: &[String], keep_going: bool) -> String {
+    let mut script = String::from("__ask_first=0\n");
+    for (i, step) in steps.iter().enumerate() {
+        let remaining = steps.len() - i - 1;
+        script.push_str(&format!(
+            "printf '%s\\n' {}\n{}\n__ask_rc=$?\n",
+            shell_quote(&format!("[{}/{}] {}", i + 1, steps.len(), step)),
+            step
+        ));
+        if keep_going || remaining == 0 {
+            script.push_str(
+                "[ $__ask_rc -eq 0 ] || [ $__ask_first -ne 0 ] || __ask_first=$__ask_rc\n",
+            );
+        } else {
+            script.push_str(&format!(
+                "[ $__ask_rc -eq 0 ] || {{ printf '%s\\n' {}; exit $__ask_rc; }}\n",
+                shell_quote(&format!(
+                    "Stopped; {} remaining command(s) not run (--keep-going runs them anyway)",
+                    remaining
+                ))
+            ));
+        }
+    }
+    script.push_str("exit $__ask_first\n");
+    script
+}
+
+/// Single-quote for POSIX shells; embedded quotes become `'\''`
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Run a hook through the user's shell with `CMD` (and `EXIT`, after execution) in its
+/// environment. Returns the hook's exit code, or None when it is not configured or could
+/// not be started. Failures only warn: a hook never aborts the command it wraps.
+async fn run_hook(
+    name: &str,
+    hook: Option<&str>,
+    command: &str,
+    exit_code: Option<i32>,
+) -> Option<i32> {
+    let hook = hook.map(str::trim).filter(|h| !h.is_empty())?;
+
+    let (shell, shell_arg) = if cfg!(windows) {
+        ("cmd".to_string(), "/C")
+    } else {
+        (
+            std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string()),
+            "-c",
+        )
+    };
+
+    let mut cmd = Command::new(shell);
+    cmd.arg(shell_arg).arg(hook).env("CMD", command);
+    if let Some(code) = exit_code {
+        cmd.env("EXIT", code.to_string());
+    }
+
+    match cmd.status().await {
+        Ok(status) => {
+            let code = status.code().unwrap_or(1);
+            if code != 0 {
+                eprintln!(
+                    "{}",
+                    format!("warning: {} hook exited with code {}", name, code).yellow()
+                );
+            }
+            Some(code)
+        }
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("warning: could not run {} hook: {}", name, e).yellow()
+            );
+            None
+        }
+    }
+}
+
+/// Append `chunk`, then drop from the front so at most `max` bytes remain
+fn keep_tail(buf: &mut Vec<u8>, chunk: &[u8], max: usize) {
+    buf.extend_from_slice(chunk);
+    if buf.len() > max {
+        buf.drain(..buf.len() - max);
+    }
+}
+
+/// Kill the command, and its whole process group when it has one of its own, then reap it
+async fn kill_child(child: &mut Child, own_group: bool) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id().filter(|_| own_group) {
+        // Negative PID targets the process group created with process_group(0)
+        let _ = std::process::Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", pid)])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+
+    let _ = child.start_kill();
+    let _ = child.wait().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_timeout_kills_command() {
+        let executor = CommandExecutor::new(&Config::default()).with_timeout(1);
+
+        let start = Instant::now();
+        let exit_code = executor.execute("sleep 5", false).await.unwrap();
+
+        assert_eq!(exit_code, TIMEOUT_EXIT_CODE);
+        assert!(start.elapsed() < Duration::from_secs(3));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_terminal_commands_stay_in_foreground_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let pgid_file = dir.path().join("pgid");
+        let pgid_of = |pid: &str| {
+            let output = std::process::Command::new("ps")
+                .args(["-o", "pgid=", "-p", pid])
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+
+        for (stdin_is_terminal, same_group) in [(true, true), (false, false)] {
+            let mut executor = CommandExecutor::new(&Config::default()).with_timeout(30);
+            executor.stdin_is_terminal = stdin_is_terminal;
+            let command = format!("ps -o pgid= -p $$ > {}", pgid_file.display());
+            assert_eq!(executor.execute(&command, false).await.unwrap(), 0);
+
+            let child_group = std::fs::read_to_string(&pgid_file).unwrap();
+            let own_group = pgid_of(&std::process::id().to_string());
+            assert_eq!(child_group.trim() == own_group, same_group);
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_timeout_kills_process_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let mut executor = CommandExecutor::new(&Config::default()).with_timeout(1);
+        executor.stdin_is_terminal = false;
+
+        let command = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let exit_code = executor.execute(&command, true).await.unwrap();
+        assert_eq!(exit_code, TIMEOUT_EXIT_CODE);
+
+        // The backgrounded grandchild must be gone too, not just the shell
+        // (a zombie awaiting reaping by init counts as gone)
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let output = std::process::Command::new("ps")
+            .args(["-o", "stat=", "-p", pid.trim()])
+            .output()
+            .unwrap();
+        let stat = String::from_utf8_lossy(&output.stdout);
+        assert!(stat.trim().is_empty() || stat.trim().starts_with('Z'));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hooks_receive_cmd_and_exit() {
+        let dir = tempfile::tempdir().unwrap();
+        let before = dir.path().join("before");
+        let after = dir.path().join("after");
+
+        let mut config = Config::default();
+        config.hooks.before_execute = Some(format!(
+            "printf '%s|%s' \"$CMD\" \"${{EXIT-unset}}\" > {}",
+            before.display()
+        ));
+        config.hooks.after_execute = Some(format!(
+            "printf '%s|%s' \"$CMD\" \"$EXIT\" > {}",
+            after.display()
+        ));
+
+        let executor = CommandExecutor::new(&config);
+        let exit_code = executor
+            .execute_with_sudo_retry("echo hi; exit 3", false)
+            .await
+            .unwrap();
+
+        assert_eq!(exit_code, 3);
+        assert_eq!(
+            std::fs::read_to_string(&before).unwrap(),
+            "echo hi; exit 3|unset"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&after).unwrap(),
+            "echo hi; exit 3|3"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_steps_stop_at_first_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let (first, last) = (dir.path().join("first"), dir.path().join("last"));
+        let steps = vec![
+            format!("touch {}", first.display()),
+            "(exit 4)".to_string(),
+            format!("touch {}", last.display()),
+        ];
+        let executor = CommandExecutor::new(&Config::default());
+
+        assert_eq!(
+            executor.execute_steps(&steps, false, false).await.unwrap(),
+            4
+        );
+        assert!(first.exists());
+        assert!(!last.exists());
+
+        assert_eq!(
+            executor.execute_steps(&steps, false, true).await.unwrap(),
+            4
+        );
+        assert!(last.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_steps_share_one_shell() {
+        let dir = tempfile::tempdir().unwrap();
+        let steps = vec![
+            format!("cd {}", shell_quote(&dir.path().display().to_string())),
+            "mkdir out".to_string(),
+            "cd out  # relative paths below".to_string(),
+            "export NAME=f".to_string(),
+            "touch \"$NAME\"".to_string(),
+        ];
+        let executor = CommandExecutor::new(&Config::default());
+
+        assert_eq!(
+            executor.execute_steps(&steps, false, false).await.unwrap(),
+            0
+        );
+        assert!(dir.path().join("out").join("f").exists());
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("it's $HOME"), r"'it'\''s $HOME'");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_steps_all_succeed() {
+        let steps = vec!["true".to_string(), "echo ok".to_string()];
+        let executor = CommandExecutor::new(&Config::default());
+        assert_eq!(
+            executor.execute_steps(&steps, false, false).await.unwrap(),
+            0
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failing_hook_does_not_abort() {
+        let mut config = Config::default();
+        config.hooks.before_execute = Some("exit 7".to_string());
+
+        let executor = CommandExecutor::new(&config);
+        let exit_code = executor
+            .execute_with_sudo_retry("true", false)
+            .await
+            .unwrap();
+        assert_eq!(exit_code, 0);
+    }
+
+    #[tokio::test]
+    async fn test_missing_hook_is_noop() {
+        assert_eq!(run_hook("before_execute", None, "ls", None).await, None);
+        assert_eq!(
+            run_hook("after_execute", Some("  "), "ls", Some(0)).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_captures_stderr() {
+        let executor = CommandExecutor::new(&Config::default()).with_stderr_tail(4000);
+        for follow in [false, true] {
+            let exit_code = executor
+                .execute("echo out; echo oops >&2; exit 2", follow)
+                .await
+                .unwrap();
+            assert_eq!(exit_code, 2);
+            assert_eq!(executor.last_stderr().trim(), "oops");
+        }
+
+        executor.execute("true", false).await.unwrap();
+        assert!(executor.last_stderr().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_keeps_only_stderr_tail() {
+        let script = "i=0; while [ $i -lt 500 ]; do echo \"line $i\" >&2; i=$((i+1)); done";
+        let executor = CommandExecutor::new(&Config::default()).with_stderr_tail(20);
+        for follow in [false, true] {
+            executor.execute(script, follow).await.unwrap();
+            let stderr = executor.last_stderr();
+            assert_eq!(stderr.len(), 20);
+            assert!(stderr.ends_with("line 499\n"), "{:?}", stderr);
+        }
+
+        let silent = CommandExecutor::new(&Config::default());
+        silent
+            .execute("echo oops >&2; exit 1", false)
+            .await
+            .unwrap();
+        assert!(silent.last_stderr().is_empty());
+    }
+
+    #[test]
+    fn test_keep_tail() {
+        let mut buf = Vec::new();
+        keep_tail(&mut buf, b"abc", 5);
+        keep_tail(&mut buf, b"defg", 5);
+        assert_eq!(buf, b"cdefg");
+        keep_tail(&mut buf, b"xyz", 0);
+        assert!(buf.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_within_timeout() {
+        let executor = CommandExecutor::new(&Config::default()).with_timeout(5);
+        let exit_code = executor.execute("exit 3", false).await.unwrap();
+        assert_eq!(exit_code, 3);
+    }
+}
@@ -7,7 +7,11 @@ use hickory_resolver::{config::ResolverConfig, name_server::TokioConnectionProvi
 use reqwest::dns::{Addrs, Name, Resolve, Resolving};
 use std::io;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::config::Config;
 
 type TokioResolver = Resolver<TokioConnectionProvider>;
 
@@ -47,10 +51,120 @@ impl Resolve for HickoryDnsResolver {
     }
 }
 
+/// Proxy from `[http] proxy` in config (takes precedence over env vars)
+static PROXY_OVERRIDE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Set the config proxy override used by every client built afterwards
+pub fn set_proxy_override(proxy: Option<String>) {
+    if let Ok(mut guard) = PROXY_OVERRIDE.write() {
+        *guard = proxy.filter(|p| !p.trim().is_empty());
+    }
+}
+
+/// Set by `--offline`; `ASK_OFFLINE` is read on every check
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Turn on no-network mode for the rest of the process (`--offline`)
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// True when `--offline` was given or `ASK_OFFLINE` is set to a truthy value
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+        || std::env::var("ASK_OFFLINE")
+            .map(|v| offline_value(&v))
+            .unwrap_or(false)
+}
+
+/// Set by `--debug-request`: print every provider request body to stderr
+static DEBUG_REQUEST: AtomicBool = AtomicBool::new(false);
+
+pub fn set_debug_request(enabled: bool) {
+    DEBUG_REQUEST.store(enabled, Ordering::Relaxed);
+}
+
+fn offline_value(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// Proxy settings resolved from config or the standard proxy env vars
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxySettings {
+    pub http: Option<String>,
+    pub https: Option<String>,
+    pub all: Option<String>,
+    pub no_proxy: Option<String>,
+}
+
+impl ProxySettings {
+    /// Resolve settings; `lookup` reads env vars (upper or lower case names)
+    pub fn resolve(config_proxy: Option<&str>, lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let env = |name: &str| {
+            lookup(name)
+                .or_else(|| lookup(&name.to_lowercase()))
+                .filter(|v| !v.trim().is_empty())
+        };
+
+        let no_proxy = env("NO_PROXY");
+
+        if let Some(proxy) = config_proxy.filter(|p| !p.trim().is_empty()) {
+            return Self {
+                all: Some(proxy.to_string()),
+                no_proxy,
+                ..Default::default()
+            };
+        }
+
+        Self {
+            http: env("HTTP_PROXY"),
+            https: env("HTTPS_PROXY"),
+            all: env("ALL_PROXY"),
+            no_proxy,
+        }
+    }
+
+    /// Resolve from the config override and the process environment
+    pub fn from_env() -> Self {
+        let config_proxy = PROXY_OVERRIDE.read().ok().and_then(|p| p.clone());
+        Self::resolve(config_proxy.as_deref(), |name| std::env::var(name).ok())
+    }
+
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        let no_proxy = || {
+            self.no_proxy
+                .as_deref()
+                .and_then(reqwest::NoProxy::from_string)
+        };
+
+        type MakeProxy = fn(&str) -> reqwest::Result<reqwest::Proxy>;
+        let proxies: [(Option<&str>, MakeProxy); 3] = [
+            (self.http.as_deref(), |url| reqwest::Proxy::http(url)),
+            (self.https.as_deref(), |url| reqwest::Proxy::https(url)),
+            (self.all.as_deref(), |url| reqwest::Proxy::all(url)),
+        ];
+
+        for (url, make) in proxies {
+            if let Some(url) = url {
+                match make(url) {
+                    Ok(proxy) => builder = builder.proxy(proxy.no_proxy(no_proxy())),
+                    Err(e) => eprintln!("Ignoring invalid proxy '{}': {}", url, e),
+                }
+            }
+        }
+
+        builder
+    }
+}
+
 /// Create an HTTP client builder with custom DNS resolver
 /// This works on all platforms including Termux/Android
 pub fn create_client_builder() -> reqwest::ClientBuilder {
-    reqwest::Client::builder().dns_resolver(Arc::new(HickoryDnsResolver::new()))
+    let builder = reqwest::Client::builder().dns_resolver(Arc::new(HickoryDnsResolver::new()));
+    ProxySettings::from_env().apply(builder)
 }
 
 /// Create an HTTP client with custom DNS resolver
@@ -60,3 +174,837 @@ pub fn create_client() -> reqwest::Client {
         .build()
         .expect("Failed to create HTTP client")
 }
+
+/// Timeouts applied to provider HTTP clients (None = no limit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientTimeouts {
+    /// Time allowed to establish the connection
+    pub connect: Option<Duration>,
+    /// Total time allowed for the whole request, body included
+    pub total: Option<Duration>,
+}
+
+fn secs(value: u64) -> Option<Duration> {
+    (value > 0).then(|| Duration::from_secs(value))
+}
+
+impl ClientTimeouts {
+    /// Non-streaming requests: connect timeout plus total `behavior.timeout`
+    pub fn request(config: &Config) -> Self {
+        Self {
+            connect: secs(config.behavior.connect_timeout),
+            total: secs(config.behavior.timeout),
+        }
+    }
+
+    /// Streaming requests: connect timeout only, so long responses are never cut mid-stream
+    pub fn streaming(config: &Config) -> Self {
+        Self {
+            connect: secs(config.behavior.connect_timeout),
+            total: None,
+        }
+    }
+
+    fn apply(self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if let Some(connect) = self.connect {
+            builder = builder.connect_timeout(connect);
+        }
+        if let Some(total) = self.total {
+            builder = builder.timeout(total);
+        }
+        builder
+    }
+}
+
+/// Create an HTTP client for non-streaming provider requests
+pub fn create_request_client(config: &Config) -> reqwest::Client {
+    ClientTimeouts::request(config)
+        .apply(create_client_builder())
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+/// Create an HTTP client for streaming provider requests (no total timeout)
+pub fn create_streaming_client(config: &Config) -> reqwest::Client {
+    ClientTimeouts::streaming(config)
+        .apply(create_client_builder())
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+/// Query parameters that carry credentials
+const SECRET_QUERY_PARAMS: &[&str] = &[
+    "key",
+    "api_key",
+    "apikey",
+    "api-key",
+    "access_token",
+    "token",
+];
+
+/// Headers that carry credentials
+const SECRET_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "api-key",
+    "x-goog-api-key",
+];
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Strip credentials (e.g. Gemini's `?key=`) from a URL before logging it
+pub fn redact_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+
+    let query: Vec<String> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if SECRET_QUERY_PARAMS.contains(&name.to_lowercase().as_str()) => {
+                format!("{}={}", name, REDACTED)
+            }
+            _ => pair.to_string(),
+        })
+        .collect();
+
+    format!("{}?{}", base, query.join("&"))
+}
+
+/// Redact credential header values, keeping the auth scheme (`Bearer [REDACTED]`)
+pub fn redact_header(name: &str, value: &str) -> String {
+    if !SECRET_HEADERS.contains(&name.to_lowercase().as_str()) {
+        return value.to_string();
+    }
+
+    match value.split_once(' ') {
+        Some((scheme, _)) => format!("{} {}", scheme, REDACTED),
+        None => REDACTED.to_string(),
+    }
+}
+
+/// Copy of a JSON request body with credential fields (`key`, `api_key`,
+/// `authorization`, ...) replaced, at any depth
+pub fn debug_request(body: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    let is_secret = |name: &str| {
+        let name = name.to_lowercase();
+        SECRET_QUERY_PARAMS.contains(&name.as_str()) || SECRET_HEADERS.contains(&name.as_str())
+    };
+
+    match body {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(name, value)| {
+                    let value = if is_secret(name) && !value.is_object() && !value.is_array() {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        debug_request(value)
+                    };
+                    (name.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(debug_request).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Method, redacted URL and headers, then the pretty JSON body with credentials redacted
+fn describe_request(request: &reqwest::Request) -> String {
+    let mut lines = vec![format!(
+        "{} {}",
+        request.method(),
+        redact_url(request.url().as_str())
+    )];
+    for (name, value) in request.headers() {
+        lines.push(format!(
+            "{}: {}",
+            name,
+            redact_header(name.as_str(), value.to_str().unwrap_or("<binary>"))
+        ));
+    }
+
+    if let Some(bytes) = request.body().and_then(|b| b.as_bytes()) {
+        lines.push(String::new());
+        lines.push(match serde_json::from_slice::<serde_json::Value>(bytes) {
+            Ok(body) => serde_json::to_string_pretty(&debug_request(&body)).unwrap_or_default(),
+            Err(_) => format!("<{} bytes, not JSON>", bytes.len()),
+        });
+    }
+    lines.join("\n")
+}
+
+/// `--debug-request` output on stderr
+fn print_debug_request(provider: &str, request: &reqwest::Request) {
+    eprintln!("[debug-request] {} {}", provider, describe_request(request));
+}
+
+/// `ASK_DEBUG_LOG=<file>`: append every provider request and response to this file
+fn debug_log_path() -> Option<std::path::PathBuf> {
+    std::env::var_os("ASK_DEBUG_LOG")
+        .filter(|path| !path.is_empty())
+        .map(std::path::PathBuf::from)
+}
+
+/// One `ASK_DEBUG_LOG` block: `[time] >>> request openai`, then the details
+fn debug_log_entry(timestamp: &str, heading: &str, details: &str) -> String {
+    if details.is_empty() {
+        format!("[{}] {}\n\n", timestamp, heading)
+    } else {
+        format!("[{}] {}\n{}\n\n", timestamp, heading, details)
+    }
+}
+
+/// Append to the debug log, if one is set. Failures only show up in `tracing`:
+/// a debug aid must never break the request itself.
+fn write_debug_log(heading: &str, details: &str) {
+    let Some(path) = debug_log_path() else {
+        return;
+    };
+    let timestamp = chrono::Local::now()
+        .format("%Y-%m-%d %H:%M:%S%.3f")
+        .to_string();
+    if let Err(e) = append_to(&path, &debug_log_entry(&timestamp, heading, details)) {
+        tracing::debug!(path = %path.display(), error = %e, "could not write debug log");
+    }
+}
+
+/// Append `text`; a new log is readable only by the user, since it holds prompts and answers
+fn append_to(path: &std::path::Path, text: &str) -> io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(text.as_bytes())
+}
+
+/// `send()` with `tracing` output: redacted URL and headers, status and timing.
+/// Enabled with `RUST_LOG=ask=debug`.
+pub(crate) trait SendLogged {
+    async fn send_logged(self, provider: &'static str) -> reqwest::Result<reqwest::Response>;
+}
+
+impl SendLogged for reqwest::RequestBuilder {
+    async fn send_logged(self, provider: &'static str) -> reqwest::Result<reqwest::Response> {
+        let (client, request) = self.build_split();
+        let request = request?;
+
+        if DEBUG_REQUEST.load(Ordering::Relaxed) {
+            print_debug_request(provider, &request);
+        }
+        write_debug_log(
+            &format!(">>> request {}", provider),
+            &describe_request(&request),
+        );
+
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            let headers: Vec<String> = request
+                .headers()
+                .iter()
+                .map(|(name, value)| {
+                    format!(
+                        "{}: {}",
+                        name,
+                        redact_header(name.as_str(), value.to_str().unwrap_or("<binary>"))
+                    )
+                })
+                .collect();
+            tracing::debug!(
+                provider,
+                method = %request.method(),
+                url = %redact_url(request.url().as_str()),
+                headers = ?headers,
+                "sending request"
+            );
+        }
+
+        let started = std::time::Instant::now();
+        let result = client.execute(request).await;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+
+        match &result {
+            Ok(response) => {
+                tracing::debug!(
+                    provider,
+                    status = %response.status(),
+                    elapsed_ms,
+                    "response headers received"
+                );
+                write_debug_log(
+                    &format!(
+                        "<<< response {} {} ({} ms)",
+                        provider,
+                        response.status(),
+                        elapsed_ms
+                    ),
+                    "",
+                );
+            }
+            Err(e) => {
+                let error = redact_url(&e.to_string());
+                tracing::debug!(provider, error = %error, elapsed_ms, "request failed");
+                write_debug_log(
+                    &format!("<<< error {} ({} ms)", provider, elapsed_ms),
+                    &error,
+                );
+            }
+        }
+
+        result
+    }
+}
+
+/// `text()` that also appends the raw body to `ASK_DEBUG_LOG`.
+/// Streamed bodies are logged event by event, see `SseBuffer::logged`.
+pub(crate) trait TextLogged {
+    async fn text_logged(self, provider: &'static str) -> reqwest::Result<String>;
+}
+
+impl TextLogged for reqwest::Response {
+    async fn text_logged(self, provider: &'static str) -> reqwest::Result<String> {
+        let body = self.text().await?;
+        write_debug_log(&format!("<<< body {}", provider), &body);
+        Ok(body)
+    }
+}
+
+/// Append one streamed event (an SSE `data:` payload or an NDJSON line) to `ASK_DEBUG_LOG`
+pub(crate) fn log_stream_event(provider: &str, data: &str) {
+    write_debug_log(&format!("<<< data {}", provider), data);
+}
+
+/// Decodes a byte stream as UTF-8 across chunk boundaries. A character split
+/// between two network chunks is held back until its remaining bytes arrive,
+/// instead of turning into replacement characters; bytes that can never be
+/// valid UTF-8 are still replaced.
+#[derive(Debug, Default)]
+pub struct Utf8StreamDecoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8StreamDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Text of every complete character received so far
+    pub fn decode(&mut self, chunk: &[u8]) -> String {
+        self.pending.extend_from_slice(chunk);
+        let complete = self.pending.len() - incomplete_tail(&self.pending);
+        let text = String::from_utf8_lossy(&self.pending[..complete]).into_owned();
+        self.pending.drain(..complete);
+        text
+    }
+
+    /// Whatever is still held back once the stream ends (a truncated character)
+    pub fn finish(self) -> String {
+        String::from_utf8_lossy(&self.pending).into_owned()
+    }
+}
+
+/// Length of a multibyte sequence cut off at the end of `bytes` (0 if none)
+fn incomplete_tail(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xC0 == 0x80 {
+            continue; // continuation byte, keep looking for the lead
+        }
+        let width = match byte {
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return 0,
+        };
+        return if width > back { back } else { 0 };
+    }
+    0
+}
+
+/// Server-sent events read from a chunked body. Network chunks do not follow
+/// line boundaries, so partial lines are buffered until their newline arrives
+/// and only complete `data:` lines are handed out.
+#[derive(Debug, Default)]
+pub struct SseBuffer {
+    decoder: Utf8StreamDecoder,
+    buffer: String,
+    /// Provider whose events are copied to `ASK_DEBUG_LOG`
+    log_as: Option<&'static str>,
+}
+
+impl SseBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Like `new`, also appending every `data:` payload to `ASK_DEBUG_LOG`
+    pub fn logged(provider: &'static str) -> Self {
+        Self {
+            log_as: Some(provider),
+            ..Self::default()
+        }
+    }
+
+    /// Payloads of the `data:` lines completed by this chunk, in order
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.push_str(&self.decoder.decode(chunk));
+        let mut events = Vec::new();
+        while let Some(line) = take_line(&mut self.buffer) {
+            events.extend(data_payload(&line));
+        }
+        if let Some(provider) = self.log_as {
+            for event in &events {
+                log_stream_event(provider, event);
+            }
+        }
+        events
+    }
+
+    /// Payload of a last `data:` line the stream ended without a newline
+    pub fn finish(mut self) -> Option<String> {
+        self.buffer.push_str(&self.decoder.finish());
+        let event = data_payload(&self.buffer);
+        if let (Some(provider), Some(event)) = (self.log_as, &event) {
+            log_stream_event(provider, event);
+        }
+        event
+    }
+}
+
+/// Remove and return the next complete line (without its `\n` or `\r\n`)
+fn take_line(buffer: &mut String) -> Option<String> {
+    let newline = buffer.find('\n')?;
+    let line: String = buffer.drain(..=newline).collect();
+    Some(line.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// `data: {...}` -> `{...}`; comments, `event:` and blank lines -> None
+fn data_payload(line: &str) -> Option<String> {
+    let data = line.trim().strip_prefix("data:")?;
+    Some(data.strip_prefix(' ').unwrap_or(data).to_string())
+}
+
+/// Longest raw body shown when an error response has no recognizable message
+const MAX_ERROR_BODY_CHARS: usize = 500;
+
+/// Readable message from a failed API response. Understands `{error: {message, type}}`
+/// (OpenAI, Anthropic, Gemini), `{error: "..."}` (Ollama), `{message}`, `{detail}`
+/// (string or FastAPI-style list) and a top-level array of those; anything else is
+/// shown as the raw body, truncated.
+pub fn parse_api_error(status: reqwest::StatusCode, body: &str) -> String {
+    let body = body.trim();
+    if body.is_empty() {
+        return status
+            .canonical_reason()
+            .unwrap_or("empty error response")
+            .to_string();
+    }
+
+    if let Some(message) = serde_json::from_str(body)
+        .ok()
+        .as_ref()
+        .and_then(error_message)
+    {
+        return message;
+    }
+
+    if body.chars().count() > MAX_ERROR_BODY_CHARS {
+        let truncated: String = body.chars().take(MAX_ERROR_BODY_CHARS).collect();
+        format!("{}...", truncated)
+    } else {
+        body.to_string()
+    }
+}
+
+fn error_message(value: &serde_json::Value) -> Option<String> {
+    use serde_json::Value;
+
+    fn text(v: &Value) -> Option<&str> {
+        v.as_str().map(str::trim).filter(|s| !s.is_empty())
+    }
+
+    match value {
+        Value::Array(items) => items.iter().find_map(error_message),
+        Value::Object(map) => {
+            if let Some(error) = map.get("error") {
+                if let Some(message) = text(error) {
+                    return Some(message.to_string());
+                }
+                if let Some(message) = error.get("message").and_then(text) {
+                    // Anthropic `type`, Gemini `status`, OpenAI `code`
+                    let kind = ["type", "status", "code"]
+                        .iter()
+                        .find_map(|k| error.get(*k).and_then(text));
+                    return Some(match kind {
+                        Some(kind) => format!("{} ({})", message, kind),
+                        None => message.to_string(),
+                    });
+                }
+            }
+            if let Some(message) = map.get("message").and_then(text) {
+                return Some(message.to_string());
+            }
+            match map.get("detail")? {
+                Value::Array(details) => {
+                    let messages: Vec<&str> = details
+                        .iter()
+                        .filter_map(|d| d.get("msg").and_then(text).or_else(|| text(d)))
+                        .collect();
+                    (!messages.is_empty()).then(|| messages.join("; "))
+                }
+                detail => text(detail).map(str::to_string),
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_api_error_nested_error() {
+        let status = reqwest::StatusCode::UNAUTHORIZED;
+        assert_eq!(
+            parse_api_error(
+                status,
+                r#"{"error": {"message": "Incorrect API key provided", "type": "invalid_request_error", "code": "invalid_api_key"}}"#
+            ),
+            "Incorrect API key provided (invalid_request_error)"
+        );
+        assert_eq!(
+            parse_api_error(
+                status,
+                r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#
+            ),
+            "Overloaded (overloaded_error)"
+        );
+        assert_eq!(
+            parse_api_error(
+                status,
+                r#"{"error": {"code": 400, "message": "API key not valid.", "status": "INVALID_ARGUMENT"}}"#
+            ),
+            "API key not valid. (INVALID_ARGUMENT)"
+        );
+        assert_eq!(
+            parse_api_error(status, r#"{"error": {"message": "plain"}}"#),
+            "plain"
+        );
+    }
+
+    #[test]
+    fn test_parse_api_error_flat_shapes() {
+        let status = reqwest::StatusCode::BAD_REQUEST;
+        assert_eq!(
+            parse_api_error(status, r#"{"error": "model 'llama9' not found"}"#),
+            "model 'llama9' not found"
+        );
+        assert_eq!(
+            parse_api_error(status, r#"{"message": "Invalid model"}"#),
+            "Invalid model"
+        );
+        assert_eq!(
+            parse_api_error(status, r#"{"detail": "Not authenticated"}"#),
+            "Not authenticated"
+        );
+        assert_eq!(
+            parse_api_error(
+                status,
+                r#"{"detail": [{"loc": ["body", "model"], "msg": "field required"}, {"msg": "bad value"}]}"#
+            ),
+            "field required; bad value"
+        );
+    }
+
+    #[test]
+    fn test_parse_api_error_array() {
+        assert_eq!(
+            parse_api_error(
+                reqwest::StatusCode::TOO_MANY_REQUESTS,
+                r#"[{"error": {"code": 429, "message": "Quota exceeded", "status": "RESOURCE_EXHAUSTED"}}]"#
+            ),
+            "Quota exceeded (RESOURCE_EXHAUSTED)"
+        );
+    }
+
+    #[test]
+    fn test_parse_api_error_falls_back_to_body() {
+        let status = reqwest::StatusCode::BAD_GATEWAY;
+        assert_eq!(
+            parse_api_error(status, "<html>Bad Gateway</html>"),
+            "<html>Bad Gateway</html>"
+        );
+        assert_eq!(
+            parse_api_error(status, r#"{"unexpected": true}"#),
+            r#"{"unexpected": true}"#
+        );
+        assert_eq!(parse_api_error(status, "  "), "Bad Gateway");
+
+        let long = "x".repeat(2000);
+        let message = parse_api_error(status, &long);
+        assert_eq!(message.chars().count(), MAX_ERROR_BODY_CHARS + 3);
+        assert!(message.ends_with("..."));
+    }
+
+    #[test]
+    fn test_offline_value() {
+        for value in ["1", "true", "YES", " on "] {
+            assert!(offline_value(value), "{value}");
+        }
+        for value in ["", "0", "false", "off"] {
+            assert!(!offline_value(value), "{value}");
+        }
+    }
+
+    #[test]
+    fn test_redact_url_strips_keys() {
+        assert_eq!(
+            redact_url(
+                "https://generativelanguage.googleapis.com/v1beta/models/gemini:streamGenerateContent?key=AIzaSECRET&alt=sse"
+            ),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini:streamGenerateContent?key=[REDACTED]&alt=sse"
+        );
+        assert_eq!(
+            redact_url("https://x.test/v1?api_key=s3cret"),
+            "https://x.test/v1?api_key=[REDACTED]"
+        );
+        assert_eq!(
+            redact_url("https://api.openai.com/v1/chat/completions"),
+            "https://api.openai.com/v1/chat/completions"
+        );
+        assert_eq!(
+            redact_url("https://res.openai.azure.com/x?api-version=2024-10-21"),
+            "https://res.openai.azure.com/x?api-version=2024-10-21"
+        );
+    }
+
+    #[test]
+    fn test_redact_header_hides_credentials() {
+        assert_eq!(
+            redact_header("Authorization", "Bearer sk-SECRET"),
+            "Bearer [REDACTED]"
+        );
+        assert_eq!(redact_header("x-api-key", "sk-ant-SECRET"), "[REDACTED]");
+        assert_eq!(redact_header("api-key", "azure-SECRET"), "[REDACTED]");
+        assert_eq!(
+            redact_header("Content-Type", "application/json"),
+            "application/json"
+        );
+    }
+
+    #[test]
+    fn test_debug_log_entry_redacts_request() {
+        let request = reqwest::Client::new()
+            .post("https://x.test/v1/models/m:generate?key=s3cret")
+            .header("Authorization", "Bearer sk-SECRET")
+            .json(&serde_json::json!({ "api_key": "sk-BODY", "model": "m" }))
+            .build()
+            .unwrap();
+        let entry = debug_log_entry(
+            "2026-01-02 03:04:05.678",
+            ">>> request gemini",
+            &describe_request(&request),
+        );
+
+        assert!(entry.starts_with(
+            "[2026-01-02 03:04:05.678] >>> request gemini\n\
+             POST https://x.test/v1/models/m:generate?key=[REDACTED]\n"
+        ));
+        assert!(entry.contains("authorization: Bearer [REDACTED]\n"));
+        assert!(entry.contains("\"api_key\": \"[REDACTED]\""));
+        assert!(entry.contains("\"model\": \"m\""));
+        assert!(!entry.contains("s3cret") && !entry.contains("SECRET") && !entry.contains("BODY"));
+        assert!(entry.ends_with("}\n\n"));
+    }
+
+    #[test]
+    fn test_debug_log_entry_without_details() {
+        assert_eq!(
+            debug_log_entry("t", "<<< response ollama 200 OK (12 ms)", ""),
+            "[t] <<< response ollama 200 OK (12 ms)\n\n"
+        );
+    }
+
+    #[test]
+    fn test_append_to_debug_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ask.log");
+        append_to(&path, "one\n").unwrap();
+        append_to(&path, "two\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // An unwritable log is reported, not a panic
+        assert!(append_to(&dir.path().join("missing/ask.log"), "x").is_err());
+    }
+
+    #[test]
+    fn test_debug_request_redacts_credentials() {
+        let body = serde_json::json!({
+            "model": "gpt-5",
+            "api_key": "sk-SECRET",
+            "messages": [{ "role": "user", "content": "what is my key?" }],
+            "auth": { "Authorization": "Bearer sk-SECRET", "x-api-key": "sk-ant-SECRET" },
+            "tools": [{ "key": "gm-SECRET", "type": "web_search" }],
+            "max_tokens": 100
+        });
+
+        let redacted = debug_request(&body);
+        let text = redacted.to_string();
+        assert!(!text.contains("SECRET"), "{}", text);
+        assert_eq!(redacted["api_key"], "[REDACTED]");
+        assert_eq!(redacted["auth"]["Authorization"], "[REDACTED]");
+        assert_eq!(redacted["tools"][0]["key"], "[REDACTED]");
+        assert_eq!(redacted["tools"][0]["type"], "web_search");
+        assert_eq!(redacted["messages"][0]["content"], "what is my key?");
+        assert_eq!(redacted["max_tokens"], 100);
+    }
+
+    fn env_from<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn test_proxy_settings_from_env_vars() {
+        let vars = [
+            ("HTTPS_PROXY", "http://proxy.corp:3128"),
+            ("http_proxy", "http://plain.corp:8080"),
+            ("NO_PROXY", "localhost,127.0.0.1"),
+        ];
+        let settings = ProxySettings::resolve(None, env_from(&vars));
+
+        assert_eq!(settings.https.as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(settings.http.as_deref(), Some("http://plain.corp:8080"));
+        assert_eq!(settings.all, None);
+        assert_eq!(settings.no_proxy.as_deref(), Some("localhost,127.0.0.1"));
+    }
+
+    #[test]
+    fn test_proxy_settings_config_override() {
+        let vars = [
+            ("HTTPS_PROXY", "http://env.corp:3128"),
+            ("NO_PROXY", "localhost"),
+        ];
+        let settings = ProxySettings::resolve(Some("socks5://cfg.corp:1080"), env_from(&vars));
+
+        assert_eq!(settings.all.as_deref(), Some("socks5://cfg.corp:1080"));
+        assert_eq!(settings.https, None);
+        assert_eq!(settings.no_proxy.as_deref(), Some("localhost"));
+    }
+
+    #[test]
+    fn test_proxy_settings_build_client() {
+        let settings = ProxySettings {
+            all: Some("http://proxy.corp:3128".to_string()),
+            no_proxy: Some("localhost".to_string()),
+            ..Default::default()
+        };
+        assert!(settings.apply(reqwest::Client::builder()).build().is_ok());
+        assert_eq!(
+            ProxySettings::resolve(None, |_| None),
+            ProxySettings::default()
+        );
+    }
+
+    #[test]
+    fn test_streaming_client_has_no_total_timeout() {
+        let config = Config::default();
+        let timeouts = ClientTimeouts::streaming(&config);
+        assert_eq!(timeouts.total, None);
+        assert_eq!(
+            timeouts.connect,
+            Some(Duration::from_secs(config.behavior.connect_timeout))
+        );
+    }
+
+    #[test]
+    fn test_request_client_keeps_total_timeout() {
+        let mut config = Config::default();
+        config.behavior.timeout = 45;
+        config.behavior.connect_timeout = 5;
+        let timeouts = ClientTimeouts::request(&config);
+        assert_eq!(timeouts.total, Some(Duration::from_secs(45)));
+        assert_eq!(timeouts.connect, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_utf8_decoder_reassembles_split_character() {
+        let bytes = "café 🦀".as_bytes();
+        // Split inside "é" (2 bytes) and again inside the crab (4 bytes)
+        let (first, rest) = bytes.split_at(4);
+        let (second, third) = rest.split_at(4);
+
+        let mut decoder = Utf8StreamDecoder::new();
+        assert_eq!(decoder.decode(first), "caf");
+        assert_eq!(decoder.decode(second), "é ");
+        assert_eq!(decoder.decode(third), "🦀");
+        assert_eq!(decoder.finish(), "");
+    }
+
+    #[test]
+    fn test_utf8_decoder_replaces_invalid_bytes() {
+        let mut decoder = Utf8StreamDecoder::new();
+        assert_eq!(decoder.decode(b"a\xFFb"), "a\u{FFFD}b");
+        // A truncated character at the very end is flushed as a replacement
+        assert_eq!(decoder.decode(&[b'x', 0xE2, 0x82]), "x");
+        assert_eq!(decoder.finish(), "\u{FFFD}");
+    }
+
+    #[test]
+    fn test_sse_buffer_joins_split_data_line() {
+        let mut sse = SseBuffer::new();
+        assert!(sse.push(b"data: {\"text\":\"hel").is_empty());
+        assert_eq!(
+            sse.push(b"lo\"}\n\ndata: {\"te"),
+            vec![r#"{"text":"hello"}"#]
+        );
+        assert_eq!(sse.push(b"xt\":\"!\"}\r\n"), vec![r#"{"text":"!"}"#]);
+        assert_eq!(sse.finish(), None);
+    }
+
+    #[test]
+    fn test_sse_buffer_skips_other_fields() {
+        let mut sse = SseBuffer::new();
+        let events = sse.push(b": keep-alive\nevent: message_start\ndata:{}\n\n");
+        assert_eq!(events, vec!["{}"]);
+    }
+
+    #[test]
+    fn test_sse_buffer_flushes_unterminated_line() {
+        let mut sse = SseBuffer::new();
+        assert!(sse.push(b"data: [DONE]").is_empty());
+        assert_eq!(sse.finish().as_deref(), Some("[DONE]"));
+    }
+
+    #[test]
+    fn test_zero_timeout_disables_limit() {
+        let mut config = Config::default();
+        config.behavior.timeout = 0;
+        config.behavior.connect_timeout = 0;
+        assert_eq!(
+            ClientTimeouts::request(&config),
+            ClientTimeouts {
+                connect: None,
+                total: None
+            }
+        );
+    }
+}
@@ -9,28 +9,70 @@ mod context;
 mod executor;
 pub mod http;
 mod output;
+mod privacy;
 mod providers;
 mod update;
 
 use anyhow::Result;
 use cli::run;
 
+/// Emit `tracing` diagnostics on stderr when RUST_LOG is set (e.g. `RUST_LOG=ask=debug`)
+fn init_tracing() {
+    if std::env::var_os("RUST_LOG").is_none() {
+        return;
+    }
+
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+/// On Ctrl-C, clear the spinner and restore the cursor before exiting with 130
+fn install_interrupt_handler() {
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            output::restore_terminal_on_interrupt();
+            executor::stop_running_command();
+            std::process::exit(130);
+        }
+    });
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    init_tracing();
+    // Detect escape support early; on Windows this turns on VT processing for colors
+    output::supports_ansi();
+
     // Handle background update check (spawned by main process)
     let args: Vec<String> = std::env::args().collect();
     if args.iter().any(|a| a == "--update-check-background") {
         return update::background_update_check().await;
     }
 
+    install_interrupt_handler();
+
+    // Before anything can touch the network, including the update check below
+    if args.iter().any(|a| a == "--offline") {
+        http::set_offline(true);
+    }
+
     // Get pending notification (don't print yet - will be handled by run())
     let update_notification = update::get_pending_notification();
 
     // Load config to check aggressive mode
     let config = config::Config::load().unwrap_or_default();
+    http::set_proxy_override(config.http.proxy.clone());
 
     // Spawn background update check
-    update::check_updates_background(config.update.aggressive, config.update.check_interval_hours);
+    update::check_updates_background(&config.update);
 
-    run(update_notification).await
+    if let Err(err) = run(update_notification).await {
+        if let Some(report) = err.report() {
+            eprintln!("{}", report);
+        }
+        std::process::exit(err.exit_code());
+    }
+    Ok(())
 }
@@ -1,5 +1,6 @@
-use super::markdown::print_markdown;
-use crate::cli::Args;
+use super::markdown::{looks_like_markdown, print_markdown};
+use super::terminal::supports_ansi;
+use crate::cli::{Args, OutputFormat};
 use crate::update::UpdateNotification;
 use std::io::IsTerminal;
 
@@ -12,10 +13,15 @@ struct UpdateInfo {
 
 pub struct OutputFormatter {
     json: bool,
-    markdown: bool,
+    /// Render Markdown; `None` = only when the answer looks like Markdown
+    markdown: Option<bool>,
     raw: bool,
+    /// `--format table`: the answer is a Markdown table
+    table: bool,
     #[allow(dead_code)]
     no_color: bool,
+    /// Prose wrap width (0 = no wrapping)
+    wrap_columns: usize,
     update_notification: Option<UpdateNotification>,
 }
 
@@ -27,9 +33,11 @@ impl OutputFormatter {
             json: args.json,
             markdown: args
                 .markdown
-                .unwrap_or(!args.raw && !args.json && !is_piped),
+                .or((args.raw || args.json || is_piped).then_some(false)),
             raw: args.raw || is_piped,
-            no_color: args.color == Some(false) || is_piped,
+            table: args.format == Some(OutputFormat::Table),
+            no_color: args.color == Some(false) || !supports_ansi(),
+            wrap_columns: 0,
             update_notification: None,
         }
     }
@@ -40,16 +48,29 @@ impl OutputFormatter {
         self
     }
 
+    /// Wrap prose at `columns`, capped at the terminal width. Pipes are never wrapped.
+    pub fn with_wrap(mut self, columns: usize) -> Self {
+        self.wrap_columns = if std::io::stdout().is_terminal() {
+            let (width, _) = termimad::terminal_size();
+            columns.min(width as usize)
+        } else {
+            0
+        };
+        self
+    }
+
     /// Format and print the response
     pub fn format(&self, text: &str) {
         if self.json {
             self.format_json(text);
+        } else if self.table {
+            self.format_table(text);
         } else if self.raw || self.no_color {
             self.format_raw(text);
-        } else if self.markdown {
-            self.format_markdown(text);
+        } else if self.markdown.unwrap_or_else(|| looks_like_markdown(text)) {
+            self.format_markdown(&wrap_prose(text, self.wrap_columns));
         } else {
-            self.format_raw(text);
+            self.format_raw(&wrap_prose(text, self.wrap_columns));
         }
     }
 
@@ -78,6 +99,17 @@ impl OutputFormatter {
         );
     }
 
+    /// Tables are rendered by the Markdown renderer on a terminal and passed
+    /// through as pipe-delimited text otherwise; never wrapped
+    fn format_table(&self, text: &str) {
+        let table = crate::providers::strip_code_fences(text);
+        if self.markdown != Some(false) && !self.raw && !self.no_color {
+            self.format_markdown(&table);
+        } else {
+            println!("{}", table);
+        }
+    }
+
     fn format_markdown(&self, text: &str) {
         print_markdown(text);
     }
@@ -91,8 +123,104 @@ impl OutputFormatter {
     }
 }
 
+/// Wrap long prose lines to `width` columns, leaving fenced code blocks and
+/// tables untouched. List items keep their hanging indent.
+pub fn wrap_prose(text: &str, width: usize) -> String {
+    if width == 0 {
+        return text.to_string();
+    }
+
+    let mut out = Vec::new();
+    let mut in_fence = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            out.push(line.to_string());
+            continue;
+        }
+        if in_fence || trimmed.starts_with('|') || textwrap::core::display_width(line) <= width {
+            out.push(line.to_string());
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        let hanging = format!("{}{}", indent, " ".repeat(list_marker_width(trimmed)));
+        let options = textwrap::Options::new(width)
+            .initial_indent(indent)
+            .subsequent_indent(&hanging);
+        out.extend(
+            textwrap::wrap(trimmed, options)
+                .into_iter()
+                .map(|l| l.into_owned()),
+        );
+    }
+
+    let mut wrapped = out.join("\n");
+    if text.ends_with('\n') {
+        wrapped.push('\n');
+    }
+    wrapped
+}
+
+/// Width of a leading list marker ("- ", "* ", "12. "), 0 if none
+fn list_marker_width(line: &str) -> usize {
+    if ["- ", "* ", "+ "].iter().any(|m| line.starts_with(m)) {
+        return 2;
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && line[digits..].starts_with(". ") {
+        return digits + 2;
+    }
+    0
+}
+
 fn unescape_ansi(text: &str) -> String {
     text.replace("\\033", "\x1b")
         .replace("\\x1b", "\x1b")
         .replace("\\e", "\x1b")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_prose_long_paragraph() {
+        let text = "word ".repeat(40);
+        let wrapped = wrap_prose(text.trim_end(), 30);
+
+        assert!(wrapped.lines().count() > 1);
+        assert!(wrapped.lines().all(|l| l.len() <= 30));
+        assert_eq!(
+            wrapped.split_whitespace().count(),
+            text.split_whitespace().count()
+        );
+    }
+
+    #[test]
+    fn test_wrap_prose_leaves_code_blocks_intact() {
+        let long_cmd = format!("docker run {}", "--flag value ".repeat(10));
+        let text = format!("Run this:\n\n```bash\n{}\n```\n", long_cmd.trim_end());
+        let wrapped = wrap_prose(&text, 30);
+
+        assert_eq!(wrapped, text);
+    }
+
+    #[test]
+    fn test_wrap_prose_list_items_hang() {
+        let text = format!("- {}", "item ".repeat(12).trim_end());
+        let wrapped = wrap_prose(&text, 20);
+        let lines: Vec<&str> = wrapped.lines().collect();
+
+        assert!(lines.len() > 1);
+        assert!(lines[0].starts_with("- item"));
+        assert!(lines[1..].iter().all(|l| l.starts_with("  item")));
+    }
+
+    #[test]
+    fn test_wrap_prose_zero_width_is_noop() {
+        let text = "a ".repeat(200);
+        assert_eq!(wrap_prose(&text, 0), text);
+    }
+}
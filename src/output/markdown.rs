@@ -1,14 +1,107 @@
 #![allow(dead_code)]
 
+use super::theme::{crossterm_color, theme};
 use termimad::MadSkin;
 
+/// termimad's skin for the terminal background, with headers in the theme's heading color
+fn skin() -> MadSkin {
+    let theme = theme();
+    let mut skin = if theme.light_background {
+        MadSkin::default_light()
+    } else {
+        MadSkin::default()
+    };
+    skin.set_headers_fg(crossterm_color(theme.heading));
+    skin
+}
+
 pub fn render_markdown(text: &str) -> String {
-    let skin = MadSkin::default();
-    skin.term_text(text).to_string()
+    skin().term_text(text).to_string()
 }
 
 /// Print markdown directly to terminal
 pub fn print_markdown(text: &str) {
-    let skin = MadSkin::default();
-    skin.print_text(text);
+    skin().print_text(text);
+}
+
+/// Whether an answer uses Markdown worth rendering: a header, a code fence, a
+/// table, a link, bold text or a list (two items or more, so "1. yes" stays plain)
+pub fn looks_like_markdown(text: &str) -> bool {
+    let mut list_items = 0;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            return true;
+        }
+        let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            return true;
+        }
+        if trimmed.starts_with('|') && trimmed.contains("---") {
+            return true;
+        }
+        if has_link(trimmed) || has_bold(trimmed) {
+            return true;
+        }
+        if is_list_item(trimmed) {
+            list_items += 1;
+            if list_items >= 2 {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// `[text](target)` somewhere in the line
+fn has_link(line: &str) -> bool {
+    line.match_indices("](").any(|(i, _)| {
+        line[..i].rfind('[').is_some_and(|open| open + 1 < i) && line[i + 2..].contains(')')
+    })
+}
+
+/// `**text**` somewhere in the line
+fn has_bold(line: &str) -> bool {
+    line.split("**").count() >= 3
+        && line
+            .split("**")
+            .nth(1)
+            .is_some_and(|s| !s.trim().is_empty())
+}
+
+fn is_list_item(line: &str) -> bool {
+    if ["- ", "* ", "+ "].iter().any(|m| line.starts_with(m)) {
+        return true;
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    digits > 0 && line[digits..].starts_with(". ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_like_markdown() {
+        assert!(looks_like_markdown("## Install\n\nRun the installer."));
+        assert!(looks_like_markdown("Use this:\n\n```bash\nls -la\n```"));
+        assert!(looks_like_markdown("- one\n- two\n- three"));
+        assert!(looks_like_markdown("1. Open the file\n2. Save it"));
+        assert!(looks_like_markdown(
+            "See [the docs](https://docs.rs) for more."
+        ));
+        assert!(looks_like_markdown("This is **important**."));
+        assert!(looks_like_markdown("| a | b |\n|---|---|\n| 1 | 2 |"));
+    }
+
+    #[test]
+    fn test_plain_text_is_not_markdown() {
+        assert!(!looks_like_markdown("The capital of France is Paris."));
+        assert!(!looks_like_markdown("ls -la | grep foo"));
+        assert!(!looks_like_markdown("#hashtag without a space"));
+        assert!(!looks_like_markdown("- just one dash line"));
+        assert!(!looks_like_markdown("2 * 3 = 6, arr[0] is odd (see above)"));
+        assert!(!looks_like_markdown("a ** b"));
+        assert!(!looks_like_markdown(""));
+    }
 }
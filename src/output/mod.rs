@@ -4,6 +4,10 @@ mod colorize;
 mod formatter;
 mod markdown;
 mod spinner;
+mod terminal;
+mod theme;
 
 pub use formatter::*;
-pub use spinner::{Spinner, StreamingIndicator};
+pub use spinner::{restore_terminal_on_interrupt, Spinner, StreamingIndicator};
+pub use terminal::supports_ansi;
+pub use theme::{set_theme, theme, Theme};
@@ -1,31 +1,107 @@
+use super::terminal::supports_ansi;
+use super::theme::theme;
 use std::io::{self, Write};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
+/// Show the cursor again (some terminals leave it hidden after an interrupt)
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+/// Flags of the spinner currently on screen, so the Ctrl-C handler can stop it
+struct ActiveSpinner {
+    id: u64,
+    running: Arc<AtomicBool>,
+    visible: Arc<AtomicBool>,
+}
+
+static ACTIVE_SPINNER: Mutex<Option<ActiveSpinner>> = Mutex::new(None);
+static NEXT_SPINNER_ID: AtomicU64 = AtomicU64::new(0);
+
+fn register(id: u64, running: &Arc<AtomicBool>, visible: &Arc<AtomicBool>) {
+    if let Ok(mut active) = ACTIVE_SPINNER.lock() {
+        *active = Some(ActiveSpinner {
+            id,
+            running: running.clone(),
+            visible: visible.clone(),
+        });
+    }
+}
+
+fn deregister(id: u64) {
+    if let Ok(mut active) = ACTIVE_SPINNER.lock() {
+        if active.as_ref().is_some_and(|a| a.id == id) {
+            *active = None;
+        }
+    }
+}
+
+/// Stop the registered spinner, if any. Returns whether its ● is still drawn.
+fn stop_active_spinner() -> bool {
+    let Some(active) = ACTIVE_SPINNER.lock().ok().and_then(|mut a| a.take()) else {
+        return false;
+    };
+    active.running.store(false, Ordering::Relaxed);
+    // Give the spinner thread a tick to notice before we touch the line
+    thread::sleep(Duration::from_millis(20));
+    active.visible.load(Ordering::Relaxed)
+}
+
+/// Write the cleanup sequence for an interrupt: erase the spinner, show the cursor, end the line
+fn write_interrupt_cleanup<W: Write>(out: &mut W, erase_spinner: bool) -> io::Result<()> {
+    if erase_spinner {
+        write!(out, "\x08 \x08")?;
+    }
+    write!(out, "{}", SHOW_CURSOR)?;
+    writeln!(out)?;
+    out.flush()
+}
+
+/// Restore the terminal after Ctrl-C: stop any active spinner and show the cursor
+pub fn restore_terminal_on_interrupt() {
+    let erase_spinner = stop_active_spinner();
+    if supports_ansi() {
+        write_interrupt_cleanup(&mut io::stdout(), erase_spinner).ok();
+    } else {
+        println!();
+    }
+}
+
 /// A simple spinner that blinks ● while waiting for response
 pub struct Spinner {
+    id: u64,
     running: Arc<AtomicBool>,
     handle: Option<JoinHandle<()>>,
     visible: Arc<AtomicBool>,
 }
 
 impl Spinner {
-    /// Start a new spinner that blinks ● every 500ms
+    /// Start a new spinner that blinks ● every 500ms (draws nothing without ANSI support)
     pub fn start() -> Self {
-        let running = Arc::new(AtomicBool::new(true));
+        let id = NEXT_SPINNER_ID.fetch_add(1, Ordering::Relaxed);
+        let running = Arc::new(AtomicBool::new(supports_ansi()));
         let visible = Arc::new(AtomicBool::new(false));
+        if !running.load(Ordering::Relaxed) {
+            return Self {
+                id,
+                running,
+                handle: None,
+                visible,
+            };
+        }
+        register(id, &running, &visible);
 
         let running_clone = running.clone();
         let visible_clone = visible.clone();
+        let dot = theme().hint("●").to_string();
 
         let handle = thread::spawn(move || {
             let mut stdout = io::stdout();
 
             while running_clone.load(Ordering::Relaxed) {
                 // Show ●
-                print!("●");
+                print!("{}", dot);
                 stdout.flush().ok();
                 visible_clone.store(true, Ordering::Relaxed);
 
@@ -57,6 +133,7 @@ impl Spinner {
         });
 
         Self {
+            id,
             running,
             handle: Some(handle),
             visible,
@@ -65,6 +142,7 @@ impl Spinner {
 
     /// Stop the spinner and clean up
     pub fn stop(&mut self) {
+        deregister(self.id);
         self.running.store(false, Ordering::Relaxed);
 
         if let Some(handle) = self.handle.take() {
@@ -90,17 +168,34 @@ impl Drop for Spinner {
 /// Streaming indicator that shows ● at the end of text while streaming
 pub struct StreamingIndicator {
     has_indicator: bool,
+    /// Without ANSI support chunks are printed plain
+    enabled: bool,
+    /// Whether any text has been printed, so the caller knows to end the line
+    printed: bool,
 }
 
 impl StreamingIndicator {
     pub fn new() -> Self {
         Self {
             has_indicator: false,
+            enabled: supports_ansi(),
+            printed: false,
         }
     }
 
+    pub fn printed(&self) -> bool {
+        self.printed
+    }
+
     /// Print chunk and add ● indicator at the end
     pub fn print_chunk(&mut self, chunk: &str) {
+        self.printed |= !chunk.is_empty();
+        if !self.enabled {
+            print!("{}", chunk);
+            io::stdout().flush().ok();
+            return;
+        }
+
         // Remove previous indicator if present
         if self.has_indicator {
             print!("\x08 \x08");
@@ -110,7 +205,7 @@ impl StreamingIndicator {
         print!("{}", chunk);
 
         // Add indicator
-        print!("●");
+        print!("{}", theme().hint("●"));
         io::stdout().flush().ok();
         self.has_indicator = true;
     }
@@ -125,6 +220,12 @@ impl StreamingIndicator {
     }
 }
 
+impl Drop for StreamingIndicator {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
 impl Default for StreamingIndicator {
     fn default() -> Self {
         Self::new()
@@ -151,6 +252,30 @@ mod tests {
         // Should not panic, drop should call stop
     }
 
+    #[test]
+    fn test_interrupt_cleanup_restores_cursor() {
+        let mut out = Vec::new();
+        write_interrupt_cleanup(&mut out, false).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1b[?25h\n");
+    }
+
+    #[test]
+    fn test_interrupt_cleanup_erases_visible_spinner() {
+        let mut out = Vec::new();
+        write_interrupt_cleanup(&mut out, true).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\x08 \x08\x1b[?25h\n");
+    }
+
+    #[test]
+    fn test_spinner_stop_deregisters_only_itself() {
+        let mut spinner = Spinner::start();
+        let id = spinner.id;
+        spinner.stop();
+
+        let active = ACTIVE_SPINNER.lock().unwrap();
+        assert!(active.as_ref().is_none_or(|a| a.id != id));
+    }
+
     #[test]
     fn test_streaming_indicator_lifecycle() {
         let mut indicator = StreamingIndicator::new();
@@ -164,9 +289,19 @@ mod tests {
     fn test_streaming_indicator_finish_without_chunks() {
         let mut indicator = StreamingIndicator::new();
         indicator.finish();
+        assert!(!indicator.printed());
         // Should not panic even without any chunks
     }
 
+    #[test]
+    fn test_streaming_indicator_tracks_printed_text() {
+        let mut indicator = StreamingIndicator::new();
+        indicator.print_chunk("");
+        assert!(!indicator.printed());
+        indicator.print_chunk("Hello");
+        assert!(indicator.printed());
+    }
+
     #[test]
     fn test_streaming_indicator_default() {
         let indicator = StreamingIndicator::default();
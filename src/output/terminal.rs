@@ -0,0 +1,95 @@
+//! Whether the terminal understands ANSI escapes (cursor moves, colors)
+
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+static SUPPORTS_ANSI: OnceLock<bool> = OnceLock::new();
+
+/// True when stdout is a terminal that can take escape sequences. On Windows this
+/// also turns on VT processing for the console, once. `NO_COLOR` and `TERM=dumb`
+/// switch escapes off entirely, so the spinner and streaming indicator go quiet too.
+pub fn supports_ansi() -> bool {
+    *SUPPORTS_ANSI.get_or_init(|| {
+        let term = std::env::var("TERM").ok();
+        let no_color = std::env::var("NO_COLOR").is_ok();
+        let is_tty = std::io::stdout().is_terminal();
+        ansi_supported(term.as_deref(), no_color, is_tty, enable_vt_processing)
+    })
+}
+
+/// Detection rules, separated from the environment for testing. `enable_vt` only
+/// runs (and touches the console mode) when nothing else rules escapes out.
+fn ansi_supported(
+    term: Option<&str>,
+    no_color: bool,
+    is_tty: bool,
+    enable_vt: impl FnOnce() -> bool,
+) -> bool {
+    is_tty && !no_color && term != Some("dumb") && enable_vt()
+}
+
+/// Enable escape processing on the Windows console; false on consoles too old for it
+#[cfg(windows)]
+fn enable_vt_processing() -> bool {
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, SetConsoleMode, ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+        STD_OUTPUT_HANDLE,
+    };
+
+    // SAFETY: plain console API calls on the process's own stdout handle
+    unsafe {
+        let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+        let mut mode = 0;
+        if GetConsoleMode(handle, &mut mode) == 0 {
+            return false;
+        }
+        mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+            || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+    }
+}
+
+/// Every other terminal handles escapes natively
+#[cfg(not(windows))]
+fn enable_vt_processing() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ansi_on_regular_terminal() {
+        assert!(ansi_supported(Some("xterm-256color"), false, true, || true));
+        // Windows consoles usually have no TERM at all
+        assert!(ansi_supported(None, false, true, || true));
+    }
+
+    #[test]
+    fn test_ansi_off_for_dumb_terminal() {
+        assert!(!ansi_supported(Some("dumb"), false, true, || true));
+    }
+
+    #[test]
+    fn test_ansi_off_with_no_color() {
+        assert!(!ansi_supported(Some("xterm"), true, true, || true));
+    }
+
+    #[test]
+    fn test_ansi_off_when_piped() {
+        assert!(!ansi_supported(Some("xterm"), false, false, || true));
+    }
+
+    #[test]
+    fn test_ansi_off_without_vt_processing() {
+        // e.g. legacy cmd.exe where the console mode can't be changed
+        assert!(!ansi_supported(None, false, true, || false));
+    }
+
+    #[test]
+    fn test_console_mode_untouched_when_piped() {
+        assert!(!ansi_supported(None, false, false, || panic!(
+            "VT processing enabled for a pipe"
+        )));
+    }
+}
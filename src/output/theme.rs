@@ -0,0 +1,214 @@
+//! Output colors by role: `[theme]` in config, `--theme light`
+
+use crate::config::ThemeConfig;
+use anyhow::{bail, Result};
+use colored::{Color, ColoredString, Colorize};
+use std::sync::OnceLock;
+
+/// The color used for each kind of output, whatever prints it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Commands shown before they run, and in confirmations
+    pub command: Color,
+    /// Titles: Markdown headers, "Sources:", numbered candidates
+    pub heading: Color,
+    /// Secondary text: hints, verbose notes, the thinking trace, the spinner
+    pub hint: Color,
+    /// Failures
+    pub error: Color,
+    /// Source numbers and links after a web search answer
+    pub citation: Color,
+    /// Render Markdown with the skin meant for light terminal backgrounds
+    pub light_background: bool,
+}
+
+impl Theme {
+    /// The colors `ask` has always used
+    pub const DARK: Theme = Theme {
+        command: Color::BrightWhite,
+        heading: Color::Cyan,
+        hint: Color::BrightBlack,
+        error: Color::Red,
+        citation: Color::Cyan,
+        light_background: false,
+    };
+
+    /// Nothing white or pale, for light terminal backgrounds
+    pub const LIGHT: Theme = Theme {
+        command: Color::Black,
+        heading: Color::Blue,
+        hint: Color::BrightBlack,
+        error: Color::Red,
+        citation: Color::Magenta,
+        light_background: true,
+    };
+
+    pub fn preset(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "dark" => Ok(Self::DARK),
+            "light" => Ok(Self::LIGHT),
+            other => bail!("Unknown theme '{}'. Use 'dark' or 'light'", other),
+        }
+    }
+
+    /// The preset (`--theme` over `[theme] preset`, dark by default), then each role set in `[theme]`
+    pub fn resolve(config: &ThemeConfig, cli_preset: Option<&str>) -> Result<Self> {
+        let mut theme = match cli_preset.or(config.preset.as_deref()) {
+            Some(name) => Self::preset(name)?,
+            None => Self::DARK,
+        };
+
+        let roles = [
+            (&mut theme.command, &config.command),
+            (&mut theme.heading, &config.heading),
+            (&mut theme.hint, &config.hint),
+            (&mut theme.error, &config.error),
+            (&mut theme.citation, &config.citation),
+        ];
+        for (color, value) in roles {
+            if let Some(value) = value {
+                *color = parse_color(value)?;
+            }
+        }
+        Ok(theme)
+    }
+
+    pub fn command(&self, text: &str) -> ColoredString {
+        text.color(self.command).bold()
+    }
+
+    pub fn heading(&self, text: &str) -> ColoredString {
+        text.color(self.heading)
+    }
+
+    pub fn hint(&self, text: &str) -> ColoredString {
+        text.color(self.hint)
+    }
+
+    pub fn error(&self, text: &str) -> ColoredString {
+        text.color(self.error)
+    }
+
+    pub fn citation(&self, text: &str) -> ColoredString {
+        text.color(self.citation)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
+
+/// A color name (`blue`, `bright black`, `bright_black`) or `#rrggbb`
+pub fn parse_color(value: &str) -> Result<Color> {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+        if let (6, Some(r), Some(g), Some(b)) = (hex.len(), channel(0), channel(2), channel(4)) {
+            return Ok(Color::TrueColor { r, g, b });
+        }
+    }
+
+    value.replace(['_', '-'], " ").parse().map_err(|_| {
+        anyhow::anyhow!(
+            "Unknown color '{}' in [theme]. Use a name like \"blue\" or \"bright black\", or #rrggbb",
+            value
+        )
+    })
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Use this theme for the rest of the process (set once the config is loaded)
+pub fn set_theme(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// The theme in use; dark until the config has been loaded
+pub fn theme() -> Theme {
+    THEME.get().copied().unwrap_or_default()
+}
+
+/// The same color for termimad's Markdown skin
+pub(super) fn crossterm_color(color: Color) -> termimad::crossterm::style::Color {
+    use termimad::crossterm::style::Color as C;
+    match color {
+        Color::Black => C::Black,
+        Color::Red => C::DarkRed,
+        Color::Green => C::DarkGreen,
+        Color::Yellow => C::DarkYellow,
+        Color::Blue => C::DarkBlue,
+        Color::Magenta => C::DarkMagenta,
+        Color::Cyan => C::DarkCyan,
+        Color::White => C::Grey,
+        Color::BrightBlack => C::DarkGrey,
+        Color::BrightRed => C::Red,
+        Color::BrightGreen => C::Green,
+        Color::BrightYellow => C::Yellow,
+        Color::BrightBlue => C::Blue,
+        Color::BrightMagenta => C::Magenta,
+        Color::BrightCyan => C::Cyan,
+        Color::BrightWhite => C::White,
+        Color::TrueColor { r, g, b } => C::Rgb { r, g, b },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        let none = ThemeConfig::default();
+        assert_eq!(Theme::resolve(&none, None).unwrap(), Theme::DARK);
+        assert_eq!(Theme::resolve(&none, Some("light")).unwrap(), Theme::LIGHT);
+        assert_eq!(Theme::preset(" Dark ").unwrap(), Theme::DARK);
+        assert!(Theme::preset("solarized").is_err());
+    }
+
+    #[test]
+    fn test_cli_preset_wins_over_config() {
+        let config = ThemeConfig {
+            preset: Some("light".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(Theme::resolve(&config, None).unwrap(), Theme::LIGHT);
+        assert_eq!(Theme::resolve(&config, Some("dark")).unwrap(), Theme::DARK);
+    }
+
+    #[test]
+    fn test_custom_roles_override_preset() {
+        let config = ThemeConfig {
+            preset: Some("light".to_string()),
+            command: Some("bright_blue".to_string()),
+            error: Some("#ff8800".to_string()),
+            ..Default::default()
+        };
+        let theme = Theme::resolve(&config, None).unwrap();
+        assert_eq!(theme.command, Color::BrightBlue);
+        assert_eq!(
+            theme.error,
+            Color::TrueColor {
+                r: 0xff,
+                g: 0x88,
+                b: 0x00
+            }
+        );
+        assert_eq!(theme.heading, Theme::LIGHT.heading);
+        assert!(theme.light_background);
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("Blue").unwrap(), Color::Blue);
+        assert_eq!(parse_color("bright black").unwrap(), Color::BrightBlack);
+        assert_eq!(parse_color("bright-cyan").unwrap(), Color::BrightCyan);
+        assert!(parse_color("#12345").is_err());
+        assert!(parse_color("#gg0000").is_err());
+        assert!(parse_color("teal").is_err());
+    }
+}
@@ -1,7 +1,14 @@
 //! Anthropic Claude provider implementation
 
-use super::{Citation, Message, Provider, ProviderOptions, ProviderResponse, StreamCallback};
-use crate::http::create_client;
+use super::{
+    join_thinking, Citation, ImageInput, Message, Provider, ProviderOptions, ProviderResponse,
+    StreamCallback, StreamEnd, Usage,
+};
+use crate::config::Config;
+use crate::http::{
+    create_client, create_request_client, create_streaming_client, parse_api_error, SendLogged,
+    SseBuffer, TextLogged,
+};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -9,11 +16,16 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// Beta header older API versions need before they honor `cache_control`
+const PROMPT_CACHING_BETA: &str = "prompt-caching-2024-07-31";
+
 pub struct AnthropicProvider {
     api_key: String,
     base_url: String,
     model: String,
     client: Client,
+    stream_client: Client,
+    prompt_cache: bool,
 }
 
 #[derive(Serialize)]
@@ -22,12 +34,38 @@ struct AnthropicRequest {
     messages: Vec<AnthropicMessage>,
     max_tokens: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    system: Option<SystemPrompt>,
     stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     thinking: Option<ThinkingConfig>,
+    /// Only sent in seeded mode; extended thinking requires the default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+}
+
+/// A plain string, or content blocks when the prompt is cached
+#[derive(Serialize)]
+#[serde(untagged)]
+enum SystemPrompt {
+    Text(String),
+    Blocks(Vec<SystemBlock>),
+}
+
+/// System prompt in content-block form, the only form `cache_control` can be set on
+#[derive(Serialize)]
+struct SystemBlock {
+    #[serde(rename = "type")]
+    block_type: &'static str,
+    text: String,
+    cache_control: CacheControl,
+}
+
+#[derive(Serialize)]
+struct CacheControl {
+    #[serde(rename = "type")]
+    cache_type: &'static str,
 }
 
 #[derive(Serialize)]
@@ -40,21 +78,39 @@ struct ThinkingConfig {
 #[derive(Serialize, Deserialize)]
 struct AnthropicMessage {
     role: String,
-    content: String,
+    content: AnthropicMessageContent,
+}
+
+/// Plain text, or content blocks such as a `tool_result` or an `image`
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum AnthropicMessageContent {
+    Text(String),
+    Blocks(Vec<Value>),
 }
 
 #[derive(Deserialize)]
 struct AnthropicResponse {
     content: Option<Vec<AnthropicContent>>,
     error: Option<AnthropicError>,
+    usage: Option<AnthropicUsage>,
+    /// "end_turn", "max_tokens", "stop_sequence", ...
+    stop_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AnthropicUsage {
+    input_tokens: u64,
+    output_tokens: u64,
 }
 
 #[derive(Deserialize)]
 struct AnthropicContent {
     #[serde(rename = "type")]
-    #[allow(dead_code)]
     content_type: Option<String>,
     text: Option<String>,
+    /// Set on `thinking` blocks when extended thinking is enabled
+    thinking: Option<String>,
     citations: Option<Vec<AnthropicCitation>>,
 }
 
@@ -79,6 +135,8 @@ struct AnthropicStreamEvent {
 #[derive(Deserialize)]
 struct AnthropicDelta {
     text: Option<String>,
+    /// Set on the `message_delta` event
+    stop_reason: Option<String>,
 }
 
 impl AnthropicProvider {
@@ -88,9 +146,24 @@ impl AnthropicProvider {
             base_url,
             model,
             client: create_client(),
+            stream_client: create_client(),
+            prompt_cache: false,
         }
     }
 
+    /// Cache the system prompt between calls (`providers.anthropic.prompt_cache`)
+    pub fn with_prompt_cache(mut self, enabled: bool) -> Self {
+        self.prompt_cache = enabled;
+        self
+    }
+
+    /// Apply configured request/stream timeouts to the HTTP clients
+    pub fn with_timeouts(mut self, config: &Config) -> Self {
+        self.client = create_request_client(config);
+        self.stream_client = create_streaming_client(config);
+        self
+    }
+
     fn convert_messages(&self, messages: &[Message]) -> (Option<String>, Vec<AnthropicMessage>) {
         let mut system = None;
         let mut result = Vec::new();
@@ -103,7 +176,22 @@ impl AnthropicProvider {
                 "user" | "assistant" => {
                     result.push(AnthropicMessage {
                         role: msg.role.clone(),
-                        content: msg.content.clone(),
+                        content: message_content(msg),
+                    });
+                }
+                // Tool results go back in a user turn
+                "tool" => {
+                    let content = match msg.tool_call_id {
+                        Some(ref id) => AnthropicMessageContent::Blocks(vec![serde_json::json!({
+                            "type": "tool_result",
+                            "tool_use_id": id,
+                            "content": msg.content,
+                        })]),
+                        None => AnthropicMessageContent::Text(msg.tool_result_text()),
+                    };
+                    result.push(AnthropicMessage {
+                        role: "user".to_string(),
+                        content,
                     });
                 }
                 _ => {}
@@ -113,6 +201,57 @@ impl AnthropicProvider {
         (system, result)
     }
 
+    /// The system prompt as a plain string, or as a text block marked for caching when enabled
+    fn system_prompt(&self, system: Option<String>) -> Option<SystemPrompt> {
+        let text = system?;
+        if !self.prompt_cache {
+            return Some(SystemPrompt::Text(text));
+        }
+        Some(SystemPrompt::Blocks(vec![SystemBlock {
+            block_type: "text",
+            text,
+            cache_control: CacheControl {
+                cache_type: "ephemeral",
+            },
+        }]))
+    }
+
+    fn build_request(
+        &self,
+        messages: &[Message],
+        options: &ProviderOptions,
+        stream: bool,
+    ) -> AnthropicRequest {
+        let (system, msgs) = self.convert_messages(messages);
+        let thinking = self.build_thinking(options);
+        AnthropicRequest {
+            model: self.model.clone(),
+            messages: msgs,
+            max_tokens: options
+                .max_tokens
+                .unwrap_or_else(|| crate::config::default_max_tokens_for("anthropic", &self.model)),
+            system: self.system_prompt(system),
+            stream,
+            tools: self.build_tools(options),
+            temperature: (options.seed.is_some() && thinking.is_none())
+                .then(|| options.temperature(1.0)),
+            thinking,
+        }
+    }
+
+    fn post(&self, client: &Client, url: &str) -> reqwest::RequestBuilder {
+        let request = client
+            .post(url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("Content-Type", "application/json");
+        if self.prompt_cache {
+            request.header("anthropic-beta", PROMPT_CACHING_BETA)
+        } else {
+            request
+        }
+    }
+
     fn build_tools(&self, options: &ProviderOptions) -> Option<Vec<Value>> {
         if !options.web_search {
             return None;
@@ -192,33 +331,23 @@ impl Provider for AnthropicProvider {
         options: &ProviderOptions,
     ) -> Result<ProviderResponse> {
         let url = format!("{}/v1/messages", self.base_url);
-        let (system, msgs) = self.convert_messages(messages);
-
-        let request = AnthropicRequest {
-            model: self.model.clone(),
-            messages: msgs,
-            max_tokens: 4096,
-            system,
-            stream: false,
-            tools: self.build_tools(options),
-            thinking: self.build_thinking(options),
-        };
+        let request = self.build_request(messages, options, false);
 
         let response = self
-            .client
-            .post(&url)
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
+            .post(&self.client, &url)
             .json(&request)
-            .send()
+            .send_logged("anthropic")
             .await?;
 
         let status = response.status();
-        let body = response.text().await?;
+        let body = response.text_logged("anthropic").await?;
 
         if !status.is_success() {
-            return Err(anyhow!("Anthropic API error ({}): {}", status, body));
+            return Err(anyhow!(
+                "Anthropic API error ({}): {}",
+                status,
+                parse_api_error(status, &body)
+            ));
         }
 
         let response: AnthropicResponse = serde_json::from_str(&body)?;
@@ -236,8 +365,18 @@ impl Provider for AnthropicProvider {
             .join("");
 
         let citations = self.extract_citations(&content);
+        let usage = response.usage.map(|u| Usage {
+            input_tokens: u.input_tokens,
+            output_tokens: u.output_tokens,
+        });
 
-        Ok(ProviderResponse { text, citations })
+        Ok(ProviderResponse {
+            text,
+            citations,
+            usage,
+            thinking_text: extract_thinking(&content),
+            finish_reason: response.stop_reason,
+        })
     }
 
     async fn stream_with_options(
@@ -245,57 +384,63 @@ impl Provider for AnthropicProvider {
         messages: &[Message],
         mut callback: StreamCallback,
         options: &ProviderOptions,
-    ) -> Result<()> {
+    ) -> Result<StreamEnd> {
         let url = format!("{}/v1/messages", self.base_url);
-        let (system, msgs) = self.convert_messages(messages);
-
-        let request = AnthropicRequest {
-            model: self.model.clone(),
-            messages: msgs,
-            max_tokens: 4096,
-            system,
-            stream: true,
-            tools: self.build_tools(options),
-            thinking: self.build_thinking(options),
-        };
+        let request = self.build_request(messages, options, true);
 
         let response = self
-            .client
-            .post(&url)
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
+            .post(&self.stream_client, &url)
             .json(&request)
-            .send()
+            .send_logged("anthropic")
             .await?;
 
-        if !response.status().is_success() {
-            let body = response.text().await?;
-            return Err(anyhow!("Anthropic API error: {}", body));
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text_logged("anthropic").await?;
+            return Err(anyhow!(
+                "Anthropic API error ({}): {}",
+                status,
+                parse_api_error(status, &body)
+            ));
         }
 
         let mut stream = response.bytes_stream();
-
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk?;
-            let text = String::from_utf8_lossy(&chunk);
-
-            for line in text.lines() {
-                if let Some(data) = line.strip_prefix("data: ") {
-                    if let Ok(event) = serde_json::from_str::<AnthropicStreamEvent>(data) {
-                        if event.event_type == "content_block_delta" {
-                            if let Some(delta) = event.delta {
-                                if let Some(text) = delta.text {
-                                    callback(&text);
-                                }
-                            }
+        let mut sse = SseBuffer::logged("anthropic");
+        let mut end = StreamEnd::default();
+
+        let mut handle_event = |data: &str| {
+            if let Ok(event) = serde_json::from_str::<AnthropicStreamEvent>(data) {
+                let Some(delta) = event.delta else {
+                    return;
+                };
+                match event.event_type.as_str() {
+                    "content_block_delta" => {
+                        if let Some(text) = delta.text {
+                            callback(&text);
                         }
                     }
+                    // The last event before `message_stop` says why generation stopped
+                    "message_delta" => end.finish_reason = delta.stop_reason,
+                    _ => {}
                 }
             }
+        };
+
+        while let Some(chunk) = stream.next().await {
+            for data in sse.push(&chunk?) {
+                handle_event(&data);
+            }
         }
+        if let Some(data) = sse.finish() {
+            handle_event(&data);
+        }
+
+        Ok(end)
+    }
 
-        Ok(())
+    fn thinking_param(&self, options: &ProviderOptions) -> Option<(&'static str, String)> {
+        self.build_thinking(options)
+            .map(|thinking| ("thinking.budget_tokens", thinking.budget_tokens.to_string()))
     }
 
     fn name(&self) -> &str {
@@ -307,10 +452,91 @@ impl Provider for AnthropicProvider {
     }
 }
 
+/// Text of the `thinking` content blocks, in order
+fn extract_thinking(content: &[AnthropicContent]) -> Option<String> {
+    join_thinking(
+        content
+            .iter()
+            .filter(|c| c.content_type.as_deref() == Some("thinking"))
+            .filter_map(|c| c.thinking.clone()),
+    )
+}
+
+/// The text alone, or image blocks followed by the text (Anthropic's
+/// recommended order). URLs use a `url` source; inline images a `base64` one.
+fn message_content(message: &Message) -> AnthropicMessageContent {
+    if message.images.is_empty() {
+        return AnthropicMessageContent::Text(message.content.clone());
+    }
+    let mut blocks: Vec<Value> = message
+        .images
+        .iter()
+        .map(|image| {
+            let source = match image {
+                ImageInput::Url(url) => serde_json::json!({ "type": "url", "url": url }),
+                ImageInput::Inline { mime_type, data } => serde_json::json!({
+                    "type": "base64",
+                    "media_type": mime_type,
+                    "data": data,
+                }),
+            };
+            serde_json::json!({ "type": "image", "source": source })
+        })
+        .collect();
+    blocks.push(serde_json::json!({ "type": "text", "text": message.content }));
+    AnthropicMessageContent::Blocks(blocks)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_extract_thinking_block() {
+        let response: AnthropicResponse = serde_json::from_str(
+            r#"{
+                "content": [
+                    {"type": "thinking", "thinking": "The user wants disk usage.", "signature": "abc"},
+                    {"type": "text", "text": "du -sh ."}
+                ],
+                "usage": {"input_tokens": 10, "output_tokens": 5}
+            }"#,
+        )
+        .unwrap();
+        let content = response.content.unwrap();
+
+        assert_eq!(
+            extract_thinking(&content).as_deref(),
+            Some("The user wants disk usage.")
+        );
+        let text: String = content.iter().filter_map(|c| c.text.clone()).collect();
+        assert_eq!(text, "du -sh .");
+    }
+
+    #[test]
+    fn test_stop_reason_max_tokens() {
+        let response: AnthropicResponse = serde_json::from_str(
+            r#"{"content": [{"type": "text", "text": "1."}], "stop_reason": "max_tokens"}"#,
+        )
+        .unwrap();
+        let answer = ProviderResponse {
+            finish_reason: response.stop_reason,
+            ..Default::default()
+        };
+        assert!(answer.is_truncated());
+
+        let response: AnthropicResponse =
+            serde_json::from_str(r#"{"content": [], "stop_reason": "end_turn"}"#).unwrap();
+        assert_eq!(response.stop_reason.as_deref(), Some("end_turn"));
+    }
+
+    #[test]
+    fn test_extract_thinking_absent() {
+        let response: AnthropicResponse =
+            serde_json::from_str(r#"{"content": [{"type": "text", "text": "hi"}]}"#).unwrap();
+        assert!(extract_thinking(&response.content.unwrap()).is_none());
+    }
+
     #[test]
     fn test_build_thinking_levels() {
         let provider =
@@ -332,6 +558,10 @@ mod tests {
                 web_search: false,
                 allowed_domains: None,
                 blocked_domains: None,
+                max_tokens: None,
+                include_thoughts: false,
+                candidates: None,
+                seed: None,
             };
 
             let config = provider.build_thinking(&options).unwrap();
@@ -343,6 +573,105 @@ mod tests {
         }
     }
 
+    fn plain_options() -> ProviderOptions {
+        ProviderOptions {
+            thinking_enabled: false,
+            thinking_value: None,
+            web_search: false,
+            allowed_domains: None,
+            blocked_domains: None,
+            max_tokens: None,
+            include_thoughts: false,
+            candidates: None,
+            seed: None,
+        }
+    }
+
+    fn messages() -> Vec<Message> {
+        vec![
+            Message {
+                role: "system".to_string(),
+                content: "You are a shell expert.".to_string(),
+                ..Default::default()
+            },
+            Message {
+                role: "user".to_string(),
+                content: "list files".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_request_with_prompt_cache_marks_system_block() {
+        let provider = AnthropicProvider::new("key".into(), "url".into(), "claude".into())
+            .with_prompt_cache(true);
+        let request = provider.build_request(&messages(), &plain_options(), false);
+        let json = serde_json::to_value(&request).unwrap();
+
+        assert_eq!(
+            json["system"],
+            serde_json::json!([{
+                "type": "text",
+                "text": "You are a shell expert.",
+                "cache_control": {"type": "ephemeral"}
+            }])
+        );
+        assert_eq!(json["messages"][0]["content"], "list files");
+    }
+
+    #[test]
+    fn test_request_without_prompt_cache() {
+        let provider = AnthropicProvider::new("key".into(), "url".into(), "claude".into());
+        let request = provider.build_request(&messages(), &plain_options(), true);
+        let json = serde_json::to_value(&request).unwrap();
+
+        assert_eq!(json["system"], "You are a shell expert.");
+        assert_eq!(json["stream"], true);
+
+        let no_system = provider.build_request(&messages()[1..], &plain_options(), false);
+        let json = serde_json::to_value(&no_system).unwrap();
+        assert!(json.get("system").is_none());
+    }
+
+    #[test]
+    fn test_request_seed_sets_temperature_only() {
+        let provider = AnthropicProvider::new("key".into(), "url".into(), "claude".into());
+        let json =
+            serde_json::to_value(provider.build_request(&messages(), &plain_options(), false))
+                .unwrap();
+        assert!(json.get("temperature").is_none());
+
+        let options = ProviderOptions {
+            seed: Some(42),
+            ..plain_options()
+        };
+        let json =
+            serde_json::to_value(provider.build_request(&messages(), &options, false)).unwrap();
+        assert_eq!(json["temperature"], 0.0);
+        assert!(json.get("seed").is_none());
+        assert!(!provider.supports_seed());
+    }
+
+    #[test]
+    fn test_request_max_tokens_default_per_model() {
+        let sonnet = AnthropicProvider::new("key".into(), "url".into(), "claude-sonnet-4-5".into());
+        let request = sonnet.build_request(&messages(), &plain_options(), false);
+        assert_eq!(request.max_tokens, 64000);
+
+        let haiku_3 =
+            AnthropicProvider::new("key".into(), "url".into(), "claude-3-haiku-20240307".into());
+        let request = haiku_3.build_request(&messages(), &plain_options(), false);
+        assert_eq!(request.max_tokens, 4096);
+
+        let options = ProviderOptions {
+            max_tokens: Some(256),
+            ..plain_options()
+        };
+        let request = sonnet.build_request(&messages(), &options, false);
+        assert_eq!(request.max_tokens, 256, "--max-tokens wins");
+    }
+
     #[test]
     fn test_build_thinking_disabled() {
         let provider =
@@ -353,7 +682,64 @@ mod tests {
             web_search: false,
             allowed_domains: None,
             blocked_domains: None,
+            max_tokens: None,
+            include_thoughts: false,
+            candidates: None,
+            seed: None,
         };
         assert!(provider.build_thinking(&options).is_none());
     }
+
+    #[test]
+    fn test_convert_tool_messages() {
+        let provider = AnthropicProvider::new("key".into(), "url".into(), "claude".into());
+        let messages = [
+            Message {
+                role: "user".into(),
+                content: "how big is /home?".into(),
+                ..Default::default()
+            },
+            Message {
+                role: "tool".into(),
+                content: "42G /home".into(),
+                tool_call_id: Some("call_1".into()),
+                name: Some("du".into()),
+                ..Default::default()
+            },
+            Message {
+                role: "tool".into(),
+                content: "ok".into(),
+                ..Default::default()
+            },
+        ];
+        let (_, converted) = provider.convert_messages(&messages);
+        let json = serde_json::to_value(converted).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3, "no message dropped");
+        assert_eq!(json[1]["role"], "user");
+        assert_eq!(json[1]["content"][0]["type"], "tool_result");
+        assert_eq!(json[1]["content"][0]["tool_use_id"], "call_1");
+        assert_eq!(json[1]["content"][0]["content"], "42G /home");
+        assert_eq!(json[2]["content"], "Tool result:\nok");
+        assert_eq!(json[0]["content"], "how big is /home?");
+    }
+
+    #[test]
+    fn test_convert_image_url_message() {
+        let provider =
+            AnthropicProvider::new("key".into(), "url".into(), "claude-sonnet-4-5".into());
+        let messages = [Message {
+            role: "user".into(),
+            content: "what is this".into(),
+            images: vec![ImageInput::Url("https://example.com/photo.jpg".into())],
+            ..Default::default()
+        }];
+        let (_, converted) = provider.convert_messages(&messages);
+        let json = serde_json::to_value(&converted).unwrap();
+        let blocks = json[0]["content"].as_array().unwrap();
+        assert_eq!(blocks[0]["type"], "image");
+        assert_eq!(blocks[0]["source"]["type"], "url");
+        assert_eq!(blocks[0]["source"]["url"], "https://example.com/photo.jpg");
+        assert_eq!(blocks[1]["type"], "text");
+        assert_eq!(blocks[1]["text"], "what is this");
+    }
 }
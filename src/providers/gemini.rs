@@ -1,7 +1,14 @@
 //! Google Gemini provider implementation
 
-use super::{Citation, Message, Provider, ProviderOptions, ProviderResponse, StreamCallback};
-use crate::http::create_client;
+use super::{
+    images, join_thinking, Citation, ImageInput, Message, Provider, ProviderOptions,
+    ProviderResponse, StreamCallback, StreamEnd, Usage,
+};
+use crate::config::Config;
+use crate::http::{
+    create_client, create_request_client, create_streaming_client, parse_api_error, SendLogged,
+    SseBuffer, TextLogged,
+};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -14,6 +21,7 @@ pub struct GeminiProvider {
     base_url: String,
     model: String,
     client: Client,
+    stream_client: Client,
 }
 
 #[derive(Serialize)]
@@ -32,8 +40,19 @@ struct GeminiContent {
 }
 
 #[derive(Serialize)]
-struct GeminiPart {
-    text: String,
+#[serde(untagged)]
+enum GeminiPart {
+    Text {
+        text: String,
+    },
+    FunctionResponse {
+        #[serde(rename = "functionResponse")]
+        function_response: Value,
+    },
+    InlineData {
+        #[serde(rename = "inlineData")]
+        inline_data: Value,
+    },
 }
 
 #[derive(Serialize)]
@@ -44,6 +63,9 @@ struct GenerationConfig {
     max_output_tokens: Option<u32>,
     #[serde(rename = "thinkingConfig", skip_serializing_if = "Option::is_none")]
     thinking_config: Option<ThinkingConfig>,
+    /// Number of candidates to generate (`-N`)
+    #[serde(rename = "candidateCount", skip_serializing_if = "Option::is_none")]
+    candidate_count: Option<u32>,
 }
 
 #[derive(Serialize)]
@@ -52,29 +74,65 @@ struct ThinkingConfig {
     thinking_level: Option<String>,
     #[serde(rename = "thinkingBudget", skip_serializing_if = "Option::is_none")]
     thinking_budget: Option<i32>,
+    #[serde(rename = "includeThoughts", skip_serializing_if = "is_false")]
+    include_thoughts: bool,
+}
+
+fn is_false(v: &bool) -> bool {
+    !v
 }
 
 #[derive(Deserialize)]
 struct GeminiResponse {
     candidates: Option<Vec<GeminiCandidate>>,
     error: Option<GeminiError>,
+    #[serde(rename = "usageMetadata")]
+    usage_metadata: Option<GeminiUsage>,
+    #[serde(rename = "promptFeedback")]
+    prompt_feedback: Option<PromptFeedback>,
+}
+
+/// Set when the prompt itself was blocked (no candidates are returned)
+#[derive(Deserialize)]
+struct PromptFeedback {
+    #[serde(rename = "blockReason")]
+    block_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GeminiUsage {
+    #[serde(rename = "promptTokenCount", default)]
+    prompt_token_count: u64,
+    #[serde(rename = "candidatesTokenCount", default)]
+    candidates_token_count: u64,
+    // Thinking tokens are billed as output
+    #[serde(rename = "thoughtsTokenCount", default)]
+    thoughts_token_count: u64,
 }
 
 #[derive(Deserialize)]
 struct GeminiCandidate {
+    /// Missing on candidates stopped by a safety filter
+    #[serde(default)]
     content: GeminiContentResponse,
     #[serde(rename = "groundingMetadata")]
     grounding_metadata: Option<GroundingMetadata>,
+    #[serde(rename = "finishReason")]
+    finish_reason: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 struct GeminiContentResponse {
+    #[serde(default)]
     parts: Vec<GeminiPartResponse>,
 }
 
 #[derive(Deserialize)]
 struct GeminiPartResponse {
     text: Option<String>,
+    /// Thought summary parts (only sent with `includeThoughts`)
+    #[serde(default)]
+    thought: bool,
 }
 
 #[derive(Deserialize)]
@@ -102,6 +160,72 @@ struct WebChunk {
 #[derive(Deserialize)]
 struct GeminiStreamResponse {
     candidates: Option<Vec<GeminiCandidate>>,
+    #[serde(rename = "promptFeedback")]
+    prompt_feedback: Option<PromptFeedback>,
+}
+
+/// Finish reasons of a candidate that ended normally
+const NORMAL_FINISH_REASONS: &[&str] = &["STOP", "MAX_TOKENS", "FINISH_REASON_UNSPECIFIED"];
+
+/// Why Gemini withheld the answer: the prompt's `blockReason`, or the
+/// `finishReason` of a candidate cut off by a filter (SAFETY, RECITATION, ...)
+fn block_reason(
+    feedback: Option<&PromptFeedback>,
+    candidates: &[GeminiCandidate],
+) -> Option<String> {
+    feedback.and_then(|f| f.block_reason.clone()).or_else(|| {
+        candidates
+            .iter()
+            .filter_map(|c| c.finish_reason.as_deref())
+            .find(|reason| !NORMAL_FINISH_REASONS.contains(reason))
+            .map(str::to_string)
+    })
+}
+
+fn blocked_error(reason: &str) -> anyhow::Error {
+    anyhow!("Gemini blocked the response (reason: {})", reason)
+}
+
+fn answer_text(candidate: &GeminiCandidate) -> Option<&str> {
+    candidate
+        .content
+        .parts
+        .iter()
+        .find(|p| !p.thought)
+        .and_then(|p| p.text.as_deref())
+}
+
+/// Send the answer parts of one SSE event to `callback`; remembers whether any
+/// text arrived and why the response was blocked, if it was
+fn handle_stream_event(
+    data: &str,
+    callback: &mut StreamCallback,
+    got_text: &mut bool,
+    blocked: &mut Option<String>,
+    finish_reason: &mut Option<String>,
+) {
+    let Ok(response) = serde_json::from_str::<GeminiStreamResponse>(data) else {
+        return;
+    };
+    let candidates = response.candidates.unwrap_or_default();
+    if blocked.is_none() {
+        *blocked = block_reason(response.prompt_feedback.as_ref(), &candidates);
+    }
+
+    for candidate in candidates {
+        if candidate.finish_reason.is_some() {
+            *finish_reason = candidate.finish_reason;
+        }
+        for part in candidate.content.parts {
+            if part.thought {
+                continue;
+            }
+            if let Some(text) = part.text {
+                *got_text |= !text.is_empty();
+                callback(&text);
+            }
+        }
+    }
 }
 
 impl GeminiProvider {
@@ -111,9 +235,17 @@ impl GeminiProvider {
             base_url,
             model,
             client: create_client(),
+            stream_client: create_client(),
         }
     }
 
+    /// Apply configured request/stream timeouts to the HTTP clients
+    pub fn with_timeouts(mut self, config: &Config) -> Self {
+        self.client = create_request_client(config);
+        self.stream_client = create_streaming_client(config);
+        self
+    }
+
     fn convert_messages(&self, messages: &[Message]) -> Vec<GeminiContent> {
         let mut contents = Vec::new();
         let mut system_text = String::new();
@@ -123,24 +255,43 @@ impl GeminiProvider {
                 "system" => {
                     system_text = msg.content.clone();
                 }
-                "user" => {
+                "tool" if msg.tool_call_id.is_some() => {
+                    let name = msg.name.as_deref().or(msg.tool_call_id.as_deref());
+                    contents.push(GeminiContent {
+                        role: "user".to_string(),
+                        parts: vec![GeminiPart::FunctionResponse {
+                            function_response: serde_json::json!({
+                                "name": name,
+                                "response": { "content": msg.content },
+                            }),
+                        }],
+                    });
+                }
+                "user" | "tool" => {
+                    let content = if msg.role == "tool" {
+                        msg.tool_result_text()
+                    } else {
+                        msg.content.clone()
+                    };
                     let text = if !system_text.is_empty() {
-                        let combined = format!("{}\n\n{}", system_text, msg.content);
+                        let combined = format!("{}\n\n{}", system_text, content);
                         system_text.clear();
                         combined
                     } else {
-                        msg.content.clone()
+                        content
                     };
 
+                    let mut parts = vec![GeminiPart::Text { text }];
+                    parts.extend(inline_image_parts(msg));
                     contents.push(GeminiContent {
                         role: "user".to_string(),
-                        parts: vec![GeminiPart { text }],
+                        parts,
                     });
                 }
                 "assistant" => {
                     contents.push(GeminiContent {
                         role: "model".to_string(),
-                        parts: vec![GeminiPart {
+                        parts: vec![GeminiPart::Text {
                             text: msg.content.clone(),
                         }],
                     });
@@ -185,6 +336,7 @@ impl GeminiProvider {
                 Some(ThinkingConfig {
                     thinking_level: Some(value),
                     thinking_budget: None,
+                    include_thoughts: options.include_thoughts,
                 })
             } else {
                 // For Gemini 2.5, convert level to budget or parse as number
@@ -198,6 +350,7 @@ impl GeminiProvider {
                 Some(ThinkingConfig {
                     thinking_level: None,
                     thinking_budget: Some(budget),
+                    include_thoughts: options.include_thoughts,
                 })
             }
         } else {
@@ -208,10 +361,25 @@ impl GeminiProvider {
             temperature: if options.thinking_enabled && self.supports_thinking() {
                 None
             } else {
-                Some(0.7)
+                Some(options.temperature(0.7))
             },
-            max_output_tokens: Some(65536),
+            max_output_tokens: Some(
+                options.max_tokens.unwrap_or_else(|| {
+                    crate::config::default_max_tokens_for("gemini", &self.model)
+                }),
+            ),
             thinking_config,
+            candidate_count: Some(options.candidate_count()).filter(|&n| n > 1),
+        }
+    }
+
+    fn candidate_response(&self, candidate: &GeminiCandidate) -> ProviderResponse {
+        ProviderResponse {
+            text: answer_text(candidate).unwrap_or_default().to_string(),
+            citations: self.extract_citations(candidate),
+            usage: None,
+            thinking_text: extract_thinking(candidate),
+            finish_reason: candidate.finish_reason.clone(),
         }
     }
 
@@ -232,22 +400,21 @@ impl GeminiProvider {
         }
         citations
     }
-}
 
-#[async_trait]
-impl Provider for GeminiProvider {
-    async fn complete_with_options(
+    /// generateContent request; one response per candidate (usage on the first)
+    async fn generate(
         &self,
         messages: &[Message],
         options: &ProviderOptions,
-    ) -> Result<ProviderResponse> {
+    ) -> Result<Vec<ProviderResponse>> {
         let url = format!(
             "{}/v1beta/models/{}:generateContent?key={}",
             self.base_url, self.model, self.api_key
         );
 
+        let messages = images::inline_images(&self.client, messages).await?;
         let request = GeminiRequest {
-            contents: self.convert_messages(messages),
+            contents: self.convert_messages(&messages),
             generation_config: Some(self.build_generation_config(options)),
             tools: self.build_tools(options),
         };
@@ -257,14 +424,18 @@ impl Provider for GeminiProvider {
             .post(&url)
             .header("Content-Type", "application/json")
             .json(&request)
-            .send()
+            .send_logged("gemini")
             .await?;
 
         let status = response.status();
-        let body = response.text().await?;
+        let body = response.text_logged("gemini").await?;
 
         if !status.is_success() {
-            return Err(anyhow!("Gemini API error ({}): {}", status, body));
+            return Err(anyhow!(
+                "Gemini API error ({}): {}",
+                status,
+                parse_api_error(status, &body)
+            ));
         }
 
         let response: GeminiResponse = serde_json::from_str(&body)?;
@@ -273,20 +444,51 @@ impl Provider for GeminiProvider {
             return Err(anyhow!("Gemini error: {}", error.message));
         }
 
-        let candidate = response.candidates.and_then(|c| c.into_iter().next());
+        let usage = response.usage_metadata.map(|u| Usage {
+            input_tokens: u.prompt_token_count,
+            output_tokens: u.candidates_token_count + u.thoughts_token_count,
+        });
 
-        let text = candidate
-            .as_ref()
-            .and_then(|c| c.content.parts.first())
-            .and_then(|p| p.text.clone())
-            .unwrap_or_default();
+        let candidates = response.candidates.unwrap_or_default();
+        if !candidates.iter().any(|c| answer_text(c).is_some()) {
+            if let Some(reason) = block_reason(response.prompt_feedback.as_ref(), &candidates) {
+                return Err(blocked_error(&reason));
+            }
+        }
 
-        let citations = candidate
-            .as_ref()
-            .map(|c| self.extract_citations(c))
-            .unwrap_or_default();
+        let mut responses: Vec<ProviderResponse> = candidates
+            .iter()
+            .map(|candidate| self.candidate_response(candidate))
+            .collect();
+        if responses.is_empty() {
+            responses.push(ProviderResponse::default());
+        }
+        responses[0].usage = usage;
 
-        Ok(ProviderResponse { text, citations })
+        Ok(responses)
+    }
+}
+
+#[async_trait]
+impl Provider for GeminiProvider {
+    async fn complete_with_options(
+        &self,
+        messages: &[Message],
+        options: &ProviderOptions,
+    ) -> Result<ProviderResponse> {
+        let single = ProviderOptions {
+            candidates: None,
+            ..options.clone()
+        };
+        Ok(self.generate(messages, &single).await?.remove(0))
+    }
+
+    async fn complete_candidates(
+        &self,
+        messages: &[Message],
+        options: &ProviderOptions,
+    ) -> Result<Vec<ProviderResponse>> {
+        self.generate(messages, options).await
     }
 
     async fn stream_with_options(
@@ -294,81 +496,79 @@ impl Provider for GeminiProvider {
         messages: &[Message],
         mut callback: StreamCallback,
         options: &ProviderOptions,
-    ) -> Result<()> {
+    ) -> Result<StreamEnd> {
         let url = format!(
             "{}/v1beta/models/{}:streamGenerateContent?key={}&alt=sse",
             self.base_url, self.model, self.api_key
         );
 
+        let messages = images::inline_images(&self.client, messages).await?;
         let request = GeminiRequest {
-            contents: self.convert_messages(messages),
+            contents: self.convert_messages(&messages),
             generation_config: Some(self.build_generation_config(options)),
             tools: self.build_tools(options),
         };
 
         let response = self
-            .client
+            .stream_client
             .post(&url)
             .header("Content-Type", "application/json")
             .json(&request)
-            .send()
+            .send_logged("gemini")
             .await?;
 
-        if !response.status().is_success() {
-            let body = response.text().await?;
-            return Err(anyhow!("Gemini API error: {}", body));
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text_logged("gemini").await?;
+            return Err(anyhow!(
+                "Gemini API error ({}): {}",
+                status,
+                parse_api_error(status, &body)
+            ));
         }
 
         let mut stream = response.bytes_stream();
-        let mut buffer = String::new();
+        let mut sse = SseBuffer::logged("gemini");
+        let mut got_text = false;
+        let mut blocked = None;
+        let mut end = StreamEnd::default();
 
         while let Some(chunk) = stream.next().await {
-            let chunk = chunk?;
-            buffer.push_str(&String::from_utf8_lossy(&chunk));
-
-            // Process complete lines from buffer
-            while let Some(newline_pos) = buffer.find('\n') {
-                let line = buffer[..newline_pos].trim().to_string();
-                buffer = buffer[newline_pos + 1..].to_string();
-
-                if line.is_empty() {
-                    continue;
-                }
-
-                if let Some(data) = line.strip_prefix("data: ") {
-                    if let Ok(response) = serde_json::from_str::<GeminiStreamResponse>(data) {
-                        if let Some(candidates) = response.candidates {
-                            for candidate in candidates {
-                                for part in candidate.content.parts {
-                                    if let Some(text) = part.text {
-                                        callback(&text);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+            for data in sse.push(&chunk?) {
+                handle_stream_event(
+                    &data,
+                    &mut callback,
+                    &mut got_text,
+                    &mut blocked,
+                    &mut end.finish_reason,
+                );
             }
         }
 
-        // Process any remaining data in buffer after stream ends
-        if !buffer.trim().is_empty() {
-            if let Some(data) = buffer.trim().strip_prefix("data: ") {
-                if let Ok(response) = serde_json::from_str::<GeminiStreamResponse>(data) {
-                    if let Some(candidates) = response.candidates {
-                        for candidate in candidates {
-                            for part in candidate.content.parts {
-                                if let Some(text) = part.text {
-                                    callback(&text);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+        // A last event the stream ended without a newline
+        if let Some(data) = sse.finish() {
+            handle_stream_event(
+                &data,
+                &mut callback,
+                &mut got_text,
+                &mut blocked,
+                &mut end.finish_reason,
+            );
         }
 
-        Ok(())
+        match blocked {
+            Some(reason) if !got_text => Err(blocked_error(&reason)),
+            _ => Ok(end),
+        }
+    }
+
+    fn thinking_param(&self, options: &ProviderOptions) -> Option<(&'static str, String)> {
+        let thinking = self.build_generation_config(options).thinking_config?;
+        match (thinking.thinking_level, thinking.thinking_budget) {
+            (Some(level), _) => Some(("thinkingConfig.thinkingLevel", level)),
+            (None, Some(budget)) => Some(("thinkingConfig.thinkingBudget", budget.to_string())),
+            (None, None) => None,
+        }
     }
 
     fn name(&self) -> &str {
@@ -379,3 +579,265 @@ impl Provider for GeminiProvider {
         &self.model
     }
 }
+
+/// Text of the thought parts (`"thought": true`), in order
+fn extract_thinking(candidate: &GeminiCandidate) -> Option<String> {
+    join_thinking(
+        candidate
+            .content
+            .parts
+            .iter()
+            .filter(|p| p.thought)
+            .filter_map(|p| p.text.clone()),
+    )
+}
+
+/// `inlineData` parts for a message's images. Gemini won't fetch arbitrary
+/// URLs, so callers download them first with `images::inline_images`.
+fn inline_image_parts(message: &Message) -> impl Iterator<Item = GeminiPart> + '_ {
+    message.images.iter().filter_map(|image| match image {
+        ImageInput::Inline { mime_type, data } => Some(GeminiPart::InlineData {
+            inline_data: serde_json::json!({ "mimeType": mime_type, "data": data }),
+        }),
+        ImageInput::Url(_) => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_thinking_thought_parts() {
+        let response: GeminiResponse = serde_json::from_str(
+            r#"{
+                "candidates": [{
+                    "content": {
+                        "parts": [
+                            {"text": "**Listing files**\nThe user wants a listing.", "thought": true},
+                            {"text": "ls -la"}
+                        ]
+                    }
+                }]
+            }"#,
+        )
+        .unwrap();
+        let candidate = response.candidates.unwrap().remove(0);
+
+        assert_eq!(
+            extract_thinking(&candidate).as_deref(),
+            Some("**Listing files**\nThe user wants a listing.")
+        );
+        let answer = candidate.content.parts.iter().find(|p| !p.thought).unwrap();
+        assert_eq!(answer.text.as_deref(), Some("ls -la"));
+    }
+
+    #[test]
+    fn test_block_reason_from_prompt_feedback() {
+        let response: GeminiResponse = serde_json::from_str(
+            r#"{"promptFeedback": {"blockReason": "SAFETY", "safetyRatings": []}}"#,
+        )
+        .unwrap();
+        let candidates = response.candidates.unwrap_or_default();
+        assert_eq!(
+            block_reason(response.prompt_feedback.as_ref(), &candidates).as_deref(),
+            Some("SAFETY")
+        );
+        assert_eq!(
+            blocked_error("SAFETY").to_string(),
+            "Gemini blocked the response (reason: SAFETY)"
+        );
+    }
+
+    #[test]
+    fn test_block_reason_from_candidate_without_content() {
+        let response: GeminiResponse =
+            serde_json::from_str(r#"{"candidates": [{"finishReason": "RECITATION", "index": 0}]}"#)
+                .unwrap();
+        let candidates = response.candidates.unwrap();
+        assert!(answer_text(&candidates[0]).is_none());
+        assert_eq!(
+            block_reason(None, &candidates).as_deref(),
+            Some("RECITATION")
+        );
+    }
+
+    #[test]
+    fn test_finish_reason_max_tokens() {
+        let provider = GeminiProvider::new("key".into(), "url".into(), "gemini-2.5-flash".into());
+        let response: GeminiResponse = serde_json::from_str(
+            r#"{"candidates": [{"content": {"parts": [{"text": "First,"}]}, "finishReason": "MAX_TOKENS"}]}"#,
+        )
+        .unwrap();
+        let candidates = response.candidates.unwrap();
+
+        let answer = provider.candidate_response(&candidates[0]);
+        assert_eq!(answer.text, "First,");
+        assert_eq!(answer.finish_reason.as_deref(), Some("MAX_TOKENS"));
+        assert!(answer.is_truncated());
+        assert!(block_reason(None, &candidates).is_none());
+    }
+
+    #[test]
+    fn test_block_reason_ignores_normal_finish() {
+        let response: GeminiResponse = serde_json::from_str(
+            r#"{"candidates": [{"content": {"parts": [{"text": "hi"}]}, "finishReason": "STOP"}]}"#,
+        )
+        .unwrap();
+        let candidates = response.candidates.unwrap();
+        assert_eq!(answer_text(&candidates[0]), Some("hi"));
+        assert!(block_reason(None, &candidates).is_none());
+    }
+
+    #[test]
+    fn test_stream_event_records_block() {
+        let mut chunks = Vec::new();
+        let mut callback: StreamCallback = Box::new(move |t: &str| chunks.push(t.to_string()));
+        let mut got_text = false;
+        let mut blocked = None;
+        let mut finish_reason = None;
+
+        handle_stream_event(
+            r#"{"candidates": [{"content": {"parts": []}, "finishReason": "SAFETY"}]}"#,
+            &mut callback,
+            &mut got_text,
+            &mut blocked,
+            &mut finish_reason,
+        );
+        assert!(!got_text);
+        assert_eq!(blocked.as_deref(), Some("SAFETY"));
+    }
+
+    #[test]
+    fn test_stream_event_records_finish_reason() {
+        let mut callback: StreamCallback = Box::new(|_: &str| {});
+        let mut got_text = false;
+        let mut blocked = None;
+        let mut end = StreamEnd::default();
+
+        for data in [
+            r#"{"candidates": [{"content": {"parts": [{"text": "1. Install"}]}}]}"#,
+            r#"{"candidates": [{"content": {"parts": [{"text": " the"}]}, "finishReason": "MAX_TOKENS"}]}"#,
+        ] {
+            handle_stream_event(
+                data,
+                &mut callback,
+                &mut got_text,
+                &mut blocked,
+                &mut end.finish_reason,
+            );
+        }
+        assert!(got_text);
+        assert!(blocked.is_none());
+        assert!(end.is_truncated());
+    }
+
+    #[test]
+    fn test_candidate_count_in_generation_config() {
+        let provider = GeminiProvider::new("key".into(), "url".into(), "gemini-2.5-flash".into());
+
+        let config =
+            serde_json::to_value(provider.build_generation_config(&ProviderOptions::default()))
+                .unwrap();
+        assert!(config.get("candidateCount").is_none());
+
+        let options = ProviderOptions {
+            candidates: Some(3),
+            ..Default::default()
+        };
+        let config = serde_json::to_value(provider.build_generation_config(&options)).unwrap();
+        assert_eq!(config["candidateCount"], 3);
+    }
+
+    #[test]
+    fn test_seed_sets_temperature_only() {
+        let provider = GeminiProvider::new("key".into(), "url".into(), "gemini-2.0-flash".into());
+        let options = ProviderOptions {
+            seed: Some(42),
+            ..Default::default()
+        };
+        let config = serde_json::to_value(provider.build_generation_config(&options)).unwrap();
+        assert_eq!(config["temperature"], 0.0);
+        assert!(config.get("seed").is_none());
+        assert!(!provider.supports_seed());
+    }
+
+    #[test]
+    fn test_include_thoughts_only_when_requested() {
+        let provider = GeminiProvider::new("key".into(), "url".into(), "gemini-2.5-flash".into());
+        let mut options = ProviderOptions {
+            thinking_enabled: true,
+            ..Default::default()
+        };
+
+        let config = serde_json::to_value(provider.build_generation_config(&options)).unwrap();
+        assert!(config["thinkingConfig"].get("includeThoughts").is_none());
+
+        options.include_thoughts = true;
+        let config = serde_json::to_value(provider.build_generation_config(&options)).unwrap();
+        assert_eq!(config["thinkingConfig"]["includeThoughts"], true);
+    }
+
+    #[test]
+    fn test_convert_tool_messages() {
+        let provider = GeminiProvider::new("key".into(), "url".into(), "gemini-2.5-flash".into());
+        let messages = [
+            Message {
+                role: "user".into(),
+                content: "how big is /home?".into(),
+                ..Default::default()
+            },
+            Message {
+                role: "tool".into(),
+                content: "42G /home".into(),
+                tool_call_id: Some("call_1".into()),
+                name: Some("du".into()),
+                ..Default::default()
+            },
+            Message {
+                role: "tool".into(),
+                content: "ok".into(),
+                ..Default::default()
+            },
+        ];
+        let json = serde_json::to_value(provider.convert_messages(&messages)).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3, "no message dropped");
+        let response = &json[1]["parts"][0]["functionResponse"];
+        assert_eq!(response["name"], "du");
+        assert_eq!(response["response"]["content"], "42G /home");
+        assert_eq!(json[2]["role"], "user");
+        assert_eq!(json[2]["parts"][0]["text"], "Tool result:\nok");
+    }
+
+    #[tokio::test]
+    async fn test_image_url_is_downloaded_and_inlined() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf);
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\nConnection: close\r\n\r\nGIF")
+                .unwrap();
+        });
+
+        let provider = GeminiProvider::new("key".into(), "url".into(), "gemini-2.5-flash".into());
+        let messages = [Message {
+            role: "user".into(),
+            content: "what is this".into(),
+            images: vec![ImageInput::Url(format!("http://{}/cat.gif", addr))],
+            ..Default::default()
+        }];
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let inlined = images::inline_images(&client, &messages).await.unwrap();
+        let json = serde_json::to_value(provider.convert_messages(&inlined)).unwrap();
+
+        let parts = json[0]["parts"].as_array().unwrap();
+        assert_eq!(parts[0]["text"], "what is this");
+        assert_eq!(parts[1]["inlineData"]["mimeType"], "image/gif");
+        assert_eq!(parts[1]["inlineData"]["data"], "R0lG");
+    }
+}
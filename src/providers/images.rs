@@ -0,0 +1,168 @@
+//! Images attached to the question (`--image-url`)
+
+use super::{ImageInput, Message};
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use std::borrow::Cow;
+
+/// Only http(s) URLs are passed on to providers or downloaded
+pub fn validate_image_url(url: &str) -> Result<()> {
+    let lower = url.to_lowercase();
+    let rest = lower
+        .strip_prefix("https://")
+        .or_else(|| lower.strip_prefix("http://"));
+    match rest {
+        Some(host) if !host.is_empty() => Ok(()),
+        _ => bail!(
+            "Image URL must start with http:// or https://, got '{}'",
+            url
+        ),
+    }
+}
+
+/// `data:` URL for inline images, the form OpenAI accepts in `image_url`
+pub fn data_url(mime_type: &str, data: &str) -> String {
+    format!("data:{};base64,{}", mime_type, data)
+}
+
+/// For providers that need the bytes (Gemini, Ollama): download every `Url`
+/// image and replace it with base64 data. Borrows when there is nothing to fetch.
+pub async fn inline_images<'a>(
+    client: &reqwest::Client,
+    messages: &'a [Message],
+) -> Result<Cow<'a, [Message]>> {
+    let has_urls = messages
+        .iter()
+        .flat_map(|m| &m.images)
+        .any(|image| matches!(image, ImageInput::Url(_)));
+    if !has_urls {
+        return Ok(Cow::Borrowed(messages));
+    }
+
+    let mut owned = messages.to_vec();
+    for message in &mut owned {
+        for image in &mut message.images {
+            if let ImageInput::Url(url) = image {
+                *image = download_image(client, url).await?;
+            }
+        }
+    }
+    Ok(Cow::Owned(owned))
+}
+
+async fn download_image(client: &reqwest::Client, url: &str) -> Result<ImageInput> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Could not download image {}", url))?;
+    if !response.status().is_success() {
+        bail!(
+            "Could not download image {}: HTTP {}",
+            url,
+            response.status()
+        );
+    }
+
+    let header_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or(v).trim().to_lowercase());
+    let mime_type = match header_type {
+        Some(mime) if mime.starts_with("image/") => mime,
+        _ => mime_from_url(url).to_string(),
+    };
+
+    let bytes = response
+        .bytes()
+        .await
+        .with_context(|| format!("Could not download image {}", url))?;
+    Ok(ImageInput::Inline {
+        mime_type,
+        data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+    })
+}
+
+/// Guess the image type from the file extension when the server doesn't say
+fn mime_from_url(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    match path.rsplit('.').next() {
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "image/jpeg",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_image_url() {
+        assert!(validate_image_url("https://example.com/photo.jpg").is_ok());
+        assert!(validate_image_url("HTTP://example.com/a.png").is_ok());
+        assert!(validate_image_url("file:///etc/passwd").is_err());
+        assert!(validate_image_url("ftp://example.com/a.png").is_err());
+        assert!(validate_image_url("photo.jpg").is_err());
+        assert!(validate_image_url("https://").is_err());
+    }
+
+    #[test]
+    fn test_mime_from_url() {
+        assert_eq!(mime_from_url("https://x.io/a.PNG"), "image/png");
+        assert_eq!(mime_from_url("https://x.io/a.webp?w=200"), "image/webp");
+        assert_eq!(mime_from_url("https://x.io/photo"), "image/jpeg");
+    }
+
+    #[tokio::test]
+    async fn test_inline_images_downloads_urls() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf);
+            let body = b"\x89PNG";
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(body).unwrap();
+        });
+
+        let messages = vec![Message {
+            role: "user".to_string(),
+            content: "what is this".to_string(),
+            images: vec![ImageInput::Url(format!("http://{}/a", addr))],
+            ..Default::default()
+        }];
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let inlined = inline_images(&client, &messages).await.unwrap();
+
+        assert!(matches!(inlined, Cow::Owned(_)));
+        assert_eq!(
+            inlined[0].images,
+            vec![ImageInput::Inline {
+                mime_type: "image/png".to_string(),
+                data: "iVBORw==".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inline_images_borrows_without_urls() {
+        let messages = vec![Message {
+            role: "user".to_string(),
+            content: "hi".to_string(),
+            ..Default::default()
+        }];
+        let client = reqwest::Client::new();
+        let result = inline_images(&client, &messages).await.unwrap();
+        assert!(matches!(result, Cow::Borrowed(_)));
+    }
+}
@@ -2,14 +2,16 @@
 
 mod anthropic;
 mod gemini;
+mod images;
 mod ollama;
 mod openai;
 mod traits;
 
 pub use anthropic::AnthropicProvider;
 pub use gemini::GeminiProvider;
+pub use images::validate_image_url;
 pub use ollama::OllamaProvider;
-pub use openai::OpenAIProvider;
+pub use openai::{is_reasoning_model, supports_none_reasoning, AzureDeployment, OpenAIProvider};
 pub use traits::*;
 
 use crate::config::Config;
@@ -47,6 +49,7 @@ pub const COMMAND_STARTERS: &[&str] = &[
     "service",
     "curl",
     "wget",
+    "ffmpeg",
     "tar",
     "zip",
     "unzip",
@@ -135,7 +138,23 @@ fn line_starts_with_command(line: &str) -> bool {
         .any(|cmd| first_word.starts_with(cmd))
 }
 
+/// Join reasoning fragments into one trace; None when there is nothing to show
+pub(crate) fn join_thinking<I: IntoIterator<Item = String>>(parts: I) -> Option<String> {
+    let parts: Vec<String> = parts
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("\n\n"))
+    }
+}
+
 /// Removes one enclosing fenced code block when the entire response is wrapped in it.
+///
+/// Responses with several fenced blocks (or prose between fences) are left untouched.
 pub fn strip_code_fences(text: &str) -> String {
     let trimmed = text.trim();
     let lines: Vec<&str> = trimmed.lines().collect();
@@ -151,25 +170,95 @@ pub fn strip_code_fences(text: &str) -> String {
         return trimmed.to_string();
     }
 
-    lines[1..lines.len() - 1].join("\n").trim().to_string()
+    let inner = &lines[1..lines.len() - 1];
+    if inner.iter().any(|l| l.trim_start().starts_with("```")) {
+        return trimmed.to_string();
+    }
+
+    inner.join("\n").trim().to_string()
+}
+
+/// Drops a short conversational lead-in ("Sure! Here's how:") before a command.
+///
+/// Only fires when the response is one or two lines of prose followed by either a
+/// single command line or one fenced block that starts with a command. Anything
+/// that looks like a list or several commands is left alone (returns `None`).
+pub fn strip_preamble_for_command(text: &str) -> Option<String> {
+    let lines: Vec<&str> = text.trim().lines().collect();
+    let fence = lines.iter().position(|l| l.trim_start().starts_with("```"));
+
+    let (preamble, command) = match fence {
+        Some(open) => {
+            let close = lines.len() - 1;
+            if close <= open + 1 || lines[close].trim() != "```" {
+                return None;
+            }
+            let inner = &lines[open + 1..close];
+            if inner.iter().any(|l| l.trim_start().starts_with("```")) {
+                return None;
+            }
+            (&lines[..open], inner.join("\n").trim().to_string())
+        }
+        None if lines.len() >= 2 => {
+            let (last, rest) = lines.split_last()?;
+            (rest, last.trim().to_string())
+        }
+        None => return None,
+    };
+
+    let first_command_line = command.lines().next().unwrap_or("").trim();
+    if !line_starts_with_command(first_command_line) {
+        return None;
+    }
+
+    let prose: Vec<&str> = preamble
+        .iter()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+    let looks_like_lead_in = !prose.is_empty()
+        && prose.len() <= 2
+        && prose.iter().map(|l| l.len()).sum::<usize>() <= 200
+        && prose
+            .iter()
+            .all(|l| !is_list_item(l) && !line_starts_with_command(l))
+        && prose
+            .last()
+            .is_some_and(|l| l.ends_with(':') || l.ends_with('!') || l.ends_with('.'));
+
+    looks_like_lead_in.then_some(command)
+}
+
+fn is_list_item(line: &str) -> bool {
+    line.starts_with("- ")
+        || line.starts_with("* ")
+        || line
+            .split_once(". ")
+            .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
 }
 
 /// Attempts to flatten a multi-line command response into a single line.
 ///
-/// Returns `Some(flattened)` only when it's safe to join lines with `&&`.
-/// Returns `None` if the text contains patterns that would break if flattened:
+/// Returns `Some(flattened)` only when it's safe to join lines with `&&`,
+/// i.e. when [`split_command_lines`] can split it.
+/// Lines are joined with the separator of `shell` (`&&`, fish `; and`, nushell `;`).
+pub fn flatten_command_if_safe(text: &str, shell: ShellFamily) -> Option<String> {
+    split_command_lines(text).map(|lines| lines.join(shell.separator()))
+}
+
+/// Splits a multi-line command response into one command per line.
+///
+/// Returns `None` if the text contains patterns that would break if split:
 /// - Line continuations (ending with `\`)
 /// - Heredocs (`<<`)
 /// - Lines that don't look like commands
 /// - Lines that are too long (likely a single wrapped command)
-///
-/// Join with `&&` is compatible with sh, bash, zsh, and fish 3.0+.
-pub fn flatten_command_if_safe(text: &str) -> Option<String> {
+pub fn split_command_lines(text: &str) -> Option<Vec<String>> {
     let trimmed = text.trim();
 
     // Already a single line - return as-is
     if !trimmed.contains('\n') {
-        return Some(trimmed.to_string());
+        return Some(vec![trimmed.to_string()]);
     }
 
     // Split by lines and filter empty ones
@@ -181,7 +270,7 @@ pub fn flatten_command_if_safe(text: &str) -> Option<String> {
 
     // Single effective line after filtering
     if lines.len() <= 1 {
-        return Some(trimmed.replace('\n', " ").trim().to_string());
+        return Some(vec![trimmed.replace('\n', " ").trim().to_string()]);
     }
 
     // Safety checks for each line
@@ -204,49 +293,101 @@ pub fn flatten_command_if_safe(text: &str) -> Option<String> {
         }
     }
 
-    // Safe to flatten
-    Some(lines.join(" && "))
+    // Safe to split
+    Some(lines.into_iter().map(str::to_string).collect())
 }
 
 /// Create a provider based on configuration
 pub fn create_provider(config: &Config) -> Result<Box<dyn Provider>> {
+    if crate::http::is_offline() {
+        anyhow::bail!(
+            "offline mode: no cached response\n\
+             Drop --offline or unset ASK_OFFLINE to query {}",
+            config.active_provider()
+        );
+    }
+
     let provider_name = config.active_provider();
-    let model = config.active_model().to_string();
 
     // Ollama does not require a real API key
-    if provider_name == "ollama" {
-        let base_url = config
-            .base_url()
-            .unwrap_or_else(|| crate::config::DEFAULT_OLLAMA_BASE_URL.to_string());
-        let api_key = config.api_key().unwrap_or_else(|| "ollama".to_string());
-        return Ok(Box::new(OllamaProvider::new(api_key, base_url, model)));
-    }
+    let api_key = if provider_name == "ollama" {
+        config.api_key().unwrap_or_else(|| "ollama".to_string())
+    } else {
+        config.api_key().ok_or_else(|| {
+            anyhow!(
+                "No API key found for provider '{}'. Run 'ask init' to configure.",
+                provider_name
+            )
+        })?
+    };
+
+    build_provider(config, api_key)
+}
 
-    let api_key = config.api_key().ok_or_else(|| {
-        anyhow!(
-            "No API key found for provider '{}'. Run 'ask init' to configure.",
-            provider_name
-        )
-    })?;
+/// The active provider for inspecting the requests it would build (`--model-info`);
+/// no API key or network access needed
+pub fn inspect_provider(config: &Config) -> Result<Box<dyn Provider>> {
+    build_provider(config, config.api_key().unwrap_or_default())
+}
+
+fn build_provider(config: &Config, api_key: String) -> Result<Box<dyn Provider>> {
+    let provider_name = config.active_provider();
+    let model = config.active_model().to_string();
 
     match provider_name {
+        "ollama" => {
+            let base_url = config
+                .base_url()
+                .unwrap_or_else(|| crate::config::DEFAULT_OLLAMA_BASE_URL.to_string());
+            Ok(Box::new(
+                OllamaProvider::new(api_key, base_url, model).with_timeouts(config),
+            ))
+        }
         "gemini" => {
             let base_url = config
                 .base_url()
                 .unwrap_or_else(|| crate::config::DEFAULT_GEMINI_BASE_URL.to_string());
-            Ok(Box::new(GeminiProvider::new(api_key, base_url, model)))
+            Ok(Box::new(
+                GeminiProvider::new(api_key, base_url, model).with_timeouts(config),
+            ))
         }
         "openai" | "openai_compatible" => {
             let base_url = config
                 .base_url()
                 .unwrap_or_else(|| crate::config::DEFAULT_OPENAI_BASE_URL.to_string());
-            Ok(Box::new(OpenAIProvider::new(api_key, base_url, model)))
+            Ok(Box::new(
+                OpenAIProvider::new(api_key, base_url, model)
+                    .with_timeouts(config)
+                    .with_reasoning_support(config.get_profile_supports_reasoning()),
+            ))
+        }
+        "azure" => {
+            let base_url = config.base_url().ok_or_else(|| {
+                anyhow!(
+                    "Azure OpenAI requires a base_url (e.g., https://myresource.openai.azure.com). \
+                     Set it in the profile or via ASK_AZURE_BASE_URL."
+                )
+            })?;
+            let (deployment, api_version) = config.get_azure_deployment();
+            Ok(Box::new(
+                OpenAIProvider::new(api_key, base_url, model)
+                    .with_timeouts(config)
+                    .with_reasoning_support(config.get_profile_supports_reasoning())
+                    .with_azure(AzureDeployment {
+                        deployment,
+                        api_version,
+                    }),
+            ))
         }
         "anthropic" | "claude" => {
             let base_url = config
                 .base_url()
                 .unwrap_or_else(|| crate::config::DEFAULT_ANTHROPIC_BASE_URL.to_string());
-            Ok(Box::new(AnthropicProvider::new(api_key, base_url, model)))
+            Ok(Box::new(
+                AnthropicProvider::new(api_key, base_url, model)
+                    .with_timeouts(config)
+                    .with_prompt_cache(config.providers.anthropic.prompt_cache.unwrap_or(false)),
+            ))
         }
         _ => Err(anyhow!("Unknown provider: {}", provider_name)),
     }
@@ -260,7 +401,7 @@ mod tests {
     fn test_flatten_command_if_safe_single_line() {
         // Single line remains unchanged
         assert_eq!(
-            flatten_command_if_safe("ls -la"),
+            flatten_command_if_safe("ls -la", ShellFamily::Posix),
             Some("ls -la".to_string())
         );
     }
@@ -269,22 +410,38 @@ mod tests {
     fn test_flatten_command_if_safe_valid_multiline() {
         // Multi-line commands joined with &&
         assert_eq!(
-            flatten_command_if_safe("mkdir test\ncd test\ntouch hello.txt"),
+            flatten_command_if_safe("mkdir test\ncd test\ntouch hello.txt", ShellFamily::Posix),
             Some("mkdir test && cd test && touch hello.txt".to_string())
         );
 
         // Extra whitespace and empty lines handled
         assert_eq!(
-            flatten_command_if_safe("  apt update  \n\n  apt upgrade  "),
+            flatten_command_if_safe("  apt update  \n\n  apt upgrade  ", ShellFamily::Posix),
             Some("apt update && apt upgrade".to_string())
         );
     }
 
+    #[test]
+    fn test_flatten_command_if_safe_shell_separator() {
+        let text = "mkdir test\ncd test";
+        assert_eq!(
+            flatten_command_if_safe(text, ShellFamily::Fish),
+            Some("mkdir test; and cd test".to_string())
+        );
+        assert_eq!(
+            flatten_command_if_safe(text, ShellFamily::Nushell),
+            Some("mkdir test; cd test".to_string())
+        );
+    }
+
     #[test]
     fn test_flatten_command_if_safe_line_continuation() {
         // Line continuation should NOT be flattened
         assert_eq!(
-            flatten_command_if_safe("docker run \\\n  --name test \\\n  nginx"),
+            flatten_command_if_safe(
+                "docker run \\\n  --name test \\\n  nginx",
+                ShellFamily::Posix
+            ),
             None
         );
     }
@@ -292,14 +449,17 @@ mod tests {
     #[test]
     fn test_flatten_command_if_safe_heredoc() {
         // Heredoc should NOT be flattened
-        assert_eq!(flatten_command_if_safe("cat <<EOF\nhello world\nEOF"), None);
+        assert_eq!(
+            flatten_command_if_safe("cat <<EOF\nhello world\nEOF", ShellFamily::Posix),
+            None
+        );
     }
 
     #[test]
     fn test_flatten_command_if_safe_non_command_line() {
         // Text that doesn't look like commands should NOT be flattened
         assert_eq!(
-            flatten_command_if_safe("ls -la\nThis is not a command"),
+            flatten_command_if_safe("ls -la\nThis is not a command", ShellFamily::Posix),
             None
         );
     }
@@ -309,11 +469,24 @@ mod tests {
         // Very long lines should NOT be flattened (likely wrapped single command)
         let long_line = format!("echo {}", "x".repeat(130));
         assert_eq!(
-            flatten_command_if_safe(&format!("ls -la\n{}", long_line)),
+            flatten_command_if_safe(&format!("ls -la\n{}", long_line), ShellFamily::Posix),
             None
         );
     }
 
+    #[test]
+    fn test_split_command_lines() {
+        assert_eq!(
+            split_command_lines("  mkdir out\n\ncd out  \n"),
+            Some(vec!["mkdir out".to_string(), "cd out".to_string()])
+        );
+        assert_eq!(
+            split_command_lines("git status"),
+            Some(vec!["git status".to_string()])
+        );
+        assert_eq!(split_command_lines("cat <<EOF\nhi\nEOF"), None);
+    }
+
     #[test]
     fn test_strip_code_fences_with_language() {
         assert_eq!(strip_code_fences("```bash\nls -la\n```"), "ls -la");
@@ -329,8 +502,53 @@ mod tests {
         assert_eq!(strip_code_fences("ls -la"), "ls -la");
     }
 
+    #[test]
+    fn test_strip_code_fences_keeps_multiple_blocks() {
+        let text = "```bash\nls\n```\nor\n```bash\nls -a\n```";
+        assert_eq!(strip_code_fences(text), text);
+    }
+
     #[test]
     fn test_strip_code_fences_ignores_unclosed_block() {
         assert_eq!(strip_code_fences("```bash\nls -la"), "```bash\nls -la");
     }
+
+    #[test]
+    fn test_strip_preamble_before_plain_command() {
+        assert_eq!(
+            strip_preamble_for_command("Sure! Here's how to list files:\nls -la").as_deref(),
+            Some("ls -la")
+        );
+    }
+
+    #[test]
+    fn test_strip_preamble_before_fenced_command() {
+        let text = "Sure!\nYou can use find for that.\n```bash\nfind . -name '*.rs'\n```";
+        assert_eq!(
+            strip_preamble_for_command(text).as_deref(),
+            Some("find . -name '*.rs'")
+        );
+    }
+
+    #[test]
+    fn test_strip_preamble_leaves_pure_commands() {
+        assert_eq!(strip_preamble_for_command("ls -la"), None);
+        assert_eq!(strip_preamble_for_command("```bash\nls -la\n```"), None);
+        assert_eq!(strip_preamble_for_command("mkdir out\ncd out"), None);
+    }
+
+    #[test]
+    fn test_strip_preamble_keeps_multi_step_answers() {
+        let steps = "Do this:\n1. Build it.\n2. Run it:\ncargo run";
+        assert_eq!(strip_preamble_for_command(steps), None);
+
+        let long = "First, make sure the daemon is running.\nThen check the logs.\nFinally:\njournalctl -u app";
+        assert_eq!(strip_preamble_for_command(long), None);
+
+        let trailing = "Run:\n```bash\nls\n```\nThat lists files.";
+        assert_eq!(strip_preamble_for_command(trailing), None);
+
+        let prose = "Paris is the capital of France.\nIt is also its largest city.";
+        assert_eq!(strip_preamble_for_command(prose), None);
+    }
 }
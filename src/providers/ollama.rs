@@ -1,7 +1,14 @@
 //! Ollama provider implementation using the native /api/chat endpoint
 
-use super::{Message, Provider, ProviderOptions, ProviderResponse, StreamCallback};
-use crate::http::create_client;
+use super::{
+    images, join_thinking, ImageInput, Message, Provider, ProviderOptions, ProviderResponse,
+    StreamCallback, StreamEnd, Usage,
+};
+use crate::config::Config;
+use crate::http::{
+    create_client, create_request_client, create_streaming_client, log_stream_event,
+    parse_api_error, SendLogged, TextLogged,
+};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -12,6 +19,7 @@ pub struct OllamaProvider {
     base_url: String,
     model: String,
     client: Client,
+    stream_client: Client,
 }
 
 #[derive(Serialize)]
@@ -21,6 +29,18 @@ struct OllamaRequest {
     stream: bool,
     #[serde(skip_serializing_if = "is_false")]
     think: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<OllamaOptions>,
+}
+
+#[derive(Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
 }
 
 fn is_false(v: &bool) -> bool {
@@ -31,6 +51,15 @@ fn is_false(v: &bool) -> bool {
 struct OllamaMessage {
     role: String,
     content: String,
+    /// Set on `tool` messages, which Ollama accepts without a call id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_name: Option<String>,
+    /// Reasoning trace, returned when the request sets `think`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thinking: Option<String>,
+    /// Base64 images for vision models; URLs are downloaded first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
 }
 
 // Non-streaming response
@@ -38,6 +67,10 @@ struct OllamaMessage {
 struct OllamaResponse {
     message: Option<OllamaMessage>,
     error: Option<String>,
+    prompt_eval_count: Option<u64>,
+    eval_count: Option<u64>,
+    /// "stop", or "length" when `num_predict` cut the answer off
+    done_reason: Option<String>,
 }
 
 // Streaming NDJSON chunk
@@ -46,6 +79,8 @@ struct OllamaStreamChunk {
     message: Option<OllamaDelta>,
     done: bool,
     error: Option<String>,
+    /// Only on the final (`done`) chunk
+    done_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -59,15 +94,47 @@ impl OllamaProvider {
             base_url,
             model,
             client: create_client(),
+            stream_client: create_client(),
         }
     }
 
+    /// Apply configured request/stream timeouts to the HTTP clients
+    pub fn with_timeouts(mut self, config: &Config) -> Self {
+        self.client = create_request_client(config);
+        self.stream_client = create_streaming_client(config);
+        self
+    }
+
+    /// `options` block: answer length cap (only when set; the model decides otherwise),
+    /// and seed plus temperature 0 when seeded
+    fn build_options(&self, options: &ProviderOptions) -> Option<OllamaOptions> {
+        let num_predict = options.max_tokens;
+        if num_predict.is_none() && options.seed.is_none() {
+            return None;
+        }
+        Some(OllamaOptions {
+            num_predict,
+            seed: options.seed,
+            temperature: options.seed.map(|_| options.temperature(0.8)),
+        })
+    }
+
     fn convert_messages(&self, messages: &[Message]) -> Vec<OllamaMessage> {
         messages
             .iter()
             .map(|m| OllamaMessage {
                 role: m.role.clone(),
                 content: m.content.clone(),
+                tool_name: m.name.clone().filter(|_| m.role == "tool"),
+                thinking: None,
+                images: m
+                    .images
+                    .iter()
+                    .filter_map(|image| match image {
+                        ImageInput::Inline { data, .. } => Some(data.clone()),
+                        ImageInput::Url(_) => None,
+                    })
+                    .collect(),
             })
             .collect()
     }
@@ -82,11 +149,13 @@ impl Provider for OllamaProvider {
     ) -> Result<ProviderResponse> {
         let url = format!("{}/api/chat", self.base_url);
 
+        let messages = images::inline_images(&self.client, messages).await?;
         let request = OllamaRequest {
             model: self.model.clone(),
-            messages: self.convert_messages(messages),
+            messages: self.convert_messages(&messages),
             stream: false,
             think: options.thinking_enabled,
+            options: self.build_options(options),
         };
 
         let response = self
@@ -94,14 +163,18 @@ impl Provider for OllamaProvider {
             .post(&url)
             .header("Content-Type", "application/json")
             .json(&request)
-            .send()
+            .send_logged("ollama")
             .await?;
 
         let status = response.status();
-        let body = response.text().await?;
+        let body = response.text_logged("ollama").await?;
 
         if !status.is_success() {
-            return Err(anyhow!("Ollama API error ({}): {}", status, body));
+            return Err(anyhow!(
+                "Ollama API error ({}): {}",
+                status,
+                parse_api_error(status, &body)
+            ));
         }
 
         let parsed: OllamaResponse = serde_json::from_str(&body)
@@ -111,11 +184,25 @@ impl Provider for OllamaProvider {
             return Err(anyhow!("Ollama error: {}", err));
         }
 
+        let usage = match (parsed.prompt_eval_count, parsed.eval_count) {
+            (None, None) => None,
+            (input, output) => Some(Usage {
+                input_tokens: input.unwrap_or(0),
+                output_tokens: output.unwrap_or(0),
+            }),
+        };
+        let thinking_text = parsed
+            .message
+            .as_ref()
+            .and_then(|m| join_thinking(m.thinking.clone()));
         let text = parsed.message.map(|m| m.content).unwrap_or_default();
 
         Ok(ProviderResponse {
             text,
             citations: vec![],
+            usage,
+            thinking_text,
+            finish_reason: parsed.done_reason,
         })
     }
 
@@ -124,32 +211,40 @@ impl Provider for OllamaProvider {
         messages: &[Message],
         mut callback: StreamCallback,
         options: &ProviderOptions,
-    ) -> Result<()> {
+    ) -> Result<StreamEnd> {
         let url = format!("{}/api/chat", self.base_url);
 
+        let messages = images::inline_images(&self.client, messages).await?;
         let request = OllamaRequest {
             model: self.model.clone(),
-            messages: self.convert_messages(messages),
+            messages: self.convert_messages(&messages),
             stream: true,
             think: options.thinking_enabled,
+            options: self.build_options(options),
         };
 
         let response = self
-            .client
+            .stream_client
             .post(&url)
             .header("Content-Type", "application/json")
             .json(&request)
-            .send()
+            .send_logged("ollama")
             .await?;
 
-        if !response.status().is_success() {
-            let body = response.text().await?;
-            return Err(anyhow!("Ollama API error: {}", body));
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text_logged("ollama").await?;
+            return Err(anyhow!(
+                "Ollama API error ({}): {}",
+                status,
+                parse_api_error(status, &body)
+            ));
         }
 
         let mut stream = response.bytes_stream();
         // Raw byte buffer to avoid splitting multibyte UTF-8 sequences at chunk boundaries
         let mut raw_buf: Vec<u8> = Vec::new();
+        let mut end = StreamEnd::default();
 
         'outer: while let Some(chunk) = stream.next().await {
             let chunk = chunk?;
@@ -166,6 +261,7 @@ impl Provider for OllamaProvider {
                 if line.is_empty() {
                     continue;
                 }
+                log_stream_event("ollama", &line);
 
                 if let Ok(parsed) = serde_json::from_str::<OllamaStreamChunk>(&line) {
                     if let Some(err) = parsed.error {
@@ -181,13 +277,25 @@ impl Provider for OllamaProvider {
                     }
 
                     if parsed.done {
+                        end.finish_reason = parsed.done_reason;
                         break 'outer;
                     }
                 }
             }
         }
 
-        Ok(())
+        Ok(end)
+    }
+
+    fn thinking_param(&self, options: &ProviderOptions) -> Option<(&'static str, String)> {
+        // `think: false` is left out of the request
+        options
+            .thinking_enabled
+            .then(|| ("think", "true".to_string()))
+    }
+
+    fn supports_seed(&self) -> bool {
+        true
     }
 
     fn name(&self) -> &str {
@@ -210,6 +318,7 @@ mod tests {
             messages: vec![],
             stream: false,
             think: true,
+            options: None,
         };
         let json = serde_json::to_string(&req).unwrap();
         assert!(json.contains(r#""think":true"#));
@@ -222,9 +331,103 @@ mod tests {
             messages: vec![],
             stream: false,
             think: false,
+            options: None,
         };
         let json = serde_json::to_string(&req).unwrap();
         // think:false must not be serialized (skip_serializing_if = is_false)
         assert!(!json.contains(r#""think""#));
     }
+
+    #[test]
+    fn test_ollama_options_seed() {
+        let provider = OllamaProvider::new(String::new(), "url".into(), "qwen3".into());
+        assert!(
+            provider
+                .build_options(&ProviderOptions::default())
+                .is_none(),
+            "no length cap unless one is set"
+        );
+        let plain =
+            serde_json::to_value(provider.build_options(&ProviderOptions::default())).unwrap();
+        assert!(plain.get("seed").is_none() && plain.get("temperature").is_none());
+
+        let options = ProviderOptions {
+            seed: Some(42),
+            ..Default::default()
+        };
+        let seeded = serde_json::to_value(provider.build_options(&options)).unwrap();
+        assert_eq!(seeded["seed"], 42);
+        assert_eq!(seeded["temperature"], 0.0);
+    }
+
+    #[test]
+    fn test_ollama_done_reason_length() {
+        let parsed: OllamaResponse = serde_json::from_str(
+            r#"{"message": {"role": "assistant", "content": "Once"}, "done": true, "done_reason": "length"}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.done_reason.as_deref(), Some("length"));
+    }
+
+    #[test]
+    fn test_ollama_response_thinking() {
+        let parsed: OllamaResponse = serde_json::from_str(
+            r#"{"message": {"role": "assistant", "content": "pwd", "thinking": "They want the current directory."}, "done": true}"#,
+        )
+        .unwrap();
+        let message = parsed.message.unwrap();
+        assert_eq!(message.content, "pwd");
+        assert_eq!(
+            message.thinking.as_deref(),
+            Some("They want the current directory.")
+        );
+    }
+
+    #[test]
+    fn test_convert_tool_messages() {
+        let provider = OllamaProvider::new("ollama".into(), "url".into(), "qwen3".into());
+        let messages = [
+            Message {
+                role: "user".into(),
+                content: "how big is /home?".into(),
+                ..Default::default()
+            },
+            Message {
+                role: "tool".into(),
+                content: "42G /home".into(),
+                tool_call_id: Some("call_1".into()),
+                name: Some("du".into()),
+                ..Default::default()
+            },
+            Message {
+                role: "tool".into(),
+                content: "ok".into(),
+                ..Default::default()
+            },
+        ];
+        let json = serde_json::to_value(provider.convert_messages(&messages)).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3, "no message dropped");
+        assert_eq!(json[1]["role"], "tool");
+        assert_eq!(json[1]["tool_name"], "du");
+        assert_eq!(json[1]["content"], "42G /home");
+        assert_eq!(json[2]["role"], "tool");
+        assert!(json[0].get("tool_name").is_none());
+    }
+
+    #[test]
+    fn test_convert_inlined_image() {
+        let provider = OllamaProvider::new("ollama".into(), "url".into(), "llava".into());
+        let messages = [Message {
+            role: "user".into(),
+            content: "what is this".into(),
+            images: vec![ImageInput::Inline {
+                mime_type: "image/png".into(),
+                data: "iVBORw==".into(),
+            }],
+            ..Default::default()
+        }];
+        let json = serde_json::to_value(provider.convert_messages(&messages)).unwrap();
+        assert_eq!(json[0]["content"], "what is this");
+        assert_eq!(json[0]["images"], serde_json::json!(["iVBORw=="]));
+    }
 }
@@ -1,7 +1,14 @@
 //! OpenAI provider implementation (also works with OpenAI-compatible APIs)
 
-use super::{Citation, Message, Provider, ProviderOptions, ProviderResponse, StreamCallback};
-use crate::http::create_client;
+use super::{
+    complete_separately, images, join_thinking, Citation, ImageInput, Message, Provider,
+    ProviderOptions, ProviderResponse, StreamCallback, StreamEnd, Usage,
+};
+use crate::config::Config;
+use crate::http::{
+    create_client, create_request_client, create_streaming_client, parse_api_error, SendLogged,
+    SseBuffer, TextLogged,
+};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -9,11 +16,64 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// OpenAI reasoning models (o-series and gpt-5 family)
+pub fn is_reasoning_model(model: &str) -> bool {
+    let model = model.to_lowercase();
+    model.starts_with("o1")
+        || model.starts_with("o3")
+        || model.starts_with("o4")
+        || model.starts_with("gpt-5")
+}
+
+/// Whether `reasoning_effort = "none"` really turns reasoning off for this model
+pub fn supports_none_reasoning(model: &str) -> bool {
+    let model = model.to_lowercase();
+    model.contains("gpt-5.1") || model.contains("gpt-5.2") || model.contains("gpt-5.3")
+}
+
+/// A reasoning level as sent to the API, with a note when it differs from the one asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReasoningEffort {
+    pub level: String,
+    pub note: Option<String>,
+}
+
+/// Map a requested reasoning level to one the model accepts ("none" needs gpt-5.1+)
+pub fn normalize_reasoning_effort(model: &str, level: &str) -> ReasoningEffort {
+    if level == "none" && !supports_none_reasoning(model) {
+        ReasoningEffort {
+            level: "minimal".to_string(),
+            note: Some(format!(
+                "reasoning 'none' not supported by {}, using 'minimal'",
+                model
+            )),
+        }
+    } else {
+        ReasoningEffort {
+            level: level.to_string(),
+            note: None,
+        }
+    }
+}
+
 pub struct OpenAIProvider {
     api_key: String,
     base_url: String,
     model: String,
     client: Client,
+    stream_client: Client,
+    /// Whether the endpoint accepts `reasoning_effort`/`max_completion_tokens`.
+    /// `None` means only official OpenAI endpoints do.
+    supports_reasoning: Option<bool>,
+    /// Azure OpenAI deployment routing (`provider = "azure"`)
+    azure: Option<AzureDeployment>,
+}
+
+/// Azure OpenAI routes requests by deployment name and API version
+#[derive(Debug, Clone)]
+pub struct AzureDeployment {
+    pub deployment: String,
+    pub api_version: String,
 }
 
 #[derive(Serialize)]
@@ -29,6 +89,11 @@ struct OpenAIRequest {
     max_completion_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     reasoning_effort: Option<String>,
+    /// Number of choices to generate (`-N`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
 }
 
 #[derive(Serialize)]
@@ -37,24 +102,55 @@ struct ResponsesAPIRequest {
     input: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning: Option<Value>,
 }
 
 #[derive(Serialize, Deserialize)]
 struct OpenAIMessage {
     role: String,
-    content: String,
+    /// A plain string, or an array of text and `image_url` parts
+    content: Value,
+    /// Set on `tool` messages: the call this result answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct OpenAIResponse {
     choices: Option<Vec<OpenAIChoice>>,
     error: Option<OpenAIError>,
+    usage: Option<OpenAIUsage>,
+}
+
+#[derive(Deserialize)]
+struct OpenAIUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
 }
 
 #[derive(Deserialize)]
 struct ResponsesAPIResponse {
     output: Option<Vec<ResponseOutput>>,
     error: Option<OpenAIError>,
+    usage: Option<ResponsesAPIUsage>,
+    /// Set when `status` is "incomplete", e.g. reason "max_output_tokens"
+    incomplete_details: Option<IncompleteDetails>,
+}
+
+#[derive(Deserialize)]
+struct IncompleteDetails {
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ResponsesAPIUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
 }
 
 #[derive(Deserialize)]
@@ -63,6 +159,8 @@ struct ResponseOutput {
     #[allow(dead_code)]
     output_type: Option<String>,
     content: Option<Vec<ResponseContent>>,
+    /// Reasoning summary (`"type": "reasoning"` items)
+    summary: Option<Vec<ResponseContent>>,
 }
 
 #[derive(Deserialize)]
@@ -85,8 +183,17 @@ struct ResponseAnnotation {
 
 #[derive(Deserialize)]
 struct OpenAIChoice {
-    message: Option<OpenAIMessage>,
+    message: Option<OpenAIResponseMessage>,
     delta: Option<OpenAIDelta>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OpenAIResponseMessage {
+    content: String,
+    /// Reasoning trace from OpenAI-compatible servers (DeepSeek, vLLM, OpenRouter)
+    #[serde(alias = "reasoning")]
+    reasoning_content: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -106,42 +213,92 @@ impl OpenAIProvider {
             base_url,
             model,
             client: create_client(),
+            stream_client: create_client(),
+            supports_reasoning: None,
+            azure: None,
+        }
+    }
+
+    /// `azure` or `openai`, for `name()` and request logs
+    fn provider_name(&self) -> &'static str {
+        if self.azure.is_some() {
+            "azure"
+        } else {
+            "openai"
         }
     }
 
+    /// Route requests to an Azure OpenAI deployment instead of `/chat/completions`
+    pub fn with_azure(mut self, azure: AzureDeployment) -> Self {
+        self.azure = Some(azure);
+        self
+    }
+
+    /// Override reasoning parameter support for OpenAI-compatible endpoints
+    pub fn with_reasoning_support(mut self, supports_reasoning: Option<bool>) -> Self {
+        self.supports_reasoning = supports_reasoning;
+        self
+    }
+
+    /// Apply configured request/stream timeouts to the HTTP clients
+    pub fn with_timeouts(mut self, config: &Config) -> Self {
+        self.client = create_request_client(config);
+        self.stream_client = create_streaming_client(config);
+        self
+    }
+
     fn convert_messages(&self, messages: &[Message]) -> Vec<OpenAIMessage> {
         messages
             .iter()
-            .map(|m| OpenAIMessage {
-                role: m.role.clone(),
-                content: m.content.clone(),
+            .map(|m| match (m.role.as_str(), &m.tool_call_id) {
+                ("tool", Some(id)) => OpenAIMessage {
+                    role: "tool".to_string(),
+                    content: Value::String(m.content.clone()),
+                    tool_call_id: Some(id.clone()),
+                },
+                ("tool", None) => OpenAIMessage {
+                    role: "user".to_string(),
+                    content: Value::String(m.tool_result_text()),
+                    tool_call_id: None,
+                },
+                _ => OpenAIMessage {
+                    role: m.role.clone(),
+                    content: message_content(m),
+                    tool_call_id: None,
+                },
             })
             .collect()
     }
 
     fn is_reasoning_model(&self) -> bool {
-        let model = self.model.to_lowercase();
-        model.starts_with("o1")
-            || model.starts_with("o3")
-            || model.starts_with("o4")
-            || model.starts_with("gpt-5")
+        is_reasoning_model(&self.model)
     }
 
-    fn supports_none_reasoning(&self) -> bool {
-        let model = self.model.to_lowercase();
-        model.contains("gpt-5.1") || model.contains("gpt-5.2") || model.contains("gpt-5.3")
+    fn normalize_reasoning_effort(&self, level: &str) -> ReasoningEffort {
+        normalize_reasoning_effort(&self.model, level)
     }
 
-    fn normalize_reasoning_effort(&self, level: &str) -> String {
-        if level == "none" && !self.supports_none_reasoning() {
-            "minimal".to_string()
-        } else {
-            level.to_string()
-        }
+    /// Third-party OpenAI-compatible servers (vLLM, LM Studio, Ollama) often
+    /// reject reasoning parameters, even for gpt-5-named models
+    fn sends_reasoning_params(&self) -> bool {
+        self.is_reasoning_model()
+            && self
+                .supports_reasoning
+                .unwrap_or_else(|| self.is_official_openai() || self.azure.is_some())
     }
 
     fn build_reasoning_effort(&self, options: &ProviderOptions) -> Option<String> {
-        if !self.is_reasoning_model() {
+        self.reasoning_effort(options).map(|effort| {
+            if let Some(ref note) = effort.note {
+                tracing::debug!(model = %self.model, "{}", note);
+            }
+            effort.level
+        })
+    }
+
+    /// The `reasoning_effort` to send, if any, and why it differs from the request
+    fn reasoning_effort(&self, options: &ProviderOptions) -> Option<ReasoningEffort> {
+        if !self.sends_reasoning_params() {
             return None;
         }
 
@@ -152,7 +309,7 @@ impl OpenAIProvider {
                 .unwrap_or_else(|| "medium".to_string());
             Some(self.normalize_reasoning_effort(&level))
         } else {
-            Some("minimal".to_string())
+            Some(self.normalize_reasoning_effort("none"))
         }
     }
 
@@ -160,6 +317,53 @@ impl OpenAIProvider {
         self.base_url.contains("api.openai.com")
     }
 
+    fn chat_completions_url(&self) -> String {
+        let base_url = self.base_url.trim_end_matches('/');
+        match self.azure {
+            Some(ref azure) => format!(
+                "{}/openai/deployments/{}/chat/completions?api-version={}",
+                base_url, azure.deployment, azure.api_version
+            ),
+            None => format!("{}/chat/completions", base_url),
+        }
+    }
+
+    /// Azure authenticates with an `api-key` header instead of a bearer token
+    fn auth_header(&self) -> (&'static str, String) {
+        if self.azure.is_some() {
+            ("api-key", self.api_key.clone())
+        } else {
+            ("Authorization", format!("Bearer {}", self.api_key))
+        }
+    }
+
+    fn build_request(
+        &self,
+        messages: &[Message],
+        options: &ProviderOptions,
+        stream: bool,
+    ) -> OpenAIRequest {
+        let is_reasoning = self.sends_reasoning_params();
+        let max_tokens = options
+            .max_tokens
+            .unwrap_or_else(|| crate::config::default_max_tokens_for("openai", &self.model));
+        OpenAIRequest {
+            model: self.model.clone(),
+            messages: self.convert_messages(messages),
+            stream,
+            temperature: if is_reasoning {
+                None
+            } else {
+                Some(options.temperature(0.7))
+            },
+            max_tokens: if is_reasoning { None } else { Some(max_tokens) },
+            max_completion_tokens: if is_reasoning { Some(max_tokens) } else { None },
+            reasoning_effort: self.build_reasoning_effort(options),
+            n: Some(options.candidate_count()).filter(|&n| n > 1),
+            seed: options.seed,
+        }
+    }
+
     fn messages_to_input(&self, messages: &[Message]) -> String {
         let mut parts = Vec::new();
         for msg in messages {
@@ -167,19 +371,31 @@ impl OpenAIProvider {
                 "system" => parts.push(format!("[System]: {}", msg.content)),
                 "user" => parts.push(msg.content.clone()),
                 "assistant" => parts.push(format!("[Assistant]: {}", msg.content)),
+                "tool" => parts.push(msg.tool_result_text()),
                 _ => {}
             }
         }
         parts.join("\n\n")
     }
 
-    async fn complete_with_responses_api(&self, messages: &[Message]) -> Result<ProviderResponse> {
+    async fn complete_with_responses_api(
+        &self,
+        messages: &[Message],
+        options: &ProviderOptions,
+    ) -> Result<ProviderResponse> {
+        if messages.iter().any(|m| !m.images.is_empty()) {
+            return Err(anyhow!(
+                "Images can't be combined with web search on OpenAI; drop --search or the image"
+            ));
+        }
         let url = format!("{}/responses", self.base_url);
 
         let request = ResponsesAPIRequest {
             model: self.model.clone(),
             input: self.messages_to_input(messages),
             tools: Some(vec![serde_json::json!({ "type": "web_search" })]),
+            reasoning: (options.include_thoughts && self.is_reasoning_model())
+                .then(|| serde_json::json!({ "summary": "auto" })),
         };
 
         let response = self
@@ -188,14 +404,18 @@ impl OpenAIProvider {
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
             .json(&request)
-            .send()
+            .send_logged("openai")
             .await?;
 
         let status = response.status();
-        let body = response.text().await?;
+        let body = response.text_logged("openai").await?;
 
         if !status.is_success() {
-            return Err(anyhow!("OpenAI Responses API error ({}): {}", status, body));
+            return Err(anyhow!(
+                "OpenAI Responses API error ({}): {}",
+                status,
+                parse_api_error(status, &body)
+            ));
         }
 
         let response: ResponsesAPIResponse = serde_json::from_str(&body)?;
@@ -206,6 +426,11 @@ impl OpenAIProvider {
 
         let mut text = String::new();
         let mut citations = Vec::new();
+        let usage = response.usage.map(|u| Usage {
+            input_tokens: u.input_tokens,
+            output_tokens: u.output_tokens,
+        });
+        let thinking_text = response.output.as_deref().and_then(reasoning_summary);
 
         if let Some(outputs) = response.output {
             for output in outputs {
@@ -235,49 +460,46 @@ impl OpenAIProvider {
             }
         }
 
-        Ok(ProviderResponse { text, citations })
+        Ok(ProviderResponse {
+            text,
+            citations,
+            usage,
+            thinking_text,
+            finish_reason: response
+                .incomplete_details
+                .and_then(|details| details.reason),
+        })
     }
-}
 
-#[async_trait]
-impl Provider for OpenAIProvider {
-    async fn complete_with_options(
+    /// Chat Completions request; one response per returned choice (usage on the first)
+    async fn complete_chat(
         &self,
         messages: &[Message],
         options: &ProviderOptions,
-    ) -> Result<ProviderResponse> {
-        if options.web_search && self.is_official_openai() {
-            return self.complete_with_responses_api(messages).await;
-        }
+    ) -> Result<Vec<ProviderResponse>> {
+        let url = self.chat_completions_url();
+        let (auth_name, auth_value) = self.auth_header();
 
-        let url = format!("{}/chat/completions", self.base_url);
-
-        let is_reasoning = self.is_reasoning_model();
-        let reasoning_effort = self.build_reasoning_effort(options);
-        let request = OpenAIRequest {
-            model: self.model.clone(),
-            messages: self.convert_messages(messages),
-            stream: false,
-            temperature: if is_reasoning { None } else { Some(0.7) },
-            max_tokens: if is_reasoning { None } else { Some(4096) },
-            max_completion_tokens: if is_reasoning { Some(4096) } else { None },
-            reasoning_effort,
-        };
+        let request = self.build_request(messages, options, false);
 
         let response = self
             .client
             .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header(auth_name, auth_value)
             .header("Content-Type", "application/json")
             .json(&request)
-            .send()
+            .send_logged(self.provider_name())
             .await?;
 
         let status = response.status();
-        let body = response.text().await?;
+        let body = response.text_logged(self.provider_name()).await?;
 
         if !status.is_success() {
-            return Err(anyhow!("OpenAI API error ({}): {}", status, body));
+            return Err(anyhow!(
+                "OpenAI API error ({}): {}",
+                status,
+                parse_api_error(status, &body)
+            ));
         }
 
         let response: OpenAIResponse = serde_json::from_str(&body)?;
@@ -286,17 +508,39 @@ impl Provider for OpenAIProvider {
             return Err(anyhow!("OpenAI error: {}", error.message));
         }
 
-        let text = response
-            .choices
-            .and_then(|c| c.into_iter().next())
-            .and_then(|c| c.message)
-            .map(|m| m.content)
-            .unwrap_or_default();
+        Ok(chat_responses(response))
+    }
+}
 
-        Ok(ProviderResponse {
-            text,
-            citations: Vec::new(),
-        })
+#[async_trait]
+impl Provider for OpenAIProvider {
+    async fn complete_with_options(
+        &self,
+        messages: &[Message],
+        options: &ProviderOptions,
+    ) -> Result<ProviderResponse> {
+        if options.web_search && self.is_official_openai() {
+            return self.complete_with_responses_api(messages, options).await;
+        }
+
+        let single = ProviderOptions {
+            candidates: None,
+            ..options.clone()
+        };
+        Ok(self.complete_chat(messages, &single).await?.remove(0))
+    }
+
+    /// Chat Completions returns all candidates from one request (`n`);
+    /// the Responses API used for web search does not support it
+    async fn complete_candidates(
+        &self,
+        messages: &[Message],
+        options: &ProviderOptions,
+    ) -> Result<Vec<ProviderResponse>> {
+        if options.web_search && self.is_official_openai() {
+            return complete_separately(self, messages, options).await;
+        }
+        self.complete_chat(messages, options).await
     }
 
     async fn stream_with_options(
@@ -304,67 +548,91 @@ impl Provider for OpenAIProvider {
         messages: &[Message],
         mut callback: StreamCallback,
         options: &ProviderOptions,
-    ) -> Result<()> {
-        let url = format!("{}/chat/completions", self.base_url);
+    ) -> Result<StreamEnd> {
+        let url = self.chat_completions_url();
+        let (auth_name, auth_value) = self.auth_header();
 
-        let is_reasoning = self.is_reasoning_model();
-        let reasoning_effort = self.build_reasoning_effort(options);
-        let request = OpenAIRequest {
-            model: self.model.clone(),
-            messages: self.convert_messages(messages),
-            stream: true,
-            temperature: if is_reasoning { None } else { Some(0.7) },
-            max_tokens: if is_reasoning { None } else { Some(4096) },
-            max_completion_tokens: if is_reasoning { Some(4096) } else { None },
-            reasoning_effort,
-        };
+        let request = self.build_request(messages, options, true);
 
         let response = self
-            .client
+            .stream_client
             .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header(auth_name, auth_value)
             .header("Content-Type", "application/json")
             .json(&request)
-            .send()
+            .send_logged(self.provider_name())
             .await?;
 
-        if !response.status().is_success() {
-            let body = response.text().await?;
-            return Err(anyhow!("OpenAI API error: {}", body));
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text_logged(self.provider_name()).await?;
+            return Err(anyhow!(
+                "OpenAI API error ({}): {}",
+                status,
+                parse_api_error(status, &body)
+            ));
         }
 
         let mut stream = response.bytes_stream();
+        let mut sse = SseBuffer::logged(self.provider_name());
+        let mut end = StreamEnd::default();
 
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk?;
-            let text = String::from_utf8_lossy(&chunk);
-
-            for line in text.lines() {
-                if let Some(data) = line.strip_prefix("data: ") {
-                    if data == "[DONE]" {
-                        break;
-                    }
+        // Returns false once the stream says `[DONE]`
+        let mut handle_event = |data: &str| {
+            if data == "[DONE]" {
+                return false;
+            }
 
-                    if let Ok(response) = serde_json::from_str::<OpenAIResponse>(data) {
-                        if let Some(choices) = response.choices {
-                            for choice in choices {
-                                if let Some(delta) = choice.delta {
-                                    if let Some(content) = delta.content {
-                                        callback(&content);
-                                    }
-                                }
+            if let Ok(response) = serde_json::from_str::<OpenAIResponse>(data) {
+                if let Some(choices) = response.choices {
+                    for choice in choices {
+                        if let Some(delta) = choice.delta {
+                            if let Some(content) = delta.content {
+                                callback(&content);
                             }
                         }
+                        if choice.finish_reason.is_some() {
+                            end.finish_reason = choice.finish_reason;
+                        }
                     }
                 }
             }
+            true
+        };
+
+        'stream: while let Some(chunk) = stream.next().await {
+            for data in sse.push(&chunk?) {
+                if !handle_event(&data) {
+                    break 'stream;
+                }
+            }
+        }
+        if let Some(data) = sse.finish() {
+            handle_event(&data);
         }
 
-        Ok(())
+        Ok(end)
+    }
+
+    fn reasoning_note(&self, options: &ProviderOptions) -> Option<String> {
+        self.reasoning_effort(options)?.note
+    }
+
+    fn thinking_param(&self, options: &ProviderOptions) -> Option<(&'static str, String)> {
+        // Web search goes through the Responses API, which sends no effort
+        if options.web_search && self.is_official_openai() {
+            return None;
+        }
+        self.reasoning_effort(options)
+            .map(|effort| ("reasoning_effort", effort.level))
+    }
+
+    fn supports_seed(&self) -> bool {
+        true
     }
 
     fn name(&self) -> &str {
-        "openai"
+        self.provider_name()
     }
 
     fn model(&self) -> &str {
@@ -372,10 +640,105 @@ impl Provider for OpenAIProvider {
     }
 }
 
+/// One response per choice, with the usage on the first
+fn chat_responses(response: OpenAIResponse) -> Vec<ProviderResponse> {
+    let usage = response.usage.map(|u| Usage {
+        input_tokens: u.prompt_tokens,
+        output_tokens: u.completion_tokens,
+    });
+    let mut responses: Vec<ProviderResponse> = response
+        .choices
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|c| {
+            let m = c.message?;
+            Some(ProviderResponse {
+                thinking_text: join_thinking(m.reasoning_content),
+                text: m.content,
+                finish_reason: c.finish_reason,
+                ..Default::default()
+            })
+        })
+        .collect();
+    if responses.is_empty() {
+        responses.push(ProviderResponse::default());
+    }
+    responses[0].usage = usage;
+    responses
+}
+
+/// Chat content for a message: the text alone, or text plus `image_url` parts.
+/// URLs go through as-is; inline images become `data:` URLs.
+fn message_content(message: &Message) -> Value {
+    if message.images.is_empty() {
+        return Value::String(message.content.clone());
+    }
+    let mut parts = vec![serde_json::json!({ "type": "text", "text": message.content })];
+    parts.extend(message.images.iter().map(|image| {
+        let url = match image {
+            ImageInput::Url(url) => url.clone(),
+            ImageInput::Inline { mime_type, data } => images::data_url(mime_type, data),
+        };
+        serde_json::json!({ "type": "image_url", "image_url": { "url": url } })
+    }));
+    Value::Array(parts)
+}
+
+/// Summary text of the Responses API `reasoning` items
+fn reasoning_summary(outputs: &[ResponseOutput]) -> Option<String> {
+    join_thinking(
+        outputs
+            .iter()
+            .filter(|o| o.output_type.as_deref() == Some("reasoning"))
+            .flat_map(|o| o.summary.iter().flatten())
+            .filter_map(|s| s.text.clone()),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_reasoning_content_from_compatible_server() {
+        let response: OpenAIResponse = serde_json::from_str(
+            r#"{"choices": [{"message": {"role": "assistant", "content": "42", "reasoning_content": "6 times 7."}}]}"#,
+        )
+        .unwrap();
+        let message = response.choices.unwrap().remove(0).message.unwrap();
+        assert_eq!(message.content, "42");
+        assert_eq!(message.reasoning_content.as_deref(), Some("6 times 7."));
+
+        let response: OpenAIResponse = serde_json::from_str(
+            r#"{"choices": [{"message": {"role": "assistant", "content": "42", "reasoning": "Multiply."}}]}"#,
+        )
+        .unwrap();
+        let message = response.choices.unwrap().remove(0).message.unwrap();
+        assert_eq!(message.reasoning_content.as_deref(), Some("Multiply."));
+    }
+
+    #[test]
+    fn test_reasoning_summary_from_responses_api() {
+        let response: ResponsesAPIResponse = serde_json::from_str(
+            r#"{
+                "output": [
+                    {"type": "reasoning", "summary": [
+                        {"type": "summary_text", "text": "Searching for the release date."}
+                    ]},
+                    {"type": "message", "content": [{"type": "output_text", "text": "May 2025"}]}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            reasoning_summary(&response.output.unwrap()).as_deref(),
+            Some("Searching for the release date.")
+        );
+    }
+
+    const OFFICIAL_URL: &str = "https://api.openai.com/v1";
+
     #[test]
     fn test_is_reasoning_model() {
         let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-5-nano".into());
@@ -390,39 +753,90 @@ mod tests {
 
     #[test]
     fn test_supports_none_reasoning() {
+        assert!(supports_none_reasoning("gpt-5.1"));
+        assert!(supports_none_reasoning("gpt-5.2-turbo"));
+        assert!(!supports_none_reasoning("gpt-5-nano"));
+        assert!(!supports_none_reasoning("gpt-5-mini"));
+    }
+
+    #[test]
+    fn test_normalize_reasoning_effort() {
+        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-5-nano".into());
+        assert_eq!(provider.normalize_reasoning_effort("none").level, "minimal");
+        assert_eq!(
+            provider.normalize_reasoning_effort("minimal").level,
+            "minimal"
+        );
+        assert_eq!(
+            provider.normalize_reasoning_effort("medium").level,
+            "medium"
+        );
+
         let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-5.1".into());
-        assert!(provider.supports_none_reasoning());
+        assert_eq!(provider.normalize_reasoning_effort("none").level, "none");
+    }
+
+    #[test]
+    fn test_normalize_reasoning_effort_notes_the_rewrite() {
+        assert_eq!(
+            normalize_reasoning_effort("gpt-5-nano", "none")
+                .note
+                .as_deref(),
+            Some("reasoning 'none' not supported by gpt-5-nano, using 'minimal'")
+        );
+        assert_eq!(normalize_reasoning_effort("gpt-5-nano", "low").note, None);
+        assert_eq!(normalize_reasoning_effort("gpt-5.1", "none").note, None);
+    }
 
-        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-5.2-turbo".into());
-        assert!(provider.supports_none_reasoning());
+    #[test]
+    fn test_reasoning_note_for_verbose() {
+        let options = ProviderOptions {
+            thinking_enabled: true,
+            thinking_value: Some("none".to_string()),
+            ..Default::default()
+        };
 
-        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-5-nano".into());
-        assert!(!provider.supports_none_reasoning());
+        let provider = OpenAIProvider::new("key".into(), OFFICIAL_URL.into(), "gpt-5-mini".into());
+        assert_eq!(
+            provider.reasoning_note(&options).as_deref(),
+            Some("reasoning 'none' not supported by gpt-5-mini, using 'minimal'")
+        );
+
+        let provider = OpenAIProvider::new("key".into(), OFFICIAL_URL.into(), "gpt-5.1".into());
+        assert_eq!(provider.reasoning_note(&options), None);
 
-        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-5-mini".into());
-        assert!(!provider.supports_none_reasoning());
+        let provider = OpenAIProvider::new("key".into(), OFFICIAL_URL.into(), "gpt-4o".into());
+        assert_eq!(provider.reasoning_note(&options), None);
     }
 
     #[test]
-    fn test_normalize_reasoning_effort() {
-        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-5-nano".into());
-        assert_eq!(provider.normalize_reasoning_effort("none"), "minimal");
-        assert_eq!(provider.normalize_reasoning_effort("minimal"), "minimal");
-        assert_eq!(provider.normalize_reasoning_effort("medium"), "medium");
+    fn test_non_reasoning_model_without_thinking_omits_reasoning_fields() {
+        let provider = OpenAIProvider::new("key".into(), OFFICIAL_URL.into(), "gpt-4o".into());
+        let options = ProviderOptions {
+            thinking_enabled: false,
+            max_tokens: Some(256),
+            ..Default::default()
+        };
 
-        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-5.1".into());
-        assert_eq!(provider.normalize_reasoning_effort("none"), "none");
+        let json = serde_json::to_value(provider.build_request(&[], &options, false)).unwrap();
+        assert!(json.get("reasoning_effort").is_none());
+        assert!(json.get("max_completion_tokens").is_none());
+        assert_eq!(json["max_tokens"], 256);
     }
 
     #[test]
     fn test_build_reasoning_effort_disabled() {
-        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-5-nano".into());
+        let provider = OpenAIProvider::new("key".into(), OFFICIAL_URL.into(), "gpt-5-nano".into());
         let options = ProviderOptions {
             thinking_enabled: false,
             thinking_value: None,
             web_search: false,
             allowed_domains: None,
             blocked_domains: None,
+            max_tokens: None,
+            include_thoughts: false,
+            candidates: None,
+            seed: None,
         };
         assert_eq!(
             provider.build_reasoning_effort(&options),
@@ -430,15 +844,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_reasoning_effort_disabled_sends_none_when_supported() {
+        let provider = OpenAIProvider::new("key".into(), OFFICIAL_URL.into(), "gpt-5.1".into());
+        assert_eq!(
+            provider.build_reasoning_effort(&ProviderOptions::default()),
+            Some("none".to_string())
+        );
+    }
+
     #[test]
     fn test_build_reasoning_effort_enabled() {
-        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-5-nano".into());
+        let provider = OpenAIProvider::new("key".into(), OFFICIAL_URL.into(), "gpt-5-nano".into());
         let options = ProviderOptions {
             thinking_enabled: true,
             thinking_value: Some("high".to_string()),
             web_search: false,
             allowed_domains: None,
             blocked_domains: None,
+            max_tokens: None,
+            include_thoughts: false,
+            candidates: None,
+            seed: None,
         };
         assert_eq!(
             provider.build_reasoning_effort(&options),
@@ -455,7 +882,279 @@ mod tests {
             web_search: false,
             allowed_domains: None,
             blocked_domains: None,
+            max_tokens: None,
+            include_thoughts: false,
+            candidates: None,
+            seed: None,
         };
         assert_eq!(provider.build_reasoning_effort(&options), None);
     }
+
+    #[test]
+    fn test_build_request_candidate_count() {
+        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-4o".into());
+        let messages = [Message {
+            role: "user".into(),
+            content: "hi".into(),
+            ..Default::default()
+        }];
+
+        let json = serde_json::to_value(provider.build_request(
+            &messages,
+            &ProviderOptions::default(),
+            false,
+        ))
+        .unwrap();
+        assert!(json.get("n").is_none());
+
+        let options = ProviderOptions {
+            candidates: Some(3),
+            ..Default::default()
+        };
+        let json =
+            serde_json::to_value(provider.build_request(&messages, &options, false)).unwrap();
+        assert_eq!(json["n"], 3);
+    }
+
+    #[test]
+    fn test_build_request_seed() {
+        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-4o".into());
+        let messages = [Message {
+            role: "user".into(),
+            content: "hi".into(),
+            ..Default::default()
+        }];
+
+        let json = serde_json::to_value(provider.build_request(
+            &messages,
+            &ProviderOptions::default(),
+            false,
+        ))
+        .unwrap();
+        assert!(json.get("seed").is_none());
+        assert_eq!(json["temperature"], 0.7f32);
+
+        let options = ProviderOptions {
+            seed: Some(42),
+            ..Default::default()
+        };
+        let json =
+            serde_json::to_value(provider.build_request(&messages, &options, false)).unwrap();
+        assert_eq!(json["seed"], 42);
+        assert_eq!(json["temperature"], 0.0);
+        assert!(provider.supports_seed());
+    }
+
+    #[test]
+    fn test_chat_finish_reason_length() {
+        let response: OpenAIResponse = serde_json::from_str(
+            r#"{"choices": [{"message": {"role": "assistant", "content": "Step 1"}, "finish_reason": "length"}]}"#,
+        )
+        .unwrap();
+        let responses = chat_responses(response);
+        assert_eq!(responses[0].finish_reason.as_deref(), Some("length"));
+        assert!(responses[0].is_truncated());
+
+        let response: OpenAIResponse = serde_json::from_str(
+            r#"{"choices": [{"message": {"role": "assistant", "content": "done"}, "finish_reason": "stop"}]}"#,
+        )
+        .unwrap();
+        assert!(!chat_responses(response)[0].is_truncated());
+    }
+
+    #[test]
+    fn test_multiple_choices_parse() {
+        let response: OpenAIResponse = serde_json::from_str(
+            r#"{"choices": [
+                {"message": {"role": "assistant", "content": "tar czf a.tgz a"}},
+                {"message": {"role": "assistant", "content": "zip -r a.zip a"}}
+            ]}"#,
+        )
+        .unwrap();
+        let texts: Vec<String> = response
+            .choices
+            .unwrap()
+            .into_iter()
+            .filter_map(|c| c.message)
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(texts, vec!["tar czf a.tgz a", "zip -r a.zip a"]);
+    }
+
+    fn thinking_options() -> ProviderOptions {
+        ProviderOptions {
+            thinking_enabled: true,
+            thinking_value: Some("high".to_string()),
+            max_tokens: Some(256),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_local_base_url_suppresses_reasoning_fields() {
+        let provider = OpenAIProvider::new(
+            "key".into(),
+            "http://localhost:8000/v1".into(),
+            "gpt-5-mini".into(),
+        );
+        let options = thinking_options();
+        assert_eq!(provider.build_reasoning_effort(&options), None);
+
+        let request = provider.build_request(&[], &options, false);
+        let json = serde_json::to_value(&request).unwrap();
+        assert!(json.get("reasoning_effort").is_none());
+        assert!(json.get("max_completion_tokens").is_none());
+        assert_eq!(json["max_tokens"], 256);
+        assert!(json.get("temperature").is_some());
+    }
+
+    #[test]
+    fn test_official_base_url_sends_reasoning_fields() {
+        let provider = OpenAIProvider::new("key".into(), OFFICIAL_URL.into(), "gpt-5-mini".into());
+        let request = provider.build_request(&[], &thinking_options(), true);
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["reasoning_effort"], "high");
+        assert_eq!(json["max_completion_tokens"], 256);
+        assert!(json.get("max_tokens").is_none());
+        assert!(json.get("temperature").is_none());
+    }
+
+    #[test]
+    fn test_supports_reasoning_override() {
+        let provider = OpenAIProvider::new(
+            "key".into(),
+            "http://localhost:8000/v1".into(),
+            "o3-mini".into(),
+        )
+        .with_reasoning_support(Some(true));
+        assert_eq!(
+            provider.build_reasoning_effort(&thinking_options()),
+            Some("high".to_string())
+        );
+
+        let provider = OpenAIProvider::new("key".into(), OFFICIAL_URL.into(), "o3-mini".into())
+            .with_reasoning_support(Some(false));
+        let json =
+            serde_json::to_value(provider.build_request(&[], &thinking_options(), false)).unwrap();
+        assert!(json.get("reasoning_effort").is_none());
+        assert_eq!(json["max_tokens"], 256);
+    }
+
+    fn azure_provider() -> OpenAIProvider {
+        OpenAIProvider::new(
+            "azure-key".into(),
+            "https://myres.openai.azure.com/".into(),
+            "gpt-4o".into(),
+        )
+        .with_azure(AzureDeployment {
+            deployment: "prod-gpt4o".into(),
+            api_version: "2024-10-21".into(),
+        })
+    }
+
+    #[test]
+    fn test_azure_chat_completions_url() {
+        assert_eq!(
+            azure_provider().chat_completions_url(),
+            "https://myres.openai.azure.com/openai/deployments/prod-gpt4o/chat/completions?api-version=2024-10-21"
+        );
+
+        let provider = OpenAIProvider::new("key".into(), OFFICIAL_URL.into(), "gpt-4o".into());
+        assert_eq!(
+            provider.chat_completions_url(),
+            "https://api.openai.com/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn test_azure_auth_header() {
+        assert_eq!(
+            azure_provider().auth_header(),
+            ("api-key", "azure-key".to_string())
+        );
+        assert_eq!(azure_provider().name(), "azure");
+
+        let provider = OpenAIProvider::new("key".into(), OFFICIAL_URL.into(), "gpt-4o".into());
+        assert_eq!(
+            provider.auth_header(),
+            ("Authorization", "Bearer key".to_string())
+        );
+    }
+
+    #[test]
+    fn test_convert_tool_messages() {
+        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-4o".into());
+        let messages = [
+            Message {
+                role: "user".into(),
+                content: "how big is /home?".into(),
+                ..Default::default()
+            },
+            Message {
+                role: "tool".into(),
+                content: "42G /home".into(),
+                tool_call_id: Some("call_1".into()),
+                name: Some("du".into()),
+                ..Default::default()
+            },
+            Message {
+                role: "tool".into(),
+                content: "ok".into(),
+                ..Default::default()
+            },
+        ];
+        let json = serde_json::to_value(provider.convert_messages(&messages)).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3, "no message dropped");
+        assert_eq!(json[1]["role"], "tool");
+        assert_eq!(json[1]["tool_call_id"], "call_1");
+        assert_eq!(json[1]["content"], "42G /home");
+        assert_eq!(
+            json[2]["role"], "user",
+            "a result without a call id goes as text"
+        );
+        assert_eq!(json[2]["content"], "Tool result:\nok");
+        assert!(json[0].get("tool_call_id").is_none());
+
+        assert!(provider
+            .messages_to_input(&messages)
+            .contains("Tool result (du):\n42G /home"));
+    }
+
+    #[test]
+    fn test_convert_image_url_message() {
+        let provider = OpenAIProvider::new("key".into(), "url".into(), "gpt-4o".into());
+        let messages = [Message {
+            role: "user".into(),
+            content: "what is this".into(),
+            images: vec![
+                ImageInput::Url("https://example.com/photo.jpg".into()),
+                ImageInput::Inline {
+                    mime_type: "image/png".into(),
+                    data: "iVBORw==".into(),
+                },
+            ],
+            ..Default::default()
+        }];
+        let json = serde_json::to_value(provider.convert_messages(&messages)).unwrap();
+        let parts = json[0]["content"].as_array().unwrap();
+        assert_eq!(parts[0]["type"], "text");
+        assert_eq!(parts[0]["text"], "what is this");
+        assert_eq!(parts[1]["type"], "image_url");
+        assert_eq!(
+            parts[1]["image_url"]["url"],
+            "https://example.com/photo.jpg"
+        );
+        assert_eq!(
+            parts[2]["image_url"]["url"],
+            "data:image/png;base64,iVBORw=="
+        );
+
+        let plain = Message {
+            role: "user".into(),
+            content: "hi".into(),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(provider.convert_messages(&[plain])).unwrap();
+        assert_eq!(json[0]["content"], "hi", "no images keeps a plain string");
+    }
 }
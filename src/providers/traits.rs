@@ -5,10 +5,39 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
 /// A message in a conversation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Message {
     pub role: String,
     pub content: String,
+    /// For `tool` messages: the id of the tool call this result answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// For `tool` messages: the tool's name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Images attached to a `user` message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageInput>,
+}
+
+/// An image sent along with the question
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ImageInput {
+    /// Remote image, passed through to providers that fetch URLs themselves
+    Url(String),
+    /// Base64-encoded bytes
+    Inline { mime_type: String, data: String },
+}
+
+impl Message {
+    /// A `tool` result as plain text, for when there is no call id to pair it with
+    /// (providers reject tool results that don't answer a call they made)
+    pub fn tool_result_text(&self) -> String {
+        match self.name {
+            Some(ref name) => format!("Tool result ({}):\n{}", name, self.content),
+            None => format!("Tool result:\n{}", self.content),
+        }
+    }
 }
 
 /// Citation from web search results
@@ -26,6 +55,49 @@ pub struct Citation {
 pub struct ProviderResponse {
     pub text: String,
     pub citations: Vec<Citation>,
+    pub usage: Option<Usage>,
+    /// Reasoning trace or summary, when the provider returned one
+    pub thinking_text: Option<String>,
+    /// Why generation stopped, as the provider reports it ("length", "max_tokens", ...)
+    pub finish_reason: Option<String>,
+}
+
+impl ProviderResponse {
+    /// The answer was cut off by the output token limit
+    pub fn is_truncated(&self) -> bool {
+        hit_token_limit(self.finish_reason.as_deref())
+    }
+}
+
+/// How a streamed answer ended
+#[derive(Debug, Clone, Default)]
+pub struct StreamEnd {
+    /// Why generation stopped, when the stream said so
+    pub finish_reason: Option<String>,
+}
+
+impl StreamEnd {
+    /// The answer was cut off by the output token limit
+    pub fn is_truncated(&self) -> bool {
+        hit_token_limit(self.finish_reason.as_deref())
+    }
+}
+
+/// "length", "max_tokens" or "MAX_TOKENS", depending on the provider
+fn hit_token_limit(finish_reason: Option<&str>) -> bool {
+    finish_reason.is_some_and(|reason| {
+        matches!(
+            reason.to_ascii_lowercase().as_str(),
+            "length" | "max_tokens" | "max_output_tokens"
+        )
+    })
+}
+
+/// Token counts reported by the provider for a single request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
 }
 
 /// Options for provider requests
@@ -36,6 +108,29 @@ pub struct ProviderOptions {
     pub blocked_domains: Option<Vec<String>>,
     pub thinking_enabled: bool,
     pub thinking_value: Option<String>,
+    /// Cap on generated tokens (None = provider default)
+    pub max_tokens: Option<u32>,
+    /// Ask for the reasoning trace in the response (`--show-thinking`)
+    pub include_thoughts: bool,
+    /// Number of candidate answers for `complete_candidates` (`-N`; None = 1)
+    pub candidates: Option<u32>,
+    /// Sampling seed for reproducible answers (`--seed`); also sets temperature 0
+    pub seed: Option<u64>,
+}
+
+impl ProviderOptions {
+    /// Temperature to send: 0 in seeded mode, otherwise the provider's usual value
+    pub fn temperature(&self, default: f32) -> f32 {
+        if self.seed.is_some() {
+            0.0
+        } else {
+            default
+        }
+    }
+
+    pub fn candidate_count(&self) -> u32 {
+        self.candidates.unwrap_or(1).max(1)
+    }
 }
 
 /// Callback type for streaming responses
@@ -58,7 +153,7 @@ pub trait Provider: Send + Sync {
     ) -> Result<ProviderResponse>;
 
     #[allow(dead_code)]
-    async fn stream(&self, messages: &[Message], callback: StreamCallback) -> Result<()> {
+    async fn stream(&self, messages: &[Message], callback: StreamCallback) -> Result<StreamEnd> {
         self.stream_with_options(messages, callback, &ProviderOptions::default())
             .await
     }
@@ -68,7 +163,33 @@ pub trait Provider: Send + Sync {
         messages: &[Message],
         callback: StreamCallback,
         options: &ProviderOptions,
-    ) -> Result<()>;
+    ) -> Result<StreamEnd>;
+
+    /// Return `options.candidates` alternative answers. Providers without a
+    /// native candidate count make one request per answer.
+    async fn complete_candidates(
+        &self,
+        messages: &[Message],
+        options: &ProviderOptions,
+    ) -> Result<Vec<ProviderResponse>> {
+        complete_separately(self, messages, options).await
+    }
+
+    /// Why the reasoning level sent differs from the one requested, shown under `-v`
+    fn reasoning_note(&self, _options: &ProviderOptions) -> Option<String> {
+        None
+    }
+
+    /// The thinking field a request with these options carries, as (`field`, value);
+    /// None when no thinking parameter is sent (`--model-info`)
+    fn thinking_param(&self, _options: &ProviderOptions) -> Option<(&'static str, String)> {
+        None
+    }
+
+    /// Whether `options.seed` reaches the API (otherwise only temperature 0 applies)
+    fn supports_seed(&self) -> bool {
+        false
+    }
 
     #[allow(dead_code)]
     fn name(&self) -> &str;
@@ -76,6 +197,85 @@ pub trait Provider: Send + Sync {
     fn model(&self) -> &str;
 }
 
+/// One `complete_with_options` call per requested candidate
+pub async fn complete_separately<P: Provider + ?Sized>(
+    provider: &P,
+    messages: &[Message],
+    options: &ProviderOptions,
+) -> Result<Vec<ProviderResponse>> {
+    let single = ProviderOptions {
+        candidates: None,
+        ..options.clone()
+    };
+    let mut responses = Vec::new();
+    for _ in 0..options.candidate_count() {
+        responses.push(provider.complete_with_options(messages, &single).await?);
+    }
+    Ok(responses)
+}
+
+/// Shell syntax family, used to chain commands the way the user's shell expects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShellFamily {
+    /// sh, bash, zsh, dash, ksh and anything unrecognized
+    #[default]
+    Posix,
+    Fish,
+    Nushell,
+}
+
+impl ShellFamily {
+    /// Detect the family from a shell path such as `$SHELL` (`/usr/bin/fish` -> Fish)
+    pub fn from_shell(shell: &str) -> Self {
+        let name = shell
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(shell)
+            .trim_end_matches(".exe");
+        match name {
+            "fish" => Self::Fish,
+            "nu" | "nushell" => Self::Nushell,
+            _ => Self::Posix,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::from_shell(&std::env::var("SHELL").unwrap_or_default())
+    }
+
+    /// Joins commands that should run one after another
+    pub fn separator(self) -> &'static str {
+        match self {
+            Self::Posix => " && ",
+            Self::Fish => "; and ",
+            Self::Nushell => "; ",
+        }
+    }
+
+    /// The shell ask runs commands with. Answers are written in this family's syntax
+    /// (see `chaining_instruction`), so fish and nushell answers run under fish and nu.
+    pub fn exec_shell(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Posix if cfg!(windows) => ("cmd", "/C"),
+            Self::Posix => ("sh", "-c"),
+            Self::Fish => ("fish", "-c"),
+            Self::Nushell => ("nu", "-c"),
+        }
+    }
+
+    fn chaining_instruction(self) -> &'static str {
+        match self {
+            Self::Posix => "Use && for multiple commands, \\ for line continuation",
+            Self::Fish => {
+                "Use `; and` for multiple commands (fish syntax, not bash), no \\ line continuation"
+            }
+            Self::Nushell => {
+                "Use ; or | pipelines for multiple commands (nushell syntax, not bash; no &&)"
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct PromptContext {
     pub os: String,
@@ -104,6 +304,10 @@ impl PromptContext {
         }
     }
 
+    pub fn shell_family(&self) -> ShellFamily {
+        ShellFamily::from_shell(&self.shell)
+    }
+
     fn format_instructions(&self) -> &'static str {
         if self.use_markdown {
             "Use markdown for formatting."
@@ -134,7 +338,7 @@ pub fn build_unified_prompt(ctx: &PromptContext) -> String {
 INTENT DETECTION:
 - If the user asks for a shell command (e.g., "list files", "delete logs", "show disk usage"), return ONLY the command
   - No explanations, no markdown, no code blocks, no backticks
-  - Use && for multiple commands, \ for line continuation
+  - {chaining}
   - Quote arguments containing spaces or special characters
   - NEVER use newlines in commands
 - If it's a question or informational request, be brief (1-3 sentences max)
@@ -143,6 +347,7 @@ INTENT DETECTION:
 Context: OS={os}, shell={shell}, cwd={cwd}, locale={locale}, now={now}
 {format_instructions}"#,
         command_emphasis = command_emphasis,
+        chaining = ctx.shell_family().chaining_instruction(),
         locale = ctx.locale,
         os = ctx.os,
         shell = ctx.shell,
@@ -167,56 +372,71 @@ Context: OS={os}, shell={shell}, cwd={cwd}, locale={locale}, now={now}
 {format}
 "#;
 
-pub fn load_custom_prompt(command_name: Option<&str>) -> Option<String> {
+/// Locate the custom prompt files: `ask[.cmd].md` searched upwards, then home, then config dir
+fn find_custom_prompts(command_name: Option<&str>) -> Vec<std::path::PathBuf> {
     use crate::config::loader::find_recursive_file;
-    use std::path::PathBuf;
 
-    let home = dirs::home_dir();
-    let config_dir = dirs::config_dir().map(|p| p.join("ask"));
-
-    let local_file = if let Some(cmd) = command_name {
-        let filename = format!("ask.{}.md", cmd);
-        let dot_filename = format!(".ask.{}.md", cmd);
-        find_recursive_file(&[&filename, &dot_filename])
-    } else {
-        find_recursive_file(&["ask.md", ".ask.md"])
+    let filename = match command_name {
+        Some(cmd) => format!("ask.{}.md", cmd),
+        None => "ask.md".to_string(),
     };
+    let dot_filename = format!(".{}", filename);
 
-    if let Some(path) = local_file {
-        if let Ok(content) = std::fs::read_to_string(&path) {
-            return Some(content);
-        }
-    }
+    let local = find_recursive_file(&[&filename, &dot_filename]);
 
     // Fallback to home and config dir
-    let search_paths: Vec<PathBuf> = if let Some(cmd) = command_name {
-        let filename = format!("ask.{}.md", cmd);
-        vec![
-            home.clone().map(|h| h.join(&filename)).unwrap_or_default(),
-            config_dir
-                .clone()
-                .map(|c| c.join(&filename))
-                .unwrap_or_default(),
-        ]
-    } else {
-        vec![
-            home.clone().map(|h| h.join("ask.md")).unwrap_or_default(),
-            config_dir
-                .clone()
-                .map(|c| c.join("ask.md"))
-                .unwrap_or_default(),
-        ]
-    };
+    let fallbacks = [dirs::home_dir(), dirs::config_dir().map(|p| p.join("ask"))]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(&filename))
+        .filter(|path| path.exists());
 
-    for path in search_paths {
-        if path.exists() {
-            if let Ok(content) = std::fs::read_to_string(&path) {
-                return Some(content);
-            }
-        }
+    local.into_iter().chain(fallbacks).collect()
+}
+
+/// The prompt files that make up the system prompt. First-wins picks one; with
+/// `[prompt] merge = true` all of them, most general first so the local file reads last.
+pub fn custom_prompt_sources(command_name: Option<&str>, merge: bool) -> Vec<std::path::PathBuf> {
+    prompt_layers(find_custom_prompts(command_name), merge)
+}
+
+fn prompt_layers(found: Vec<std::path::PathBuf>, merge: bool) -> Vec<std::path::PathBuf> {
+    if !merge {
+        return found.into_iter().take(1).collect();
     }
 
-    None
+    // The upward search can stop at home and find ~/ask.md a second time
+    let mut seen = std::collections::HashSet::new();
+    let mut layers: Vec<std::path::PathBuf> = found
+        .into_iter()
+        .filter(|path| seen.insert(std::fs::canonicalize(path).unwrap_or_else(|_| path.clone())))
+        .collect();
+    layers.reverse();
+    layers
+}
+
+pub fn load_custom_prompt(command_name: Option<&str>, merge: bool) -> Option<String> {
+    merge_prompt_files(&custom_prompt_sources(command_name, merge))
+}
+
+/// Concatenate prompt files in order, separated by a Markdown rule; unreadable files are skipped
+fn merge_prompt_files(paths: &[std::path::PathBuf]) -> Option<String> {
+    let parts: Vec<String> = paths
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .collect();
+
+    match parts.as_slice() {
+        [] => None,
+        [only] => Some(only.clone()),
+        _ => Some(
+            parts
+                .iter()
+                .map(|p| p.trim())
+                .collect::<Vec<_>>()
+                .join("\n\n---\n\n"),
+        ),
+    }
 }
 
 pub fn expand_prompt_variables(template: &str, ctx: &PromptContext) -> String {
@@ -229,10 +449,68 @@ pub fn expand_prompt_variables(template: &str, ctx: &PromptContext) -> String {
         .replace("{format}", ctx.format_instructions())
 }
 
+/// Append configured prompt fragments to an assembled system prompt
+pub fn append_prompt_fragment(prompt: String, fragment: Option<&str>) -> String {
+    match fragment {
+        Some(extra) if !extra.trim().is_empty() => {
+            format!("{}\n\n{}", prompt.trim_end(), extra.trim())
+        }
+        _ => prompt,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_prompt_layers_first_wins_by_default() {
+        let found = vec!["/repo/ask.md".into(), "/home/u/ask.md".into()];
+        assert_eq!(
+            prompt_layers(found, false),
+            vec![std::path::PathBuf::from("/repo/ask.md")]
+        );
+        assert!(prompt_layers(Vec::new(), false).is_empty());
+    }
+
+    #[test]
+    fn test_prompt_layers_merge_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("ask.md");
+        std::fs::write(&home, "home").unwrap();
+        let local = std::path::PathBuf::from("/repo/ask.md");
+        let config = std::path::PathBuf::from("/home/u/.config/ask/ask.md");
+
+        // Search order is local, home, config; the merge reads most general first,
+        // and a home file found by both searches appears once
+        let found = vec![local.clone(), home.clone(), home.clone(), config.clone()];
+        assert_eq!(prompt_layers(found, true), vec![config, home, local]);
+    }
+
+    #[test]
+    fn test_merge_prompt_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let personal = dir.path().join("personal.md");
+        let repo = dir.path().join("repo.md");
+        std::fs::write(&personal, "Answer in British English.\n").unwrap();
+        std::fs::write(&repo, "This repo uses pnpm.\n").unwrap();
+
+        assert_eq!(
+            merge_prompt_files(&[personal, repo.clone()]).as_deref(),
+            Some("Answer in British English.\n\n---\n\nThis repo uses pnpm.")
+        );
+        assert_eq!(
+            merge_prompt_files(std::slice::from_ref(&repo)).as_deref(),
+            Some("This repo uses pnpm.\n"),
+            "a single file is used as-is"
+        );
+        assert_eq!(
+            merge_prompt_files(&[dir.path().join("missing.md"), repo]).as_deref(),
+            Some("This repo uses pnpm.\n")
+        );
+        assert_eq!(merge_prompt_files(&[]), None);
+    }
+
     #[test]
     fn test_build_unified_prompt_instructions() {
         let ctx = PromptContext::default();
@@ -242,4 +520,140 @@ mod tests {
         assert!(prompt.contains("NEVER use newlines in commands"));
         assert!(prompt.contains("Quote arguments containing spaces or special characters"));
     }
+
+    struct CountingProvider {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Provider for CountingProvider {
+        async fn complete_with_options(
+            &self,
+            _messages: &[Message],
+            options: &ProviderOptions,
+        ) -> Result<ProviderResponse> {
+            assert_eq!(options.candidates, None, "each call asks for one answer");
+            let n = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(ProviderResponse {
+                text: format!("answer {}", n + 1),
+                ..Default::default()
+            })
+        }
+
+        async fn stream_with_options(
+            &self,
+            _messages: &[Message],
+            _callback: StreamCallback,
+            _options: &ProviderOptions,
+        ) -> Result<StreamEnd> {
+            Ok(StreamEnd::default())
+        }
+
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn model(&self) -> &str {
+            "counting"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_complete_candidates_falls_back_to_separate_calls() {
+        let provider = CountingProvider {
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        };
+        let options = ProviderOptions {
+            candidates: Some(3),
+            ..Default::default()
+        };
+
+        let responses = provider.complete_candidates(&[], &options).await.unwrap();
+        let texts: Vec<&str> = responses.iter().map(|r| r.text.as_str()).collect();
+        assert_eq!(texts, vec!["answer 1", "answer 2", "answer 3"]);
+
+        let single = provider
+            .complete_candidates(&[], &ProviderOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(single.len(), 1);
+    }
+
+    #[test]
+    fn test_shell_family_from_shell() {
+        assert_eq!(ShellFamily::from_shell("/bin/bash"), ShellFamily::Posix);
+        assert_eq!(ShellFamily::from_shell("/usr/bin/zsh"), ShellFamily::Posix);
+        assert_eq!(ShellFamily::from_shell(""), ShellFamily::Posix);
+        assert_eq!(ShellFamily::from_shell("/usr/bin/fish"), ShellFamily::Fish);
+        assert_eq!(
+            ShellFamily::from_shell("/opt/homebrew/bin/nu"),
+            ShellFamily::Nushell
+        );
+        assert_eq!(
+            ShellFamily::from_shell(r"C:\tools\nu.exe"),
+            ShellFamily::Nushell
+        );
+
+        assert_eq!(ShellFamily::Posix.separator(), " && ");
+        assert_eq!(ShellFamily::Fish.separator(), "; and ");
+        assert_eq!(ShellFamily::Nushell.separator(), "; ");
+        assert_eq!(ShellFamily::Fish.exec_shell(), ("fish", "-c"));
+        assert_eq!(ShellFamily::Nushell.exec_shell(), ("nu", "-c"));
+    }
+
+    #[test]
+    fn test_build_unified_prompt_chaining_per_shell() {
+        let prompt_for = |shell: &str| {
+            build_unified_prompt(&PromptContext {
+                shell: shell.to_string(),
+                ..Default::default()
+            })
+        };
+
+        let fish = prompt_for("/usr/bin/fish");
+        assert!(fish.contains("Use `; and` for multiple commands"));
+        assert!(!fish.contains("Use && for multiple commands"));
+
+        let nu = prompt_for("/usr/bin/nu");
+        assert!(nu.contains("nushell syntax"));
+        assert!(!nu.contains("Use && for multiple commands"));
+
+        assert!(prompt_for("/bin/zsh").contains("Use && for multiple commands"));
+    }
+
+    #[test]
+    fn test_append_prompt_fragment_to_unified_prompt() {
+        let ctx = PromptContext::default();
+        let prompt = append_prompt_fragment(
+            build_unified_prompt(&ctx),
+            Some("Always prefer ripgrep over grep."),
+        );
+
+        assert!(prompt.contains("NEVER use newlines in commands"));
+        assert!(prompt.ends_with("\n\nAlways prefer ripgrep over grep."));
+    }
+
+    #[test]
+    fn test_append_prompt_fragment_to_custom_prompt() {
+        let ctx = PromptContext {
+            os: "linux".to_string(),
+            ..Default::default()
+        };
+        let custom = expand_prompt_variables("You are terse. OS={os}\n", &ctx);
+        let prompt = append_prompt_fragment(custom, Some("Never suggest sudo unless asked."));
+
+        assert_eq!(
+            prompt,
+            "You are terse. OS=linux\n\nNever suggest sudo unless asked."
+        );
+    }
+
+    #[test]
+    fn test_append_prompt_fragment_empty() {
+        assert_eq!(append_prompt_fragment("base".to_string(), None), "base");
+        assert_eq!(
+            append_prompt_fragment("base".to_string(), Some("  ")),
+            "base"
+        );
+    }
 }
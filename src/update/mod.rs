@@ -9,6 +9,15 @@ use std::path::PathBuf;
 
 const RELEASES_URL: &str = "https://api.github.com/repos/verseles/ask/releases/latest";
 
+/// The only host a GitHub token is ever sent to
+const GITHUB_API_HOST: &str = "api.github.com";
+
+/// Backoff after a rate-limited check when GitHub sends no Retry-After
+const DEFAULT_BACKOFF_SECS: i64 = 3600;
+
+/// Random extra delay added to a backoff so many installs don't retry in lockstep
+const BACKOFF_JITTER_SECS: i64 = 300;
+
 #[derive(Debug, Deserialize)]
 struct GitHubRelease {
     tag_name: String,
@@ -28,7 +37,7 @@ struct GitHubError {
     message: String,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct UpdateNotification {
     pub old_version: String,
     pub new_version: String,
@@ -37,8 +46,18 @@ pub struct UpdateNotification {
     pub timestamp: i64,
 }
 
-#[allow(dead_code)]
-pub fn should_check_update(aggressive: bool, interval_hours: u64, last_check: Option<i64>) -> bool {
+/// Decide whether to check for updates. `next_check` is the backoff marker written
+/// after a rate-limited check; no check happens before it, whatever `last_check` says.
+pub fn should_check_update(
+    aggressive: bool,
+    interval_hours: u64,
+    last_check: Option<i64>,
+    next_check: Option<i64>,
+) -> bool {
+    if next_check.is_some_and(|next| chrono::Utc::now().timestamp() < next) {
+        return false;
+    }
+
     match last_check {
         None => true,
         Some(timestamp) => {
@@ -55,6 +74,39 @@ pub fn should_check_update(aggressive: bool, interval_hours: u64, last_check: Op
     }
 }
 
+/// Timestamp before which no update check should run, after a rate-limited response.
+/// `Retry-After` may be delta-seconds or an HTTP date; without it we wait an hour.
+fn backoff_until(now: i64, retry_after: Option<&str>, jitter: i64) -> i64 {
+    let wait = retry_after
+        .and_then(|value| {
+            let value = value.trim();
+            value.parse::<i64>().ok().or_else(|| {
+                chrono::DateTime::parse_from_rfc2822(value)
+                    .ok()
+                    .map(|date| date.timestamp() - now)
+            })
+        })
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_BACKOFF_SECS);
+
+    now + wait + jitter
+}
+
+/// Cheap jitter in `0..=BACKOFF_JITTER_SECS` from the clock's sub-second part
+fn backoff_jitter() -> i64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    i64::from(nanos) % (BACKOFF_JITTER_SECS + 1)
+}
+
+fn read_timestamp(path: &std::path::Path) -> Option<i64> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| content.trim().parse::<i64>().ok())
+}
+
 pub fn format_changelog(changelog: &str, max_lines: usize) -> String {
     changelog
         .lines()
@@ -200,9 +252,13 @@ fn is_newer_version(current: &str, remote: &str) -> bool {
     false
 }
 
-/// Check for updates in background (non-blocking)
-pub fn check_updates_background(aggressive: bool, interval_hours: u64) {
-    if std::env::var("ASK_NO_UPDATE").is_ok() {
+/// Check for updates in background (non-blocking). Nothing is spawned when
+/// self-update is off, since the child would refuse anyway.
+pub fn check_updates_background(update: &crate::config::UpdateConfig) {
+    if std::env::var("ASK_NO_UPDATE").is_ok()
+        || crate::http::is_offline()
+        || !update.allow_self_update
+    {
         return;
     }
 
@@ -211,16 +267,15 @@ pub fn check_updates_background(aggressive: bool, interval_hours: u64) {
         None => return,
     };
 
-    let last_check_file = data_dir.join("last_update_check");
-    let last_check = if last_check_file.exists() {
-        fs::read_to_string(&last_check_file)
-            .ok()
-            .and_then(|content| content.trim().parse::<i64>().ok())
-    } else {
-        None
-    };
+    let last_check = read_timestamp(&data_dir.join("last_update_check"));
+    let next_check = read_timestamp(&data_dir.join("next_update_check"));
 
-    if !should_check_update(aggressive, interval_hours, last_check) {
+    if !should_check_update(
+        update.aggressive,
+        update.check_interval_hours,
+        last_check,
+        next_check,
+    ) {
         return;
     }
 
@@ -262,8 +317,98 @@ pub fn check_updates_background(aggressive: bool, interval_hours: u64) {
     }
 }
 
+/// Token for authenticated release checks: ASK_GITHUB_TOKEN, then GITHUB_TOKEN
+fn github_token() -> Option<String> {
+    ["ASK_GITHUB_TOKEN", "GITHUB_TOKEN"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|token| token.trim().to_string())
+        .find(|token| !token.is_empty())
+}
+
+/// `[update] user_agent` from the config, or `ask/<version>`
+fn update_user_agent() -> String {
+    crate::config::Config::load()
+        .ok()
+        .and_then(|config| config.update.user_agent)
+        .filter(|ua| !ua.trim().is_empty())
+        .unwrap_or_else(|| format!("ask/{}", env!("CARGO_PKG_VERSION")))
+}
+
+/// Extra headers for a request to `url`: the bearer token, but only for api.github.com
+fn github_headers(url: &str, token: Option<&str>) -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    let is_api = reqwest::Url::parse(url)
+        .ok()
+        .is_some_and(|u| u.scheme() == "https" && u.host_str() == Some(GITHUB_API_HOST));
+
+    if let Some(token) = token.filter(|_| is_api) {
+        if let Ok(mut value) = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+        {
+            value.set_sensitive(true);
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+        }
+    }
+    headers
+}
+
+const SELF_UPDATE_DISABLED: &str = "self-update disabled by config";
+
+/// `[update] allow_self_update`; a config that fails to load keeps the default
+fn self_update_allowed() -> bool {
+    crate::config::Config::load()
+        .map(|config| config.update.allow_self_update)
+        .unwrap_or(true)
+}
+
+/// Write the downloaded binary next to `current_exe` and swap it in.
+/// Refuses before touching the disk when self-update is disabled.
+fn replace_binary(current_exe: &std::path::Path, bytes: &[u8], allowed: bool) -> Result<()> {
+    if !allowed {
+        anyhow::bail!(SELF_UPDATE_DISABLED);
+    }
+
+    // Create temp file
+    let temp_path = current_exe.with_extension("new");
+
+    // Write new binary
+    fs::write(&temp_path, bytes)?;
+
+    // Set executable permission on Unix
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(&temp_path)?.permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&temp_path, perms)?;
+    }
+
+    // Replace binary
+    #[cfg(unix)]
+    {
+        fs::rename(&temp_path, current_exe)?;
+    }
+
+    #[cfg(windows)]
+    {
+        let backup_path = current_exe.with_extension("old");
+        let _ = fs::remove_file(&backup_path);
+        fs::rename(current_exe, &backup_path)?;
+        fs::rename(&temp_path, current_exe)?;
+        let _ = fs::remove_file(&backup_path);
+    }
+
+    Ok(())
+}
+
 /// Background update check (called from spawned process)
 pub async fn background_update_check() -> Result<()> {
+    if crate::http::is_offline() {
+        return Ok(());
+    }
+    if !self_update_allowed() {
+        anyhow::bail!(SELF_UPDATE_DISABLED);
+    }
     let current_version = env!("CARGO_PKG_VERSION");
 
     // Update last check time
@@ -277,13 +422,34 @@ pub async fn background_update_check() -> Result<()> {
     // Fetch latest release
     let client = create_client_builder()
         .timeout(std::time::Duration::from_secs(10))
-        .user_agent(format!("ask/{}", current_version))
+        .user_agent(update_user_agent())
         .build()?;
 
-    let response = client.get(RELEASES_URL).send().await?;
+    let response = client
+        .get(RELEASES_URL)
+        .headers(github_headers(RELEASES_URL, github_token().as_deref()))
+        .send()
+        .await?;
     let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
     let body = response.text().await?;
 
+    // Unauthenticated GitHub calls answer 403 (or 429) when rate limited:
+    // persist a backoff so the next launches don't hit the API again
+    if status == reqwest::StatusCode::FORBIDDEN || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+    {
+        let until = backoff_until(
+            chrono::Utc::now().timestamp(),
+            retry_after.as_deref(),
+            backoff_jitter(),
+        );
+        fs::write(data_dir.join("next_update_check"), until.to_string())?;
+    }
+
     // Check for API errors (rate limit, not found, etc.)
     if !status.is_success() {
         if let Ok(error) = serde_json::from_str::<GitHubError>(&body) {
@@ -313,38 +479,8 @@ pub async fn background_update_check() -> Result<()> {
     let response = client.get(&asset.browser_download_url).send().await?;
     let bytes = response.bytes().await?;
 
-    // Get current executable path
-    let current_exe = std::env::current_exe()?;
-
-    // Create temp file
-    let temp_path = current_exe.with_extension("new");
-
-    // Write new binary
-    fs::write(&temp_path, &bytes)?;
-
-    // Set executable permission on Unix
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&temp_path)?.permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(&temp_path, perms)?;
-    }
-
-    // Replace binary
-    #[cfg(unix)]
-    {
-        fs::rename(&temp_path, &current_exe)?;
-    }
-
-    #[cfg(windows)]
-    {
-        let backup_path = current_exe.with_extension("old");
-        let _ = fs::remove_file(&backup_path);
-        fs::rename(&current_exe, &backup_path)?;
-        fs::rename(&temp_path, &current_exe)?;
-        let _ = fs::remove_file(&backup_path);
-    }
+    // Re-checked right before writing, in case the config changed meanwhile
+    replace_binary(&std::env::current_exe()?, &bytes, self_update_allowed())?;
 
     // Save notification
     let changelog = release.body.unwrap_or_default();
@@ -360,16 +496,29 @@ pub async fn background_update_check() -> Result<()> {
 
 /// Interactive update check and install
 pub async fn check_and_update() -> Result<()> {
+    if crate::http::is_offline() {
+        anyhow::bail!("offline mode: cannot check for updates");
+    }
+    if !self_update_allowed() {
+        anyhow::bail!(
+            "{}\nThis copy is managed elsewhere; update it with your package manager",
+            SELF_UPDATE_DISABLED
+        );
+    }
     let current_version = env!("CARGO_PKG_VERSION");
 
     println!("{}", "Checking for updates...".cyan());
 
     let client = create_client_builder()
         .timeout(std::time::Duration::from_secs(30))
-        .user_agent(format!("ask/{}", current_version))
+        .user_agent(update_user_agent())
         .build()?;
 
-    let response = client.get(RELEASES_URL).send().await?;
+    let response = client
+        .get(RELEASES_URL)
+        .headers(github_headers(RELEASES_URL, github_token().as_deref()))
+        .send()
+        .await?;
     let status = response.status();
     let body = response.text().await?;
 
@@ -441,7 +590,7 @@ pub async fn check_and_update() -> Result<()> {
     // Download with longer timeout for large binary
     let download_client = create_client_builder()
         .timeout(std::time::Duration::from_secs(300))
-        .user_agent(format!("ask/{}", current_version))
+        .user_agent(update_user_agent())
         .build()?;
 
     let response = download_client
@@ -461,38 +610,8 @@ pub async fn check_and_update() -> Result<()> {
     let bytes = response.bytes().await?;
     pb.finish_and_clear();
 
-    // Get current executable path
-    let current_exe = std::env::current_exe()?;
-
-    // Create temp file
-    let temp_path = current_exe.with_extension("new");
-
-    // Write new binary
-    fs::write(&temp_path, &bytes)?;
-
-    // Set executable permission on Unix
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&temp_path)?.permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(&temp_path, perms)?;
-    }
-
-    // Replace binary
-    #[cfg(unix)]
-    {
-        fs::rename(&temp_path, &current_exe)?;
-    }
-
-    #[cfg(windows)]
-    {
-        let backup_path = current_exe.with_extension("old");
-        let _ = fs::remove_file(&backup_path);
-        fs::rename(&current_exe, &backup_path)?;
-        fs::rename(&temp_path, &current_exe)?;
-        let _ = fs::remove_file(&backup_path);
-    }
+    // Re-checked right before writing, in case the config changed meanwhile
+    replace_binary(&std::env::current_exe()?, &bytes, self_update_allowed())?;
 
     println!(
         "{} {} → {}",
@@ -514,6 +633,43 @@ pub async fn check_and_update() -> Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_github_headers_with_and_without_token() {
+        let headers = github_headers(RELEASES_URL, Some("ghp_secret"));
+        let auth = headers.get(reqwest::header::AUTHORIZATION).unwrap();
+        assert_eq!(auth.to_str().unwrap(), "Bearer ghp_secret");
+        assert!(auth.is_sensitive());
+
+        assert!(github_headers(RELEASES_URL, None).is_empty());
+    }
+
+    #[test]
+    fn test_github_headers_never_leave_api_host() {
+        for url in [
+            "https://github.com/verseles/ask/releases/download/v1.0.0/ask-linux-x86_64",
+            "https://objects.githubusercontent.com/asset",
+            "https://api.github.com.evil.example/repos",
+            "http://api.github.com/repos/verseles/ask/releases/latest",
+        ] {
+            assert!(github_headers(url, Some("ghp_secret")).is_empty(), "{url}");
+        }
+    }
+
+    #[test]
+    fn test_replace_binary_refuses_when_disabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("ask");
+        fs::write(&exe, b"old").unwrap();
+
+        let err = replace_binary(&exe, b"new", false).unwrap_err();
+        assert_eq!(err.to_string(), SELF_UPDATE_DISABLED);
+        assert_eq!(fs::read(&exe).unwrap(), b"old");
+        assert!(!exe.with_extension("new").exists());
+
+        replace_binary(&exe, b"new", true).unwrap();
+        assert_eq!(fs::read(&exe).unwrap(), b"new");
+    }
+
     #[test]
     fn test_is_newer_version() {
         assert!(is_newer_version("0.14.4", "0.15.0"));
@@ -534,23 +690,85 @@ mod tests {
     #[test]
     fn test_should_check_update_aggressive() {
         let now = chrono::Utc::now().timestamp();
-        assert!(should_check_update(true, 24, None));
-        assert!(should_check_update(true, 24, Some(0)));
-        assert!(should_check_update(true, 24, Some(now - 3601)));
-        assert!(!should_check_update(true, 24, Some(now - 3599)));
+        assert!(should_check_update(true, 24, None, None));
+        assert!(should_check_update(true, 24, Some(0), None));
+        assert!(should_check_update(true, 24, Some(now - 3601), None));
+        assert!(!should_check_update(true, 24, Some(now - 3599), None));
     }
 
     #[test]
     fn test_should_check_update_normal() {
         let now = chrono::Utc::now().timestamp();
-        assert!(!should_check_update(false, 24, Some(now)));
-        assert!(!should_check_update(false, 24, Some(now - 3600)));
-        assert!(should_check_update(false, 24, Some(now - 86401)));
-        assert!(should_check_update(false, 24, None));
+        assert!(!should_check_update(false, 24, Some(now), None));
+        assert!(!should_check_update(false, 24, Some(now - 3600), None));
+        assert!(should_check_update(false, 24, Some(now - 86401), None));
+        assert!(should_check_update(false, 24, None, None));
 
         // Custom interval
-        assert!(should_check_update(false, 1, Some(now - 3601)));
-        assert!(!should_check_update(false, 1, Some(now - 3599)));
+        assert!(should_check_update(false, 1, Some(now - 3601), None));
+        assert!(!should_check_update(false, 1, Some(now - 3599), None));
+    }
+
+    #[test]
+    fn test_should_check_update_respects_backoff() {
+        let now = chrono::Utc::now().timestamp();
+
+        // Backoff in the future wins over an old (or missing) last check
+        assert!(!should_check_update(true, 24, None, Some(now + 600)));
+        assert!(!should_check_update(false, 24, Some(0), Some(now + 600)));
+
+        // Expired backoff falls back to the normal rules
+        assert!(should_check_update(
+            true,
+            24,
+            Some(now - 3601),
+            Some(now - 1)
+        ));
+        assert!(!should_check_update(
+            true,
+            24,
+            Some(now - 60),
+            Some(now - 1)
+        ));
+    }
+
+    #[test]
+    fn test_backoff_until_retry_after_seconds() {
+        assert_eq!(backoff_until(1_000, Some("120"), 0), 1_120);
+        assert_eq!(backoff_until(1_000, Some(" 120 "), 7), 1_127);
+    }
+
+    #[test]
+    fn test_backoff_until_retry_after_date() {
+        let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2026 07:28:00 GMT")
+            .unwrap()
+            .timestamp();
+        assert_eq!(
+            backoff_until(now, Some("Wed, 21 Oct 2026 07:58:00 GMT"), 0),
+            now + 1800
+        );
+    }
+
+    #[test]
+    fn test_backoff_until_defaults_to_an_hour() {
+        assert_eq!(backoff_until(1_000, None, 0), 1_000 + DEFAULT_BACKOFF_SECS);
+        assert_eq!(
+            backoff_until(1_000, Some("soon"), 0),
+            1_000 + DEFAULT_BACKOFF_SECS
+        );
+        // A zero or past Retry-After is no reason to retry immediately
+        assert_eq!(
+            backoff_until(1_000, Some("0"), 0),
+            1_000 + DEFAULT_BACKOFF_SECS
+        );
+    }
+
+    #[test]
+    fn test_backoff_jitter_bounds() {
+        for _ in 0..100 {
+            let jitter = backoff_jitter();
+            assert!((0..=BACKOFF_JITTER_SECS).contains(&jitter));
+        }
     }
 
     #[test]
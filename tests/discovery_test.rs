@@ -94,3 +94,69 @@ fn test_recursive_prompt_discovery() {
     // To make this integration test work, let's assume if it finds the config
     // it also finds the prompt as they use the same find_recursive_file.
 }
+
+#[test]
+fn test_pinned_config_skips_discovery() {
+    let root = tempdir().unwrap();
+    let root_path = root.path();
+
+    // A local ask.toml that must be ignored
+    fs::write(
+        root_path.join("ask.toml"),
+        r#"
+[profiles.localonly]
+provider = "openai"
+model = "gpt-local"
+"#,
+    )
+    .unwrap();
+
+    let pinned = root_path.join("pinned.toml");
+    fs::write(
+        &pinned,
+        r#"
+[profiles.pinnedonly]
+provider = "anthropic"
+model = "claude-pinned"
+"#,
+    )
+    .unwrap();
+
+    let exe_path = std::env::current_dir().unwrap().join("target/debug/ask");
+
+    for use_flag in [false, true] {
+        let mut cmd = Command::new(&exe_path);
+        cmd.arg("profiles").current_dir(root_path);
+        if use_flag {
+            cmd.arg("--config").arg(&pinned);
+        } else {
+            cmd.env("ASK_CONFIG", &pinned);
+        }
+        let output = cmd.output().unwrap();
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success());
+        assert!(stdout.contains("pinnedonly"), "pinned profile should load");
+        assert!(
+            !stdout.contains("localonly"),
+            "discovered ask.toml should be skipped"
+        );
+    }
+}
+
+#[test]
+fn test_missing_pinned_config_errors() {
+    let root = tempdir().unwrap();
+    let exe_path = std::env::current_dir().unwrap().join("target/debug/ask");
+
+    let output = Command::new(exe_path)
+        .arg("profiles")
+        .current_dir(root.path())
+        .env("ASK_CONFIG", root.path().join("nope.toml"))
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Config file not found"));
+}
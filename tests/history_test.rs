@@ -204,3 +204,37 @@ fn history_prune_removes_deleted_directories() {
     assert!(list_stdout.contains(&*live_dir.to_string_lossy()));
     assert!(!list_stdout.contains(&*orphan_dir.to_string_lossy()));
 }
+
+#[test]
+fn history_export_writes_markdown_transcript() {
+    let temp = tempdir().unwrap();
+    let storage_dir = temp.path().join("contexts");
+    let project_dir = temp.path().join("project-export");
+    let export_path = temp.path().join("session.md");
+
+    fs::create_dir_all(&storage_dir).unwrap();
+    fs::create_dir_all(&project_dir).unwrap();
+
+    seed_context(
+        &storage_dir,
+        "eeee5555ffff6666",
+        &project_dir,
+        &[("user", "How do I list files?"), ("assistant", "ls -la")],
+        Utc::now(),
+    );
+
+    let output = Command::new(ask_bin())
+        .current_dir(temp.path())
+        .env("ASK_CONTEXT_PATH", &storage_dir)
+        .args(["history", "eeee5555", "--export"])
+        .arg(&export_path)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let markdown = fs::read_to_string(&export_path).unwrap();
+    assert!(markdown.contains("### User"));
+    assert!(markdown.contains("How do I list files?"));
+    assert!(markdown.contains("```sh\nls -la\n```"));
+}
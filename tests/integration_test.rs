@@ -26,6 +26,21 @@ fn version_flag_shows_version() {
     assert!(stdout.contains("ask"));
 }
 
+#[test]
+fn version_json_includes_build_info() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "--version", "--json"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("--version --json prints JSON");
+    assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+    assert!(json["target"].is_string());
+    assert!(json["git_hash"].is_string());
+}
+
 #[test]
 fn no_arguments_does_not_panic() {
     let output = Command::new("cargo")
@@ -449,3 +464,415 @@ fn ollama_provider_uses_env_base_url() {
         stderr
     );
 }
+
+/// Serve a single OpenAI-style SSE response on a local port
+fn spawn_mock_openai_stream(chunks: &'static [&'static str]) -> String {
+    spawn_mock_openai_capture(chunks).0
+}
+
+/// Like `spawn_mock_openai_stream`, also handing back the JSON request body
+fn spawn_mock_openai_capture(
+    chunks: &'static [&'static str],
+) -> (String, std::sync::mpsc::Receiver<serde_json::Value>) {
+    let mut body = String::new();
+    for chunk in chunks {
+        let data = serde_json::json!({ "choices": [{ "delta": { "content": chunk } }] });
+        body.push_str(&format!("data: {}\n\n", data));
+    }
+    body.push_str("data: [DONE]\n\n");
+    spawn_mock_http("text/event-stream", body)
+}
+
+/// Serve a single non-streaming OpenAI chat completion on a local port
+fn spawn_mock_openai_completion(text: &str) -> String {
+    let body = serde_json::json!({
+        "choices": [{ "message": { "content": text }, "finish_reason": "stop" }]
+    });
+    spawn_mock_http("application/json", body.to_string()).0
+}
+
+/// `cargo run -- -p mock` against `base_url`, with the profile written to
+/// `dir/ask.toml` (plus `extra_toml` lines) and no proxy in the way
+fn mock_ask(dir: &std::path::Path, base_url: &str, extra_toml: &str) -> Command {
+    let config_path = dir.join("ask.toml");
+    std::fs::write(
+        &config_path,
+        format!(
+            r#"
+[profiles.mock]
+provider = "openai"
+model = "gpt-test"
+api_key = "sk-test"
+base_url = "{}"
+{}"#,
+            base_url, extra_toml
+        ),
+    )
+    .unwrap();
+
+    let mut command = Command::new("cargo");
+    command
+        .env("ASK_CONFIG", &config_path)
+        .env("ASK_NO_UPDATE", "1")
+        .env_remove("HTTP_PROXY")
+        .env_remove("HTTPS_PROXY")
+        .env_remove("ALL_PROXY")
+        .env_remove("http_proxy")
+        .env_remove("https_proxy")
+        .env_remove("all_proxy")
+        .args(["run", "--", "-p", "mock"]);
+    command
+}
+
+/// Answer one request with `body`, handing back the JSON request body
+fn spawn_mock_http(
+    content_type: &'static str,
+    body: String,
+) -> (String, std::sync::mpsc::Receiver<serde_json::Value>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" || line.is_empty() {
+                break;
+            }
+            let lower = line.to_lowercase();
+            if let Some(value) = lower.strip_prefix("content-length:") {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+        let mut request_body = vec![0; content_length];
+        reader.read_exact(&mut request_body).unwrap();
+        if let Ok(json) = serde_json::from_slice(&request_body) {
+            let _ = tx.send(json);
+        }
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            content_type,
+            body.len(),
+            body
+        );
+
+        let mut stream = reader.into_inner();
+        stream.write_all(response.as_bytes()).unwrap();
+        stream.flush().unwrap();
+    });
+
+    (format!("http://{}", addr), rx)
+}
+
+#[test]
+fn raw_stream_writes_plain_chunks() {
+    let base_url = spawn_mock_openai_stream(&["ls", " -la", " /tmp"]);
+
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = mock_ask(dir.path(), &base_url, "")
+        .args(["--raw-stream", "list files"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(stdout, "ls -la /tmp");
+    assert!(!stdout.contains('\x1b'));
+    assert!(!stdout.contains('●'));
+}
+
+#[test]
+fn json_stream_writes_ndjson_events() {
+    let base_url = spawn_mock_openai_stream(&["ls", " -la"]);
+
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = mock_ask(dir.path(), &base_url, "")
+        .args(["--json-stream", "list files"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let events: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("every line is JSON"))
+        .collect();
+
+    let (done, deltas) = events.split_last().expect("at least one event");
+    assert_eq!(done["type"], "done");
+    assert!(deltas.iter().all(|e| e["type"] == "delta"));
+    let text: String = deltas.iter().map(|e| e["text"].as_str().unwrap()).collect();
+    assert_eq!(text, "ls -la");
+}
+
+#[test]
+fn json_output_includes_provider_and_model() {
+    let base_url = spawn_mock_openai_completion("ls -la");
+
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = mock_ask(dir.path(), &base_url, "stream = true\n")
+        .args(["--json", "list files"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let envelope: serde_json::Value = serde_json::from_str(&stdout).expect("stdout is JSON");
+    assert_eq!(envelope["provider"], "openai");
+    assert_eq!(envelope["model"], "gpt-test");
+    assert_eq!(envelope["response"], "ls -la");
+    assert_eq!(envelope["is_command"], true);
+    assert!(envelope["usage"].is_null());
+}
+
+#[test]
+fn prompt_file_replaces_system_prompt() {
+    let (base_url, requests) = spawn_mock_openai_capture(&["ok"]);
+
+    let dir = tempfile::tempdir().unwrap();
+    let prompt_path = dir.path().join("experiment.md");
+    std::fs::write(&prompt_path, "You are a pirate on {os}.").unwrap();
+
+    let output = mock_ask(dir.path(), &base_url, "")
+        .args(["--raw-stream", "--prompt-file"])
+        .arg(&prompt_path)
+        .arg("what is x")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let request = requests
+        .recv_timeout(std::time::Duration::from_secs(5))
+        .expect("mock server received no request");
+    let system = request["messages"][0]["content"].as_str().unwrap();
+    assert!(system.starts_with("You are a pirate on "), "{}", system);
+    assert!(!system.contains("{os}"));
+    assert_eq!(request["messages"][1]["content"], "what is x");
+}
+
+#[test]
+fn offline_without_cache_fails_without_connecting() {
+    let (base_url, requests) = spawn_mock_openai_capture(&["ok"]);
+
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = mock_ask(dir.path(), &base_url, "")
+        .env_remove("ASK_OFFLINE")
+        .args(["--offline", "--no-fallback", "hi"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("offline mode: no cached response"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(requests
+        .recv_timeout(std::time::Duration::from_millis(200))
+        .is_err());
+}
+
+#[test]
+fn prompt_file_missing_is_an_error() {
+    let output = Command::new("cargo")
+        .env("ASK_NO_UPDATE", "1")
+        .args([
+            "run",
+            "--",
+            "--prompt-file",
+            "/nonexistent/ask-prompt.md",
+            "what is x",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Could not read prompt file /nonexistent/ask-prompt.md"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn conflicting_flags_exit_with_usage_code() {
+    let output = Command::new("cargo")
+        .env("ASK_NO_UPDATE", "1")
+        .args(["run", "--", "-p", "work", "-P", "openai", "what", "is", "x"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Cannot use --profile (-p) and --provider (-P) together"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn tee_saves_the_streamed_answer() {
+    let base_url = spawn_mock_openai_stream(&["# Timers", "\n\nUse ", "`systemctl list-timers`."]);
+
+    let dir = tempfile::tempdir().unwrap();
+    let tee_path = dir.path().join("answer.md");
+
+    let output = mock_ask(dir.path(), &base_url, "")
+        .args(["--no-color", "--tee"])
+        .arg(&tee_path)
+        .args(["--question", "explain timers"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let streamed = "# Timers\n\nUse `systemctl list-timers`.";
+    assert_eq!(std::fs::read_to_string(&tee_path).unwrap(), streamed);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("systemctl list-timers"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn tee_with_bad_path_fails_before_the_request() {
+    let dir = tempfile::tempdir().unwrap();
+    let tee_path = dir.path().join("missing/answer.md");
+
+    let output = Command::new("cargo")
+        .env("ASK_PROVIDER", "openai")
+        .env("ASK_OPENAI_API_KEY", "sk-test")
+        .env("ASK_OPENAI_BASE_URL", "http://127.0.0.1:9")
+        .env("ASK_NO_UPDATE", "1")
+        .args(["run", "--", "--tee"])
+        .arg(&tee_path)
+        .arg("hello")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Cannot create --tee file"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn preserve_keeps_leading_whitespace() {
+    let answer = "    indented: true\n  nested:\n    - item\n\n";
+    let base_url = spawn_mock_openai_completion(answer);
+
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = mock_ask(dir.path(), &base_url, "")
+        .args(["--preserve", "write yaml"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), answer);
+}
+
+#[test]
+fn streamed_answer_cut_off_shows_truncation_hint() {
+    let mut body = String::new();
+    for (content, finish_reason) in [("1. Install the", None), (" package", Some("length"))] {
+        let data = serde_json::json!({
+            "choices": [{ "delta": { "content": content }, "finish_reason": finish_reason }]
+        });
+        body.push_str(&format!("data: {}\n\n", data));
+    }
+    body.push_str("data: [DONE]\n\n");
+    let (base_url, _) = spawn_mock_http("text/event-stream", body);
+
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = mock_ask(dir.path(), &base_url, "")
+        .args(["--no-color", "--question", "how do I install it"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Install the package"));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("response truncated"),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn debug_log_records_streamed_events() {
+    let base_url = spawn_mock_openai_stream(&["ls", " -la"]);
+
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("ask.log");
+
+    let output = mock_ask(dir.path(), &base_url, "")
+        .env("ASK_DEBUG_LOG", &log_path)
+        .args(["--raw-stream", "list files"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let log = std::fs::read_to_string(&log_path).unwrap();
+    assert!(log.contains(">>> request openai"), "log: {}", log);
+    assert!(log.contains("<<< data openai"), "log: {}", log);
+    assert!(log.contains(r#""content":" -la""#), "log: {}", log);
+    assert!(log.contains("[DONE]"), "log: {}", log);
+}